@@ -64,6 +64,43 @@ impl AccountCheckCommand {
             }
         }
 
+        #[cfg(feature = "file")]
+        if backend.allows_file() {
+            if let Some(file_config) = account_config.file.clone() {
+                report.backends.push(check_file(file_config));
+            }
+        }
+
+        #[cfg(feature = "graph")]
+        if backend.allows_graph() {
+            if let Some(graph_config) = account_config.graph.clone() {
+                report.backends.push(check_graph(graph_config));
+            }
+        }
+
+        #[cfg(feature = "jmap")]
+        if backend.allows_jmap() {
+            if let Some(jmap_config) = account_config.jmap.clone() {
+                report.backends.push(check_jmap(jmap_config));
+            }
+        }
+
+        #[cfg(feature = "google")]
+        if backend.allows_google() {
+            if let Some(google_config) = account_config.google.clone() {
+                report
+                    .backends
+                    .push(check_google(&report.account, google_config));
+            }
+        }
+
+        #[cfg(feature = "ics")]
+        if backend.allows_ics() {
+            if let Some(ics_config) = account_config.ics.clone() {
+                report.backends.push(check_ics(&report.account, ics_config));
+            }
+        }
+
         if report.backends.is_empty() {
             bail!("No backend matching `{backend}` is configured for this account");
         }
@@ -108,6 +145,67 @@ fn check_caldav(
     BackendCheck::from("caldav", result)
 }
 
+/// Parses every calendar file, missing ones counting as empty.
+#[cfg(feature = "file")]
+fn check_file(file_config: crate::config::FileConfig) -> BackendCheck {
+    let result = (|| -> Result<()> {
+        crate::file::client::FileClient::new(file_config).list_calendars()?;
+        Ok(())
+    })();
+
+    BackendCheck::from("file", result)
+}
+
+/// Resolves the bearer token and lists the calendars of the user.
+#[cfg(feature = "graph")]
+fn check_graph(graph_config: crate::config::GraphConfig) -> BackendCheck {
+    let result = (|| -> Result<()> {
+        crate::graph::client::GraphClient::new(graph_config)?.list_calendars()?;
+        Ok(())
+    })();
+
+    BackendCheck::from("graph", result)
+}
+
+/// Discovers the JMAP session and lists the calendars of the
+/// account.
+#[cfg(feature = "jmap")]
+fn check_jmap(jmap_config: crate::config::JmapConfig) -> BackendCheck {
+    let result = (|| -> Result<()> {
+        crate::jmap::client::JmapClient::new(jmap_config)?.list_calendars()?;
+        Ok(())
+    })();
+
+    BackendCheck::from("jmap", result)
+}
+
+/// Resolves the bearer token and lists the calendar list of the user.
+#[cfg(feature = "google")]
+fn check_google(account_name: &str, google_config: crate::config::GoogleConfig) -> BackendCheck {
+    let result = (|| -> Result<()> {
+        crate::google::client::GoogleClient::new(account_name, google_config)?.list_calendars()?;
+        Ok(())
+    })();
+
+    BackendCheck::from("google", result)
+}
+
+/// Loads every feed, downloading the remote ones unless they are
+/// cached and unchanged.
+#[cfg(feature = "ics")]
+fn check_ics(account_name: &str, ics_config: crate::config::IcsConfig) -> BackendCheck {
+    let result = (|| -> Result<()> {
+        let ids: Vec<String> = ics_config.feeds.iter().map(|f| f.id.clone()).collect();
+        let mut client = crate::ics::client::IcsClient::new(account_name, ics_config)?;
+        for id in ids {
+            client.list_items(&id)?;
+        }
+        Ok(())
+    })();
+
+    BackendCheck::from("ics", result)
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct CheckReport {
     pub account: String,
@@ -12,12 +12,18 @@ use comfy_table::{Color as TableColor, ContentArrangement, presets};
 use crossterm::style::Color;
 use dirs::download_dir;
 
-use crate::config::{
-    AccountConfig, CalendarListTableConfig, Config, EventListTableConfig, ItemListTableConfig,
-    TableArrangementConfig,
+use crate::{
+    config::{
+        AccountConfig, CalendarListTableConfig, Config, EventAgendaConfig, EventListTableConfig,
+        ItemListTableConfig, JournalListTableConfig, TableArrangementConfig, TodoListTableConfig,
+        WatchConfig,
+    },
+    shared::timezone::Timezone,
 };
 
 const DEFAULT_LIST_PAGE_SIZE: u32 = 25;
+const DEFAULT_WATCH_INTERVAL: u64 = 60;
+const DEFAULT_WATCH_NOTIFY_CMD: &str = "notify-send --app-name=calendula {summary} {start}";
 
 #[derive(Debug, Default)]
 pub struct Account {
@@ -26,14 +32,33 @@ pub struct Account {
     pub table_arrangement: Option<TableArrangementConfig>,
 
     pub events_list_page_size: Option<u32>,
+    pub todos_list_page_size: Option<u32>,
+    pub journals_list_page_size: Option<u32>,
     pub items_list_page_size: Option<u32>,
 
+    /// Zone event times are rendered in. The global `--tz` flag is
+    /// folded on top by the dispatch layer.
+    pub timezone: Option<Timezone>,
+
+    /// Calendar user address of the account owner: ORGANIZER of `event
+    /// invite`, ATTENDEE of `event reply`.
+    pub email: Option<String>,
+
+    /// Hour range and slot size of the `event agenda
+    /// --day/--week-grid` time grid.
+    pub events_agenda: EventAgendaConfig,
+
+    /// Notification command and polling interval of `watch`.
+    pub watch: WatchConfig,
+
     /// Fallback calendar id for `event` and `item` commands when their
     /// `-k/--calendar` flag is omitted.
     pub calendar_default: Option<String>,
 
     pub calendars_list_table: CalendarListTableConfig,
     pub events_list_table: EventListTableConfig,
+    pub todos_list_table: TodoListTableConfig,
+    pub journals_list_table: JournalListTableConfig,
     pub items_list_table: ItemListTableConfig,
 }
 
@@ -46,8 +71,29 @@ impl Account {
             table_arrangement: other.table_arrangement.or(self.table_arrangement),
 
             events_list_page_size: other.events_list_page_size.or(self.events_list_page_size),
+            todos_list_page_size: other.todos_list_page_size.or(self.todos_list_page_size),
+            journals_list_page_size: other
+                .journals_list_page_size
+                .or(self.journals_list_page_size),
             items_list_page_size: other.items_list_page_size.or(self.items_list_page_size),
 
+            timezone: other.timezone.or(self.timezone),
+            email: other.email.or(self.email),
+
+            events_agenda: EventAgendaConfig {
+                start_hour: other
+                    .events_agenda
+                    .start_hour
+                    .or(self.events_agenda.start_hour),
+                end_hour: other.events_agenda.end_hour.or(self.events_agenda.end_hour),
+                slot: other.events_agenda.slot.or(self.events_agenda.slot),
+            },
+
+            watch: WatchConfig {
+                notify_cmd: other.watch.notify_cmd.or(self.watch.notify_cmd),
+                interval: other.watch.interval.or(self.watch.interval),
+            },
+
             calendar_default: other.calendar_default.or(self.calendar_default),
 
             calendars_list_table: merge_calendar_table(
@@ -55,12 +101,16 @@ impl Account {
                 other.calendars_list_table,
             ),
             events_list_table: merge_event_table(self.events_list_table, other.events_list_table),
+            todos_list_table: merge_todo_table(self.todos_list_table, other.todos_list_table),
+            journals_list_table: merge_journal_table(
+                self.journals_list_table,
+                other.journals_list_table,
+            ),
             items_list_table: merge_item_table(self.items_list_table, other.items_list_table),
         }
     }
 
     /// Effective downloads directory.
-    #[allow(dead_code)]
     pub fn downloads_dir(&self) -> PathBuf {
         self.downloads_dir
             .as_ref()
@@ -86,16 +136,61 @@ impl Account {
             .into()
     }
 
+    /// Effective notification command of `watch`.
+    pub fn watch_notify_cmd(&self) -> &str {
+        self.watch
+            .notify_cmd
+            .as_deref()
+            .unwrap_or(DEFAULT_WATCH_NOTIFY_CMD)
+    }
+
+    /// Effective polling interval of `watch`, in seconds.
+    pub fn watch_interval(&self) -> u64 {
+        self.watch.interval.unwrap_or(DEFAULT_WATCH_INTERVAL)
+    }
+
     /// Effective default page size for `events list`.
     pub fn events_list_page_size(&self) -> u32 {
         self.events_list_page_size.unwrap_or(DEFAULT_LIST_PAGE_SIZE)
     }
 
+    /// Effective default page size for `todo list`.
+    pub fn todos_list_page_size(&self) -> u32 {
+        self.todos_list_page_size.unwrap_or(DEFAULT_LIST_PAGE_SIZE)
+    }
+
+    /// Effective default page size for `journals list`.
+    pub fn journals_list_page_size(&self) -> u32 {
+        self.journals_list_page_size
+            .unwrap_or(DEFAULT_LIST_PAGE_SIZE)
+    }
+
     /// Effective default page size for `items list`.
     pub fn items_list_page_size(&self) -> u32 {
         self.items_list_page_size.unwrap_or(DEFAULT_LIST_PAGE_SIZE)
     }
 
+    /// Effective zone event times are rendered in.
+    pub fn timezone(&self) -> Timezone {
+        self.timezone.unwrap_or_default()
+    }
+
+    /// Effective first hour of the `event agenda` time grid.
+    pub fn events_agenda_start_hour(&self) -> u32 {
+        self.events_agenda.start_hour.unwrap_or(8)
+    }
+
+    /// Effective end hour of the `event agenda` time grid.
+    pub fn events_agenda_end_hour(&self) -> u32 {
+        self.events_agenda.end_hour.unwrap_or(20)
+    }
+
+    /// Effective slot size of the `event agenda` time grid, in
+    /// minutes.
+    pub fn events_agenda_slot(&self) -> u32 {
+        self.events_agenda.slot.unwrap_or(30)
+    }
+
     /// Resolves the calendar id an `event` or `item` command operates
     /// on: the `-k/--calendar` flag wins; otherwise the
     /// `calendar.default` config is used; otherwise the command bails.
@@ -127,6 +222,15 @@ impl Account {
     pub fn events_list_table_id_color(&self) -> TableColor {
         map_color_or(self.events_list_table.id_color, Color::Red)
     }
+    pub fn events_list_table_account_color(&self) -> TableColor {
+        map_color_or(self.events_list_table.account_color, Color::Magenta)
+    }
+    pub fn events_list_table_calendar_color(&self) -> TableColor {
+        map_color_or(self.events_list_table.calendar_color, Color::Blue)
+    }
+    pub fn events_list_table_recurrence_id_color(&self) -> TableColor {
+        map_color_or(self.events_list_table.recurrence_id_color, Color::DarkRed)
+    }
     pub fn events_list_table_summary_color(&self) -> TableColor {
         map_color_or(self.events_list_table.summary_color, Color::Green)
     }
@@ -137,6 +241,39 @@ impl Account {
         map_color_or(self.events_list_table.end_color, Color::DarkYellow)
     }
 
+    // todo list column colors
+
+    pub fn todos_list_table_id_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.id_color, Color::Red)
+    }
+    pub fn todos_list_table_summary_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.summary_color, Color::Green)
+    }
+    pub fn todos_list_table_status_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.status_color, Color::Blue)
+    }
+    pub fn todos_list_table_due_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.due_color, Color::DarkYellow)
+    }
+    pub fn todos_list_table_priority_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.priority_color, Color::Magenta)
+    }
+    pub fn todos_list_table_percent_color(&self) -> TableColor {
+        map_color_or(self.todos_list_table.percent_color, Color::Reset)
+    }
+
+    // journals list column colors
+
+    pub fn journals_list_table_id_color(&self) -> TableColor {
+        map_color_or(self.journals_list_table.id_color, Color::Red)
+    }
+    pub fn journals_list_table_date_color(&self) -> TableColor {
+        map_color_or(self.journals_list_table.date_color, Color::DarkYellow)
+    }
+    pub fn journals_list_table_summary_color(&self) -> TableColor {
+        map_color_or(self.journals_list_table.summary_color, Color::Green)
+    }
+
     // items list column colors
 
     pub fn items_list_table_id_color(&self) -> TableColor {
@@ -194,12 +331,37 @@ fn merge_event_table(
 ) -> EventListTableConfig {
     EventListTableConfig {
         id_color: over.id_color.or(base.id_color),
+        account_color: over.account_color.or(base.account_color),
+        calendar_color: over.calendar_color.or(base.calendar_color),
+        recurrence_id_color: over.recurrence_id_color.or(base.recurrence_id_color),
         summary_color: over.summary_color.or(base.summary_color),
         start_color: over.start_color.or(base.start_color),
         end_color: over.end_color.or(base.end_color),
     }
 }
 
+fn merge_todo_table(base: TodoListTableConfig, over: TodoListTableConfig) -> TodoListTableConfig {
+    TodoListTableConfig {
+        id_color: over.id_color.or(base.id_color),
+        summary_color: over.summary_color.or(base.summary_color),
+        status_color: over.status_color.or(base.status_color),
+        due_color: over.due_color.or(base.due_color),
+        priority_color: over.priority_color.or(base.priority_color),
+        percent_color: over.percent_color.or(base.percent_color),
+    }
+}
+
+fn merge_journal_table(
+    base: JournalListTableConfig,
+    over: JournalListTableConfig,
+) -> JournalListTableConfig {
+    JournalListTableConfig {
+        id_color: over.id_color.or(base.id_color),
+        date_color: over.date_color.or(base.date_color),
+        summary_color: over.summary_color.or(base.summary_color),
+    }
+}
+
 fn merge_item_table(base: ItemListTableConfig, over: ItemListTableConfig) -> ItemListTableConfig {
     ItemListTableConfig {
         id_color: over.id_color.or(base.id_color),
@@ -215,10 +377,18 @@ impl From<Config> for Account {
             table_preset: config.table.preset,
             table_arrangement: config.table.arrangement,
             events_list_page_size: config.event.list.page_size,
+            todos_list_page_size: config.todo.list.page_size,
+            journals_list_page_size: config.journal.list.page_size,
             items_list_page_size: config.item.list.page_size,
+            timezone: config.event.timezone,
+            email: config.event.email,
+            events_agenda: config.event.agenda,
+            watch: config.watch,
             calendar_default: config.calendar.default,
             calendars_list_table: config.calendar.list.table,
             events_list_table: config.event.list.table,
+            todos_list_table: config.todo.list.table,
+            journals_list_table: config.journal.list.table,
             items_list_table: config.item.list.table,
         }
     }
@@ -231,10 +401,18 @@ impl From<AccountConfig> for Account {
             table_preset: config.table.preset,
             table_arrangement: config.table.arrangement,
             events_list_page_size: config.event.list.page_size,
+            todos_list_page_size: config.todo.list.page_size,
+            journals_list_page_size: config.journal.list.page_size,
             items_list_page_size: config.item.list.page_size,
+            timezone: config.event.timezone,
+            email: config.event.email,
+            events_agenda: config.event.agenda,
+            watch: config.watch,
             calendar_default: config.calendar.default,
             calendars_list_table: config.calendar.list.table,
             events_list_table: config.event.list.table,
+            todos_list_table: config.todo.list.table,
+            journals_list_table: config.journal.list.table,
             items_list_table: config.item.list.table,
         }
     }
@@ -6,13 +6,13 @@ use clap::Parser;
 /// Selects which backend a cross-protocol command should target.
 ///
 /// `Auto` lets the command pick the first configured-and-supported
-/// backend in its own priority order (vdir wins over caldav when both
-/// are configured). The named variants pin the command to that
+/// backend in its own priority order (vdir, caldav, file, graph, jmap,
+/// google, then ics). The named variants pin the command to that
 /// backend; the command bails if it cannot be served (config missing,
 /// or the operation has no arm for that backend).
 ///
 /// The protocol-specific subcommands (`vdir`, `caldav`) ignore this
-/// arg entirely.
+/// arg entirely. `webcal` is accepted as an alias of `ics`.
 #[derive(Clone, Copy, Debug, Default, Parser, PartialEq, Eq)]
 pub enum Backend {
     #[default]
@@ -21,6 +21,16 @@ pub enum Backend {
     Caldav,
     #[cfg(feature = "vdir")]
     Vdir,
+    #[cfg(feature = "file")]
+    File,
+    #[cfg(feature = "graph")]
+    Graph,
+    #[cfg(feature = "jmap")]
+    Jmap,
+    #[cfg(feature = "google")]
+    Google,
+    #[cfg(feature = "ics")]
+    Ics,
 }
 
 #[allow(unused)]
@@ -36,6 +46,40 @@ impl Backend {
     pub fn allows_vdir(self) -> bool {
         matches!(self, Self::Auto | Self::Vdir)
     }
+
+    /// Whether the single-file arm of a shared command is allowed to
+    /// run.
+    #[cfg(feature = "file")]
+    pub fn allows_file(self) -> bool {
+        matches!(self, Self::Auto | Self::File)
+    }
+
+    /// Whether the Microsoft Graph arm of a shared command is allowed
+    /// to run.
+    #[cfg(feature = "graph")]
+    pub fn allows_graph(self) -> bool {
+        matches!(self, Self::Auto | Self::Graph)
+    }
+
+    /// Whether the JMAP arm of a shared command is allowed to run.
+    #[cfg(feature = "jmap")]
+    pub fn allows_jmap(self) -> bool {
+        matches!(self, Self::Auto | Self::Jmap)
+    }
+
+    /// Whether the Google Calendar arm of a shared command is allowed
+    /// to run.
+    #[cfg(feature = "google")]
+    pub fn allows_google(self) -> bool {
+        matches!(self, Self::Auto | Self::Google)
+    }
+
+    /// Whether the read-only ics arm of a shared command is allowed to
+    /// run.
+    #[cfg(feature = "ics")]
+    pub fn allows_ics(self) -> bool {
+        matches!(self, Self::Auto | Self::Ics)
+    }
 }
 
 impl FromStr for Backend {
@@ -48,6 +92,16 @@ impl FromStr for Backend {
             "caldav" => Ok(Self::Caldav),
             #[cfg(feature = "vdir")]
             "vdir" => Ok(Self::Vdir),
+            #[cfg(feature = "file")]
+            "file" => Ok(Self::File),
+            #[cfg(feature = "graph")]
+            "graph" => Ok(Self::Graph),
+            #[cfg(feature = "jmap")]
+            "jmap" => Ok(Self::Jmap),
+            #[cfg(feature = "google")]
+            "google" => Ok(Self::Google),
+            #[cfg(feature = "ics")]
+            "ics" | "webcal" => Ok(Self::Ics),
             backend => bail!("Invalid backend {backend}"),
         }
     }
@@ -61,6 +115,16 @@ impl fmt::Display for Backend {
             Self::Caldav => write!(f, "caldav"),
             #[cfg(feature = "vdir")]
             Self::Vdir => write!(f, "vdir"),
+            #[cfg(feature = "file")]
+            Self::File => write!(f, "file"),
+            #[cfg(feature = "graph")]
+            Self::Graph => write!(f, "graph"),
+            #[cfg(feature = "jmap")]
+            Self::Jmap => write!(f, "jmap"),
+            #[cfg(feature = "google")]
+            Self::Google => write!(f, "google"),
+            #[cfg(feature = "ics")]
+            Self::Ics => write!(f, "ics"),
         }
     }
 }
@@ -4,13 +4,15 @@ use pimalaya_cli::printer::Printer;
 
 use crate::caldav::{
     client::CaldavClient, create::CaldavCalendarCreateCommand, delete::CaldavCalendarDeleteCommand,
-    discover::CaldavDiscoverCommand, list::CaldavCalendarListCommand,
+    discover::CaldavDiscoverCommand, freebusy::CaldavFreeBusyCommand, inbox::CaldavInboxCommand,
+    list::CaldavCalendarListCommand, outbox::CaldavOutboxCommand,
 };
 
 /// CalDAV CLI.
 ///
 /// Direct access to the CalDAV backend: discover endpoints, list,
-/// create, delete calendars without going through the shared API.
+/// create, delete calendars, query their free/busy time and use the
+/// scheduling inbox and outbox without going through the shared API.
 #[derive(Debug, Subcommand)]
 #[command(rename_all = "kebab-case")]
 pub enum CaldavCommand {
@@ -19,6 +21,12 @@ pub enum CaldavCommand {
     List(CaldavCalendarListCommand),
     Create(CaldavCalendarCreateCommand),
     Delete(CaldavCalendarDeleteCommand),
+    #[command(name = "freebusy")]
+    FreeBusy(CaldavFreeBusyCommand),
+    #[command(subcommand)]
+    Inbox(CaldavInboxCommand),
+    #[command(subcommand)]
+    Outbox(CaldavOutboxCommand),
 }
 
 impl CaldavCommand {
@@ -28,6 +36,9 @@ impl CaldavCommand {
             Self::List(cmd) => cmd.execute(printer, client),
             Self::Create(cmd) => cmd.execute(printer, client),
             Self::Delete(cmd) => cmd.execute(printer, client),
+            Self::FreeBusy(cmd) => cmd.execute(printer, client),
+            Self::Inbox(cmd) => cmd.execute(printer, client),
+            Self::Outbox(cmd) => cmd.execute(printer, client),
         }
     }
 }
@@ -5,14 +5,15 @@
 //! (server-uri, discover.host, or home-uri), opens the TCP/TLS
 //! connection via pimalaya-stream, then optionally walks the RFC 6764
 //! well-known + RFC 5397 principal + RFC 4791 calendar-home-set
-//! discovery chain.
+//! discovery chain. The RFC 6638 scheduling inbox and outbox of the
+//! principal are resolved lazily, on first use.
 
 use std::{
     ops::{Deref, DerefMut},
     path::PathBuf,
 };
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use io_http::{rfc6750::bearer::HttpAuthBearer, rfc7617::basic::HttpAuthBasic};
 use io_webdav::{client::WebdavClientStd, rfc4918::WebdavAuth};
 use pimalaya_config::toml::TomlConfig;
@@ -32,11 +33,145 @@ const DEFAULT_RESOLVER: &str = "tcp://1.1.1.1:53";
 pub struct CaldavClient {
     inner: WebdavClientStd,
     pub account: Account,
+    /// Scheduling collections, resolved on first use.
+    schedule: Option<ScheduleUrls>,
+}
+
+/// RFC 6638 scheduling collections of the current user principal,
+/// [`None`] on servers without CalDAV scheduling.
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleUrls {
+    /// `schedule-inbox-URL`, where incoming iTIP messages land.
+    pub inbox: Option<Url>,
+    /// `schedule-outbox-URL`, accepting free-busy POST requests.
+    pub outbox: Option<Url>,
+}
+
+/// Item of a collection listed by URL: an iTIP message of the
+/// scheduling inbox, or an item of a calendar.
+#[derive(Clone, Debug)]
+pub struct DavItem {
+    /// Last path segment of the item URL.
+    pub id: String,
+    pub etag: Option<String>,
+    pub contents: Vec<u8>,
+}
+
+/// Answer of one recipient to a scheduling outbox POST.
+#[derive(Clone, Debug)]
+pub struct ScheduleResponse {
+    pub recipient: String,
+    /// iTIP REQUEST-STATUS, e.g. `2.0;Success`.
+    pub request_status: String,
+    pub calendar_data: Option<String>,
 }
 
 impl CaldavClient {
     pub fn new(inner: WebdavClientStd, account: Account) -> Self {
-        Self { inner, account }
+        Self {
+            inner,
+            account,
+            schedule: None,
+        }
+    }
+
+    /// Resolves the `schedule-inbox-URL` and `schedule-outbox-URL`
+    /// properties of the current user principal.
+    pub fn schedule_urls(&mut self) -> Result<ScheduleUrls> {
+        if let Some(urls) = &self.schedule {
+            return Ok(urls.clone());
+        }
+
+        let principal = self.inner.current_user_principal()?;
+        let (inbox, outbox) = self.inner.principal_schedule_urls(&principal)?;
+        let urls = ScheduleUrls { inbox, outbox };
+
+        self.schedule = Some(urls.clone());
+        Ok(urls)
+    }
+
+    fn schedule_inbox(&mut self) -> Result<Url> {
+        match self.schedule_urls()?.inbox {
+            Some(inbox) => Ok(inbox),
+            None => bail!("The server does not advertise a scheduling inbox (RFC 6638)"),
+        }
+    }
+
+    fn schedule_outbox(&mut self) -> Result<Url> {
+        match self.schedule_urls()?.outbox {
+            Some(outbox) => Ok(outbox),
+            None => bail!("The server does not advertise a scheduling outbox (RFC 6638)"),
+        }
+    }
+
+    /// Lists the iTIP messages of the scheduling inbox.
+    pub fn list_inbox(&mut self) -> Result<Vec<DavItem>> {
+        let inbox = self.schedule_inbox()?;
+        self.list_items_at_url(&inbox)
+    }
+
+    /// Lists the items of the calendar `calendar_id` of the calendar
+    /// home set.
+    pub fn list_calendar_items(&mut self, calendar_id: &str) -> Result<Vec<DavItem>> {
+        let calendar = self
+            .inner
+            .calendar_home_set()?
+            .join(&format!("{calendar_id}/"))?;
+        self.list_items_at_url(&calendar)
+    }
+
+    fn list_items_at_url(&mut self, url: &Url) -> Result<Vec<DavItem>> {
+        let items = self.inner.list_items_at(url)?;
+
+        let items = items
+            .into_iter()
+            .map(|item| DavItem {
+                id: item
+                    .href
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned(),
+                etag: item.etag,
+                contents: item.contents,
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    /// Deletes the processed iTIP message `item` from the scheduling
+    /// inbox.
+    pub fn delete_inbox_item(&mut self, item: &DavItem) -> Result<()> {
+        let url = self.schedule_inbox()?.join(&item.id)?;
+        self.inner.delete_at(&url, item.etag.as_deref())?;
+        Ok(())
+    }
+
+    /// POSTs the iTIP `contents` to the scheduling outbox on behalf of
+    /// `originator`, for `recipients` (RFC 6638 §3.2.1).
+    pub fn post_outbox(
+        &mut self,
+        originator: &str,
+        recipients: &[String],
+        contents: Vec<u8>,
+    ) -> Result<Vec<ScheduleResponse>> {
+        let outbox = self.schedule_outbox()?;
+        let responses = self
+            .inner
+            .schedule_post(&outbox, originator, recipients, contents)?;
+
+        let responses = responses
+            .into_iter()
+            .map(|response| ScheduleResponse {
+                recipient: response.recipient,
+                request_status: response.request_status,
+                calendar_data: response.calendar_data,
+            })
+            .collect();
+
+        Ok(responses)
     }
 }
 
@@ -10,9 +10,12 @@ use crate::caldav::client::CaldavClient;
 /// Run the CalDAV discovery chain.
 ///
 /// Walks well-known caldav -> current-user-principal -> calendar
-/// home-set and prints each resolved URL.
+/// home-set and prints each resolved URL, plus the RFC 6638
+/// scheduling inbox and outbox of the principal when the server
+/// supports CalDAV scheduling.
 ///
-/// JSON output: `{"principal", "calendar_home_set"}`.
+/// JSON output: `{"principal", "calendar_home_set", "schedule_inbox",
+/// "schedule_outbox"}`.
 #[derive(Debug, Parser)]
 pub struct CaldavDiscoverCommand;
 
@@ -20,10 +23,13 @@ impl CaldavDiscoverCommand {
     pub fn execute(self, printer: &mut impl Printer, mut client: CaldavClient) -> Result<()> {
         let principal = client.current_user_principal()?;
         let home = client.calendar_home_set()?;
+        let schedule = client.schedule_urls()?;
 
         printer.out(DiscoveryReport {
             principal: principal.to_string(),
             calendar_home_set: home.to_string(),
+            schedule_inbox: schedule.inbox.map(|url| url.to_string()),
+            schedule_outbox: schedule.outbox.map(|url| url.to_string()),
         })
     }
 }
@@ -32,12 +38,20 @@ impl CaldavDiscoverCommand {
 pub struct DiscoveryReport {
     pub principal: String,
     pub calendar_home_set: String,
+    pub schedule_inbox: Option<String>,
+    pub schedule_outbox: Option<String>,
 }
 
 impl fmt::Display for DiscoveryReport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Principal: {}", self.principal)?;
         writeln!(f, "Calendar home-set: {}", self.calendar_home_set)?;
+
+        let unsupported = "(unsupported)";
+        let inbox = self.schedule_inbox.as_deref().unwrap_or(unsupported);
+        let outbox = self.schedule_outbox.as_deref().unwrap_or(unsupported);
+        writeln!(f, "Schedule inbox: {inbox}")?;
+        writeln!(f, "Schedule outbox: {outbox}")?;
         Ok(())
     }
 }
@@ -0,0 +1,272 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Days, NaiveDateTime, NaiveTime, Utc};
+use clap::Parser;
+use comfy_table::{Cell, Row, Table};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::{
+    caldav::client::CaldavClient,
+    shared::{
+        datetime::{parse_date, parse_duration},
+        timezone::Timezone,
+    },
+};
+
+/// Query the free/busy time of a CalDAV calendar.
+///
+/// Issues an RFC 4791 `free-busy-query` REPORT against the calendar
+/// for the days from `--from` (today by default) to `--to` (inclusive,
+/// a week later by default), and renders the VFREEBUSY periods the
+/// server returns, converted into the `--tz` / `event.timezone` zone.
+/// Only busy time is transferred, not the events themselves.
+///
+/// JSON output: `{"periods": [{"type", "start", "end"}]}`.
+#[derive(Debug, Parser)]
+pub struct CaldavFreeBusyCommand {
+    /// Calendar identifier (last path segment of the calendar URL).
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    #[command(flatten)]
+    pub range: FreeBusyRangeArg,
+}
+
+impl CaldavFreeBusyCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CaldavClient) -> Result<()> {
+        let timezone = client.account.timezone();
+        let (start, end) = self.range.resolve(timezone)?;
+        let stamp = |dt: DateTime<Utc>| dt.format("%Y%m%dT%H%M%SZ").to_string();
+
+        let ical = client.free_busy_query(&self.id, &stamp(start), &stamp(end))?;
+        let periods = periods(timezone, &String::from_utf8_lossy(&ical), None)?;
+
+        printer.out(FreeBusyTable {
+            preset: client.account.table_preset().to_string(),
+            periods,
+        })
+    }
+}
+
+/// `--from`/`--to` days of the free-busy lookups.
+#[derive(Debug, Parser)]
+pub struct FreeBusyRangeArg {
+    /// First day to query: `YYYY-MM-DD`, `today`, etc. Defaults to
+    /// today.
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<String>,
+
+    /// Last day to query (inclusive). Defaults to 6 days after
+    /// `--from`.
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<String>,
+}
+
+impl FreeBusyRangeArg {
+    /// Resolves the days into a UTC `[start, end)` range, from the
+    /// midnight of `--from` to the one following `--to` in `timezone`.
+    pub fn resolve(&self, timezone: Timezone) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let today = timezone.from_utc(Utc::now()).date();
+
+        let from = match &self.from {
+            Some(date) => parse_date(date, today)?,
+            None => today,
+        };
+
+        let to = match &self.to {
+            Some(date) => parse_date(date, today)?,
+            None => from + Days::new(6),
+        };
+
+        if to < from {
+            bail!("The --to date must not be before the --from one");
+        }
+
+        let start = timezone.to_utc(from.and_time(NaiveTime::MIN));
+        let end = timezone.to_utc(to.and_time(NaiveTime::MIN) + Days::new(1));
+
+        Ok((start, end))
+    }
+}
+
+/// Renders the periods of a VFREEBUSY response in `timezone`.
+pub fn periods(
+    timezone: Timezone,
+    ical: &str,
+    attendee: Option<&str>,
+) -> Result<Vec<FreeBusyPeriod>> {
+    let format = |dt: DateTime<Utc>| timezone.from_utc(dt).format("%Y-%m-%d %H:%M").to_string();
+
+    let periods = parse_periods(ical)?
+        .into_iter()
+        .map(|(fbtype, start, end)| FreeBusyPeriod {
+            attendee: attendee.map(ToOwned::to_owned),
+            fbtype,
+            start: format(start),
+            end: format(end),
+        })
+        .collect();
+
+    Ok(periods)
+}
+
+/// Extracts the FREEBUSY periods of a VFREEBUSY response, as
+/// `(FBTYPE, start, end)` in UTC, sorted by start. Periods are either
+/// `start/end` or `start/duration` (RFC 5545 §3.3.9); FBTYPE defaults
+/// to `BUSY`.
+pub fn parse_periods(ical: &str) -> Result<Vec<(String, DateTime<Utc>, DateTime<Utc>)>> {
+    let mut periods = Vec::new();
+
+    // Unfold continuation lines first.
+    let unfolded = ical
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    for line in unfolded.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let mut params = key.split(';');
+
+        if !params
+            .next()
+            .is_some_and(|name| name.eq_ignore_ascii_case("FREEBUSY"))
+        {
+            continue;
+        }
+
+        let fbtype = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.eq_ignore_ascii_case("FBTYPE"))
+            .map(|(_, fbtype)| fbtype.trim_matches('"').to_ascii_uppercase())
+            .unwrap_or_else(|| String::from("BUSY"));
+
+        for period in value.split(',') {
+            let Some((start, end)) = period.trim().split_once('/') else {
+                bail!("Invalid FREEBUSY period `{period}`");
+            };
+
+            let start = parse_utc(start)?;
+            let end = match end.trim_start_matches('+') {
+                duration if duration.starts_with(['P', 'p']) => start + parse_duration(duration)?,
+                end => parse_utc(end)?,
+            };
+
+            periods.push((fbtype.clone(), start, end));
+        }
+    }
+
+    periods.sort_by_key(|(_, start, _)| *start);
+    Ok(periods)
+}
+
+/// Parses a UTC DATE-TIME (`YYYYMMDDTHHMMSSZ`).
+fn parse_utc(s: &str) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+
+    match NaiveDateTime::parse_from_str(s.trim_end_matches('Z'), "%Y%m%dT%H%M%S") {
+        Ok(dt) => Ok(dt.and_utc()),
+        Err(_) => bail!("Invalid FREEBUSY date-time `{s}`"),
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FreeBusyPeriod {
+    /// Attendee the period belongs to, for outbox lookups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attendee: Option<String>,
+    #[serde(rename = "type")]
+    pub fbtype: String,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FreeBusyTable {
+    #[serde(skip)]
+    pub preset: String,
+    pub periods: Vec<FreeBusyPeriod>,
+}
+
+impl fmt::Display for FreeBusyTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+        let attendees = self.periods.iter().any(|p| p.attendee.is_some());
+
+        let mut header = Row::new();
+        if attendees {
+            header.add_cell(Cell::new("ATTENDEE"));
+        }
+        header
+            .add_cell(Cell::new("TYPE"))
+            .add_cell(Cell::new("START"))
+            .add_cell(Cell::new("END"));
+
+        table
+            .load_preset(&self.preset)
+            .set_header(header)
+            .add_rows(self.periods.iter().map(|p| {
+                let mut row = Row::new();
+                row.max_height(1);
+                if attendees {
+                    row.add_cell(Cell::new(p.attendee.as_deref().unwrap_or_default()));
+                }
+                row.add_cell(Cell::new(&p.fbtype))
+                    .add_cell(Cell::new(&p.start))
+                    .add_cell(Cell::new(&p.end));
+                row
+            }));
+
+        writeln!(f)?;
+        write!(f, "{table}")?;
+        writeln!(f)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_periods;
+
+    #[test]
+    fn parses_freebusy_periods() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                    BEGIN:VFREEBUSY\r\n\
+                    DTSTART:20260316T000000Z\r\n\
+                    FREEBUSY;FBTYPE=BUSY-TENTATIVE:20260316T140000Z/PT1H\r\n\
+                    FREEBUSY:20260316T090000Z/20260316T100000Z,20260316T\r\n \
+                    110000Z/20260316T113000Z\r\n\
+                    END:VFREEBUSY\r\n\
+                    END:VCALENDAR\r\n";
+
+        let periods: Vec<_> = parse_periods(ical)
+            .unwrap()
+            .into_iter()
+            .map(|(fbtype, start, end)| {
+                let format = "%H:%M";
+                (
+                    fbtype,
+                    start.format(format).to_string(),
+                    end.format(format).to_string(),
+                )
+            })
+            .collect();
+
+        let period = |fbtype: &str, start: &str, end: &str| {
+            (fbtype.to_owned(), start.to_owned(), end.to_owned())
+        };
+
+        assert_eq!(
+            periods,
+            [
+                period("BUSY", "09:00", "10:00"),
+                period("BUSY", "11:00", "11:30"),
+                period("BUSY-TENTATIVE", "14:00", "15:00"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{Parser, Subcommand};
+use comfy_table::{Cell, Row, Table};
+use pimalaya_cli::printer::{Message, Printer};
+use serde::Serialize;
+
+use crate::{
+    caldav::client::{CaldavClient, DavItem},
+    shared::{arg::CalendarIdArg, ical::unescape_text, itip, timezone::Timezone},
+};
+
+/// Use the CalDAV scheduling inbox (RFC 6638).
+///
+/// On servers implementing CalDAV scheduling, the iTIP messages sent
+/// to the user (invitations, replies, cancellations) are delivered to
+/// the scheduling inbox of their principal.
+#[derive(Debug, Subcommand)]
+#[command(rename_all = "kebab-case")]
+pub enum CaldavInboxCommand {
+    #[command(visible_alias = "ls")]
+    List(CaldavInboxListCommand),
+    Accept(CaldavInboxAcceptCommand),
+    Decline(CaldavInboxDeclineCommand),
+}
+
+impl CaldavInboxCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CaldavClient) -> Result<()> {
+        match self {
+            Self::List(cmd) => cmd.execute(printer, client),
+            Self::Accept(cmd) => cmd.execute(printer, client),
+            Self::Decline(cmd) => cmd.execute(printer, client),
+        }
+    }
+}
+
+/// List the messages of the scheduling inbox.
+///
+/// JSON output: `{"items": [{"id", "method", "organizer", "summary",
+/// "start"}]}`.
+#[derive(Debug, Parser)]
+pub struct CaldavInboxListCommand;
+
+impl CaldavInboxListCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CaldavClient) -> Result<()> {
+        let timezone = client.account.timezone();
+
+        let rows = client
+            .list_inbox()?
+            .into_iter()
+            .map(|item| {
+                let value = |name| itip::event_value(&item.contents, name);
+
+                InboxRow {
+                    method: itip::method(&item.contents),
+                    organizer: value("ORGANIZER").map(|o| itip::normalize_address(&o)),
+                    summary: value("SUMMARY").map(|s| unescape_text(&s)),
+                    start: value("DTSTART").map(|s| format_start(&s, timezone)),
+                    id: item.id,
+                }
+            })
+            .collect();
+
+        printer.out(InboxTable {
+            preset: client.account.table_preset().to_string(),
+            rows,
+        })
+    }
+}
+
+/// Accept an invitation of the scheduling inbox.
+///
+/// Stores the invited event into the calendar, replacing the copy of
+/// the same UID already there, with the PARTSTAT of the attendee
+/// (`--attendee`, falling back to the `event.email` config) set to
+/// ACCEPTED, then removes the message from the inbox.
+/// The server delivers the REPLY to the organizer itself.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct CaldavInboxAcceptCommand {
+    #[command(flatten)]
+    pub answer: InboxAnswerArgs,
+}
+
+impl CaldavInboxAcceptCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CaldavClient) -> Result<()> {
+        let id = self.answer.answer(client, "ACCEPTED")?;
+        printer.out(Message::new(format!(
+            "Invitation `{id}` successfully accepted"
+        )))
+    }
+}
+
+/// Decline an invitation of the scheduling inbox.
+///
+/// Stores the invited event into the calendar, replacing the copy of
+/// the same UID already there, with the PARTSTAT of the attendee
+/// (`--attendee`, falling back to the `event.email` config) set to
+/// DECLINED, so that the server delivers the REPLY to the organizer,
+/// then removes the message from the inbox.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct CaldavInboxDeclineCommand {
+    #[command(flatten)]
+    pub answer: InboxAnswerArgs,
+}
+
+impl CaldavInboxDeclineCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CaldavClient) -> Result<()> {
+        let id = self.answer.answer(client, "DECLINED")?;
+        printer.out(Message::new(format!(
+            "Invitation `{id}` successfully declined"
+        )))
+    }
+}
+
+/// Arguments shared by `caldav inbox accept` and `decline`.
+#[derive(Debug, Parser)]
+pub struct InboxAnswerArgs {
+    /// Inbox item identifier, as shown by `caldav inbox list`.
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Address of the answering attendee. Defaults to the
+    /// `event.email` config.
+    #[arg(long, value_name = "ADDRESS")]
+    pub attendee: Option<String>,
+}
+
+impl InboxAnswerArgs {
+    /// Stores the invitation with the attendee's `partstat`, then
+    /// deletes it from the inbox. Returns the inbox item id.
+    ///
+    /// The invitation replaces the calendar item holding its UID, when
+    /// any: an updated REQUEST, or one the server already filed into
+    /// the calendar.
+    fn answer(self, mut client: CaldavClient, partstat: &str) -> Result<String> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+
+        let Some(attendee) = self.attendee.or_else(|| client.account.email.clone()) else {
+            bail!("Cannot find the attendee to answer as; pass --attendee");
+        };
+
+        let items = client.list_inbox()?;
+        let Some(item) = items.into_iter().find(|item| item.id == self.id) else {
+            bail!("Cannot find item `{}` in the scheduling inbox", self.id);
+        };
+
+        match itip::method(&item.contents) {
+            Some(method) if method == "REQUEST" => (),
+            Some(method) => bail!("Inbox item `{}` is a {method}, not an invitation", self.id),
+            None => bail!("Inbox item `{}` is not an iTIP message", self.id),
+        }
+
+        let Some(contents) = itip::set_partstat(&item.contents, &attendee, partstat, None) else {
+            bail!(
+                "Cannot find attendee `{attendee}` in invitation `{}`",
+                self.id
+            );
+        };
+
+        let contents = itip::without_method(&contents);
+        let stored = client.list_calendar_items(&calendar_id)?;
+
+        if let Some(stored) = stored_item(&stored, &contents) {
+            let etag = stored.etag.as_deref();
+            client.update_item(&calendar_id, &stored.id, contents, etag)?;
+        } else {
+            client.create_item(&calendar_id, contents)?;
+        }
+
+        client.delete_inbox_item(&item)?;
+
+        Ok(self.id)
+    }
+}
+
+/// Finds the item of the calendar `items` holding the UID of the
+/// `invitation`.
+fn stored_item<'a>(items: &'a [DavItem], invitation: &[u8]) -> Option<&'a DavItem> {
+    let uid = itip::event_value(invitation, "UID")?;
+    let uid = uid.trim();
+
+    items.iter().find(|item| {
+        itip::event_value(&item.contents, "UID").is_some_and(|stored| stored.trim() == uid)
+    })
+}
+
+/// Renders a raw DTSTART value: UTC times in `timezone`, others as
+/// written.
+fn format_start(value: &str, timezone: Timezone) -> String {
+    let value = value.trim();
+
+    if let Some(utc) = value.strip_suffix('Z')
+        && let Ok(dt) = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+    {
+        return timezone
+            .from_utc(dt.and_utc())
+            .format("%Y-%m-%d %H:%M")
+            .to_string();
+    }
+
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return dt.format("%Y-%m-%d %H:%M").to_string();
+    }
+
+    match NaiveDate::parse_from_str(value, "%Y%m%d") {
+        Ok(date) => date.format("%Y-%m-%d").to_string(),
+        Err(_) => value.to_owned(),
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InboxRow {
+    pub id: String,
+    pub method: Option<String>,
+    pub organizer: Option<String>,
+    pub summary: Option<String>,
+    pub start: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InboxTable {
+    #[serde(skip)]
+    pub preset: String,
+    #[serde(rename = "items")]
+    pub rows: Vec<InboxRow>,
+}
+
+impl fmt::Display for InboxTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+        let cell = |value: &Option<String>| Cell::new(value.as_deref().unwrap_or_default());
+
+        table
+            .load_preset(&self.preset)
+            .set_header(Row::from([
+                Cell::new("ID"),
+                Cell::new("METHOD"),
+                Cell::new("ORGANIZER"),
+                Cell::new("SUMMARY"),
+                Cell::new("START"),
+            ]))
+            .add_rows(self.rows.iter().map(|r| {
+                let mut row = Row::new();
+                row.max_height(1)
+                    .add_cell(Cell::new(&r.id))
+                    .add_cell(cell(&r.method))
+                    .add_cell(cell(&r.organizer))
+                    .add_cell(cell(&r.summary))
+                    .add_cell(cell(&r.start));
+                row
+            }));
+
+        writeln!(f)?;
+        write!(f, "{table}")?;
+        writeln!(f)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_start, stored_item};
+    use crate::{caldav::client::DavItem, shared::timezone::Timezone};
+
+    fn item(id: &str, uid: &str) -> DavItem {
+        let contents = format!(
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:{}\r\n",
+                "DTSTART:20260316T090000Z\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            ),
+            uid,
+        );
+
+        DavItem {
+            id: id.to_owned(),
+            etag: Some(format!("\"{id}\"")),
+            contents: contents.into_bytes(),
+        }
+    }
+
+    #[test]
+    fn find_stored_invitation() {
+        let stored = [
+            item("a.ics", "standup@ex.org"),
+            item("b.ics", "retro@ex.org"),
+        ];
+
+        let invitation = item("inbox-1.ics", "retro@ex.org").contents;
+        let found = stored_item(&stored, &invitation).unwrap();
+        assert_eq!(found.id, "b.ics");
+        assert_eq!(found.etag.as_deref(), Some("\"b.ics\""));
+
+        let invitation = item("inbox-2.ics", "new@ex.org").contents;
+        assert!(stored_item(&stored, &invitation).is_none());
+    }
+
+    #[test]
+    fn format_inbox_start() {
+        let paris = Timezone::Tz(chrono_tz::Europe::Paris);
+
+        assert_eq!(format_start("20260316T080000Z", paris), "2026-03-16 09:00");
+        assert_eq!(format_start("20260316T080000", paris), "2026-03-16 08:00");
+        assert_eq!(format_start("20260316", paris), "2026-03-16");
+        assert_eq!(format_start("soon", paris), "soon");
+    }
+}
@@ -3,4 +3,7 @@ pub mod client;
 pub mod create;
 pub mod delete;
 pub mod discover;
+pub mod freebusy;
+pub mod inbox;
 pub mod list;
+pub mod outbox;
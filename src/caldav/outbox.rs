@@ -0,0 +1,98 @@
+use anyhow::{Result, bail};
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use log::warn;
+use pimalaya_cli::printer::Printer;
+
+use crate::{
+    caldav::{
+        client::CaldavClient,
+        freebusy::{FreeBusyRangeArg, FreeBusyTable, periods},
+    },
+    shared::itip,
+};
+
+/// Use the CalDAV scheduling outbox (RFC 6638).
+#[derive(Debug, Subcommand)]
+#[command(rename_all = "kebab-case")]
+pub enum CaldavOutboxCommand {
+    #[command(name = "freebusy")]
+    FreeBusy(CaldavOutboxFreeBusyCommand),
+}
+
+impl CaldavOutboxCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CaldavClient) -> Result<()> {
+        match self {
+            Self::FreeBusy(cmd) => cmd.execute(printer, client),
+        }
+    }
+}
+
+/// Look up the free/busy time of calendar users.
+///
+/// POSTs a METHOD:REQUEST VFREEBUSY to the scheduling outbox of the
+/// principal, for the days from `--from` (today by default) to `--to`
+/// (inclusive, a week later by default). The server answers for each
+/// attendee it knows, usually the users of the same server; their
+/// busy periods are rendered in the `--tz` / `event.timezone` zone.
+/// Attendees the server cannot answer for are reported as warnings.
+///
+/// JSON output: `{"periods": [{"attendee", "type", "start", "end"}]}`.
+#[derive(Debug, Parser)]
+pub struct CaldavOutboxFreeBusyCommand {
+    /// Addresses of the calendar users to look up.
+    #[arg(value_name = "ADDRESS", required = true)]
+    pub attendees: Vec<String>,
+
+    #[command(flatten)]
+    pub range: FreeBusyRangeArg,
+
+    /// Address of the requesting user. Defaults to the `event.email`
+    /// config.
+    #[arg(long, value_name = "ADDRESS")]
+    pub organizer: Option<String>,
+}
+
+impl CaldavOutboxFreeBusyCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CaldavClient) -> Result<()> {
+        let Some(organizer) = self.organizer.or_else(|| client.account.email.clone()) else {
+            bail!("Cannot find the address of the requesting user; pass --organizer");
+        };
+
+        let timezone = client.account.timezone();
+        let (start, end) = self.range.resolve(timezone)?;
+
+        let organizer = itip::normalize_address(&organizer);
+        let attendees: Vec<String> = self
+            .attendees
+            .iter()
+            .map(|attendee| itip::normalize_address(attendee))
+            .collect();
+
+        let request = itip::freebusy_request(&organizer, &attendees, start, end, Utc::now());
+        let originator = format!("mailto:{organizer}");
+        let recipients: Vec<String> = attendees.iter().map(|a| format!("mailto:{a}")).collect();
+        let responses = client.post_outbox(&originator, &recipients, request)?;
+
+        let mut all = Vec::new();
+
+        for response in responses {
+            let attendee = itip::normalize_address(&response.recipient);
+
+            match response.calendar_data {
+                Some(ical) if response.request_status.starts_with("2.") => {
+                    all.extend(periods(timezone, &ical, Some(&attendee))?);
+                }
+                _ => warn!(
+                    "cannot look up the free/busy time of `{attendee}`: {}",
+                    response.request_status
+                ),
+            }
+        }
+
+        printer.out(FreeBusyTable {
+            preset: client.account.table_preset().to_string(),
+            periods: all,
+        })
+    }
+}
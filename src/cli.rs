@@ -15,6 +15,8 @@ use pimalaya_config::toml::TomlConfig;
 
 #[cfg(feature = "caldav")]
 use crate::caldav::{cli::CaldavCommand, client::build_caldav_client};
+#[cfg(all(feature = "caldav", feature = "vdir"))]
+use crate::sync::cli::SyncCommand;
 #[cfg(feature = "vdir")]
 use crate::vdir::{cli::VdirCommand, client::build_vdir_client};
 use crate::{
@@ -23,8 +25,10 @@ use crate::{
     config::Config,
     shared::{
         calendars::cli::CalendarCommand, client::CalendarClient, events::cli::EventCommand,
-        items::cli::ItemCommand,
+        items::cli::ItemCommand, journals::cli::JournalCommand, timezone::Timezone,
+        todos::cli::TodoCommand,
     },
+    watch::cli::WatchCommand,
     wizard,
 };
 
@@ -54,16 +58,25 @@ pub struct CalendulaCli {
     /// Force a specific backend for cross-protocol commands.
     ///
     /// Only consumed by the shared commands (`calendar`, `event`,
-    /// `item`); the protocol-specific subcommands (`vdir`, `caldav`)
-    /// ignore it and always use their own backend.
+    /// `todo`, `journal`, `item`, `watch`); the protocol-specific
+    /// subcommands (`vdir`, `caldav`) ignore it and always use their
+    /// own backend.
     ///
-    /// Possible values: `auto` (default), `vdir`, `caldav`. With
-    /// `auto`, the shared command picks the first configured backend
-    /// it supports (vdir wins over caldav); with an explicit value, it
-    /// uses only that backend (and bails if the account has no
-    /// matching config block).
+    /// Possible values: `auto` (default), `vdir`, `caldav`, `file`,
+    /// `graph`, `jmap`, `google`, `ics` (alias `webcal`). With `auto`,
+    /// the shared command picks the first configured backend it
+    /// supports (vdir, caldav, file, graph, jmap, google, then ics);
+    /// with an explicit value, it uses only that backend (and bails if
+    /// the account has no matching config block).
     #[arg(short, long, global = true, default_value_t)]
     pub backend: Backend,
+    /// Render event times in the given zone.
+    ///
+    /// Either `local` or an IANA name (`Europe/Paris`, `UTC`). Wins
+    /// over the `event.timezone` config, which defaults to the local
+    /// zone.
+    #[arg(long = "tz", global = true, value_name = "TZ")]
+    pub timezone: Option<Timezone>,
     #[command(flatten)]
     pub json: JsonFlag,
     #[command(flatten)]
@@ -78,8 +91,14 @@ pub enum CalendulaCommand {
     Calendar(CalendarCommand),
     #[command(subcommand, alias = "events")]
     Event(EventCommand),
+    #[command(subcommand, alias = "todos")]
+    Todo(TodoCommand),
+    #[command(subcommand, alias = "journals")]
+    Journal(JournalCommand),
     #[command(subcommand, alias = "items")]
     Item(ItemCommand),
+    #[command(visible_alias = "remind")]
+    Watch(WatchCommand),
 
     // --- Protocol-specific APIs
     //
@@ -89,6 +108,8 @@ pub enum CalendulaCommand {
     #[cfg(feature = "vdir")]
     #[command(subcommand)]
     Vdir(VdirCommand),
+    #[cfg(all(feature = "caldav", feature = "vdir"))]
+    Sync(SyncCommand),
 
     // --- Meta
     //
@@ -116,41 +137,79 @@ impl CalendulaCommand {
         config_paths: &[PathBuf],
         account_name: Option<&str>,
         backend: Backend,
+        timezone: Option<Timezone>,
     ) -> Result<()> {
-        let configs = || {
+        // Accounts selected by `--account`: the default one when
+        // omitted, otherwise every account expanded from `all`, a comma
+        // list or a group.
+        let accounts = || {
             let mut config = load_or_wizard(config_paths)?;
 
-            let Some((_, account_config)) = config.take_account(account_name)? else {
-                bail!("Cannot find default account; use --account or set account.default = true")
+            let Some(selector) = account_name else {
+                let Some((name, account_config)) = config.take_account(None)? else {
+                    bail!(
+                        "Cannot find default account; use --account or set account.default = true"
+                    )
+                };
+
+                return Ok(vec![(config, name, account_config)]);
             };
 
-            Ok((config, account_config))
+            let names = config.expand_accounts(selector)?;
+            let mut accounts = Vec::new();
+
+            for name in names {
+                let mut config = config.clone();
+                if let Some((name, account_config)) = config.take_named_account(&name) {
+                    accounts.push((config, name, account_config));
+                }
+            }
+
+            Ok(accounts)
+        };
+
+        // Shared client of one selected account, `--tz` winning over
+        // the `event.timezone` config.
+        let build = |(config, name, account_config): (Config, String, _)| -> Result<_> {
+            let mut client = CalendarClient::new(config, &name, account_config, backend)?;
+            client.account.timezone = timezone.or(client.account.timezone);
+            Ok(client)
+        };
+
+        let clients = || {
+            accounts()?
+                .into_iter()
+                .map(&build)
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let client = || {
+            let mut accounts: Vec<_> = accounts()?;
+
+            if accounts.len() != 1 {
+                bail!("This command operates on a single account; pass one --account");
+            }
+
+            build(accounts.remove(0))
         };
 
         match self {
             // --- Shared API
             //
-            Self::Calendar(cmd) => {
-                let (config, account_config) = configs()?;
-                let client = CalendarClient::new(config, account_config, backend)?;
-                cmd.execute(printer, client)
-            }
-            Self::Event(cmd) => {
-                let (config, account_config) = configs()?;
-                let client = CalendarClient::new(config, account_config, backend)?;
-                cmd.execute(printer, client)
-            }
-            Self::Item(cmd) => {
-                let (config, account_config) = configs()?;
-                let client = CalendarClient::new(config, account_config, backend)?;
-                cmd.execute(printer, client)
-            }
+            Self::Calendar(cmd) => cmd.execute(printer, client()?),
+            Self::Event(cmd) if cmd.spans_accounts() => cmd.execute_many(printer, clients()?),
+            Self::Event(cmd) => cmd.execute(printer, client()?),
+            Self::Todo(cmd) => cmd.execute(printer, client()?),
+            Self::Journal(cmd) => cmd.execute(printer, client()?),
+            Self::Item(cmd) => cmd.execute(printer, client()?),
+            Self::Watch(cmd) => cmd.execute(printer, clients()?),
 
             // --- Protocol-specific APIs
             //
             #[cfg(feature = "caldav")]
             Self::Caldav(cmd) => {
-                let client = build_caldav_client(config_paths, account_name)?;
+                let mut client = build_caldav_client(config_paths, account_name)?;
+                client.account.timezone = timezone.or(client.account.timezone);
                 cmd.execute(printer, client)
             }
             #[cfg(feature = "vdir")]
@@ -158,6 +217,8 @@ impl CalendulaCommand {
                 let client = build_vdir_client(config_paths, account_name)?;
                 cmd.execute(printer, client)
             }
+            #[cfg(all(feature = "caldav", feature = "vdir"))]
+            Self::Sync(cmd) => cmd.execute(printer, config_paths, account_name),
 
             // --- Meta
             //
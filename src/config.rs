@@ -1,21 +1,32 @@
 use std::{collections::HashMap, fs, path::Path, path::PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use comfy_table::ContentArrangement;
 use crossterm::style::Color;
+#[cfg(any(
+    feature = "caldav",
+    feature = "graph",
+    feature = "google",
+    feature = "jmap"
+))]
+use pimalaya_config::secret::Secret;
 use pimalaya_config::toml::TomlConfig;
-#[cfg(feature = "caldav")]
-use pimalaya_config::{secret::Secret, toml::shell_expanded_string};
+#[cfg(any(feature = "caldav", feature = "ics", feature = "jmap"))]
+use pimalaya_config::toml::shell_expanded_string;
 use pimalaya_stream::tls::{Rustls, RustlsCrypto, Tls, TlsProvider};
 use serde::{Deserialize, Serialize};
 
+use crate::shared::timezone::Timezone;
+#[cfg(all(feature = "caldav", feature = "vdir"))]
+use crate::sync::conflict::ConflictPolicy;
+
 /// Global configuration.
 ///
 /// Represents the whole TOML user's configuration file.
 /// `deny_unknown_fields` is intentionally omitted so future TUI fields
 /// can coexist; today only `[accounts.*]` plus the global rendering
 /// sections are consumed.
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
     pub downloads_dir: Option<PathBuf>,
@@ -26,10 +37,20 @@ pub struct Config {
     #[serde(default)]
     pub event: EventConfig,
     #[serde(default)]
+    pub todo: TodoConfig,
+    #[serde(default)]
+    pub journal: JournalConfig,
+    #[serde(default)]
     pub item: ItemConfig,
+    #[serde(default)]
+    pub watch: WatchConfig,
     /// `account list` rendering options (global only).
     #[serde(default)]
     pub account: AccountListingConfig,
+    /// Named sets of accounts the read-only `event` commands can fan
+    /// out over, e.g. `--account work`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub account_groups: HashMap<String, Vec<String>>,
     pub accounts: HashMap<String, AccountConfig>,
 }
 
@@ -55,6 +76,47 @@ impl TomlConfig for Config {
 }
 
 impl Config {
+    /// Expands an `--account` selector into account names: `all` for
+    /// every account (sorted by name), otherwise a comma-separated list
+    /// of account and group names, deduplicated in order. Account names
+    /// win over group names.
+    pub fn expand_accounts(&self, selector: &str) -> Result<Vec<String>> {
+        if selector == "all" {
+            let mut names: Vec<String> = self.accounts.keys().cloned().collect();
+
+            if names.is_empty() {
+                bail!("Cannot find any account");
+            }
+
+            names.sort();
+            return Ok(names);
+        }
+
+        let mut names: Vec<String> = Vec::new();
+
+        for name in selector.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let members = if self.accounts.contains_key(name) {
+                vec![name.to_owned()]
+            } else if let Some(members) = self.account_groups.get(name) {
+                members.clone()
+            } else {
+                bail!("Cannot find account or account group `{name}`");
+            };
+
+            for member in members {
+                if !self.accounts.contains_key(&member) {
+                    bail!("Cannot find account `{member}` of account group `{name}`");
+                }
+
+                if !names.contains(&member) {
+                    names.push(member);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+
     /// Serializes `self` to TOML and writes it to `path`, creating
     /// any missing parent directories. Used by the wizard to persist
     /// a freshly-built configuration.
@@ -89,12 +151,31 @@ pub struct AccountConfig {
     #[serde(default)]
     pub event: EventConfig,
     #[serde(default)]
+    pub todo: TodoConfig,
+    #[serde(default)]
+    pub journal: JournalConfig,
+    #[serde(default)]
     pub item: ItemConfig,
+    #[serde(default)]
+    pub watch: WatchConfig,
 
     #[cfg(feature = "vdir")]
     pub vdir: Option<VdirConfig>,
     #[cfg(feature = "caldav")]
     pub caldav: Option<CaldavConfig>,
+    #[cfg(feature = "file")]
+    pub file: Option<FileConfig>,
+    #[cfg(feature = "graph")]
+    pub graph: Option<GraphConfig>,
+    #[cfg(feature = "jmap")]
+    pub jmap: Option<JmapConfig>,
+    #[cfg(feature = "google")]
+    pub google: Option<GoogleConfig>,
+    #[cfg(feature = "ics")]
+    pub ics: Option<IcsConfig>,
+
+    #[cfg(all(feature = "caldav", feature = "vdir"))]
+    pub sync: Option<SyncConfig>,
 }
 
 /// Calendar-level options.
@@ -129,8 +210,39 @@ pub struct CalendarListTableConfig {
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct EventConfig {
+    /// Zone event times are rendered in, overridden by the global
+    /// `--tz` flag. Either `local` (default) or an IANA name.
+    pub timezone: Option<Timezone>,
+    /// Calendar user address of the account owner, used by the iTIP
+    /// commands (`event invite`, `event reply`).
+    pub email: Option<String>,
     #[serde(default)]
     pub list: EventListConfig,
+    #[serde(default)]
+    pub agenda: EventAgendaConfig,
+}
+
+/// `watch` alarm daemon options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Default `--notify-cmd` value: command run through the shell for
+    /// each fired alarm, with `{summary}`-like placeholders.
+    pub notify_cmd: Option<String>,
+    /// Default `--interval` value, in seconds.
+    pub interval: Option<u64>,
+}
+
+/// `event agenda --day/--week-grid` time grid options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct EventAgendaConfig {
+    /// Default `--start-hour` value.
+    pub start_hour: Option<u32>,
+    /// Default `--end-hour` value.
+    pub end_hour: Option<u32>,
+    /// Default `--slot` value, in minutes.
+    pub slot: Option<u32>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -146,11 +258,67 @@ pub struct EventListConfig {
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct EventListTableConfig {
     pub id_color: Option<Color>,
+    pub account_color: Option<Color>,
+    pub calendar_color: Option<Color>,
+    pub recurrence_id_color: Option<Color>,
     pub summary_color: Option<Color>,
     pub start_color: Option<Color>,
     pub end_color: Option<Color>,
 }
 
+/// Todo-level rendering options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TodoConfig {
+    #[serde(default)]
+    pub list: TodoListConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TodoListConfig {
+    /// Default `-s/--page-size` value for `todo list`.
+    pub page_size: Option<u32>,
+    #[serde(default)]
+    pub table: TodoListTableConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TodoListTableConfig {
+    pub id_color: Option<Color>,
+    pub summary_color: Option<Color>,
+    pub status_color: Option<Color>,
+    pub due_color: Option<Color>,
+    pub priority_color: Option<Color>,
+    pub percent_color: Option<Color>,
+}
+
+/// Journal-level rendering options.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JournalConfig {
+    #[serde(default)]
+    pub list: JournalListConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JournalListConfig {
+    /// Default `-s/--page-size` value for `journals list`.
+    pub page_size: Option<u32>,
+    #[serde(default)]
+    pub table: JournalListTableConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JournalListTableConfig {
+    pub id_color: Option<Color>,
+    pub date_color: Option<Color>,
+    pub summary_color: Option<Color>,
+}
+
 /// Item-level rendering options.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -262,6 +430,11 @@ pub struct CaldavConfig {
 
     /// Authentication configuration.
     pub auth: CaldavAuthConfig,
+
+    /// Cache items under the XDG cache directory, refreshed through
+    /// the calendar CTag and sync-collection (RFC 6578). Defaults to
+    /// `true`.
+    pub cache: Option<bool>,
 }
 
 /// CalDAV authentication configuration.
@@ -280,6 +453,180 @@ pub enum CaldavAuthConfig {
     },
 }
 
+/// Single-file backend configuration.
+#[cfg(feature = "file")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FileConfig {
+    /// Calendars, each stored in its own `.ics` file.
+    pub calendars: Vec<FileCalendarConfig>,
+}
+
+/// One calendar of the file backend.
+#[cfg(feature = "file")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FileCalendarConfig {
+    /// Calendar id the file is exposed as.
+    pub id: String,
+
+    /// Filesystem path of the `.ics` file. Created on first write.
+    pub path: PathBuf,
+
+    /// Display name, used when the file has no `X-WR-CALNAME`.
+    /// Defaults to the calendar id.
+    pub name: Option<String>,
+
+    /// Description, used when the file has no `X-WR-CALDESC`.
+    pub description: Option<String>,
+
+    /// Hex color (`#RRGGBB`), used when the file has no
+    /// `X-APPLE-CALENDAR-COLOR`.
+    pub color: Option<String>,
+}
+
+/// Microsoft Graph (Outlook, Microsoft 365) backend configuration.
+#[cfg(feature = "graph")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct GraphConfig {
+    /// Base URL of the Graph API. Defaults to
+    /// `https://graph.microsoft.com/v1.0`.
+    pub api_url: Option<url::Url>,
+
+    /// TLS configuration.
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// Authentication configuration.
+    pub auth: GraphAuthConfig,
+}
+
+/// Microsoft Graph authentication configuration.
+#[cfg(feature = "graph")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum GraphAuthConfig {
+    /// OAuth 2.0 access token granted the `Calendars.ReadWrite`
+    /// scope.
+    Bearer { token: Secret },
+}
+
+/// JMAP for Calendars backend configuration.
+#[cfg(feature = "jmap")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JmapConfig {
+    /// URL of the JMAP session resource (RFC 8620), e.g.
+    /// `https://api.fastmail.com/jmap/session`. A URL without path is
+    /// completed with `/.well-known/jmap`.
+    pub server: url::Url,
+
+    /// TLS configuration.
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// Authentication configuration.
+    pub auth: JmapAuthConfig,
+}
+
+/// JMAP authentication configuration.
+#[cfg(feature = "jmap")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum JmapAuthConfig {
+    Basic {
+        #[serde(deserialize_with = "shell_expanded_string")]
+        username: String,
+        password: Secret,
+    },
+    Bearer {
+        token: Secret,
+    },
+}
+
+/// Google Calendar backend configuration.
+#[cfg(feature = "google")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct GoogleConfig {
+    /// Base URL of the Calendar API. Defaults to
+    /// `https://www.googleapis.com/calendar/v3`.
+    pub api_url: Option<url::Url>,
+
+    /// TLS configuration.
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// Authentication configuration.
+    pub auth: GoogleAuthConfig,
+}
+
+/// Google Calendar authentication configuration.
+#[cfg(feature = "google")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum GoogleAuthConfig {
+    /// OAuth 2.0 access token granted the
+    /// `https://www.googleapis.com/auth/calendar` scope.
+    Bearer { token: Secret },
+}
+
+/// Read-only iCalendar subscription (ics/webcal) backend
+/// configuration.
+#[cfg(feature = "ics")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IcsConfig {
+    /// Subscribed feeds, each exposed as one read-only calendar.
+    pub feeds: Vec<IcsFeedConfig>,
+
+    /// TLS configuration, shared by every `https` feed.
+    #[serde(default)]
+    pub tls: TlsConfig,
+}
+
+/// One iCalendar feed of the ics backend.
+#[cfg(feature = "ics")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IcsFeedConfig {
+    /// Calendar id the feed is exposed as.
+    pub id: String,
+
+    /// `http(s)://` or `webcal(s)://` URL of the feed, or path of a
+    /// local `.ics` file (`file://` URLs are accepted too).
+    #[serde(deserialize_with = "shell_expanded_string")]
+    pub source: String,
+
+    /// Display name. Defaults to the calendar id.
+    pub name: Option<String>,
+
+    /// Free-form description.
+    pub description: Option<String>,
+
+    /// Hex color (`#RRGGBB`).
+    pub color: Option<String>,
+}
+
+/// `calendula sync` configuration. Per-account only.
+#[cfg(all(feature = "caldav", feature = "vdir"))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Default conflict policy, overridden by `--conflict`. Defaults
+    /// to `ask`.
+    pub conflict: Option<ConflictPolicy>,
+
+    /// Calendars to synchronize, overridden by `-k`. Defaults to every
+    /// CalDAV calendar.
+    pub calendars: Option<Vec<String>>,
+
+    /// Directory of the status databases. Defaults to the XDG data
+    /// directory (`~/.local/share/calendula/sync`).
+    pub status_dir: Option<PathBuf>,
+}
+
 /// SSL/TLS configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
@@ -328,3 +675,38 @@ impl From<TlsConfig> for Tls {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountConfig, Config};
+
+    fn config() -> Config {
+        let mut config = Config::default();
+
+        for name in ["posteo", "fastmail", "local"] {
+            config
+                .accounts
+                .insert(name.to_owned(), AccountConfig::default());
+        }
+
+        let work = vec!["fastmail".to_owned(), "local".to_owned()];
+        config.account_groups.insert("work".to_owned(), work);
+        config
+    }
+
+    #[test]
+    fn expands_account_selectors() {
+        let config = config();
+
+        assert_eq!(
+            config.expand_accounts("all").unwrap(),
+            ["fastmail", "local", "posteo"]
+        );
+        assert_eq!(config.expand_accounts("posteo").unwrap(), ["posteo"]);
+        assert_eq!(
+            config.expand_accounts("local, work,posteo").unwrap(),
+            ["local", "fastmail", "posteo"]
+        );
+        assert!(config.expand_accounts("posteo,unknown").is_err());
+    }
+}
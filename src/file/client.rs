@@ -0,0 +1,462 @@
+//! Client of the single-file backend.
+//!
+//! Serves the calendars and items of the configured `.ics` files to
+//! [`crate::shared::client::CalendarClient`]. Items are the components
+//! of a file split per UID, their id being the UID (derived from the
+//! contents of a component without one) and their ETag a hash of
+//! their contents. The calendar name, description and color
+//! live in the `X-WR-CALNAME`, `X-WR-CALDESC` and
+//! `X-APPLE-CALENDAR-COLOR` properties of the file, as written by
+//! Thunderbird and most exporters.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use io_calendar::{
+    calendar::{Calendar, CalendarDiff},
+    item::CalendarItem,
+};
+
+use crate::{
+    config::{FileCalendarConfig, FileConfig},
+    file::lock::FileLock,
+    shared::{
+        cache::write_user_file,
+        client::Conflict,
+        ical::{
+            IcalObject, IcalWriter, calendar_properties, content_hash, escape_text, merge_objects,
+            split_objects, split_unquoted, unescape_text, unfold,
+        },
+    },
+};
+
+const NAME: &str = "X-WR-CALNAME";
+const DESCRIPTION: &str = "X-WR-CALDESC";
+const COLOR: &str = "X-APPLE-CALENDAR-COLOR";
+
+pub struct FileClient {
+    calendars: Vec<FileCalendarConfig>,
+}
+
+impl FileClient {
+    /// Builds the client from `config`, shell-expanding the paths of
+    /// the calendar files.
+    pub fn new(config: FileConfig) -> Self {
+        let calendars = config
+            .calendars
+            .into_iter()
+            .map(|mut calendar| {
+                if let Ok(path) = shellexpand::full(&calendar.path.to_string_lossy()) {
+                    calendar.path = PathBuf::from(path.into_owned());
+                }
+                calendar
+            })
+            .collect();
+
+        Self { calendars }
+    }
+
+    /// Lists one calendar per configured file. The CTag is a hash of
+    /// the whole file.
+    pub fn list_calendars(&self) -> Result<Vec<Calendar>> {
+        self.calendars
+            .iter()
+            .map(|config| {
+                let file = CalendarFile::read(&config.path)?;
+
+                Ok(Calendar {
+                    id: config.id.clone(),
+                    name: file
+                        .property(NAME)
+                        .or_else(|| config.name.clone())
+                        .unwrap_or_else(|| config.id.clone()),
+                    description: file
+                        .property(DESCRIPTION)
+                        .or_else(|| config.description.clone()),
+                    color: file.property(COLOR).or_else(|| config.color.clone()),
+                    ctag: file.ctag,
+                })
+            })
+            .collect()
+    }
+
+    /// Creates the file of the declared calendar `calendar_id`.
+    pub fn create_calendar(
+        &self,
+        calendar_id: &str,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<()> {
+        self.modify(calendar_id, |file| {
+            if file.ctag.is_some() {
+                bail!("Calendar `{calendar_id}` already exists");
+            }
+
+            file.set_property(NAME, Some(&escape_text(name)));
+            file.set_property(DESCRIPTION, description.map(escape_text).as_deref());
+            file.set_property(COLOR, color);
+            Ok(())
+        })
+    }
+
+    pub fn update_calendar(&self, calendar_id: &str, patch: CalendarDiff) -> Result<()> {
+        self.modify(calendar_id, |file| {
+            if let Some(name) = patch.name {
+                file.set_property(NAME, Some(&escape_text(&name)));
+            }
+            if let Some(description) = patch.description {
+                file.set_property(DESCRIPTION, description.map(|d| escape_text(&d)).as_deref());
+            }
+            if let Some(color) = patch.color {
+                file.set_property(COLOR, color.as_deref());
+            }
+            Ok(())
+        })
+    }
+
+    /// Deletes the file of `calendar_id`. The calendar stays declared
+    /// in the config, empty.
+    pub fn delete_calendar(&self, calendar_id: &str) -> Result<()> {
+        let path = &self.calendar(calendar_id)?.path;
+        let _lock = FileLock::acquire(path)?;
+
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                bail!("Calendar `{calendar_id}` does not exist")
+            }
+            Err(err) => {
+                Err(err).with_context(|| format!("Delete calendar file `{}` error", path.display()))
+            }
+        }
+    }
+
+    pub fn list_items(&self, calendar_id: &str) -> Result<Vec<CalendarItem>> {
+        let file = CalendarFile::read(&self.calendar(calendar_id)?.path)?;
+
+        let items = file
+            .objects
+            .into_iter()
+            .map(|object| CalendarItem {
+                id: object.uid,
+                calendar_id: calendar_id.to_owned(),
+                etag: Some(content_hash(&object.contents)),
+                contents: object.contents,
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    pub fn get_item(&self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        self.list_items(calendar_id)?
+            .into_iter()
+            .find(|item| item.id == item_id)
+            .ok_or_else(|| anyhow!("Cannot find item `{item_id}` in calendar `{calendar_id}`"))
+    }
+
+    /// Adds an item to `calendar_id`, returning its id (its UID).
+    pub fn create_item(&self, calendar_id: &str, contents: Vec<u8>) -> Result<String> {
+        self.modify(calendar_id, |file| file.insert(&contents))
+    }
+
+    pub fn update_item(
+        &self,
+        calendar_id: &str,
+        item_id: &str,
+        contents: Vec<u8>,
+        etag: Option<&str>,
+    ) -> Result<()> {
+        self.modify(calendar_id, |file| file.replace(item_id, &contents, etag))
+    }
+
+    pub fn delete_item(&self, calendar_id: &str, item_id: &str) -> Result<()> {
+        self.modify(calendar_id, |file| file.remove(item_id))
+    }
+
+    fn calendar(&self, calendar_id: &str) -> Result<&FileCalendarConfig> {
+        self.calendars
+            .iter()
+            .find(|calendar| calendar.id == calendar_id)
+            .ok_or_else(|| anyhow!("Calendar `{calendar_id}` is not declared in `file.calendars`"))
+    }
+
+    /// Reads the file of `calendar_id`, applies `f` then rewrites the
+    /// file, all under its lock.
+    fn modify<T>(
+        &self,
+        calendar_id: &str,
+        f: impl FnOnce(&mut CalendarFile) -> Result<T>,
+    ) -> Result<T> {
+        let path = &self.calendar(calendar_id)?.path;
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Create calendar directory `{}` error", dir.display()))?;
+        }
+
+        let _lock = FileLock::acquire(path)?;
+        let mut file = CalendarFile::read(path)?;
+        let out = f(&mut file)?;
+        file.write(path)?;
+
+        Ok(out)
+    }
+}
+
+/// Parsed calendar file.
+#[derive(Debug, Default)]
+struct CalendarFile {
+    /// Hash of the whole file, [`None`] when it does not exist.
+    ctag: Option<String>,
+    /// Calendar properties, as content lines.
+    properties: Vec<String>,
+    /// Components of the file, split per UID.
+    objects: Vec<IcalObject>,
+}
+
+impl CalendarFile {
+    /// Reads the file at `path`, empty when missing.
+    fn read(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Read calendar file `{}` error", path.display()))
+            }
+        }
+    }
+
+    fn parse(contents: &[u8]) -> Self {
+        Self {
+            ctag: Some(content_hash(contents)),
+            properties: calendar_properties(contents),
+            objects: split_objects(contents),
+        }
+    }
+
+    /// Writes the file at `path` through a temporary file renamed
+    /// over it, following symlinks and keeping its permissions.
+    fn write(&self, path: &Path) -> Result<()> {
+        write_user_file(path, &self.to_bytes())
+            .with_context(|| format!("Write calendar file `{}` error", path.display()))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let objects = self.objects.iter().map(|object| object.contents.as_slice());
+        merge_objects(&self.properties, objects)
+    }
+
+    /// Returns the unescaped value of the calendar property `name`.
+    fn property(&self, name: &str) -> Option<String> {
+        self.properties.iter().find_map(|line| {
+            let line = unfold(line);
+            let (head, value) = split_unquoted(&line, ':').unwrap_or((&line, ""));
+            let prop = head.split(';').next().unwrap_or_default().trim();
+            prop.eq_ignore_ascii_case(name)
+                .then(|| unescape_text(value.trim()))
+        })
+    }
+
+    /// Replaces the calendar property `name` with `value`, written as
+    /// is, or removes it.
+    fn set_property(&mut self, name: &str, value: Option<&str>) {
+        self.properties.retain(|line| {
+            let (head, _) = split_unquoted(line, ':').unwrap_or((line, ""));
+            let prop = head.split(';').next().unwrap_or_default().trim();
+            !prop.eq_ignore_ascii_case(name)
+        });
+
+        if let Some(value) = value {
+            let mut writer = IcalWriter::new();
+            writer.property(name, &[], value);
+            let line = String::from_utf8_lossy(&writer.finish()).into_owned();
+            self.properties
+                .push(line.trim_end_matches("\r\n").to_owned());
+        }
+    }
+
+    /// Adds the single UID held by `contents`, returning it.
+    fn insert(&mut self, contents: &[u8]) -> Result<String> {
+        let object = single_object(contents)?;
+        let uid = object.uid.clone();
+
+        if self.objects.iter().any(|o| o.uid == uid) {
+            bail!("Item `{uid}` already exists");
+        }
+
+        self.objects.push(object);
+        Ok(uid)
+    }
+
+    /// Replaces the item `item_id` with `contents`, provided its
+    /// current ETag matches `etag`.
+    fn replace(&mut self, item_id: &str, contents: &[u8], etag: Option<&str>) -> Result<()> {
+        let Some(current) = self.objects.iter_mut().find(|o| o.uid == item_id) else {
+            bail!("Cannot find item `{item_id}`");
+        };
+
+        if etag.is_some_and(|expected| expected != content_hash(&current.contents)) {
+            return Err(Conflict.into());
+        }
+
+        let object = single_object(contents)?;
+
+        if object.uid != item_id {
+            bail!(
+                "Cannot change the UID of item `{item_id}` to `{}`",
+                object.uid
+            );
+        }
+
+        *current = object;
+        Ok(())
+    }
+
+    fn remove(&mut self, item_id: &str) -> Result<()> {
+        let count = self.objects.len();
+        self.objects.retain(|object| object.uid != item_id);
+
+        if self.objects.len() == count {
+            bail!("Cannot find item `{item_id}`");
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses `contents` as an item holding a single UID.
+fn single_object(contents: &[u8]) -> Result<IcalObject> {
+    let mut objects = split_objects(contents);
+
+    match objects.len() {
+        0 => bail!("Cannot find any component in item"),
+        1 => Ok(objects.remove(0)),
+        n => bail!("Item holds {n} UIDs; use `calendar import` to add several"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{CalendarFile, FileClient};
+    use crate::{
+        config::{FileCalendarConfig, FileConfig},
+        shared::ical::content_hash,
+    };
+
+    #[test]
+    fn rewrite_items() {
+        let event = |uid: &str, summary: &str| {
+            format!(
+                concat!(
+                    "BEGIN:VCALENDAR\r\n",
+                    "VERSION:2.0\r\n",
+                    "PRODID:-//Test//EN\r\n",
+                    "BEGIN:VTIMEZONE\r\n",
+                    "TZID:Europe/Paris\r\n",
+                    "END:VTIMEZONE\r\n",
+                    "BEGIN:VEVENT\r\n",
+                    "UID:{uid}\r\n",
+                    "SUMMARY:{summary}\r\n",
+                    "DTSTART;TZID=Europe/Paris:20261001T090000\r\n",
+                    "END:VEVENT\r\n",
+                    "END:VCALENDAR\r\n",
+                ),
+                uid = uid,
+                summary = summary,
+            )
+        };
+
+        let contents = event("a", "Standup").replace(
+            "PRODID:-//Test//EN\r\n",
+            "PRODID:-//Test//EN\r\nX-WR-CALNAME:Team\\, shared\r\n",
+        );
+        let mut file = CalendarFile::parse(contents.as_bytes());
+        assert_eq!(
+            file.property("X-WR-CALNAME").as_deref(),
+            Some("Team, shared")
+        );
+
+        let uid = file.insert(event("b", "Review").as_bytes()).unwrap();
+        assert_eq!(uid, "b");
+        assert!(file.insert(event("b", "Review").as_bytes()).is_err());
+
+        let current = content_hash(&file.objects[0].contents);
+        let update = event("a", "Daily standup");
+        assert!(file.replace("a", update.as_bytes(), Some("stale")).is_err());
+        assert!(
+            file.replace("a", event("c", "Other").as_bytes(), None)
+                .is_err()
+        );
+        file.replace("a", update.as_bytes(), Some(&current))
+            .unwrap();
+
+        file.remove("b").unwrap();
+        assert!(file.remove("b").is_err());
+
+        file.set_property("X-APPLE-CALENDAR-COLOR", Some("#3465a4"));
+
+        let rewritten = String::from_utf8(file.to_bytes()).unwrap();
+        assert_eq!(rewritten.matches("X-WR-CALNAME:Team\\, shared").count(), 1);
+        assert_eq!(rewritten.matches("BEGIN:VTIMEZONE").count(), 1);
+        assert!(rewritten.contains("SUMMARY:Daily standup"));
+        assert!(rewritten.contains("X-APPLE-CALENDAR-COLOR:#3465a4"));
+        assert!(!rewritten.contains("UID:b"));
+    }
+
+    #[test]
+    fn stable_uid_of_uidless_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.ics");
+        fs::write(
+            &path,
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Test//EN\r\n",
+                "BEGIN:VEVENT\r\n",
+                "SUMMARY:No UID\r\n",
+                "DTSTART:20261001T090000Z\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            ),
+        )
+        .unwrap();
+
+        let client = FileClient::new(FileConfig {
+            calendars: vec![FileCalendarConfig {
+                id: "personal".into(),
+                path,
+                name: None,
+                description: None,
+                color: None,
+            }],
+        });
+
+        let items = client.list_items("personal").unwrap();
+        assert_eq!(items.len(), 1);
+
+        let item = client.get_item("personal", &items[0].id).unwrap();
+        assert_eq!(item.etag, items[0].etag);
+        assert_eq!(item.contents, items[0].contents);
+
+        let contents = String::from_utf8(item.contents).unwrap();
+        let update = contents.replace("SUMMARY:No UID", "SUMMARY:Updated");
+        client
+            .update_item(
+                "personal",
+                &item.id,
+                update.into_bytes(),
+                item.etag.as_deref(),
+            )
+            .unwrap();
+        client.delete_item("personal", &item.id).unwrap();
+    }
+}
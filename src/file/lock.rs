@@ -0,0 +1,182 @@
+//! Lock file guarding the rewrites of a calendar file.
+//!
+//! The lock is a `<file>.lock` sibling created exclusively, holding
+//! the PID of its owner, and removed when the guard drops. Locks left
+//! behind by a crashed run are broken once older than
+//! [`STALE_AFTER`]: moved aside first, then removed only if they still
+//! hold the PID and modification time seen stale, so that two runs
+//! breaking the same lock never remove the one either of them took.
+
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result, bail};
+use log::{debug, warn};
+
+/// How long to wait for a lock held by another run.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay between two attempts to take the lock.
+const RETRY: Duration = Duration::from_millis(100);
+
+/// Age after which a lock is considered left behind by a crashed run.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Exclusive lock on a calendar file, released on drop.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Takes the lock of the calendar file `target`, waiting up to
+    /// [`TIMEOUT`] for another run to release it.
+    pub fn acquire(target: &Path) -> Result<Self> {
+        let mut path = OsString::from(target.as_os_str());
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let deadline = Instant::now() + TIMEOUT;
+
+        loop {
+            let err = match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    if let Err(err) = write!(file, "{}", std::process::id()) {
+                        debug!("cannot write pid to lock {}: {err}", path.display());
+                    }
+                    return Ok(Self { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => err,
+                Err(err) => {
+                    let path = path.display();
+                    return Err(err).with_context(|| format!("Create lock `{path}` error"));
+                }
+            };
+
+            if let Some(holder) = Holder::read(&path).filter(Holder::is_stale) {
+                warn!(
+                    "breaking stale lock {} of pid {}",
+                    path.display(),
+                    holder.pid
+                );
+                break_stale(&path, &holder);
+                continue;
+            }
+
+            if Instant::now() >= deadline {
+                debug!("lock {} still held: {err}", path.display());
+                bail!(
+                    "Calendar file `{}` is locked by another process (lock `{}`)",
+                    target.display(),
+                    path.display(),
+                );
+            }
+
+            thread::sleep(RETRY);
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            debug!("cannot remove lock {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// Owner of a lock, as read from its file.
+#[derive(Debug, PartialEq, Eq)]
+struct Holder {
+    pid: String,
+    modified: SystemTime,
+}
+
+impl Holder {
+    fn read(path: &Path) -> Option<Self> {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+        let pid = fs::read_to_string(path);
+
+        Some(Self {
+            pid: pid.ok()?,
+            modified: modified.ok()?,
+        })
+    }
+
+    /// Whether the lock was last modified more than [`STALE_AFTER`]
+    /// ago.
+    fn is_stale(&self) -> bool {
+        self.modified.elapsed().is_ok_and(|age| age > STALE_AFTER)
+    }
+}
+
+/// Removes the lock at `path` seen stale as `holder`. The lock is
+/// moved aside atomically, then checked again: a lock another run took
+/// in the meantime is linked back in place, unless yet another run
+/// took the lock since.
+fn break_stale(path: &Path, holder: &Holder) {
+    let mut aside = OsString::from(path.as_os_str());
+    aside.push(format!(".stale-{}", std::process::id()));
+    let aside = PathBuf::from(aside);
+
+    // already broken by another run
+    if fs::rename(path, &aside).is_err() {
+        return;
+    }
+
+    if Holder::read(&aside).as_ref() != Some(holder)
+        && let Err(err) = fs::hard_link(&aside, path)
+    {
+        debug!("cannot restore lock {}: {err}", path.display());
+    }
+
+    if let Err(err) = fs::remove_file(&aside) {
+        debug!("cannot remove lock {}: {err}", aside.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use super::{FileLock, Holder, STALE_AFTER, break_stale};
+
+    /// Writes a lock held by `pid` and last modified past
+    /// [`STALE_AFTER`].
+    fn write_stale(path: &Path, pid: &str) {
+        fs::write(path, pid).unwrap();
+        let modified = SystemTime::now() - STALE_AFTER - Duration::from_secs(1);
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn break_stale_locks_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("work.ics");
+        let lock = dir.path().join("work.ics.lock");
+
+        write_stale(&lock, "1");
+        let stale = Holder::read(&lock).unwrap();
+        assert!(stale.is_stale());
+
+        // another run broke it first and took the lock
+        fs::write(&lock, "2").unwrap();
+        break_stale(&lock, &stale);
+        assert_eq!(fs::read_to_string(&lock).unwrap(), "2");
+
+        write_stale(&lock, "1");
+        drop(FileLock::acquire(&target).unwrap());
+        assert!(!lock.exists());
+    }
+}
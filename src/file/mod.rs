@@ -0,0 +1,11 @@
+//! Single-file backend (`file`).
+//!
+//! Each configured calendar is one `.ics` file holding a single
+//! VCALENDAR, whose items are its components split per UID. Writes
+//! read, modify and rewrite the whole file: under a lock file, so
+//! concurrent runs do not lose each other's changes, and through a
+//! temporary file renamed over the original, so readers never see a
+//! partial calendar.
+
+pub mod client;
+pub mod lock;
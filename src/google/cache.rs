@@ -0,0 +1,83 @@
+//! Cached events of the Google Calendar backend.
+//!
+//! Each calendar is cached as one [`CachedEvents`] document of the
+//! account [`crate::shared::cache::CacheDir`]: the raw Google events,
+//! the default reminders of the calendar and the `nextSyncToken` of the
+//! last listing. The next listing sends the token back, so Google only
+//! returns the events changed since.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+/// Events of a calendar as of its last listing.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CachedEvents {
+    /// `nextSyncToken` of the last listing, [`None`] when never
+    /// listed.
+    pub sync_token: Option<String>,
+    /// Reminders of the events using the calendar defaults.
+    #[serde(default)]
+    pub default_reminders: Json,
+    /// Raw Google events keyed by event id, exceptions of recurring
+    /// events included.
+    #[serde(default)]
+    pub events: BTreeMap<String, Json>,
+}
+
+impl CachedEvents {
+    /// Applies the `events` changed since the sync token. Cancelled
+    /// events are removed along with their exceptions, except the
+    /// cancelled occurrences of a recurring event: they are kept, as
+    /// exclusions of the series.
+    pub fn apply(&mut self, events: Vec<Json>) {
+        for event in events {
+            let Some(id) = event["id"].as_str().map(ToOwned::to_owned) else {
+                continue;
+            };
+
+            let cancelled = event["status"] == "cancelled";
+            let occurrence = event["recurringEventId"].is_string();
+
+            if cancelled && !occurrence {
+                self.events.remove(&id);
+                self.events
+                    .retain(|_, exception| exception["recurringEventId"] != id.as_str());
+            } else {
+                self.events.insert(id, event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CachedEvents;
+
+    #[test]
+    fn apply_changes() {
+        let mut cached = CachedEvents::default();
+        cached.apply(vec![
+            json!({ "id": "single", "summary": "Lunch" }),
+            json!({ "id": "series", "summary": "Standup", "recurrence": ["RRULE:FREQ=DAILY"] }),
+            json!({ "id": "series_1", "recurringEventId": "series", "summary": "Late standup" }),
+            json!({ "id": "other", "summary": "Retro" }),
+            json!({ "id": "other_1", "recurringEventId": "other" }),
+        ]);
+
+        cached.apply(vec![
+            json!({ "id": "single", "summary": "Long lunch" }),
+            json!({ "id": "series_2", "recurringEventId": "series", "status": "cancelled" }),
+            json!({ "id": "other", "status": "cancelled" }),
+            json!({ "id": "unknown", "status": "cancelled" }),
+        ]);
+
+        let ids: Vec<&str> = cached.events.keys().map(String::as_str).collect();
+        assert_eq!(ids, ["series", "series_1", "series_2", "single"]);
+        assert_eq!(cached.events["single"]["summary"], "Long lunch");
+    }
+}
@@ -0,0 +1,691 @@
+//! Client of the Google Calendar backend.
+//!
+//! Serves the calendars of the user's calendar list and their events
+//! to [`crate::shared::client::CalendarClient`], through the Calendar
+//! v3 REST API. Events are listed incrementally: the [`CachedEvents`]
+//! of each calendar keep its events with the `nextSyncToken` they
+//! were listed at, so that the next listing only downloads the events
+//! changed since. Items are Google events converted to iCalendar (see
+//! [`crate::google::convert`]), their id being the Google id of the
+//! event (or of the series) and their ETag its `etag`.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use io_calendar::{
+    calendar::{Calendar, CalendarDiff},
+    item::CalendarItem,
+};
+use io_http::rfc6750::bearer::HttpAuthBearer;
+use io_webdav::rfc4918::WebdavAuth;
+use log::{debug, warn};
+use pimalaya_stream::tls::Tls;
+use secrecy::ExposeSecret;
+use serde_json::{Map, Value as Json, json};
+use url::Url;
+
+use crate::{
+    config::{GoogleAuthConfig, GoogleConfig},
+    google::{
+        cache::CachedEvents,
+        convert::{overrides, to_google, to_ical},
+    },
+    shared::{cache::CacheDir, rest::RestClient},
+};
+
+/// Default base URL of the Calendar API.
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
+
+/// Number of events asked per page, the maximum Google accepts.
+const PAGE_SIZE: &str = "2500";
+
+pub struct GoogleClient {
+    http: RestClient,
+    api_url: Url,
+    cache: CacheDir,
+}
+
+impl GoogleClient {
+    /// Builds the client of the account `account` from `config`,
+    /// resolving the bearer token.
+    pub fn new(account: &str, config: GoogleConfig) -> Result<Self> {
+        let mut tls: Tls = config.tls.into();
+        tls.rustls.alpn = vec!["http/1.1".into()];
+
+        let GoogleAuthConfig::Bearer { token } = config.auth;
+        let token = token.get()?;
+
+        let api_url = match config.api_url {
+            Some(url) => url,
+            None => Url::parse(API_URL)?,
+        };
+
+        Ok(Self {
+            http: RestClient::new(
+                tls,
+                WebdavAuth::Bearer(HttpAuthBearer::new(token.expose_secret())),
+            ),
+            api_url,
+            cache: CacheDir::new(account, "google")?,
+        })
+    }
+
+    /// Lists the calendars of the calendar list of the user, with the
+    /// name and the colour they have there. The CTag is the sync token
+    /// of the cached events.
+    pub fn list_calendars(&self) -> Result<Vec<Calendar>> {
+        let url = self.url(&["users", "me", "calendarList"])?;
+        let calendars = get_all(&self.http, url)?;
+
+        let calendars = calendars
+            .into_iter()
+            .map(|calendar| {
+                let text = |key: &str| {
+                    calendar[key]
+                        .as_str()
+                        .filter(|value| !value.is_empty())
+                        .map(ToOwned::to_owned)
+                };
+
+                let id = text("id").unwrap_or_default();
+                let ctag = self.cache.load::<CachedEvents>(&id).sync_token;
+
+                Calendar {
+                    name: text("summaryOverride")
+                        .or(text("summary"))
+                        .unwrap_or_default(),
+                    description: text("description"),
+                    color: text("backgroundColor"),
+                    ctag,
+                    id,
+                }
+            })
+            .collect();
+
+        Ok(calendars)
+    }
+
+    /// Creates a calendar named `name`, returning the id Google
+    /// assigns it in place of `calendar_id`.
+    pub fn create_calendar(
+        &self,
+        calendar_id: &str,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<String> {
+        let url = self.url(&["calendars"])?;
+        let body = json!({ "summary": name, "description": description.unwrap_or_default() });
+        let calendar: Json = self
+            .http
+            .send_json("POST", &url, &[], Some(&body))?
+            .json()?;
+
+        let Some(id) = calendar["id"].as_str() else {
+            bail!("Missing id of the calendar `{calendar_id}` created by Google");
+        };
+
+        if let Some(color) = color {
+            self.set_color(id, color)?;
+        }
+
+        Ok(id.to_owned())
+    }
+
+    /// Patches the calendar `calendar_id`. The colour lives in the
+    /// calendar list entry, and cannot be cleared.
+    pub fn update_calendar(&self, calendar_id: &str, patch: CalendarDiff) -> Result<()> {
+        let mut calendar = Map::new();
+
+        if let Some(name) = patch.name {
+            calendar.insert("summary".into(), json!(name));
+        }
+
+        if let Some(description) = patch.description {
+            calendar.insert("description".into(), json!(description.unwrap_or_default()));
+        }
+
+        if !calendar.is_empty() {
+            let url = self.url(&["calendars", calendar_id])?;
+            let body = Json::Object(calendar);
+            self.http.send_json("PATCH", &url, &[], Some(&body))?;
+        }
+
+        match patch.color {
+            Some(Some(color)) => self.set_color(calendar_id, &color)?,
+            Some(None) => warn!("Google calendar colours cannot be cleared, ignoring it"),
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_calendar(&self, calendar_id: &str) -> Result<()> {
+        let url = self.url(&["calendars", calendar_id])?;
+        self.http.send_json("DELETE", &url, &[], None)?;
+        Ok(())
+    }
+
+    /// Lists the events of `calendar_id`, downloading only the ones
+    /// changed since the last listing. A recurring event and its
+    /// exceptions make one item.
+    pub fn list_items(&self, calendar_id: &str) -> Result<Vec<CalendarItem>> {
+        let url = self.url(&["calendars", calendar_id, "events"])?;
+        let mut cached: CachedEvents = self.cache.load(calendar_id);
+
+        let changes = match list_changes(&self.http, &url, cached.sync_token.as_deref())? {
+            Some(changes) => changes,
+            None => {
+                debug!("sync token of calendar `{calendar_id}` expired, listing every event");
+                cached = CachedEvents::default();
+                list_changes(&self.http, &url, None)?
+                    .ok_or_else(|| anyhow!("Cannot list the events of `{calendar_id}`"))?
+            }
+        };
+
+        cached.apply(changes.events);
+        cached.sync_token = changes.sync_token;
+        if let Some(default_reminders) = changes.default_reminders {
+            cached.default_reminders = default_reminders;
+        }
+
+        if let Err(err) = self.cache.save(calendar_id, &cached) {
+            debug!("cannot save event cache of `{calendar_id}`: {err:?}");
+        }
+
+        Ok(items(calendar_id, &cached))
+    }
+
+    /// Fetches the event `item_id` alone, then the exceptions of its
+    /// series and the default reminders of the calendar through a
+    /// listing filtered on its UID, leaving the event cache alone.
+    pub fn get_item(&self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        let url = self.url(&["calendars", calendar_id, "events", item_id])?;
+        let event: Json = self.http.send_json("GET", &url, &[], None)?.json()?;
+
+        if event["status"] == "cancelled" {
+            bail!("Cannot find Google event `{item_id}`");
+        }
+
+        let mut changes = Changes::default();
+        if let Some(uid) = event["iCalUID"].as_str() {
+            let mut url = self.url(&["calendars", calendar_id, "events"])?;
+            url.query_pairs_mut()
+                .append_pair("iCalUID", uid)
+                .append_pair("showDeleted", "true");
+            changes = list_changes(&self.http, &url, None)?.unwrap_or_default();
+        }
+
+        let default_reminders = match changes.default_reminders {
+            Some(default_reminders) => default_reminders,
+            None => {
+                self.cache
+                    .load::<CachedEvents>(calendar_id)
+                    .default_reminders
+            }
+        };
+
+        let exceptions: Vec<&Json> = changes
+            .events
+            .iter()
+            .filter(|exception| exception["recurringEventId"] == item_id)
+            .collect();
+
+        Ok(CalendarItem {
+            id: item_id.to_owned(),
+            calendar_id: calendar_id.to_owned(),
+            etag: event["etag"].as_str().map(ToOwned::to_owned),
+            contents: to_ical(&event, &exceptions, &default_reminders, Utc::now()),
+        })
+    }
+
+    /// Creates an event in `calendar_id` with its overridden
+    /// occurrences, returning its Google id.
+    pub fn create_item(&self, calendar_id: &str, contents: Vec<u8>) -> Result<String> {
+        let url = self.url(&["calendars", calendar_id, "events"])?;
+        let body = to_google(&contents)?;
+        let event: Json = self
+            .http
+            .send_json("POST", &url, &[], Some(&body))?
+            .json()?;
+
+        let Some(id) = event["id"].as_str() else {
+            bail!("Missing id of the event created by Google");
+        };
+
+        self.update_overrides(calendar_id, id, &contents)?;
+        Ok(id.to_owned())
+    }
+
+    /// Patches the event `item_id` with the master VEVENT of
+    /// `contents`, guarded by `etag` when given, then its overridden
+    /// occurrences.
+    pub fn update_item(
+        &self,
+        calendar_id: &str,
+        item_id: &str,
+        contents: Vec<u8>,
+        etag: Option<&str>,
+    ) -> Result<()> {
+        let url = self.url(&["calendars", calendar_id, "events", item_id])?;
+        let mut body = to_google(&contents)?;
+        if let Some(event) = body.as_object_mut() {
+            event.remove("iCalUID");
+        }
+
+        let mut headers = Vec::new();
+        if let Some(etag) = etag {
+            headers.push(("If-Match", etag.to_owned()));
+        }
+
+        self.http.send_json("PATCH", &url, &headers, Some(&body))?;
+        self.update_overrides(calendar_id, item_id, &contents)
+    }
+
+    pub fn delete_item(&self, calendar_id: &str, item_id: &str) -> Result<()> {
+        let url = self.url(&["calendars", calendar_id, "events", item_id])?;
+        self.http.send_json("DELETE", &url, &[], None)?;
+        Ok(())
+    }
+
+    /// Patches the occurrences of the series `item_id` overridden by
+    /// `contents`, each looked up among the instances of the series by
+    /// its original start.
+    fn update_overrides(&self, calendar_id: &str, item_id: &str, contents: &[u8]) -> Result<()> {
+        for exception in overrides(contents)? {
+            let original_start = if exception.all_day {
+                exception.original_start.format("%Y-%m-%d").to_string()
+            } else {
+                exception.original_start.to_rfc3339()
+            };
+
+            let mut url = self.url(&["calendars", calendar_id, "events", item_id, "instances"])?;
+            url.query_pairs_mut()
+                .append_pair("originalStart", &original_start);
+
+            let instances = get_all(&self.http, url)?;
+            let instance = instances
+                .iter()
+                .find(|instance| exception.is_occurrence(instance));
+
+            let Some(id) = instance.and_then(|instance| instance["id"].as_str()) else {
+                bail!(
+                    "Cannot find the occurrence of Google event `{item_id}` starting at {}",
+                    exception.original_start,
+                );
+            };
+
+            let url = self.url(&["calendars", calendar_id, "events", id])?;
+            self.http
+                .send_json("PATCH", &url, &[], Some(&exception.event))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the colour of the calendar list entry of `calendar_id`,
+    /// with a black or white foreground depending on its lightness.
+    fn set_color(&self, calendar_id: &str, color: &str) -> Result<()> {
+        let mut url = self.url(&["users", "me", "calendarList", calendar_id])?;
+        url.query_pairs_mut().append_pair("colorRgbFormat", "true");
+
+        let body = json!({
+            "backgroundColor": color,
+            "foregroundColor": foreground(color),
+        });
+        self.http.send_json("PATCH", &url, &[], Some(&body))?;
+
+        Ok(())
+    }
+
+    /// Builds the API URL of the path `segments`, percent-encoding
+    /// each of them: calendar ids may contain `#`.
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.api_url.clone();
+
+        url.path_segments_mut()
+            .map_err(|()| anyhow!("Invalid Google API URL `{}`", self.api_url))?
+            .pop_if_empty()
+            .extend(segments);
+
+        Ok(url)
+    }
+}
+
+/// Events changed since a sync token.
+#[derive(Debug, Default)]
+struct Changes {
+    events: Vec<Json>,
+    default_reminders: Option<Json>,
+    sync_token: Option<String>,
+}
+
+/// Lists the events at `url` changed since `sync_token`, or every
+/// event without token, following `nextPageToken`. Returns [`None`]
+/// when Google expired the token (`410 Gone`).
+fn list_changes(http: &RestClient, url: &Url, sync_token: Option<&str>) -> Result<Option<Changes>> {
+    let mut url = url.clone();
+    url.query_pairs_mut().append_pair("maxResults", PAGE_SIZE);
+    if let Some(sync_token) = sync_token {
+        url.query_pairs_mut().append_pair("syncToken", sync_token);
+    }
+
+    let mut changes = Changes::default();
+    let mut page_url = url.clone();
+
+    loop {
+        let response = http.send("GET", &page_url, &[], None)?;
+        if response.status == 410 {
+            return Ok(None);
+        }
+
+        let mut page: Map<String, Json> = response.check("GET", &page_url)?.json()?;
+
+        if let Some(Json::Array(items)) = page.remove("items") {
+            changes.events.extend(items);
+        }
+
+        if let Some(default_reminders) = page.remove("defaultReminders") {
+            changes.default_reminders = Some(default_reminders);
+        }
+
+        if let Some(Json::String(sync_token)) = page.remove("nextSyncToken") {
+            changes.sync_token = Some(sync_token);
+        }
+
+        let Some(Json::String(page_token)) = page.remove("nextPageToken") else {
+            return Ok(Some(changes));
+        };
+
+        page_url = url.clone();
+        page_url
+            .query_pairs_mut()
+            .append_pair("pageToken", &page_token);
+    }
+}
+
+/// Collects the `items` of every page of the collection at `url`,
+/// following `nextPageToken`.
+fn get_all(http: &RestClient, url: Url) -> Result<Vec<Json>> {
+    let mut items = Vec::new();
+    let mut page_url = url.clone();
+
+    loop {
+        let mut page: Map<String, Json> = http.send_json("GET", &page_url, &[], None)?.json()?;
+
+        if let Some(Json::Array(page_items)) = page.remove("items") {
+            items.extend(page_items);
+        }
+
+        let Some(Json::String(page_token)) = page.remove("nextPageToken") else {
+            return Ok(items);
+        };
+
+        page_url = url.clone();
+        page_url
+            .query_pairs_mut()
+            .append_pair("pageToken", &page_token);
+    }
+}
+
+/// Builds the items of the `cached` events of `calendar_id`, one per
+/// single event or series. Exceptions whose series is not in the
+/// calendar make items of their own.
+fn items(calendar_id: &str, cached: &CachedEvents) -> Vec<CalendarItem> {
+    let mut exceptions: HashMap<&str, Vec<&Json>> = HashMap::new();
+
+    for event in cached.events.values() {
+        if let Some(series_id) = event["recurringEventId"].as_str()
+            && cached.events.contains_key(series_id)
+        {
+            exceptions.entry(series_id).or_default().push(event);
+        }
+    }
+
+    cached
+        .events
+        .iter()
+        .filter(|(_, event)| {
+            event["recurringEventId"]
+                .as_str()
+                .is_none_or(|series_id| !cached.events.contains_key(series_id))
+        })
+        .filter(|(_, event)| event["status"] != "cancelled")
+        .map(|(id, event)| {
+            let exceptions = exceptions.get(id.as_str()).map_or(&[][..], Vec::as_slice);
+
+            CalendarItem {
+                id: id.clone(),
+                calendar_id: calendar_id.to_owned(),
+                etag: event["etag"].as_str().map(ToOwned::to_owned),
+                contents: to_ical(event, exceptions, &cached.default_reminders, Utc::now()),
+            }
+        })
+        .collect()
+}
+
+/// Picks a black or a white foreground for the `#rrggbb` background
+/// `color`, from its relative luminance.
+fn foreground(color: &str) -> &'static str {
+    let channel = |i: usize| {
+        color
+            .trim_start_matches('#')
+            .get(i..i + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .map_or(0.0, f64::from)
+    };
+
+    let luminance = 0.299 * channel(0) + 0.587 * channel(2) + 0.114 * channel(4);
+
+    if luminance > 150.0 {
+        "#000000"
+    } else {
+        "#ffffff"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use io_webdav::rfc4918::WebdavAuth;
+    use pimalaya_config::secret::Secret;
+    use url::Url;
+
+    use super::{GoogleClient, foreground, list_changes};
+    use crate::{
+        config::{GoogleAuthConfig, GoogleConfig, TlsConfig},
+        shared::{
+            mock::{bind, serve},
+            rest::RestClient,
+        },
+    };
+
+    fn client(server_url: &str) -> GoogleClient {
+        GoogleClient::new(
+            "test",
+            GoogleConfig {
+                api_url: Some(server_url.parse().unwrap()),
+                tls: TlsConfig::default(),
+                auth: GoogleAuthConfig::Bearer {
+                    token: Secret::Raw(String::from("token").into()),
+                },
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn list_changes_following_pages() {
+        let (listener, server_url) = bind();
+
+        let first = serde_json::json!({
+            "items": [{ "id": "e1" }],
+            "defaultReminders": [{ "method": "popup", "minutes": 10 }],
+            "nextPageToken": "p2",
+        });
+        let second = serde_json::json!({
+            "items": [{ "id": "e2", "status": "cancelled" }],
+            "nextSyncToken": "s2",
+        });
+        let server = serve(
+            listener,
+            vec![
+                (200, first.to_string()),
+                (200, second.to_string()),
+                (410, String::from("{}")),
+            ],
+        );
+
+        let http = RestClient::new(TlsConfig::default().into(), WebdavAuth::None);
+        let url = format!("{server_url}/calendars/me%40ex.org/events");
+        let url = Url::parse(&url).unwrap();
+
+        let changes = list_changes(&http, &url, Some("s1")).unwrap().unwrap();
+        assert!(list_changes(&http, &url, Some("s2")).unwrap().is_none());
+
+        let requests = server.join().unwrap();
+        let request_lines: Vec<&str> = requests
+            .iter()
+            .filter_map(|request| request.lines().next())
+            .collect();
+        assert_eq!(
+            request_lines,
+            [
+                "GET /calendars/me%40ex.org/events?maxResults=2500&syncToken=s1 HTTP/1.1",
+                "GET /calendars/me%40ex.org/events?maxResults=2500&syncToken=s1&pageToken=p2 HTTP/1.1",
+                "GET /calendars/me%40ex.org/events?maxResults=2500&syncToken=s2 HTTP/1.1",
+            ],
+        );
+
+        let ids: Vec<&str> = changes
+            .events
+            .iter()
+            .filter_map(|e| e["id"].as_str())
+            .collect();
+        assert_eq!(ids, ["e1", "e2"]);
+        assert_eq!(changes.sync_token.as_deref(), Some("s2"));
+        assert_eq!(changes.default_reminders.unwrap()[0]["minutes"], 10);
+    }
+
+    #[test]
+    fn update_patches_overrides() {
+        let (listener, server_url) = bind();
+
+        let instances = serde_json::json!({
+            "items": [{
+                "id": "abc_20260319T080000Z",
+                "originalStartTime": { "dateTime": "2026-03-19T09:00:00+01:00" },
+            }],
+        });
+        let server = serve(
+            listener,
+            vec![
+                (200, String::from("{}")),
+                (200, instances.to_string()),
+                (200, String::from("{}")),
+            ],
+        );
+
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc@google.com\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,TH\r\n",
+            "SUMMARY:Standup\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc@google.com\r\n",
+            "RECURRENCE-ID;TZID=Europe/Paris:20260319T090000\r\n",
+            "DTSTART;TZID=Europe/Paris:20260319T100000\r\n",
+            "SUMMARY:Late standup\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        client(&server_url)
+            .update_item("primary", "abc", ical.as_bytes().to_vec(), Some("\"1\""))
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        let request_lines: Vec<&str> = requests
+            .iter()
+            .filter_map(|request| request.lines().next())
+            .collect();
+        assert_eq!(
+            request_lines,
+            [
+                "PATCH /calendars/primary/events/abc HTTP/1.1",
+                "GET /calendars/primary/events/abc/instances?originalStart=2026-03-19T08%3A00%3A00%2B00%3A00 HTTP/1.1",
+                "PATCH /calendars/primary/events/abc_20260319T080000Z HTTP/1.1",
+            ],
+        );
+        assert!(requests[0].contains("\r\nAuthorization: Bearer token\r\n"));
+
+        let (_, body) = requests[2].split_once("\r\n\r\n").unwrap();
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["summary"], "Late standup");
+        assert!(body.get("recurrence").is_none());
+    }
+
+    #[test]
+    fn get_item_without_sync() {
+        let (listener, server_url) = bind();
+
+        let event = serde_json::json!({
+            "id": "abc",
+            "iCalUID": "abc@google.com",
+            "etag": "\"2\"",
+            "summary": "Standup",
+            "start": { "dateTime": "2026-03-16T09:00:00+01:00", "timeZone": "Europe/Paris" },
+            "end": { "dateTime": "2026-03-16T09:15:00+01:00", "timeZone": "Europe/Paris" },
+            "recurrence": ["RRULE:FREQ=WEEKLY;BYDAY=MO,TH"],
+        });
+        let listing = serde_json::json!({
+            "items": [
+                event,
+                {
+                    "id": "abc_20260319T080000Z",
+                    "recurringEventId": "abc",
+                    "status": "cancelled",
+                    "originalStartTime": {
+                        "dateTime": "2026-03-19T09:00:00+01:00",
+                        "timeZone": "Europe/Paris",
+                    },
+                },
+            ],
+            "defaultReminders": [{ "method": "popup", "minutes": 10 }],
+        });
+        let server = serve(
+            listener,
+            vec![(200, event.to_string()), (200, listing.to_string())],
+        );
+
+        let item = client(&server_url).get_item("primary", "abc").unwrap();
+
+        let requests = server.join().unwrap();
+        let request_lines: Vec<&str> = requests
+            .iter()
+            .filter_map(|request| request.lines().next())
+            .collect();
+        assert_eq!(
+            request_lines,
+            [
+                "GET /calendars/primary/events/abc HTTP/1.1",
+                "GET /calendars/primary/events?iCalUID=abc%40google.com&showDeleted=true&maxResults=2500 HTTP/1.1",
+            ],
+        );
+
+        assert_eq!(item.id, "abc");
+        assert_eq!(item.etag.as_deref(), Some("\"2\""));
+        let contents = String::from_utf8(item.contents).unwrap();
+        assert!(contents.contains("\r\nEXDATE;TZID=Europe/Paris:20260319T090000\r\n"));
+        assert!(contents.contains("\r\nTRIGGER:-PT10M\r\n"));
+    }
+
+    #[test]
+    fn foreground_contrast() {
+        assert_eq!(foreground("#ffad46"), "#000000");
+        assert_eq!(foreground("#1a237e"), "#ffffff");
+    }
+}
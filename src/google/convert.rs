@@ -0,0 +1,718 @@
+//! Conversion between Google Calendar events and iCalendar.
+//!
+//! A Google recurring event comes with its modified occurrences as
+//! separate events (`recurringEventId`), and its deleted occurrences
+//! as cancelled ones. They are gathered back into one VCALENDAR: the
+//! master VEVENT carries an EXDATE per deleted occurrence, and each
+//! modified occurrence becomes a VEVENT with a RECURRENCE-ID. The
+//! `recurrence` lines of Google are iCalendar content lines already,
+//! and pass through untouched.
+//!
+//! The master VEVENT of an object is sent back as the event, its
+//! EXDATEs in the `recurrence`. Each RECURRENCE-ID VEVENT becomes an
+//! [`Override`] patching the matching instance of the series. Google
+//! assigns the organizer (the owner of the calendar) and ignores the
+//! event SEQUENCE.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use serde_json::{Map, Value as Json, json};
+
+use crate::shared::{
+    datetime::parse_duration,
+    ical::{
+        ContentLine, IcalWriter, PRODID, VEvent, events, master_event, parse_lines, unescape_text,
+    },
+    timezone::Timezone,
+};
+
+/// Recurrence properties passed through the Google `recurrence`.
+const RECURRENCE: [&str; 4] = ["RRULE", "EXRULE", "RDATE", "EXDATE"];
+
+/// Maximum number of reminder overrides of a Google event.
+const MAX_REMINDERS: usize = 5;
+
+/// Renders the Google `event` and its `exceptions` as a VCALENDAR.
+/// Events using the default reminders get `default_reminders`, and
+/// `now` stamps events without `updated` date.
+pub fn to_ical(
+    event: &Json,
+    exceptions: &[&Json],
+    default_reminders: &Json,
+    now: DateTime<Utc>,
+) -> Vec<u8> {
+    let (cancelled, modified): (Vec<&Json>, Vec<&Json>) = exceptions
+        .iter()
+        .partition(|exception| exception["status"] == "cancelled");
+
+    let mut writer = IcalWriter::new();
+    writer
+        .begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .property("PRODID", &[], PRODID);
+
+    write_event(&mut writer, event, &cancelled, default_reminders, now);
+
+    for exception in modified {
+        write_event(&mut writer, exception, &[], default_reminders, now);
+    }
+
+    writer.end("VCALENDAR");
+    writer.finish()
+}
+
+/// Builds the Google event matching the master VEVENT of `contents`.
+/// Empty values are sent explicitly, so that the event can patch an
+/// existing one.
+pub fn to_google(contents: &[u8]) -> Result<Json> {
+    let Some(event) = master_event(parse_lines(contents)) else {
+        bail!("Cannot find any VEVENT to send to Google");
+    };
+
+    google_event(event)
+}
+
+/// Occurrence of a series modified by a RECURRENCE-ID VEVENT.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    /// Original start of the occurrence, only its date counting for
+    /// all-day series.
+    pub original_start: DateTime<Utc>,
+    pub all_day: bool,
+    /// Google event patching the occurrence.
+    pub event: Json,
+}
+
+impl Override {
+    /// Whether the Google occurrence `instance` is the one this
+    /// override applies to.
+    pub fn is_occurrence(&self, instance: &Json) -> bool {
+        let original = &instance["originalStartTime"];
+
+        if self.all_day {
+            original["date"].as_str() == Some(&self.original_start.format("%Y-%m-%d").to_string())
+        } else {
+            original["dateTime"].as_str().and_then(parse_instant) == Some(self.original_start)
+        }
+    }
+}
+
+/// Lists the occurrences modified by the RECURRENCE-ID VEVENTs of
+/// `contents`.
+pub fn overrides(contents: &[u8]) -> Result<Vec<Override>> {
+    let mut overrides = Vec::new();
+
+    for event in events(parse_lines(contents)) {
+        let Some(recurrence_id) = event.props.iter().find(|line| line.is("RECURRENCE-ID")) else {
+            continue;
+        };
+
+        let (original_start, all_day) = instant(recurrence_id)?;
+        let mut event = google_event(event)?;
+        if let Some(event) = event.as_object_mut() {
+            event.remove("iCalUID");
+            event.remove("recurrence");
+        }
+
+        overrides.push(Override {
+            original_start,
+            all_day,
+            event,
+        });
+    }
+
+    Ok(overrides)
+}
+
+/// Builds the Google event of the VEVENT `event`.
+fn google_event(VEvent { props, alarms }: VEvent) -> Result<Json> {
+    let prop = |name: &str| props.iter().find(|line| line.is(name));
+    let text = |name: &str| prop(name).map(|line| unescape_text(&line.value));
+    let upper = |name: &str| prop(name).map(|line| line.value.trim().to_ascii_uppercase());
+
+    let Some(dtstart) = prop("DTSTART") else {
+        bail!("Cannot send an event without DTSTART to Google");
+    };
+    let (start, all_day) = dtstart.date_time()?;
+
+    let end = match (prop("DTEND"), prop("DURATION")) {
+        (Some(dtend), _) => time(dtend.date_time()?.0, all_day, zone(dtend)),
+        (None, Some(duration)) => {
+            let end = start + parse_duration(&duration.value)?;
+            time(end, all_day, zone(dtstart))
+        }
+        (None, None) if all_day => time(start + TimeDelta::days(1), true, None),
+        (None, None) => time(start, false, zone(dtstart)),
+    };
+
+    let mut event = Map::new();
+    if let Some(uid) = text("UID") {
+        event.insert("iCalUID".into(), json!(uid));
+    }
+    event.insert("summary".into(), json!(text("SUMMARY").unwrap_or_default()));
+    event.insert(
+        "description".into(),
+        json!(text("DESCRIPTION").unwrap_or_default()),
+    );
+    event.insert(
+        "location".into(),
+        json!(text("LOCATION").unwrap_or_default()),
+    );
+    event.insert("start".into(), time(start, all_day, zone(dtstart)));
+    event.insert("end".into(), end);
+
+    let recurrence: Vec<String> = props
+        .iter()
+        .filter(|line| RECURRENCE.contains(&line.name.as_str()))
+        .map(content_line)
+        .collect();
+    event.insert("recurrence".into(), json!(recurrence));
+
+    let status = match upper("STATUS").as_deref() {
+        Some("TENTATIVE") => "tentative",
+        Some("CANCELLED") => "cancelled",
+        _ => "confirmed",
+    };
+    event.insert("status".into(), json!(status));
+
+    let transparency = match upper("TRANSP").as_deref() {
+        Some("TRANSPARENT") => "transparent",
+        _ => "opaque",
+    };
+    event.insert("transparency".into(), json!(transparency));
+
+    let visibility = match upper("CLASS").as_deref() {
+        Some("PUBLIC") => "public",
+        Some("PRIVATE") => "private",
+        Some("CONFIDENTIAL") => "confidential",
+        _ => "default",
+    };
+    event.insert("visibility".into(), json!(visibility));
+
+    let attendees: Vec<Json> = props
+        .iter()
+        .filter(|line| line.is("ATTENDEE"))
+        .map(attendee)
+        .collect();
+    event.insert("attendees".into(), json!(attendees));
+
+    let overrides: Vec<Json> = alarms
+        .iter()
+        .filter_map(|alarm| reminder(alarm))
+        .take(MAX_REMINDERS)
+        .collect();
+    event.insert(
+        "reminders".into(),
+        json!({ "useDefault": false, "overrides": overrides }),
+    );
+
+    Ok(Json::Object(event))
+}
+
+/// Writes the Google `event` as a VEVENT, excluding the occurrences
+/// of the `cancelled` exceptions.
+fn write_event(
+    writer: &mut IcalWriter,
+    event: &Json,
+    cancelled: &[&Json],
+    default_reminders: &Json,
+    now: DateTime<Utc>,
+) {
+    let text = |key: &str| {
+        event[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let instant = |key: &str| text(key).and_then(parse_instant);
+
+    writer.begin("VEVENT");
+
+    if let Some(uid) = text("iCalUID").or(text("id")) {
+        writer.text("UID", uid);
+    }
+
+    let stamp = instant("updated").unwrap_or(now);
+    writer.property("DTSTAMP", &[], &format_instant(stamp));
+
+    if let Some(created) = instant("created") {
+        writer.property("CREATED", &[], &format_instant(created));
+    }
+
+    if let Some(updated) = instant("updated") {
+        writer.property("LAST-MODIFIED", &[], &format_instant(updated));
+    }
+
+    if let Some(sequence) = event["sequence"].as_u64().filter(|n| *n > 0) {
+        writer.property("SEQUENCE", &[], &sequence.to_string());
+    }
+
+    write_time(writer, "RECURRENCE-ID", &event["originalStartTime"]);
+    write_time(writer, "DTSTART", &event["start"]);
+    write_time(writer, "DTEND", &event["end"]);
+
+    let recurrence = event["recurrence"].as_array().into_iter().flatten();
+    for line in recurrence
+        .filter_map(Json::as_str)
+        .filter_map(ContentLine::parse)
+    {
+        line.write(writer);
+    }
+
+    for exception in cancelled {
+        write_time(writer, "EXDATE", &exception["originalStartTime"]);
+    }
+
+    if let Some(summary) = text("summary") {
+        writer.text("SUMMARY", summary);
+    }
+
+    if let Some(description) = text("description") {
+        writer.text("DESCRIPTION", description);
+    }
+
+    if let Some(location) = text("location") {
+        writer.text("LOCATION", location);
+    }
+
+    if let Some(status) = text("status") {
+        writer.property("STATUS", &[], &status.to_ascii_uppercase());
+    }
+
+    let transp = match text("transparency") {
+        Some("transparent") => "TRANSPARENT",
+        _ => "OPAQUE",
+    };
+    writer.property("TRANSP", &[], transp);
+
+    let class = match text("visibility") {
+        Some("public") => Some("PUBLIC"),
+        Some("private") => Some("PRIVATE"),
+        Some("confidential") => Some("CONFIDENTIAL"),
+        _ => None,
+    };
+    if let Some(class) = class {
+        writer.property("CLASS", &[], class);
+    }
+
+    if let Some(email) = event["organizer"]["email"].as_str() {
+        let mut params = Vec::new();
+        if let Some(name) = event["organizer"]["displayName"].as_str() {
+            params.push(("CN", name));
+        }
+        writer.property("ORGANIZER", &params, &format!("mailto:{email}"));
+    }
+
+    for attendee in event["attendees"].as_array().into_iter().flatten() {
+        let Some(email) = attendee["email"].as_str() else {
+            continue;
+        };
+
+        let mut params = Vec::new();
+        if let Some(name) = attendee["displayName"].as_str() {
+            params.push(("CN", name));
+        }
+
+        if attendee["resource"].as_bool() == Some(true) {
+            params.push(("CUTYPE", "RESOURCE"));
+        }
+
+        let role = match attendee["optional"].as_bool() {
+            Some(true) => "OPT-PARTICIPANT",
+            _ => "REQ-PARTICIPANT",
+        };
+        params.push(("ROLE", role));
+
+        let partstat = match attendee["responseStatus"].as_str() {
+            Some("accepted") => "ACCEPTED",
+            Some("declined") => "DECLINED",
+            Some("tentative") => "TENTATIVE",
+            _ => "NEEDS-ACTION",
+        };
+        params.push(("PARTSTAT", partstat));
+
+        writer.property("ATTENDEE", &params, &format!("mailto:{email}"));
+    }
+
+    let reminders = match event["reminders"]["useDefault"].as_bool() {
+        Some(false) => &event["reminders"]["overrides"],
+        _ => default_reminders,
+    };
+
+    for reminder in reminders.as_array().into_iter().flatten() {
+        let Some(minutes) = reminder["minutes"].as_i64() else {
+            continue;
+        };
+
+        let action = match reminder["method"].as_str() {
+            Some("email") => "EMAIL",
+            _ => "DISPLAY",
+        };
+
+        writer
+            .begin("VALARM")
+            .property("ACTION", &[], action)
+            .text("DESCRIPTION", text("summary").unwrap_or("Reminder"))
+            .property("TRIGGER", &[], &format!("-PT{minutes}M"))
+            .end("VALARM");
+    }
+
+    writer.end("VEVENT");
+}
+
+/// Writes the Google event `time` as the DATE or DATE-TIME property
+/// `name`, in the time zone of the event when it has one.
+fn write_time(writer: &mut IcalWriter, name: &str, time: &Json) {
+    let date = time["date"]
+        .as_str()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+    if let Some(date) = date {
+        writer.property(
+            name,
+            &[("VALUE", "DATE")],
+            &date.format("%Y%m%d").to_string(),
+        );
+        return;
+    }
+
+    let Some(dt) = time["dateTime"].as_str().and_then(parse_instant) else {
+        return;
+    };
+
+    let zone = time["timeZone"]
+        .as_str()
+        .filter(|zone| !matches!(*zone, "UTC" | "Etc/UTC"));
+
+    match zone.and_then(|zone| Some((zone, zone.parse::<Tz>().ok()?))) {
+        Some((zone, tz)) => {
+            let local = dt.with_timezone(&tz).naive_local();
+            let value = local.format("%Y%m%dT%H%M%S").to_string();
+            writer.property(name, &[("TZID", zone)], &value);
+        }
+        None => {
+            writer.property(name, &[], &format_instant(dt));
+        }
+    }
+}
+
+/// Builds the Google event time of the local `dt` in `zone`, UTC when
+/// [`None`]. The other fields are nulled, for patches to switch
+/// between dates and date-times.
+fn time(dt: NaiveDateTime, all_day: bool, zone: Option<&str>) -> Json {
+    match zone {
+        _ if all_day => json!({
+            "date": dt.format("%Y-%m-%d").to_string(),
+            "dateTime": null,
+            "timeZone": null,
+        }),
+        Some(zone) => json!({
+            "date": null,
+            "dateTime": dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "timeZone": zone,
+        }),
+        None => json!({
+            "date": null,
+            "dateTime": dt.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "timeZone": null,
+        }),
+    }
+}
+
+/// TZID of the DATE-TIME property `line`, [`None`] in UTC. Floating
+/// times are taken as UTC too.
+fn zone(line: &ContentLine) -> Option<&str> {
+    if line.value.trim().ends_with(['z', 'Z']) {
+        return None;
+    }
+
+    line.param("TZID")
+}
+
+/// Resolves the DATE or DATE-TIME property `line` to an instant, with
+/// whether it is a date. Floating times are taken as UTC, like
+/// [`zone`] does.
+fn instant(line: &ContentLine) -> Result<(DateTime<Utc>, bool)> {
+    let (dt, all_day) = line.date_time()?;
+
+    let tzid = match zone(line) {
+        Some(tzid) if !all_day => tzid,
+        _ => return Ok((dt.and_utc(), all_day)),
+    };
+
+    let Ok(tz) = tzid.parse::<Tz>() else {
+        bail!(
+            "Cannot resolve the TZID `{tzid}` of {} for Google",
+            line.name
+        );
+    };
+
+    Ok((Timezone::Tz(tz).to_utc(dt), false))
+}
+
+/// Builds the Google attendee of the ATTENDEE property `line`.
+fn attendee(line: &ContentLine) -> Json {
+    let value = line.value.trim();
+    let email = match value.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
+    };
+
+    let status = match line
+        .param("PARTSTAT")
+        .map(str::to_ascii_uppercase)
+        .as_deref()
+    {
+        Some("ACCEPTED") => "accepted",
+        Some("DECLINED") => "declined",
+        Some("TENTATIVE") => "tentative",
+        _ => "needsAction",
+    };
+
+    let mut attendee = json!({ "email": email, "responseStatus": status });
+
+    if let Some(name) = line.param("CN") {
+        attendee["displayName"] = json!(name);
+    }
+
+    if line
+        .param("ROLE")
+        .is_some_and(|role| role.eq_ignore_ascii_case("OPT-PARTICIPANT"))
+    {
+        attendee["optional"] = json!(true);
+    }
+
+    if line
+        .param("CUTYPE")
+        .is_some_and(|cutype| cutype.eq_ignore_ascii_case("RESOURCE"))
+    {
+        attendee["resource"] = json!(true);
+    }
+
+    attendee
+}
+
+/// Builds the Google reminder override of the VALARM properties
+/// `alarm`. Google only reminds before the start, so absolute
+/// triggers and triggers relative to the end are dropped, and those
+/// after the start remind at it.
+fn reminder(alarm: &[ContentLine]) -> Option<Json> {
+    let trigger = alarm.iter().find(|line| line.is("TRIGGER"))?;
+    let related_end = trigger
+        .param("RELATED")
+        .is_some_and(|related| related.eq_ignore_ascii_case("END"));
+
+    if related_end || trigger.param("VALUE").is_some() {
+        return None;
+    }
+
+    let minutes = match trigger.value.trim().strip_prefix('-') {
+        Some(before) => parse_duration(before).ok()?.num_minutes(),
+        None => 0,
+    };
+
+    let method = match alarm.iter().find(|line| line.is("ACTION")) {
+        Some(action) if action.value.trim().eq_ignore_ascii_case("EMAIL") => "email",
+        _ => "popup",
+    };
+
+    Some(json!({ "method": method, "minutes": minutes }))
+}
+
+/// Renders `line` unfolded, as the Google `recurrence` expects it.
+/// Parameter values are quoted like [`IcalWriter::property`] does.
+fn content_line(line: &ContentLine) -> String {
+    let mut rendered = line.name.clone();
+
+    for (key, val) in &line.params {
+        if val.contains([':', ';', ',']) {
+            rendered.push_str(&format!(";{key}=\"{}\"", val.replace('"', "")));
+        } else {
+            rendered.push_str(&format!(";{key}={val}"));
+        }
+    }
+
+    rendered.push(':');
+    rendered.push_str(line.value.trim());
+    rendered
+}
+
+/// Parses a Google `datetime` (`2026-03-16T09:00:00+01:00`).
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn format_instant(instant: DateTime<Utc>) -> String {
+    instant.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use super::{overrides, to_google, to_ical};
+
+    #[test]
+    fn google_event_to_ical() {
+        let event = json!({
+            "id": "abc123",
+            "iCalUID": "abc123@google.com",
+            "etag": "\"3181\"",
+            "status": "confirmed",
+            "summary": "Standup",
+            "updated": "2026-03-01T08:30:00.000Z",
+            "start": { "dateTime": "2026-03-16T09:00:00+01:00", "timeZone": "Europe/Paris" },
+            "end": { "dateTime": "2026-03-16T09:15:00+01:00", "timeZone": "Europe/Paris" },
+            "recurrence": ["RRULE:FREQ=WEEKLY;BYDAY=MO,TH"],
+            "visibility": "private",
+            "organizer": { "email": "jane@ex.org", "displayName": "Jane" },
+            "attendees": [{ "email": "bob@ex.org", "optional": true, "responseStatus": "tentative" }],
+            "reminders": { "useDefault": true },
+        });
+        let moved = json!({
+            "id": "abc123_20260319T080000Z",
+            "iCalUID": "abc123@google.com",
+            "recurringEventId": "abc123",
+            "summary": "Late standup",
+            "originalStartTime": { "dateTime": "2026-03-19T09:00:00+01:00", "timeZone": "Europe/Paris" },
+            "start": { "dateTime": "2026-03-19T10:00:00+01:00", "timeZone": "Europe/Paris" },
+            "end": { "dateTime": "2026-03-19T10:15:00+01:00", "timeZone": "Europe/Paris" },
+            "reminders": { "useDefault": false },
+        });
+        let deleted = json!({
+            "id": "abc123_20260323T080000Z",
+            "recurringEventId": "abc123",
+            "status": "cancelled",
+            "originalStartTime": { "dateTime": "2026-03-23T09:00:00+01:00", "timeZone": "Europe/Paris" },
+        });
+        let default_reminders = json!([{ "method": "popup", "minutes": 10 }]);
+
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let ical = to_ical(&event, &[&moved, &deleted], &default_reminders, now);
+        let ical = String::from_utf8(ical).unwrap();
+
+        for line in [
+            "UID:abc123@google.com",
+            "DTSTAMP:20260301T083000Z",
+            "DTSTART;TZID=Europe/Paris:20260316T090000",
+            "DTEND;TZID=Europe/Paris:20260316T091500",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,TH",
+            "EXDATE;TZID=Europe/Paris:20260323T090000",
+            "SUMMARY:Standup",
+            "CLASS:PRIVATE",
+            "ORGANIZER;CN=Jane:mailto:jane@ex.org",
+            "ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=TENTATIVE:mailto:bob@ex.org",
+            "TRIGGER:-PT10M",
+            "RECURRENCE-ID;TZID=Europe/Paris:20260319T090000",
+            "DTSTART;TZID=Europe/Paris:20260319T100000",
+            "SUMMARY:Late standup",
+        ] {
+            assert!(
+                ical.contains(&format!("{line}\r\n")),
+                "missing {line} in {ical}"
+            );
+        }
+
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 2);
+        assert_eq!(ical.matches("BEGIN:VALARM").count(), 1);
+    }
+
+    #[test]
+    fn ical_to_google_event() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "DURATION:PT1H30M\r\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20260430T220000Z\r\n",
+            "EXDATE;TZID=Europe/Paris:20260319T090000\r\n",
+            "SUMMARY:Standup\r\n",
+            "TRANSP:TRANSPARENT\r\n",
+            "ATTENDEE;CN=Bob;ROLE=OPT-PARTICIPANT:mailto:bob@example.com\r\n",
+            "BEGIN:VALARM\r\n",
+            "ACTION:EMAIL\r\n",
+            "TRIGGER:-PT1H\r\n",
+            "END:VALARM\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let event = to_google(ical.as_bytes()).unwrap();
+
+        assert_eq!(event["iCalUID"], "abc");
+        assert_eq!(event["summary"], "Standup");
+        assert_eq!(
+            event["start"],
+            json!({ "date": null, "dateTime": "2026-03-16T09:00:00", "timeZone": "Europe/Paris" }),
+        );
+        assert_eq!(event["end"]["dateTime"], "2026-03-16T10:30:00");
+        assert_eq!(
+            event["recurrence"],
+            json!([
+                "RRULE:FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20260430T220000Z",
+                "EXDATE;TZID=Europe/Paris:20260319T090000",
+            ]),
+        );
+        assert_eq!(event["transparency"], "transparent");
+        assert_eq!(
+            event["attendees"],
+            json!([{
+                "email": "bob@example.com",
+                "displayName": "Bob",
+                "optional": true,
+                "responseStatus": "needsAction",
+            }]),
+        );
+        assert_eq!(
+            event["reminders"],
+            json!({ "useDefault": false, "overrides": [{ "method": "email", "minutes": 60 }] }),
+        );
+    }
+
+    #[test]
+    fn ical_overrides() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,TH\r\n",
+            "SUMMARY:Standup\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "RECURRENCE-ID;TZID=Europe/Paris:20260319T090000\r\n",
+            "DTSTART;TZID=Europe/Paris:20260319T100000\r\n",
+            "SUMMARY:Late standup\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let overrides = overrides(ical.as_bytes()).unwrap();
+        assert_eq!(overrides.len(), 1);
+
+        let moved = &overrides[0];
+        assert_eq!(
+            moved.original_start,
+            DateTime::parse_from_rfc3339("2026-03-19T08:00:00Z").unwrap()
+        );
+        assert!(!moved.all_day);
+        assert_eq!(moved.event["summary"], "Late standup");
+        assert_eq!(moved.event["start"]["dateTime"], "2026-03-19T10:00:00");
+        assert!(moved.event.get("iCalUID").is_none());
+        assert!(moved.event.get("recurrence").is_none());
+
+        let instance = |original: &str| {
+            json!({
+                "originalStartTime": { "dateTime": original, "timeZone": "Europe/Paris" },
+            })
+        };
+        assert!(moved.is_occurrence(&instance("2026-03-19T09:00:00+01:00")));
+        assert!(!moved.is_occurrence(&instance("2026-03-16T09:00:00+01:00")));
+    }
+}
@@ -0,0 +1,12 @@
+//! Google Calendar backend (`google`), for Gmail and Google Workspace
+//! calendars.
+//!
+//! Calendars and events go through the Calendar v3 REST API,
+//! authenticated with an OAuth 2.0 bearer token, instead of Google's
+//! CalDAV endpoint and its non-standard layout. Events are listed
+//! incrementally through sync tokens, and converted to and from
+//! iCalendar at the boundary.
+
+pub mod cache;
+pub mod client;
+pub mod convert;
@@ -0,0 +1,377 @@
+//! Client of the Microsoft Graph backend.
+//!
+//! Serves the calendars and events of the signed-in user to
+//! [`crate::shared::client::CalendarClient`], through the
+//! `/me/calendars` and `/me/events` endpoints of the Graph REST API.
+//! Items are Graph events converted to iCalendar (see
+//! [`crate::graph::convert`]), their id being the Graph id and their
+//! ETag the `@odata.etag` of the event.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{TimeDelta, Utc};
+use io_calendar::{
+    calendar::{Calendar, CalendarDiff},
+    item::CalendarItem,
+};
+use io_http::rfc6750::bearer::HttpAuthBearer;
+use io_webdav::rfc4918::WebdavAuth;
+use log::warn;
+use pimalaya_stream::tls::Tls;
+use secrecy::ExposeSecret;
+use serde_json::{Map, Value as Json, json};
+use url::Url;
+
+use crate::{
+    config::{GraphAuthConfig, GraphConfig},
+    graph::convert::{EVENT_FIELDS, exceptions, to_graph, to_ical},
+    shared::rest::RestClient,
+};
+
+/// Default base URL of the Graph API.
+const API_URL: &str = "https://graph.microsoft.com/v1.0";
+
+/// Asks for event times in UTC and bodies as plain text.
+const PREFER: &str = "outlook.timezone=\"UTC\", outlook.body-content-type=\"text\"";
+
+/// Number of events asked per page.
+const PAGE_SIZE: &str = "100";
+
+pub struct GraphClient {
+    http: RestClient,
+    api_url: Url,
+}
+
+impl GraphClient {
+    /// Builds the client from `config`, resolving the bearer token.
+    pub fn new(config: GraphConfig) -> Result<Self> {
+        let mut tls: Tls = config.tls.into();
+        tls.rustls.alpn = vec!["http/1.1".into()];
+
+        let GraphAuthConfig::Bearer { token } = config.auth;
+        let token = token.get()?;
+
+        let api_url = match config.api_url {
+            Some(url) => url,
+            None => Url::parse(API_URL)?,
+        };
+
+        Ok(Self {
+            http: RestClient::new(
+                tls,
+                WebdavAuth::Bearer(HttpAuthBearer::new(token.expose_secret())),
+            ),
+            api_url,
+        })
+    }
+
+    /// Lists the calendars of the user. The CTag is the `changeKey` of
+    /// the calendar, Graph calendars having no description.
+    pub fn list_calendars(&self) -> Result<Vec<Calendar>> {
+        let calendars = self.get_all(self.url(&["me", "calendars"])?)?;
+
+        let calendars = calendars
+            .into_iter()
+            .map(|calendar| {
+                let text = |key: &str| {
+                    calendar[key]
+                        .as_str()
+                        .filter(|value| !value.is_empty())
+                        .map(ToOwned::to_owned)
+                };
+
+                Calendar {
+                    id: text("id").unwrap_or_default(),
+                    name: text("name").unwrap_or_default(),
+                    description: None,
+                    color: text("hexColor"),
+                    ctag: text("changeKey"),
+                }
+            })
+            .collect();
+
+        Ok(calendars)
+    }
+
+    /// Creates a calendar named `name`, returning the id Graph assigns
+    /// it in place of `calendar_id`. Graph calendars cannot store a
+    /// description nor a hex color.
+    pub fn create_calendar(
+        &self,
+        calendar_id: &str,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<String> {
+        if description.is_some() || color.is_some() {
+            warn!("Graph calendars have no description nor hex color, ignoring them");
+        }
+
+        let url = self.url(&["me", "calendars"])?;
+        let body = json!({ "name": name });
+        let calendar: Json = self
+            .http
+            .send_json("POST", &url, &[], Some(&body))?
+            .json()?;
+
+        let Some(id) = calendar["id"].as_str() else {
+            bail!("Missing id of the calendar `{calendar_id}` created by Graph");
+        };
+
+        Ok(id.to_owned())
+    }
+
+    /// Renames the calendar `calendar_id`, the only change Graph
+    /// accepts.
+    pub fn update_calendar(&self, calendar_id: &str, patch: CalendarDiff) -> Result<()> {
+        if patch.description.is_some() || patch.color.is_some() {
+            warn!("Graph calendars have no description nor hex color, ignoring them");
+        }
+
+        let Some(name) = patch.name else {
+            return Ok(());
+        };
+
+        let url = self.url(&["me", "calendars", calendar_id])?;
+        let body = json!({ "name": name });
+        self.http.send_json("PATCH", &url, &[], Some(&body))?;
+
+        Ok(())
+    }
+
+    pub fn delete_calendar(&self, calendar_id: &str) -> Result<()> {
+        let url = self.url(&["me", "calendars", calendar_id])?;
+        self.http.send_json("DELETE", &url, &[], None)?;
+        Ok(())
+    }
+
+    /// Lists the events of `calendar_id`: single events and series
+    /// masters, along with the modified and cancelled occurrences of
+    /// the latter.
+    pub fn list_items(&self, calendar_id: &str) -> Result<Vec<CalendarItem>> {
+        let mut url = self.url(&["me", "calendars", calendar_id, "events"])?;
+        url.query_pairs_mut().append_pair("$top", PAGE_SIZE);
+        select_occurrences(&mut url);
+
+        let events = self.get_all(url)?;
+        Ok(events
+            .iter()
+            .map(|event| item(calendar_id, event))
+            .collect())
+    }
+
+    pub fn get_item(&self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        let mut url = self.url(&["me", "events", item_id])?;
+        select_occurrences(&mut url);
+
+        let headers = [("Prefer", PREFER.to_owned())];
+        let event: Json = self.http.send_json("GET", &url, &headers, None)?.json()?;
+
+        Ok(item(calendar_id, &event))
+    }
+
+    /// Creates an event in `calendar_id`, returning its Graph id.
+    pub fn create_item(&self, calendar_id: &str, contents: Vec<u8>) -> Result<String> {
+        let url = self.url(&["me", "calendars", calendar_id, "events"])?;
+        let body = to_graph(&contents)?;
+        let event: Json = self
+            .http
+            .send_json("POST", &url, &[], Some(&body))?
+            .json()?;
+
+        let Some(id) = event["id"].as_str() else {
+            bail!("Missing id of the event created by Graph");
+        };
+
+        self.update_occurrences(id, &contents)?;
+        Ok(id.to_owned())
+    }
+
+    /// Replaces the event `item_id`, guarded by `etag` when given.
+    pub fn update_item(&self, item_id: &str, contents: Vec<u8>, etag: Option<&str>) -> Result<()> {
+        let url = self.url(&["me", "events", item_id])?;
+        let body = to_graph(&contents)?;
+
+        let mut headers = Vec::new();
+        if let Some(etag) = etag {
+            headers.push(("If-Match", etag.to_owned()));
+        }
+
+        self.http.send_json("PATCH", &url, &headers, Some(&body))?;
+        self.update_occurrences(item_id, &contents)
+    }
+
+    pub fn delete_item(&self, item_id: &str) -> Result<()> {
+        let url = self.url(&["me", "events", item_id])?;
+        self.http.send_json("DELETE", &url, &[], None)?;
+        Ok(())
+    }
+
+    /// Applies the occurrences modified and cancelled by `contents` to
+    /// the series `item_id`, each looked up among the instances of the
+    /// series around its original start. Occurrences already cancelled
+    /// are left alone.
+    fn update_occurrences(&self, item_id: &str, contents: &[u8]) -> Result<()> {
+        for exception in exceptions(contents)? {
+            let start = exception.original_start - TimeDelta::days(1);
+            let end = exception.original_start + TimeDelta::days(2);
+
+            let mut url = self.url(&["me", "events", item_id, "instances"])?;
+            url.query_pairs_mut()
+                .append_pair("startDateTime", &start.to_rfc3339())
+                .append_pair("endDateTime", &end.to_rfc3339());
+
+            let instances = self.get_all(url)?;
+            let instance = instances
+                .iter()
+                .find(|instance| exception.is_occurrence(instance));
+
+            let Some(id) = instance.and_then(|instance| instance["id"].as_str()) else {
+                match exception.event {
+                    Some(_) => bail!(
+                        "Cannot find the occurrence of Graph event `{item_id}` starting at {}",
+                        exception.original_start,
+                    ),
+                    None => continue,
+                }
+            };
+
+            let url = self.url(&["me", "events", id])?;
+            match &exception.event {
+                Some(event) => self.http.send_json("PATCH", &url, &[], Some(event))?,
+                None => self.http.send_json("DELETE", &url, &[], None)?,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Builds the API URL of the path `segments`, percent-encoding
+    /// each of them: Graph ids may contain `/`.
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.api_url.clone();
+
+        url.path_segments_mut()
+            .map_err(|()| anyhow!("Invalid Graph API URL `{}`", self.api_url))?
+            .pop_if_empty()
+            .extend(segments);
+
+        Ok(url)
+    }
+
+    /// Collects the `value` of every page of the collection at `url`,
+    /// following `@odata.nextLink`.
+    fn get_all(&self, mut url: Url) -> Result<Vec<Json>> {
+        let headers = [("Prefer", PREFER.to_owned())];
+        let mut values = Vec::new();
+
+        loop {
+            let mut page: Map<String, Json> =
+                self.http.send_json("GET", &url, &headers, None)?.json()?;
+
+            if let Some(Json::Array(value)) = page.remove("value") {
+                values.extend(value);
+            }
+
+            let Some(next) = page.get("@odata.nextLink").and_then(Json::as_str) else {
+                return Ok(values);
+            };
+
+            url = Url::parse(next).with_context(|| format!("Invalid Graph next link `{next}`"))?;
+        }
+    }
+}
+
+/// Selects the fields of the events fetched from `url`, along with
+/// the modified occurrences (`exceptionOccurrences`) and the ids of
+/// the cancelled ones (`cancelledOccurrences`) of series masters, so
+/// that they come with the events themselves.
+fn select_occurrences(url: &mut Url) {
+    url.query_pairs_mut()
+        .append_pair("$select", EVENT_FIELDS)
+        .append_pair("$expand", "exceptionOccurrences");
+}
+
+/// Builds the item of the Graph `event` of `calendar_id`.
+fn item(calendar_id: &str, event: &Json) -> CalendarItem {
+    CalendarItem {
+        id: event["id"].as_str().unwrap_or_default().to_owned(),
+        calendar_id: calendar_id.to_owned(),
+        etag: event["@odata.etag"].as_str().map(ToOwned::to_owned),
+        contents: to_ical(event, Utc::now()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pimalaya_config::secret::Secret;
+
+    use super::GraphClient;
+    use crate::{
+        config::{GraphAuthConfig, GraphConfig},
+        shared::mock::{bind, serve},
+    };
+
+    #[test]
+    fn list_events_following_next_link() {
+        let (listener, api_url) = bind();
+
+        let first = serde_json::json!({
+            "value": [{
+                "id": "e1",
+                "@odata.etag": "W/\"1\"",
+                "type": "seriesMaster",
+                "subject": "One",
+                "cancelledOccurrences": ["OID.e1.2026-03-23"],
+                "start": { "dateTime": "2026-03-16T09:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2026-03-16T10:00:00.0000000", "timeZone": "UTC" },
+            }],
+            "@odata.nextLink": format!("{api_url}/me/calendars/c%2F1/events?%24skip=1"),
+        });
+        let second = serde_json::json!({ "value": [{ "id": "e2", "subject": "Two" }] });
+        let server = serve(
+            listener,
+            vec![(200, first.to_string()), (200, second.to_string())],
+        );
+
+        let client = GraphClient::new(GraphConfig {
+            api_url: Some(api_url.parse().unwrap()),
+            tls: Default::default(),
+            auth: GraphAuthConfig::Bearer {
+                token: Secret::Raw(String::from("token").into()),
+            },
+        })
+        .unwrap();
+
+        let items = client.list_items("c/1").unwrap();
+        let requests = server.join().unwrap();
+
+        let request_lines: Vec<&str> = requests
+            .iter()
+            .filter_map(|request| request.lines().next())
+            .collect();
+        // Occurrences come with the listing, without one request per
+        // series master.
+        assert_eq!(request_lines.len(), 2);
+        assert!(
+            request_lines[0].starts_with("GET /me/calendars/c%2F1/events?%24top=100&%24select=")
+        );
+        assert!(request_lines[0].contains("%2CcancelledOccurrences&"));
+        assert!(request_lines[0].ends_with("&%24expand=exceptionOccurrences HTTP/1.1"));
+        assert_eq!(
+            request_lines[1],
+            "GET /me/calendars/c%2F1/events?%24skip=1 HTTP/1.1"
+        );
+        for request in &requests {
+            assert!(request.contains("\r\nAuthorization: Bearer token\r\n"));
+        }
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "e1");
+        assert_eq!(items[0].calendar_id, "c/1");
+        assert_eq!(items[0].etag.as_deref(), Some("W/\"1\""));
+        let contents = String::from_utf8_lossy(&items[0].contents);
+        assert!(contents.contains("\r\nSUMMARY:One\r\n"));
+        assert!(contents.contains("\r\nDTSTART:20260316T090000Z\r\n"));
+        assert!(contents.contains("\r\nEXDATE:20260323T090000Z\r\n"));
+    }
+}
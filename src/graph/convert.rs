@@ -0,0 +1,976 @@
+//! Conversion between Microsoft Graph events and iCalendar.
+//!
+//! Events are read with their times in UTC and their body as plain
+//! text (see the `Prefer` header of the client). They are written back
+//! with the TZID of their DTSTART as Graph time zone, since Graph
+//! accepts IANA and Windows zone names alike. Like
+//! [`itip`](crate::shared::itip), iCalendar objects are read at the
+//! content-line level.
+//!
+//! The master VEVENT of an object becomes the Graph event. Overridden
+//! occurrences (RECURRENCE-ID) and cancelled ones (EXDATE) live in
+//! their own Graph events, rendered from the `exceptionOccurrences`
+//! and `cancelledOccurrences` of the series master and written back
+//! as [`Exception`]s. Properties Graph has no field for are dropped.
+//! Graph assigns the UID (`iCalUId`) of the events it creates.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use serde_json::{Map, Value as Json, json};
+
+use crate::shared::{
+    datetime::parse_duration,
+    ical::{
+        ContentLine, IcalWriter, PRODID, VEvent, escape_text, events, master_event, parse_lines,
+        split_unescaped, unescape_text,
+    },
+    timezone::Timezone,
+};
+
+/// Event fields read by [`to_ical`], selected when fetching events:
+/// Graph only returns the `cancelledOccurrences` of series masters
+/// when asked for.
+pub const EVENT_FIELDS: &str = "id,iCalUId,createdDateTime,lastModifiedDateTime,start,end,\
+    isAllDay,originalStart,recurrence,subject,body,location,categories,sensitivity,importance,\
+    isCancelled,showAs,organizer,attendees,isReminderOn,reminderMinutesBeforeStart,\
+    cancelledOccurrences";
+
+/// Graph weekdays, in the order of the iCalendar ones.
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("MO", "monday"),
+    ("TU", "tuesday"),
+    ("WE", "wednesday"),
+    ("TH", "thursday"),
+    ("FR", "friday"),
+    ("SA", "saturday"),
+    ("SU", "sunday"),
+];
+
+/// Graph week indexes of relative patterns, by BYSETPOS.
+const INDEXES: [(i32, &str); 5] = [
+    (1, "first"),
+    (2, "second"),
+    (3, "third"),
+    (4, "fourth"),
+    (-1, "last"),
+];
+
+/// Renders the Graph `event` as a VCALENDAR holding its VEVENT,
+/// followed by one VEVENT per modified occurrence when `event` is a
+/// series master. `now` stamps events without modification date.
+pub fn to_ical(event: &Json, now: DateTime<Utc>) -> Vec<u8> {
+    let mut writer = IcalWriter::new();
+    writer
+        .begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .property("PRODID", &[], PRODID);
+
+    write_event(&mut writer, event, None, now);
+
+    for occurrence in event["exceptionOccurrences"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        write_event(&mut writer, occurrence, Some(event), now);
+    }
+
+    writer.end("VCALENDAR");
+    writer.finish()
+}
+
+/// Writes the VEVENT of the Graph `event`, a modified occurrence of
+/// the series `master` when given.
+fn write_event(writer: &mut IcalWriter, event: &Json, master: Option<&Json>, now: DateTime<Utc>) {
+    let text = |pointer: &str| {
+        event
+            .pointer(pointer)
+            .and_then(Json::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let all_day = event["isAllDay"].as_bool() == Some(true);
+    let instant = |pointer: &str| text(pointer).and_then(parse_instant);
+
+    writer.begin("VEVENT");
+
+    // occurrences share the UID of their series
+    let series = master.unwrap_or(event);
+    let uid = ["/iCalUId", "/id"].into_iter().find_map(|pointer| {
+        series
+            .pointer(pointer)
+            .and_then(Json::as_str)
+            .filter(|uid| !uid.is_empty())
+    });
+    if let Some(uid) = uid {
+        writer.text("UID", uid);
+    }
+
+    let stamp = instant("/lastModifiedDateTime").unwrap_or(now);
+    writer.property("DTSTAMP", &[], &format_instant(stamp));
+
+    if let Some(created) = instant("/createdDateTime") {
+        writer.property("CREATED", &[], &format_instant(created));
+    }
+
+    if let Some(modified) = instant("/lastModifiedDateTime") {
+        writer.property("LAST-MODIFIED", &[], &format_instant(modified));
+    }
+
+    write_time(writer, "DTSTART", &event["start"], all_day);
+    write_time(writer, "DTEND", &event["end"], all_day);
+
+    match master {
+        Some(master) => {
+            let all_day = master["isAllDay"].as_bool() == Some(true);
+            if let Some(original) = text("/originalStart") {
+                write_recurrence_id(writer, original, all_day);
+            }
+        }
+        None => {
+            if let Some(rrule) = rrule(&event["recurrence"], all_day) {
+                writer.property("RRULE", &[], &rrule);
+            }
+            write_exdates(writer, event, all_day);
+        }
+    }
+
+    if let Some(subject) = text("/subject") {
+        writer.text("SUMMARY", subject);
+    }
+
+    if text("/body/contentType") != Some("html")
+        && let Some(body) = text("/body/content")
+    {
+        writer.text("DESCRIPTION", body);
+    }
+
+    if let Some(location) = text("/location/displayName") {
+        writer.text("LOCATION", location);
+    }
+
+    let categories: Vec<String> = event["categories"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Json::as_str)
+        .map(escape_text)
+        .collect();
+
+    if !categories.is_empty() {
+        writer.property("CATEGORIES", &[], &categories.join(","));
+    }
+
+    let class = match text("/sensitivity") {
+        Some("private" | "personal") => Some("PRIVATE"),
+        Some("confidential") => Some("CONFIDENTIAL"),
+        _ => None,
+    };
+    if let Some(class) = class {
+        writer.property("CLASS", &[], class);
+    }
+
+    let priority = match text("/importance") {
+        Some("high") => Some("1"),
+        Some("low") => Some("9"),
+        _ => None,
+    };
+    if let Some(priority) = priority {
+        writer.property("PRIORITY", &[], priority);
+    }
+
+    let status = if event["isCancelled"].as_bool() == Some(true) {
+        "CANCELLED"
+    } else if text("/showAs") == Some("tentative") {
+        "TENTATIVE"
+    } else {
+        "CONFIRMED"
+    };
+    writer.property("STATUS", &[], status);
+
+    let transp = match text("/showAs") {
+        Some("free") => "TRANSPARENT",
+        _ => "OPAQUE",
+    };
+    writer.property("TRANSP", &[], transp);
+
+    if let Some(organizer) = event.get("organizer") {
+        write_address(writer, "ORGANIZER", organizer, &[]);
+    }
+
+    for attendee in event["attendees"].as_array().into_iter().flatten() {
+        let partstat = match attendee.pointer("/status/response").and_then(Json::as_str) {
+            Some("accepted" | "organizer") => "ACCEPTED",
+            Some("declined") => "DECLINED",
+            Some("tentativelyAccepted") => "TENTATIVE",
+            _ => "NEEDS-ACTION",
+        };
+
+        let params: &[(&str, &str)] = match attendee["type"].as_str() {
+            Some("optional") => &[("ROLE", "OPT-PARTICIPANT"), ("PARTSTAT", partstat)],
+            Some("resource") => &[("CUTYPE", "RESOURCE"), ("PARTSTAT", partstat)],
+            _ => &[("ROLE", "REQ-PARTICIPANT"), ("PARTSTAT", partstat)],
+        };
+
+        write_address(writer, "ATTENDEE", attendee, params);
+    }
+
+    if event["isReminderOn"].as_bool() == Some(true)
+        && let Some(minutes) = event["reminderMinutesBeforeStart"].as_i64()
+    {
+        writer
+            .begin("VALARM")
+            .property("ACTION", &[], "DISPLAY")
+            .text("DESCRIPTION", text("/subject").unwrap_or("Reminder"))
+            .property("TRIGGER", &[], &format!("-PT{minutes}M"))
+            .end("VALARM");
+    }
+
+    writer.end("VEVENT");
+}
+
+/// Builds the Graph event matching the master VEVENT of `contents`.
+pub fn to_graph(contents: &[u8]) -> Result<Json> {
+    let Some(event) = master_event(parse_lines(contents)) else {
+        bail!("Cannot find any VEVENT to send to Graph");
+    };
+
+    graph_event(event)
+}
+
+/// Occurrence of a series modified or cancelled by an iCalendar
+/// object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exception {
+    /// Original start of the occurrence, only its date counting for
+    /// all-day series.
+    pub original_start: DateTime<Utc>,
+    pub all_day: bool,
+    /// Graph event patching the occurrence, [`None`] when cancelled.
+    pub event: Option<Json>,
+}
+
+impl Exception {
+    /// Whether the Graph occurrence `instance` is the one this
+    /// exception applies to.
+    pub fn is_occurrence(&self, instance: &Json) -> bool {
+        let Some(original) = instance["originalStart"].as_str() else {
+            return false;
+        };
+
+        if self.all_day {
+            original.get(..10) == Some(&self.original_start.format("%Y-%m-%d").to_string())
+        } else {
+            parse_instant(original) == Some(self.original_start)
+        }
+    }
+}
+
+/// Lists the occurrences modified (VEVENTs with RECURRENCE-ID) and
+/// cancelled (EXDATEs of the master) by `contents`.
+pub fn exceptions(contents: &[u8]) -> Result<Vec<Exception>> {
+    let mut exceptions = Vec::new();
+
+    for event in events(parse_lines(contents)) {
+        let recurrence_id = event
+            .props
+            .iter()
+            .find(|line| line.is("RECURRENCE-ID"))
+            .cloned();
+
+        if let Some(recurrence_id) = recurrence_id {
+            let (original_start, all_day) = instant(&recurrence_id)?;
+            let mut event = graph_event(event)?;
+            if let Some(event) = event.as_object_mut() {
+                event.remove("recurrence");
+            }
+
+            exceptions.push(Exception {
+                original_start,
+                all_day,
+                event: Some(event),
+            });
+            continue;
+        }
+
+        for exdate in event.props.iter().filter(|line| line.is("EXDATE")) {
+            for value in exdate.value.split(',') {
+                let mut line = exdate.clone();
+                line.value = value.trim().to_owned();
+                let (original_start, all_day) = instant(&line)?;
+
+                exceptions.push(Exception {
+                    original_start,
+                    all_day,
+                    event: None,
+                });
+            }
+        }
+    }
+
+    Ok(exceptions)
+}
+
+/// Builds the Graph event of the VEVENT `event`.
+fn graph_event(VEvent { props, alarms }: VEvent) -> Result<Json> {
+    let prop = |name: &str| props.iter().find(|line| line.is(name));
+    let text = |name: &str| prop(name).map(|line| unescape_text(&line.value));
+
+    let Some(dtstart) = prop("DTSTART") else {
+        bail!("Cannot send an event without DTSTART to Graph");
+    };
+    let (start, all_day) = dtstart.date_time()?;
+
+    let end = match (prop("DTEND"), prop("DURATION")) {
+        (Some(dtend), _) => dtend.date_time()?.0,
+        (None, Some(duration)) => start + parse_duration(&duration.value)?,
+        (None, None) if all_day => start + TimeDelta::days(1),
+        (None, None) => start,
+    };
+
+    let zone = match (dtstart.param("TZID"), dtstart.value.ends_with(['z', 'Z'])) {
+        (Some(tzid), false) if !all_day => tzid.to_owned(),
+        _ => String::from("UTC"),
+    };
+    let time = |dt: NaiveDateTime| {
+        json!({
+            "dateTime": dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "timeZone": zone,
+        })
+    };
+
+    let mut event = Map::new();
+    event.insert("subject".into(), json!(text("SUMMARY").unwrap_or_default()));
+    event.insert(
+        "body".into(),
+        json!({
+            "contentType": "text",
+            "content": text("DESCRIPTION").unwrap_or_default(),
+        }),
+    );
+    event.insert("start".into(), time(start));
+    event.insert("end".into(), time(end));
+    event.insert("isAllDay".into(), json!(all_day));
+    event.insert(
+        "location".into(),
+        json!({ "displayName": text("LOCATION").unwrap_or_default() }),
+    );
+
+    let categories: Vec<String> = props
+        .iter()
+        .filter(|line| line.is("CATEGORIES"))
+        .flat_map(|line| split_unescaped(&line.value, ','))
+        .map(|category| unescape_text(category.trim()))
+        .filter(|category| !category.is_empty())
+        .collect();
+    event.insert("categories".into(), json!(categories));
+
+    let sensitivity = match prop("CLASS").map(|line| line.value.to_ascii_uppercase()) {
+        Some(class) if class == "PRIVATE" => "private",
+        Some(class) if class == "CONFIDENTIAL" => "confidential",
+        _ => "normal",
+    };
+    event.insert("sensitivity".into(), json!(sensitivity));
+
+    let importance = match prop("PRIORITY").and_then(|line| line.value.trim().parse::<u8>().ok()) {
+        Some(1..=4) => "high",
+        Some(6..=9) => "low",
+        _ => "normal",
+    };
+    event.insert("importance".into(), json!(importance));
+
+    let is = |name: &str, value: &str| {
+        prop(name).is_some_and(|line| line.value.eq_ignore_ascii_case(value))
+    };
+    let show_as = if is("TRANSP", "TRANSPARENT") {
+        "free"
+    } else if is("STATUS", "TENTATIVE") {
+        "tentative"
+    } else {
+        "busy"
+    };
+    event.insert("showAs".into(), json!(show_as));
+
+    let attendees: Vec<Json> = props
+        .iter()
+        .filter(|line| line.is("ATTENDEE"))
+        .map(|line| {
+            let kind = match (line.param("CUTYPE"), line.param("ROLE")) {
+                (Some(cutype), _) if cutype.eq_ignore_ascii_case("RESOURCE") => "resource",
+                (_, Some(role)) if role.eq_ignore_ascii_case("OPT-PARTICIPANT") => "optional",
+                _ => "required",
+            };
+
+            json!({ "type": kind, "emailAddress": email_address(line) })
+        })
+        .collect();
+    event.insert("attendees".into(), json!(attendees));
+
+    match props.iter().find(|line| line.is("RRULE")) {
+        Some(rrule) => {
+            let recurrence = recurrence(&rrule.value, start.date())?;
+            event.insert("recurrence".into(), recurrence);
+        }
+        None => {
+            event.insert("recurrence".into(), Json::Null);
+        }
+    }
+
+    let reminder = alarms.iter().find_map(|alarm| {
+        let trigger = alarm.iter().find(|line| line.is("TRIGGER"))?;
+        let related_end = trigger
+            .param("RELATED")
+            .is_some_and(|related| related.eq_ignore_ascii_case("END"));
+
+        // Absolute triggers and triggers relative to the end have no
+        // Graph equivalent; those at or after the start remind at it.
+        if related_end || trigger.param("VALUE").is_some() {
+            return None;
+        }
+
+        match trigger.value.trim().strip_prefix('-') {
+            Some(before) => Some(parse_duration(before).ok()?.num_minutes()),
+            None => Some(0),
+        }
+    });
+    event.insert("isReminderOn".into(), json!(reminder.is_some()));
+    if let Some(minutes) = reminder {
+        event.insert("reminderMinutesBeforeStart".into(), json!(minutes));
+    }
+
+    Ok(Json::Object(event))
+}
+
+/// Parses a Graph `DateTimeOffset` (`2026-03-16T09:00:00.1234567Z`).
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn format_instant(instant: DateTime<Utc>) -> String {
+    instant.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Resolves the DATE or DATE-TIME property `line` to an instant,
+/// returning whether it is a DATE. Floating times are read as UTC,
+/// the zone Graph events are rendered in.
+fn instant(line: &ContentLine) -> Result<(DateTime<Utc>, bool)> {
+    let (dt, all_day) = line.date_time()?;
+
+    let tzid = match line.param("TZID") {
+        Some(tzid) if !all_day && !line.value.ends_with(['z', 'Z']) => tzid,
+        _ => return Ok((dt.and_utc(), all_day)),
+    };
+
+    let Ok(tz) = tzid.parse::<chrono_tz::Tz>() else {
+        bail!(
+            "Cannot resolve the TZID `{tzid}` of {} for Graph",
+            line.name
+        );
+    };
+
+    Ok((Timezone::Tz(tz).to_utc(dt), false))
+}
+
+/// Writes the RECURRENCE-ID of the occurrence originally starting at
+/// the Graph `DateTimeOffset` `original`.
+fn write_recurrence_id(writer: &mut IcalWriter, original: &str, all_day: bool) {
+    if all_day {
+        let date = original.get(..10).unwrap_or_default();
+        if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            let value = date.format("%Y%m%d").to_string();
+            writer.property("RECURRENCE-ID", &[("VALUE", "DATE")], &value);
+        }
+    } else if let Some(original) = parse_instant(original) {
+        writer.property("RECURRENCE-ID", &[], &format_instant(original));
+    }
+}
+
+/// Writes an EXDATE per cancelled occurrence of the series master
+/// `event`. Their ids end with the date of the occurrence
+/// (`OID.{id}.2026-03-19`), its time being the one of the master.
+fn write_exdates(writer: &mut IcalWriter, event: &Json, all_day: bool) {
+    let start = event
+        .pointer("/start/dateTime")
+        .and_then(Json::as_str)
+        .and_then(|start| NaiveDateTime::parse_from_str(start, "%Y-%m-%dT%H:%M:%S%.f").ok());
+    let Some(start) = start else {
+        return;
+    };
+
+    for id in event["cancelledOccurrences"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Json::as_str)
+    {
+        let date = id.rsplit('.').next().unwrap_or_default();
+        let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+            continue;
+        };
+
+        if all_day {
+            let value = date.format("%Y%m%d").to_string();
+            writer.property("EXDATE", &[("VALUE", "DATE")], &value);
+        } else {
+            let value = format_instant(date.and_time(start.time()).and_utc());
+            writer.property("EXDATE", &[], &value);
+        }
+    }
+}
+
+/// Writes the Graph `dateTimeTimeZone` `time` as the DATE or
+/// DATE-TIME property `name`.
+fn write_time(writer: &mut IcalWriter, name: &str, time: &Json, all_day: bool) {
+    let Some(value) = time["dateTime"].as_str() else {
+        return;
+    };
+
+    let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") else {
+        return;
+    };
+
+    let zone = time["timeZone"].as_str().unwrap_or("UTC");
+
+    if all_day {
+        writer.property(name, &[("VALUE", "DATE")], &dt.format("%Y%m%d").to_string());
+    } else if zone.is_empty() || zone.eq_ignore_ascii_case("UTC") {
+        writer.property(name, &[], &dt.format("%Y%m%dT%H%M%SZ").to_string());
+    } else {
+        let value = dt.format("%Y%m%dT%H%M%S").to_string();
+        writer.property(name, &[("TZID", zone)], &value);
+    }
+}
+
+/// Writes the Graph `recipient` (or `attendee`) as the CAL-ADDRESS
+/// property `name`.
+fn write_address(writer: &mut IcalWriter, name: &str, recipient: &Json, params: &[(&str, &str)]) {
+    let Some(address) = recipient
+        .pointer("/emailAddress/address")
+        .and_then(Json::as_str)
+    else {
+        return;
+    };
+
+    let mut params = params.to_vec();
+    let cn = recipient
+        .pointer("/emailAddress/name")
+        .and_then(Json::as_str);
+    if let Some(cn) = cn.filter(|cn| !cn.is_empty()) {
+        params.insert(0, ("CN", cn));
+    }
+
+    writer.property(name, &params, &format!("mailto:{address}"));
+}
+
+/// Graph `emailAddress` of the CAL-ADDRESS property `line`.
+fn email_address(line: &ContentLine) -> Json {
+    let value = line.value.trim();
+    let address = match value.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+        _ => value,
+    };
+
+    match line.param("CN") {
+        Some(name) => json!({ "address": address, "name": name }),
+        None => json!({ "address": address }),
+    }
+}
+
+/// Renders the Graph `patternedRecurrence` as an RRULE value.
+fn rrule(recurrence: &Json, all_day: bool) -> Option<String> {
+    let pattern = &recurrence["pattern"];
+    let range = &recurrence["range"];
+
+    let freq = match pattern["type"].as_str()? {
+        "daily" => "DAILY",
+        "weekly" => "WEEKLY",
+        "absoluteMonthly" | "relativeMonthly" => "MONTHLY",
+        "absoluteYearly" | "relativeYearly" => "YEARLY",
+        _ => return None,
+    };
+
+    let mut rule = format!("FREQ={freq}");
+
+    if let Some(interval) = pattern["interval"].as_u64().filter(|n| *n > 1) {
+        rule.push_str(&format!(";INTERVAL={interval}"));
+    }
+
+    if let Some(month) = pattern["month"].as_u64().filter(|n| *n > 0) {
+        rule.push_str(&format!(";BYMONTH={month}"));
+    }
+
+    if let Some(day) = pattern["dayOfMonth"].as_u64().filter(|n| *n > 0) {
+        rule.push_str(&format!(";BYMONTHDAY={day}"));
+    }
+
+    let days: Vec<&str> = pattern["daysOfWeek"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Json::as_str)
+        .filter_map(|day| WEEKDAYS.iter().find(|(_, graph)| *graph == day))
+        .map(|(ical, _)| *ical)
+        .collect();
+
+    if !days.is_empty() && freq != "DAILY" {
+        rule.push_str(&format!(";BYDAY={}", days.join(",")));
+
+        if freq != "WEEKLY" {
+            let index = pattern["index"].as_str().unwrap_or("first");
+            if let Some((pos, _)) = INDEXES.iter().find(|(_, graph)| *graph == index) {
+                rule.push_str(&format!(";BYSETPOS={pos}"));
+            }
+        }
+    }
+
+    if freq == "WEEKLY"
+        && let Some(first) = pattern["firstDayOfWeek"].as_str()
+        && let Some((wkst, _)) = WEEKDAYS.iter().find(|(_, graph)| *graph == first)
+    {
+        rule.push_str(&format!(";WKST={wkst}"));
+    }
+
+    match range["type"].as_str() {
+        Some("numbered") => {
+            let count = range["numberOfOccurrences"].as_u64()?;
+            rule.push_str(&format!(";COUNT={count}"));
+        }
+        Some("endDate") => {
+            let date = range["endDate"].as_str()?;
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            let until = match all_day {
+                true => date.format("%Y%m%d").to_string(),
+                false => date.format("%Y%m%dT235959Z").to_string(),
+            };
+            rule.push_str(&format!(";UNTIL={until}"));
+        }
+        _ => (),
+    }
+
+    Some(rule)
+}
+
+/// Builds the Graph `patternedRecurrence` of the RRULE value `rrule`
+/// of an event starting on `start`.
+fn recurrence(rrule: &str, start: NaiveDate) -> Result<Json> {
+    let parts: Vec<(String, &str)> = rrule
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.trim().to_ascii_uppercase(), value.trim()))
+        .collect();
+    let part = |key: &str| parts.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+    let number = |key: &str| part(key).and_then(|value| value.parse::<i32>().ok());
+
+    let unsupported = || anyhow!("RRULE `{rrule}` cannot be expressed as a Graph recurrence");
+    let weekday = |day: &str| {
+        WEEKDAYS
+            .iter()
+            .find(|(ical, _)| day.eq_ignore_ascii_case(ical))
+            .map(|(_, graph)| *graph)
+    };
+
+    // Splits BYDAY into its weekdays and its ordinal, shared by every
+    // weekday as Graph relative patterns require.
+    let mut ordinal = number("BYSETPOS");
+    let mut days = Vec::new();
+    for day in part("BYDAY")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|d| !d.is_empty())
+    {
+        if !day.is_ascii() || day.len() < 2 {
+            return Err(unsupported());
+        }
+
+        let (pos, day) = day.split_at(day.len() - 2);
+        days.push(weekday(day).ok_or_else(unsupported)?);
+
+        if !pos.is_empty() {
+            let pos = pos
+                .trim_start_matches('+')
+                .parse::<i32>()
+                .map_err(|_| unsupported())?;
+            if ordinal.is_some_and(|ordinal| ordinal != pos) {
+                return Err(unsupported());
+            }
+            ordinal = Some(pos);
+        }
+    }
+
+    let index = match ordinal {
+        Some(ordinal) => INDEXES
+            .iter()
+            .find(|(pos, _)| *pos == ordinal)
+            .map(|(_, index)| *index)
+            .ok_or_else(unsupported)?,
+        None => "first",
+    };
+
+    let start_weekday = WEEKDAYS[start.weekday().num_days_from_monday() as usize].1;
+    let month = number("BYMONTH").unwrap_or(start.month() as i32);
+    let day_of_month = number("BYMONTHDAY").unwrap_or(start.day() as i32);
+
+    let mut pattern = json!({ "interval": number("INTERVAL").unwrap_or(1) });
+
+    match (
+        part("FREQ").map(str::to_ascii_uppercase).as_deref(),
+        days.is_empty(),
+    ) {
+        (Some("DAILY"), true) => {
+            pattern["type"] = json!("daily");
+        }
+        (Some("WEEKLY"), _) => {
+            if days.is_empty() {
+                days.push(start_weekday);
+            }
+            let first = part("WKST").and_then(weekday).unwrap_or("monday");
+            pattern["type"] = json!("weekly");
+            pattern["daysOfWeek"] = json!(days);
+            pattern["firstDayOfWeek"] = json!(first);
+        }
+        (Some("MONTHLY"), true) => {
+            pattern["type"] = json!("absoluteMonthly");
+            pattern["dayOfMonth"] = json!(day_of_month);
+        }
+        (Some("MONTHLY"), false) => {
+            pattern["type"] = json!("relativeMonthly");
+            pattern["daysOfWeek"] = json!(days);
+            pattern["index"] = json!(index);
+        }
+        (Some("YEARLY"), true) => {
+            pattern["type"] = json!("absoluteYearly");
+            pattern["month"] = json!(month);
+            pattern["dayOfMonth"] = json!(day_of_month);
+        }
+        (Some("YEARLY"), false) => {
+            pattern["type"] = json!("relativeYearly");
+            pattern["month"] = json!(month);
+            pattern["daysOfWeek"] = json!(days);
+            pattern["index"] = json!(index);
+        }
+        _ => return Err(unsupported()),
+    }
+
+    let start_date = start.format("%Y-%m-%d").to_string();
+
+    let range = match (number("COUNT"), part("UNTIL")) {
+        (Some(count), _) => json!({
+            "type": "numbered",
+            "startDate": start_date,
+            "numberOfOccurrences": count,
+        }),
+        (None, Some(until)) => {
+            let until = until.get(..8).unwrap_or_default();
+            let Ok(until) = NaiveDate::parse_from_str(until, "%Y%m%d") else {
+                return Err(unsupported());
+            };
+            json!({
+                "type": "endDate",
+                "startDate": start_date,
+                "endDate": until.format("%Y-%m-%d").to_string(),
+            })
+        }
+        (None, None) => json!({ "type": "noEnd", "startDate": start_date }),
+    };
+
+    Ok(json!({ "pattern": pattern, "range": range }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use super::{exceptions, to_graph, to_ical};
+
+    #[test]
+    fn graph_event_to_ical() {
+        let event = json!({
+            "id": "AAMkAD=",
+            "iCalUId": "040000008200E0",
+            "subject": "Sprint review",
+            "body": { "contentType": "text", "content": "Demo, then retro" },
+            "start": { "dateTime": "2026-03-16T09:00:00.0000000", "timeZone": "UTC" },
+            "end": { "dateTime": "2026-03-16T10:00:00.0000000", "timeZone": "UTC" },
+            "isAllDay": false,
+            "lastModifiedDateTime": "2026-03-01T08:30:00.1234567Z",
+            "showAs": "tentative",
+            "sensitivity": "private",
+            "categories": ["Work"],
+            "organizer": { "emailAddress": { "name": "Jane", "address": "jane@example.com" } },
+            "attendees": [{
+                "type": "optional",
+                "status": { "response": "accepted" },
+                "emailAddress": { "address": "bob@example.com" },
+            }],
+            "recurrence": {
+                "pattern": { "type": "relativeMonthly", "interval": 1, "daysOfWeek": ["monday"], "index": "third" },
+                "range": { "type": "numbered", "startDate": "2026-03-16", "numberOfOccurrences": 6 },
+            },
+            "isReminderOn": true,
+            "reminderMinutesBeforeStart": 15,
+        });
+
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let ical = String::from_utf8(to_ical(&event, now)).unwrap();
+
+        for line in [
+            "UID:040000008200E0",
+            "DTSTAMP:20260301T083000Z",
+            "DTSTART:20260316T090000Z",
+            "DTEND:20260316T100000Z",
+            "RRULE:FREQ=MONTHLY;BYDAY=MO;BYSETPOS=3;COUNT=6",
+            "SUMMARY:Sprint review",
+            "DESCRIPTION:Demo\\, then retro",
+            "CLASS:PRIVATE",
+            "STATUS:TENTATIVE",
+            "ORGANIZER;CN=Jane:mailto:jane@example.com",
+            "ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:bob@example.com",
+            "TRIGGER:-PT15M",
+        ] {
+            assert!(
+                ical.contains(&format!("{line}\r\n")),
+                "missing {line} in {ical}"
+            );
+        }
+    }
+
+    #[test]
+    fn ical_to_graph_event() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "DURATION:PT1H30M\r\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20260430T220000Z\r\n",
+            "SUMMARY:Standup\r\n",
+            "CATEGORIES:Work,Team\\, core\r\n",
+            "TRANSP:TRANSPARENT\r\n",
+            "ATTENDEE;CN=Bob;ROLE=OPT-PARTICIPANT:mailto:bob@example.com\r\n",
+            "BEGIN:VALARM\r\n",
+            "TRIGGER:-PT10M\r\n",
+            "END:VALARM\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "RECURRENCE-ID;TZID=Europe/Paris:20260319T090000\r\n",
+            "DTSTART;TZID=Europe/Paris:20260319T100000\r\n",
+            "SUMMARY:Moved standup\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let event = to_graph(ical.as_bytes()).unwrap();
+
+        assert_eq!(event["subject"], "Standup");
+        assert_eq!(
+            event["start"],
+            json!({ "dateTime": "2026-03-16T09:00:00", "timeZone": "Europe/Paris" }),
+        );
+        assert_eq!(event["end"]["dateTime"], "2026-03-16T10:30:00");
+        assert_eq!(event["isAllDay"], false);
+        assert_eq!(event["categories"], json!(["Work", "Team, core"]));
+        assert_eq!(event["showAs"], "free");
+        assert_eq!(
+            event["attendees"],
+            json!([{
+                "type": "optional",
+                "emailAddress": { "address": "bob@example.com", "name": "Bob" },
+            }]),
+        );
+        assert_eq!(
+            event["recurrence"],
+            json!({
+                "pattern": {
+                    "type": "weekly",
+                    "interval": 1,
+                    "daysOfWeek": ["monday", "thursday"],
+                    "firstDayOfWeek": "monday",
+                },
+                "range": { "type": "endDate", "startDate": "2026-03-16", "endDate": "2026-04-30" },
+            }),
+        );
+        assert_eq!(event["reminderMinutesBeforeStart"], 10);
+    }
+
+    #[test]
+    fn graph_occurrences_to_ical() {
+        let event = json!({
+            "id": "AAMkAD=",
+            "iCalUId": "040000008200E0",
+            "type": "seriesMaster",
+            "subject": "Standup",
+            "start": { "dateTime": "2026-03-16T08:00:00.0000000", "timeZone": "UTC" },
+            "end": { "dateTime": "2026-03-16T08:15:00.0000000", "timeZone": "UTC" },
+            "recurrence": {
+                "pattern": { "type": "daily", "interval": 1 },
+                "range": { "type": "noEnd", "startDate": "2026-03-16" },
+            },
+            "cancelledOccurrences": ["OID.AAMkAD=.2026-03-18"],
+            "exceptionOccurrences": [{
+                "id": "AAMkAE=",
+                "type": "exception",
+                "subject": "Moved standup",
+                "originalStart": "2026-03-19T08:00:00Z",
+                "start": { "dateTime": "2026-03-19T09:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2026-03-19T09:15:00.0000000", "timeZone": "UTC" },
+            }],
+        });
+
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let ical = String::from_utf8(to_ical(&event, now)).unwrap();
+
+        let (master, occurrence) = ical.split_once("END:VEVENT\r\n").unwrap();
+        assert!(master.contains("\r\nEXDATE:20260318T080000Z\r\n"));
+        assert!(occurrence.contains("\r\nUID:040000008200E0\r\n"));
+        assert!(occurrence.contains("\r\nRECURRENCE-ID:20260319T080000Z\r\n"));
+        assert!(occurrence.contains("\r\nDTSTART:20260319T090000Z\r\n"));
+        assert!(occurrence.contains("\r\nSUMMARY:Moved standup\r\n"));
+        assert!(!occurrence.contains("RRULE"));
+    }
+
+    #[test]
+    fn ical_exceptions() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "RRULE:FREQ=DAILY\r\n",
+            "EXDATE;TZID=Europe/Paris:20260317T090000,20260318T090000\r\n",
+            "SUMMARY:Standup\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc\r\n",
+            "RECURRENCE-ID:20260319T080000Z\r\n",
+            "DTSTART;TZID=Europe/Paris:20260319T100000\r\n",
+            "SUMMARY:Moved standup\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let exceptions = exceptions(ical.as_bytes()).unwrap();
+        let starts: Vec<String> = exceptions
+            .iter()
+            .map(|exception| exception.original_start.to_rfc3339())
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                "2026-03-17T08:00:00+00:00",
+                "2026-03-18T08:00:00+00:00",
+                "2026-03-19T08:00:00+00:00",
+            ],
+        );
+
+        assert!(exceptions[0].event.is_none());
+        let moved = exceptions[2].event.as_ref().unwrap();
+        assert_eq!(moved["subject"], "Moved standup");
+        assert_eq!(moved["start"]["dateTime"], "2026-03-19T10:00:00");
+        assert!(moved.get("recurrence").is_none());
+        assert!(exceptions[2].is_occurrence(&json!({ "originalStart": "2026-03-19T08:00:00Z" })));
+    }
+}
@@ -0,0 +1,10 @@
+//! Microsoft Graph backend (`graph`), for Outlook and Microsoft 365
+//! calendars.
+//!
+//! Calendars and events go through the Graph REST API, authenticated
+//! with an OAuth 2.0 bearer token. Events are converted to and from
+//! iCalendar on the fly, so that the shared commands keep emitting and
+//! accepting ICS.
+
+pub mod client;
+pub mod convert;
@@ -0,0 +1,300 @@
+//! Client of the read-only ics backend.
+//!
+//! Serves the calendars and items of the configured feeds to
+//! [`crate::shared::client::CalendarClient`]. Remote feeds go through
+//! the account cache: the body is cached along with the `ETag` and
+//! `Last-Modified` validators it was downloaded with, sent back as
+//! `If-None-Match` and `If-Modified-Since`, so an unchanged feed costs
+//! a `304 Not Modified` round-trip. The cached body is served as is
+//! when the feed cannot be reached. Local files are read on every
+//! call.
+//!
+//! Items are the components of a feed split per UID, their id being
+//! the UID. Components without one get a UID derived from their
+//! contents, so that ids (and the fired alarms of `watch`) stay the
+//! same from one run to the next.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use io_calendar::{calendar::Calendar, item::CalendarItem};
+use io_webdav::{client::WebdavClientStd, rfc4918::WebdavAuth};
+use log::{debug, warn};
+use pimalaya_stream::tls::Tls;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    config::{IcsConfig, IcsFeedConfig},
+    shared::{cache::CacheDir, ical::split_objects},
+};
+
+/// Maximum number of redirections followed when downloading a feed.
+const MAX_REDIRECTS: usize = 5;
+
+pub struct IcsClient {
+    feeds: Vec<IcsFeedConfig>,
+    tls: Tls,
+    cache: CacheDir,
+    /// Items of the feeds already loaded by this run, by calendar id.
+    loaded: HashMap<String, Vec<CalendarItem>>,
+}
+
+impl IcsClient {
+    /// Builds the client of the account `account` from `config`.
+    pub fn new(account: &str, config: IcsConfig) -> Result<Self> {
+        let mut tls: Tls = config.tls.into();
+        tls.rustls.alpn = vec!["http/1.1".into()];
+
+        Ok(Self {
+            feeds: config.feeds,
+            tls,
+            cache: CacheDir::new(account, "ics")?,
+            loaded: HashMap::new(),
+        })
+    }
+
+    /// Lists one calendar per configured feed, without downloading
+    /// anything. The CTag is the `ETag` of the cached feed.
+    pub fn list_calendars(&self) -> Vec<Calendar> {
+        self.feeds
+            .iter()
+            .map(|feed| Calendar {
+                id: feed.id.clone(),
+                name: feed.name.clone().unwrap_or_else(|| feed.id.clone()),
+                description: feed.description.clone(),
+                color: feed.color.clone(),
+                ctag: self.cache.load::<CachedFeed>(&feed.id).etag,
+            })
+            .collect()
+    }
+
+    /// Lists the items of the feed `calendar_id`, one per UID.
+    pub fn list_items(&mut self, calendar_id: &str) -> Result<Vec<CalendarItem>> {
+        if let Some(items) = self.loaded.get(calendar_id) {
+            return Ok(items.clone());
+        }
+
+        let Some(feed) = self.feeds.iter().find(|feed| feed.id == calendar_id) else {
+            bail!("Cannot find ics feed `{calendar_id}`");
+        };
+
+        let contents = match parse_source(&feed.source)? {
+            Source::File(path) => fs::read(&path)
+                .with_context(|| format!("Read ics feed `{}` error", path.display()))?,
+            Source::Url(url) => self.fetch(calendar_id, url)?,
+        };
+
+        let items: Vec<CalendarItem> = split_objects(&contents)
+            .into_iter()
+            .map(|object| CalendarItem {
+                id: object.uid,
+                calendar_id: calendar_id.to_owned(),
+                etag: None,
+                contents: object.contents,
+            })
+            .collect();
+
+        self.loaded.insert(calendar_id.to_owned(), items.clone());
+        Ok(items)
+    }
+
+    /// Returns the item `item_id` (its UID) of the feed `calendar_id`.
+    pub fn get_item(&mut self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        self.list_items(calendar_id)?
+            .into_iter()
+            .find(|item| item.id == item_id)
+            .ok_or_else(|| anyhow!("Cannot find item `{item_id}` in ics feed `{calendar_id}`"))
+    }
+
+    /// Returns the body of the remote feed `calendar_id`, revalidating
+    /// the cached one. Falls back to the cache when the download fails.
+    fn fetch(&self, calendar_id: &str, url: Url) -> Result<Vec<u8>> {
+        let cached: CachedFeed = self.cache.load(calendar_id);
+
+        match download(url, &self.tls, &cached) {
+            Ok(Download::Modified(feed)) => {
+                if let Err(err) = self.cache.save(calendar_id, &feed) {
+                    debug!("cannot save feed cache of `{calendar_id}`: {err:?}");
+                }
+                return Ok(feed.contents.unwrap_or_default().into_bytes());
+            }
+            Ok(Download::NotModified) => {
+                debug!("ics feed `{calendar_id}` not modified, serving cache");
+            }
+            Err(err) if cached.contents.is_none() => {
+                return Err(err.context(format!("Download ics feed `{calendar_id}` error")));
+            }
+            Err(err) => {
+                warn!("cannot download ics feed `{calendar_id}`, serving cache: {err}");
+            }
+        }
+
+        // Validators are only sent along a cached body, so a feed not
+        // modified always has one.
+        Ok(cached.contents.unwrap_or_default().into_bytes())
+    }
+}
+
+/// Last downloaded version of a feed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CachedFeed {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Raw iCalendar body, [`None`] when never downloaded.
+    contents: Option<String>,
+}
+
+/// Where a feed is read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    Url(Url),
+    File(PathBuf),
+}
+
+/// Resolves the `source` of a feed: `webcal(s)://` URLs are fetched
+/// over HTTPS, `file://` URLs and anything not looking like a URL are
+/// local paths.
+fn parse_source(source: &str) -> Result<Source> {
+    let scheme = source
+        .split_once("://")
+        .map(|(scheme, rest)| (scheme.to_ascii_lowercase(), rest));
+
+    let url = match scheme {
+        Some((scheme, rest)) if scheme == "webcal" || scheme == "webcals" => {
+            format!("https://{rest}")
+        }
+        Some((scheme, _)) if scheme == "http" || scheme == "https" => source.to_owned(),
+        Some((scheme, _)) if scheme == "file" => {
+            let url = Url::parse(source).with_context(|| format!("Invalid feed `{source}`"))?;
+            let path = url
+                .to_file_path()
+                .map_err(|()| anyhow!("Invalid feed path `{source}`"))?;
+            return Ok(Source::File(path));
+        }
+        Some((scheme, _)) => bail!("Unsupported feed scheme `{scheme}` in `{source}`"),
+        None => return Ok(Source::File(PathBuf::from(source))),
+    };
+
+    let url = Url::parse(&url).with_context(|| format!("Invalid feed URL `{source}`"))?;
+    Ok(Source::Url(url))
+}
+
+/// Outcome of a conditional feed download.
+enum Download {
+    NotModified,
+    Modified(CachedFeed),
+}
+
+/// Downloads the feed at `url`, sending the validators of `cached` as
+/// `If-None-Match` and `If-Modified-Since`, following redirections.
+fn download(mut url: Url, tls: &Tls, cached: &CachedFeed) -> Result<Download> {
+    let mut headers = Vec::new();
+
+    if cached.contents.is_some() {
+        if let Some(etag) = &cached.etag {
+            headers.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.push(("If-Modified-Since", last_modified.clone()));
+        }
+    }
+
+    for _ in 0..=MAX_REDIRECTS {
+        let mut client = WebdavClientStd::connect(&url, tls, WebdavAuth::None)?;
+        let response = client.get(&url, &headers)?;
+
+        match response.status {
+            304 => return Ok(Download::NotModified),
+            200..=299 => {
+                return Ok(Download::Modified(CachedFeed {
+                    etag: response.header("ETag").map(ToOwned::to_owned),
+                    last_modified: response.header("Last-Modified").map(ToOwned::to_owned),
+                    contents: Some(String::from_utf8_lossy(&response.body).into_owned()),
+                }));
+            }
+            301 | 302 | 303 | 307 | 308 => {
+                let Some(location) = response.header("Location") else {
+                    bail!("Redirection from `{url}` without Location");
+                };
+                url = url
+                    .join(location)
+                    .with_context(|| format!("Invalid redirection `{location}` from `{url}`"))?;
+                debug!("ics feed redirected to {url}");
+            }
+            status => bail!("Download `{url}` failed with status {status}"),
+        }
+    }
+
+    bail!("Too many redirections downloading `{url}`")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use url::Url;
+
+    use super::{IcsClient, Source, parse_source};
+    use crate::config::{IcsConfig, IcsFeedConfig, TlsConfig};
+
+    #[test]
+    fn feed_sources() {
+        let url = |url: &str| Source::Url(Url::parse(url).unwrap());
+
+        assert_eq!(
+            parse_source("webcal://example.org/holidays.ics").unwrap(),
+            url("https://example.org/holidays.ics"),
+        );
+        assert_eq!(
+            parse_source("http://example.org/team.ics").unwrap(),
+            url("http://example.org/team.ics"),
+        );
+        assert_eq!(
+            parse_source("/tmp/roster.ics").unwrap(),
+            Source::File(PathBuf::from("/tmp/roster.ics")),
+        );
+        assert!(parse_source("ftp://example.org/holidays.ics").is_err());
+    }
+
+    #[test]
+    fn stable_uid_across_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roster.ics");
+        fs::write(
+            &path,
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Test//EN\r\n",
+                "BEGIN:VEVENT\r\n",
+                "SUMMARY:On call\r\n",
+                "DTSTART:20261001T090000Z\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            ),
+        )
+        .unwrap();
+
+        let client = || {
+            let config = IcsConfig {
+                feeds: vec![IcsFeedConfig {
+                    id: "roster".into(),
+                    source: path.to_string_lossy().into_owned(),
+                    name: None,
+                    description: None,
+                    color: None,
+                }],
+                tls: TlsConfig::default(),
+            };
+            IcsClient::new("test", config).unwrap()
+        };
+
+        let items = client().list_items("roster").unwrap();
+        assert_eq!(items.len(), 1);
+
+        let item = client().get_item("roster", &items[0].id).unwrap();
+        assert_eq!(item.contents, items[0].contents);
+    }
+}
@@ -0,0 +1,9 @@
+//! Read-only iCalendar subscription backend (`ics`, alias `webcal`).
+//!
+//! Each configured feed, a remote `http(s)`/`webcal` URL or a local
+//! `.ics` file, is exposed as one calendar whose items are the
+//! components of the feed split per UID. Remote feeds are cached under
+//! the XDG cache directory and revalidated through `ETag` and
+//! `Last-Modified` on every run. Every write is rejected.
+
+pub mod client;
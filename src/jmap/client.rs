@@ -0,0 +1,559 @@
+//! Client of the JMAP for Calendars backend.
+//!
+//! Serves the calendars and events of the primary calendars account
+//! of the session to [`crate::shared::client::CalendarClient`]. Items
+//! are JSCalendar events converted to iCalendar (see
+//! [`crate::jmap::convert`]), their id being the JMAP id and their
+//! ETag the `updated` timestamp of the event. The account-wide
+//! `CalendarEvent` state is not used as ETag: any change elsewhere in
+//! the account would turn every update into a conflict.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Utc;
+use io_calendar::{
+    calendar::{Calendar, CalendarDiff},
+    item::CalendarItem,
+};
+use io_http::{rfc6750::bearer::HttpAuthBearer, rfc7617::basic::HttpAuthBasic};
+use io_webdav::rfc4918::WebdavAuth;
+use pimalaya_stream::tls::Tls;
+use secrecy::ExposeSecret;
+use serde_json::{Map, Value as Json, json};
+use url::Url;
+
+use crate::{
+    config::{JmapAuthConfig, JmapConfig},
+    jmap::convert::{to_ical, to_jscal},
+    shared::{client::Conflict, rest::RestClient},
+};
+
+/// Capabilities used by every request.
+const USING: [&str; 2] = [
+    "urn:ietf:params:jmap:core",
+    "urn:ietf:params:jmap:calendars",
+];
+
+/// Capability naming the primary calendars account of the session.
+const CALENDARS: &str = "urn:ietf:params:jmap:calendars";
+
+/// Maximum number of redirects followed during session discovery.
+const MAX_REDIRECTS: usize = 5;
+
+/// Number of events asked per `CalendarEvent/query`.
+const PAGE_SIZE: u64 = 100;
+
+pub struct JmapClient {
+    http: RestClient,
+    api_url: Url,
+    account_id: String,
+}
+
+impl JmapClient {
+    /// Builds the client from `config`, resolving the credentials and
+    /// fetching the session resource.
+    pub fn new(config: JmapConfig) -> Result<Self> {
+        let mut tls: Tls = config.tls.into();
+        tls.rustls.alpn = vec!["http/1.1".into()];
+
+        let auth = match config.auth {
+            JmapAuthConfig::Basic { username, password } => WebdavAuth::Basic(HttpAuthBasic {
+                username,
+                password: password.get()?,
+            }),
+            JmapAuthConfig::Bearer { token } => {
+                let token = token.get()?;
+                WebdavAuth::Bearer(HttpAuthBearer::new(token.expose_secret()))
+            }
+        };
+
+        let http = RestClient::new(tls, auth);
+
+        let (session_url, session) = discover(&http, config.server)?;
+
+        let Some(api_url) = session["apiUrl"].as_str() else {
+            bail!("Missing apiUrl in JMAP session `{session_url}`");
+        };
+        let api_url = session_url
+            .join(api_url)
+            .with_context(|| format!("Invalid JMAP API URL `{api_url}`"))?;
+
+        let Some(account_id) = session["primaryAccounts"][CALENDARS].as_str() else {
+            bail!("JMAP server `{session_url}` does not support calendars");
+        };
+
+        Ok(Self {
+            http,
+            api_url,
+            account_id: account_id.to_owned(),
+        })
+    }
+
+    /// Lists the calendars of the account. JMAP calendars have no
+    /// CTag.
+    pub fn list_calendars(&self) -> Result<Vec<Calendar>> {
+        let [response] = self.call([("Calendar/get", json!({ "ids": null }))])?;
+
+        let calendars = response["list"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|calendar| {
+                let text = |key: &str| {
+                    calendar[key]
+                        .as_str()
+                        .filter(|value| !value.is_empty())
+                        .map(ToOwned::to_owned)
+                };
+
+                Calendar {
+                    id: text("id").unwrap_or_default(),
+                    name: text("name").unwrap_or_default(),
+                    description: text("description"),
+                    color: text("color"),
+                    ctag: None,
+                }
+            })
+            .collect();
+
+        Ok(calendars)
+    }
+
+    /// Creates a calendar named `name`, returning the id the server
+    /// assigns it in place of `calendar_id`.
+    pub fn create_calendar(
+        &self,
+        calendar_id: &str,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<String> {
+        let mut calendar = json!({ "name": name });
+        if let Some(description) = description {
+            calendar["description"] = json!(description);
+        }
+        if let Some(color) = color {
+            calendar["color"] = json!(color);
+        }
+
+        let args = json!({ "create": { "c0": calendar } });
+        let [response] = self.call([("Calendar/set", args)])?;
+        set_error("Calendar/set", &response, "notCreated", "c0")?;
+
+        let Some(id) = response["created"]["c0"]["id"].as_str() else {
+            bail!("Missing id of the calendar `{calendar_id}` created by JMAP");
+        };
+
+        Ok(id.to_owned())
+    }
+
+    /// Patches the calendar `calendar_id`, cleared fields being set to
+    /// `null`.
+    pub fn update_calendar(&self, calendar_id: &str, patch: CalendarDiff) -> Result<()> {
+        let mut calendar = Map::new();
+
+        if let Some(name) = patch.name {
+            calendar.insert("name".into(), json!(name));
+        }
+
+        if let Some(description) = patch.description {
+            calendar.insert("description".into(), json!(description));
+        }
+
+        if let Some(color) = patch.color {
+            calendar.insert("color".into(), json!(color));
+        }
+
+        if calendar.is_empty() {
+            return Ok(());
+        }
+
+        let args = json!({ "update": { calendar_id: calendar } });
+        let [response] = self.call([("Calendar/set", args)])?;
+        set_error("Calendar/set", &response, "notUpdated", calendar_id)
+    }
+
+    /// Deletes the calendar `calendar_id` together with its events.
+    pub fn delete_calendar(&self, calendar_id: &str) -> Result<()> {
+        let args = json!({ "destroy": [calendar_id], "onDestroyRemoveEvents": true });
+        let [response] = self.call([("Calendar/set", args)])?;
+        set_error("Calendar/set", &response, "notDestroyed", calendar_id)
+    }
+
+    /// Lists the events of `calendar_id`, one `CalendarEvent/query`
+    /// page at a time, each page fetched in the same request by a
+    /// back-referenced `CalendarEvent/get`. Occurrences are left to
+    /// the recurrence rules.
+    pub fn list_items(&self, calendar_id: &str) -> Result<Vec<CalendarItem>> {
+        let mut items = Vec::new();
+        let mut position = 0;
+
+        loop {
+            let query = json!({
+                "filter": { "inCalendars": [calendar_id] },
+                "position": position,
+                "limit": PAGE_SIZE,
+                "calculateTotal": true,
+            });
+            let get = json!({
+                "#ids": { "resultOf": "0", "name": "CalendarEvent/query", "path": "/ids" },
+            });
+
+            let [query, get] =
+                self.call([("CalendarEvent/query", query), ("CalendarEvent/get", get)])?;
+            let count = query["ids"].as_array().map_or(0, Vec::len) as u64;
+            items.extend(get_items(calendar_id, &get));
+
+            position += count;
+            let total = query["total"].as_u64().unwrap_or(position);
+            if count == 0 || position >= total {
+                return Ok(items);
+            }
+        }
+    }
+
+    pub fn get_item(&self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        let args = json!({ "ids": [item_id] });
+        let [response] = self.call([("CalendarEvent/get", args)])?;
+
+        get_items(calendar_id, &response)
+            .next()
+            .ok_or_else(|| anyhow!("Cannot find JMAP event `{item_id}`"))
+    }
+
+    /// Creates an event in `calendar_id`, returning its JMAP id.
+    pub fn create_item(&self, calendar_id: &str, contents: Vec<u8>) -> Result<String> {
+        let mut event = to_jscal(&contents)?;
+        if let Some(event) = event.as_object_mut() {
+            event.retain(|_, value| !value.is_null());
+            event.insert("calendarIds".into(), json!({ calendar_id: true }));
+        }
+
+        let args = json!({ "create": { "e0": event } });
+        let [response] = self.call([("CalendarEvent/set", args)])?;
+        set_error("CalendarEvent/set", &response, "notCreated", "e0")?;
+
+        response["created"]["e0"]["id"]
+            .as_str()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| anyhow!("Missing id of the event created by JMAP"))
+    }
+
+    /// Replaces the event `item_id`. When given, `etag` (the `updated`
+    /// timestamp the event was fetched at) is compared to the current
+    /// one right before the update.
+    pub fn update_item(&self, item_id: &str, contents: Vec<u8>, etag: Option<&str>) -> Result<()> {
+        let mut event = to_jscal(&contents)?;
+        if let Some(event) = event.as_object_mut() {
+            // the UID of a JMAP event is immutable
+            event.remove("uid");
+        }
+
+        if let Some(etag) = etag {
+            let args = json!({ "ids": [item_id], "properties": ["updated"] });
+            let [response] = self.call([("CalendarEvent/get", args)])?;
+
+            let Some(current) = response["list"].get(0) else {
+                bail!("Cannot find JMAP event `{item_id}`");
+            };
+            if current["updated"].as_str() != Some(etag) {
+                return Err(Conflict.into());
+            }
+        }
+
+        let args = json!({ "update": { item_id: event } });
+        let [response] = self.call([("CalendarEvent/set", args)])?;
+        set_error("CalendarEvent/set", &response, "notUpdated", item_id)
+    }
+
+    pub fn delete_item(&self, item_id: &str) -> Result<()> {
+        let args = json!({ "destroy": [item_id] });
+        let [response] = self.call([("CalendarEvent/set", args)])?;
+        set_error("CalendarEvent/set", &response, "notDestroyed", item_id)
+    }
+
+    /// Sends the `calls` in one JMAP request, returning the arguments
+    /// of their responses. The account id is added to every call, and
+    /// the call ids are their index.
+    fn call<const N: usize>(&self, calls: [(&str, Json); N]) -> Result<[Json; N]> {
+        let method_calls: Vec<Json> = calls
+            .iter()
+            .enumerate()
+            .map(|(i, (method, args))| {
+                let mut args = args.clone();
+                args["accountId"] = json!(self.account_id);
+                json!([method, args, i.to_string()])
+            })
+            .collect();
+
+        let body = json!({ "using": USING, "methodCalls": method_calls });
+        let response: Json = self
+            .http
+            .send_json("POST", &self.api_url, &[], Some(&body))?
+            .json()?;
+
+        let mut responses = response["methodResponses"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter();
+
+        // responses come in the order of the calls
+        let mut results = Vec::with_capacity(N);
+        for (method, _) in &calls {
+            let Some(mut response) = responses.next() else {
+                bail!("Missing response of JMAP {method}");
+            };
+
+            if let Some(err) = method_error(&response) {
+                bail!("JMAP {method} failed: {err}");
+            }
+
+            results.push(response[1].take());
+        }
+
+        results
+            .try_into()
+            .map_err(|_| anyhow!("Unexpected number of JMAP responses"))
+    }
+}
+
+/// Builds the items of the `CalendarEvent/get` `response`.
+fn get_items<'a>(
+    calendar_id: &'a str,
+    response: &'a Json,
+) -> impl Iterator<Item = CalendarItem> + 'a {
+    response["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(move |event| CalendarItem {
+            id: event["id"].as_str().unwrap_or_default().to_owned(),
+            calendar_id: calendar_id.to_owned(),
+            etag: event["updated"].as_str().map(ToOwned::to_owned),
+            contents: to_ical(event, Utc::now()),
+        })
+}
+
+/// Fetches the session resource at `url`, following redirects.
+/// Returns its final URL, against which the API URL resolves.
+fn discover(http: &RestClient, mut url: Url) -> Result<(Url, Json)> {
+    if matches!(url.path(), "" | "/") {
+        url.set_path("/.well-known/jmap");
+    }
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = http.send("GET", &url, &[], None)?;
+
+        if !(300..400).contains(&response.status) {
+            let session = response.check("GET", &url)?.json()?;
+            return Ok((url, session));
+        }
+
+        let Some(location) = &response.location else {
+            bail!("Missing Location of JMAP session redirect from `{url}`");
+        };
+
+        url = url
+            .join(location)
+            .with_context(|| format!("Invalid JMAP session redirect `{location}`"))?;
+    }
+
+    bail!("Too many redirects while discovering JMAP session at `{url}`")
+}
+
+/// Describes the method-level error `response` (`["error", {type},
+/// id]`), if any.
+fn method_error(response: &Json) -> Option<String> {
+    if response[0] != "error" {
+        return None;
+    }
+
+    let args = &response[1];
+    let kind = args["type"].as_str().unwrap_or("unknown");
+
+    match args["description"].as_str() {
+        Some(description) => Some(format!("{kind}: {description}")),
+        None => Some(kind.to_owned()),
+    }
+}
+
+/// Bails with the `SetError` of `id` found in the `kind` map
+/// (`notCreated`, `notUpdated` or `notDestroyed`) of the `/set`
+/// `response`.
+fn set_error(method: &str, response: &Json, kind: &str, id: &str) -> Result<()> {
+    let err = &response[kind][id];
+    if err.is_null() {
+        return Ok(());
+    }
+
+    let kind = err["type"].as_str().unwrap_or("unknown");
+    match err["description"].as_str() {
+        Some(description) => bail!("JMAP {method} of `{id}` failed: {kind}: {description}"),
+        None => bail!("JMAP {method} of `{id}` failed: {kind}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pimalaya_config::secret::Secret;
+    use serde_json::json;
+
+    use super::JmapClient;
+    use crate::{
+        config::{JmapAuthConfig, JmapConfig},
+        shared::{
+            client::Conflict,
+            mock::{bind, serve},
+        },
+    };
+
+    const EVENT: &str = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "PRODID:-//Test//EN\r\n",
+        "BEGIN:VEVENT\r\n",
+        "UID:abc@example.com\r\n",
+        "SUMMARY:Two\r\n",
+        "DTSTART:20260316T090000Z\r\n",
+        "DURATION:PT1H\r\n",
+        "END:VEVENT\r\n",
+        "END:VCALENDAR\r\n",
+    );
+
+    #[test]
+    fn discover_session_and_list_events() {
+        let (listener, server_url) = bind();
+
+        let session = json!({
+            "apiUrl": "/jmap/api/",
+            "primaryAccounts": { "urn:ietf:params:jmap:calendars": "a1" },
+        });
+        let events = json!({
+            "methodResponses": [
+                ["CalendarEvent/query", { "ids": ["e1"], "position": 0, "total": 1 }, "0"],
+                ["CalendarEvent/get", {
+                    "state": "s42",
+                    "list": [{
+                        "id": "e1",
+                        "uid": "abc@example.com",
+                        "updated": "2026-03-01T08:00:00Z",
+                        "title": "One",
+                        "start": "2026-03-16T09:00:00",
+                        "timeZone": "Etc/UTC",
+                        "duration": "PT1H",
+                    }],
+                }, "1"],
+            ],
+        });
+        let server = serve(
+            listener,
+            vec![(200, session.to_string()), (200, events.to_string())],
+        );
+
+        let client = JmapClient::new(JmapConfig {
+            server: server_url.parse().unwrap(),
+            tls: Default::default(),
+            auth: JmapAuthConfig::Basic {
+                username: String::from("user"),
+                password: Secret::Raw(String::from("pass").into()),
+            },
+        })
+        .unwrap();
+
+        let items = client.list_items("c1").unwrap();
+        let requests = server.join().unwrap();
+
+        assert!(requests[0].starts_with("GET /.well-known/jmap HTTP/1.1\r\n"));
+        assert!(requests[1].starts_with("POST /jmap/api/ HTTP/1.1\r\n"));
+        for request in &requests {
+            assert!(request.contains("\r\nAuthorization: Basic dXNlcjpwYXNz\r\n"));
+        }
+
+        let (_, body) = requests[1].split_once("\r\n\r\n").unwrap();
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["methodCalls"][0][0], "CalendarEvent/query");
+        assert_eq!(body["methodCalls"][0][1]["accountId"], "a1");
+        assert_eq!(
+            body["methodCalls"][0][1]["filter"],
+            json!({ "inCalendars": ["c1"] })
+        );
+        assert_eq!(body["methodCalls"][1][1]["#ids"]["resultOf"], "0");
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "e1");
+        assert_eq!(items[0].calendar_id, "c1");
+        assert_eq!(items[0].etag.as_deref(), Some("2026-03-01T08:00:00Z"));
+        let contents = String::from_utf8_lossy(&items[0].contents);
+        assert!(contents.contains("\r\nSUMMARY:One\r\n"));
+        assert!(contents.contains("\r\nDTSTART:20260316T090000Z\r\n"));
+    }
+
+    #[test]
+    fn update_compares_event_updated() {
+        let (listener, server_url) = bind();
+
+        let session = json!({
+            "apiUrl": "/jmap/api/",
+            "primaryAccounts": { "urn:ietf:params:jmap:calendars": "a1" },
+        });
+        let current = |updated: &str| {
+            json!({
+                "methodResponses": [
+                    ["CalendarEvent/get", {
+                        "state": "s43",
+                        "list": [{ "id": "e1", "updated": updated }],
+                    }, "0"],
+                ],
+            })
+            .to_string()
+        };
+        let updated = json!({
+            "methodResponses": [
+                ["CalendarEvent/set", { "updated": { "e1": null } }, "0"],
+            ],
+        });
+        let server = serve(
+            listener,
+            vec![
+                (200, session.to_string()),
+                (200, current("2026-03-02T08:00:00Z")),
+                (200, current("2026-03-01T08:00:00Z")),
+                (200, updated.to_string()),
+            ],
+        );
+
+        let client = JmapClient::new(JmapConfig {
+            server: server_url.parse().unwrap(),
+            tls: Default::default(),
+            auth: JmapAuthConfig::Basic {
+                username: String::from("user"),
+                password: Secret::Raw(String::from("pass").into()),
+            },
+        })
+        .unwrap();
+
+        let etag = Some("2026-03-01T08:00:00Z");
+        let err = client
+            .update_item("e1", EVENT.as_bytes().to_vec(), etag)
+            .unwrap_err();
+        assert!(err.downcast_ref::<Conflict>().is_some());
+
+        client
+            .update_item("e1", EVENT.as_bytes().to_vec(), etag)
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        let body = |request: &str| -> serde_json::Value {
+            let (_, body) = request.split_once("\r\n\r\n").unwrap();
+            serde_json::from_str(body).unwrap()
+        };
+
+        assert_eq!(body(&requests[1])["methodCalls"][0][0], "CalendarEvent/get");
+        assert_eq!(body(&requests[2])["methodCalls"][0][0], "CalendarEvent/get");
+
+        let set = body(&requests[3]);
+        assert_eq!(set["methodCalls"][0][0], "CalendarEvent/set");
+        assert!(set["methodCalls"][0][1].get("ifInState").is_none());
+        assert_eq!(set["methodCalls"][0][1]["update"]["e1"]["title"], "Two");
+    }
+}
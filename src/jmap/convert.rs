@@ -0,0 +1,869 @@
+//! Conversion between JSCalendar (RFC 8984) events and iCalendar.
+//!
+//! JMAP stores events as JSCalendar objects, whose properties mostly
+//! have a direct iCalendar counterpart (RFC 8984 section 1.4):
+//! `start`, `timeZone` and `duration` become DTSTART and DURATION,
+//! `recurrenceRules` RRULEs, excluded `recurrenceOverrides` EXDATEs,
+//! `participants` the ORGANIZER and ATTENDEEs, and `alerts` VALARMs.
+//! Only the master VEVENT of an object is converted, and iCalendar
+//! objects are read at the content-line level.
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{Map, Value as Json, json};
+
+use crate::shared::ical::{
+    ContentLine, IcalWriter, PRODID, VEvent, escape_text, format_duration, master_event,
+    parse_lines, split_unescaped, unescape_text,
+};
+
+/// Format of the JSCalendar `LocalDateTime` values.
+const LOCAL_DATE_TIME: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// RECUR rule parts holding numbers, with their JSCalendar names.
+const RULE_NUMBERS: [(&str, &str); 7] = [
+    ("BYMONTHDAY", "byMonthDay"),
+    ("BYYEARDAY", "byYearDay"),
+    ("BYWEEKNO", "byWeekNo"),
+    ("BYHOUR", "byHour"),
+    ("BYMINUTE", "byMinute"),
+    ("BYSECOND", "bySecond"),
+    ("BYSETPOS", "bySetPosition"),
+];
+
+/// Renders the JSCalendar `event` as a VCALENDAR holding one VEVENT.
+/// `now` stamps events without `updated` date.
+pub fn to_ical(event: &Json, now: DateTime<Utc>) -> Vec<u8> {
+    let text = |key: &str| {
+        event[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let instant = |key: &str| text(key).and_then(parse_instant);
+    let all_day = event["showWithoutTime"].as_bool() == Some(true);
+    let zone = text("timeZone");
+
+    let mut writer = IcalWriter::new();
+    writer
+        .begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .property("PRODID", &[], PRODID)
+        .begin("VEVENT");
+
+    if let Some(uid) = text("uid").or(text("id")) {
+        writer.text("UID", uid);
+    }
+
+    let stamp = instant("updated").unwrap_or(now);
+    writer.property("DTSTAMP", &[], &format_instant(stamp));
+
+    if let Some(created) = instant("created") {
+        writer.property("CREATED", &[], &format_instant(created));
+    }
+
+    if let Some(updated) = instant("updated") {
+        writer.property("LAST-MODIFIED", &[], &format_instant(updated));
+    }
+
+    if let Some(sequence) = event["sequence"].as_u64().filter(|n| *n > 0) {
+        writer.property("SEQUENCE", &[], &sequence.to_string());
+    }
+
+    if let Some(start) = text("start").and_then(parse_local) {
+        write_local(&mut writer, "DTSTART", start, all_day, zone);
+    }
+
+    if let Some(duration) = text("duration") {
+        writer.property("DURATION", &[], duration);
+    }
+
+    for rule in event["recurrenceRules"].as_array().into_iter().flatten() {
+        if let Some(rrule) = rrule(rule, all_day, zone) {
+            writer.property("RRULE", &[], &rrule);
+        }
+    }
+
+    for (id, patch) in event["recurrenceOverrides"]
+        .as_object()
+        .into_iter()
+        .flatten()
+    {
+        if patch["excluded"].as_bool() == Some(true)
+            && let Some(exdate) = parse_local(id)
+        {
+            write_local(&mut writer, "EXDATE", exdate, all_day, zone);
+        }
+    }
+
+    if let Some(title) = text("title") {
+        writer.text("SUMMARY", title);
+    }
+
+    if let Some(description) = text("description") {
+        writer.text("DESCRIPTION", description);
+    }
+
+    let location = event["locations"]
+        .as_object()
+        .into_iter()
+        .flat_map(Map::values)
+        .find_map(|location| location["name"].as_str());
+
+    if let Some(location) = location {
+        writer.text("LOCATION", location);
+    }
+
+    let keywords: Vec<String> = event["keywords"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, set)| set.as_bool() == Some(true))
+        .map(|(keyword, _)| escape_text(keyword))
+        .collect();
+
+    if !keywords.is_empty() {
+        writer.property("CATEGORIES", &[], &keywords.join(","));
+    }
+
+    if let Some(status) = text("status") {
+        writer.property("STATUS", &[], &status.to_ascii_uppercase());
+    }
+
+    if let Some(free_busy) = text("freeBusyStatus") {
+        let transp = if free_busy == "free" {
+            "TRANSPARENT"
+        } else {
+            "OPAQUE"
+        };
+        writer.property("TRANSP", &[], transp);
+    }
+
+    let class = match text("privacy") {
+        Some("private") => Some("PRIVATE"),
+        Some("secret") => Some("CONFIDENTIAL"),
+        _ => None,
+    };
+
+    if let Some(class) = class {
+        writer.property("CLASS", &[], class);
+    }
+
+    if let Some(priority) = event["priority"].as_u64().filter(|n| *n > 0) {
+        writer.property("PRIORITY", &[], &priority.to_string());
+    }
+
+    for participant in event["participants"]
+        .as_object()
+        .into_iter()
+        .flat_map(Map::values)
+    {
+        write_participant(&mut writer, participant);
+    }
+
+    for alert in event["alerts"]
+        .as_object()
+        .into_iter()
+        .flat_map(Map::values)
+    {
+        write_alert(&mut writer, alert, text("title").unwrap_or("Reminder"));
+    }
+
+    writer.end("VEVENT").end("VCALENDAR");
+    writer.finish()
+}
+
+/// Builds the JSCalendar event matching the master VEVENT of
+/// `contents`. Properties the VEVENT lacks are set to `null`, which
+/// resets them when the object is used as a JMAP update patch.
+pub fn to_jscal(contents: &[u8]) -> Result<Json> {
+    let Some(VEvent { props, alarms }) = master_event(parse_lines(contents)) else {
+        bail!("Cannot find any VEVENT to send to JMAP");
+    };
+
+    let prop = |name: &str| props.iter().find(|line| line.is(name));
+    let text = |name: &str| prop(name).map(|line| unescape_text(&line.value));
+    let upper = |name: &str| prop(name).map(|line| line.value.trim().to_ascii_uppercase());
+
+    let Some(dtstart) = prop("DTSTART") else {
+        bail!("Cannot send an event without DTSTART to JMAP");
+    };
+    let (start, all_day) = dtstart.date_time()?;
+
+    let zone = match (dtstart.param("TZID"), dtstart.value.ends_with(['z', 'Z'])) {
+        _ if all_day => None,
+        (_, true) => Some("Etc/UTC"),
+        (tzid, false) => tzid,
+    };
+
+    let duration = match (prop("DTEND"), prop("DURATION")) {
+        (Some(dtend), _) => format_duration((dtend.date_time()?.0 - start).max(Default::default())),
+        (None, Some(duration)) => duration.value.trim().to_ascii_uppercase(),
+        (None, None) if all_day => String::from("P1D"),
+        (None, None) => String::from("PT0S"),
+    };
+
+    let mut event = Map::new();
+    event.insert("@type".into(), json!("Event"));
+    if let Some(uid) = text("UID") {
+        event.insert("uid".into(), json!(uid));
+    }
+    event.insert("title".into(), json!(text("SUMMARY").unwrap_or_default()));
+    event.insert(
+        "description".into(),
+        json!(text("DESCRIPTION").unwrap_or_default()),
+    );
+    event.insert(
+        "start".into(),
+        json!(start.format(LOCAL_DATE_TIME).to_string()),
+    );
+    event.insert("timeZone".into(), json!(zone));
+    event.insert("duration".into(), json!(duration));
+    event.insert("showWithoutTime".into(), json!(all_day));
+
+    let status = match upper("STATUS").as_deref() {
+        Some("CANCELLED") => "cancelled",
+        Some("TENTATIVE") => "tentative",
+        _ => "confirmed",
+    };
+    event.insert("status".into(), json!(status));
+
+    let free_busy = match upper("TRANSP").as_deref() {
+        Some("TRANSPARENT") => "free",
+        _ => "busy",
+    };
+    event.insert("freeBusyStatus".into(), json!(free_busy));
+
+    let privacy = match upper("CLASS").as_deref() {
+        Some("PRIVATE") => "private",
+        Some("CONFIDENTIAL") => "secret",
+        _ => "public",
+    };
+    event.insert("privacy".into(), json!(privacy));
+
+    let priority = upper("PRIORITY").and_then(|p| p.parse::<u8>().ok());
+    event.insert("priority".into(), json!(priority.unwrap_or_default()));
+
+    let sequence = upper("SEQUENCE").and_then(|s| s.parse::<u32>().ok());
+    event.insert("sequence".into(), json!(sequence.unwrap_or_default()));
+
+    let keywords: Map<String, Json> = props
+        .iter()
+        .filter(|line| line.is("CATEGORIES"))
+        .flat_map(|line| split_unescaped(&line.value, ','))
+        .map(|keyword| unescape_text(keyword.trim()))
+        .filter(|keyword| !keyword.is_empty())
+        .map(|keyword| (keyword, Json::Bool(true)))
+        .collect();
+    event.insert("keywords".into(), non_empty(keywords));
+
+    let locations: Map<String, Json> = text("LOCATION")
+        .filter(|location| !location.is_empty())
+        .map(|name| {
+            (
+                String::from("1"),
+                json!({ "@type": "Location", "name": name }),
+            )
+        })
+        .into_iter()
+        .collect();
+    event.insert("locations".into(), non_empty(locations));
+
+    let rules: Vec<Json> = props
+        .iter()
+        .filter(|line| line.is("RRULE"))
+        .map(|line| recurrence_rule(&line.value, zone))
+        .collect();
+    let rules = if rules.is_empty() {
+        Json::Null
+    } else {
+        json!(rules)
+    };
+    event.insert("recurrenceRules".into(), rules);
+
+    let mut overrides = Map::new();
+    for exdate in props.iter().filter(|line| line.is("EXDATE")) {
+        for value in exdate.value.split(',') {
+            let line = ContentLine {
+                value: value.to_owned(),
+                ..exdate.clone()
+            };
+            let (dt, _) = line.date_time()?;
+            let dt = match value.trim().ends_with(['z', 'Z']) {
+                true => from_utc(dt, zone),
+                false => dt,
+            };
+            overrides.insert(
+                dt.format(LOCAL_DATE_TIME).to_string(),
+                json!({ "excluded": true }),
+            );
+        }
+    }
+    event.insert("recurrenceOverrides".into(), non_empty(overrides));
+
+    let (participants, organizer) = participants(&props);
+    event.insert("participants".into(), non_empty(participants));
+    let reply_to = organizer.map(|address| json!({ "imip": address }));
+    event.insert("replyTo".into(), reply_to.unwrap_or_default());
+
+    let alerts: Map<String, Json> = alarms
+        .iter()
+        .filter_map(|alarm| alert(alarm))
+        .enumerate()
+        .map(|(i, alert)| ((i + 1).to_string(), alert))
+        .collect();
+    event.insert("alerts".into(), non_empty(alerts));
+
+    Ok(Json::Object(event))
+}
+
+/// Builds the JSCalendar participants of the ORGANIZER and ATTENDEEs
+/// of `props`, the organizer being merged into the attendee sharing
+/// its address. Returns the address of the organizer too.
+fn participants(props: &[ContentLine]) -> (Map<String, Json>, Option<String>) {
+    let mut participants = Map::new();
+    let organizer = props.iter().find(|line| line.is("ORGANIZER"));
+    let organizer_address = organizer.map(|line| line.value.trim().to_owned());
+    let mut organizer_merged = false;
+
+    for line in props.iter().filter(|line| line.is("ATTENDEE")) {
+        let mut participant = participant(line);
+        let roles = match line.param("ROLE").map(str::to_ascii_uppercase).as_deref() {
+            Some("CHAIR") => json!({ "attendee": true, "chair": true }),
+            Some("OPT-PARTICIPANT") => json!({ "attendee": true, "optional": true }),
+            Some("NON-PARTICIPANT") => json!({ "informational": true }),
+            _ => json!({ "attendee": true }),
+        };
+        participant["roles"] = roles;
+
+        if let Some(address) = &organizer_address
+            && address.eq_ignore_ascii_case(line.value.trim())
+        {
+            participant["roles"]["owner"] = json!(true);
+            organizer_merged = true;
+        }
+
+        let status = line.param("PARTSTAT").unwrap_or("NEEDS-ACTION");
+        participant["participationStatus"] = json!(status.to_ascii_lowercase());
+
+        if let Some(kind) = line.param("CUTYPE").map(str::to_ascii_lowercase)
+            && kind != "unknown"
+        {
+            participant["kind"] = json!(kind);
+        }
+
+        if line
+            .param("RSVP")
+            .is_some_and(|rsvp| rsvp.eq_ignore_ascii_case("TRUE"))
+        {
+            participant["expectReply"] = json!(true);
+        }
+
+        participants.insert((participants.len() + 1).to_string(), participant);
+    }
+
+    if let Some(organizer) = organizer
+        && !organizer_merged
+    {
+        let mut participant = participant(organizer);
+        participant["roles"] = json!({ "owner": true });
+        participants.insert((participants.len() + 1).to_string(), participant);
+    }
+
+    (participants, organizer_address)
+}
+
+/// Builds the JSCalendar participant of the CAL-ADDRESS property
+/// `line`, roles left to the caller.
+fn participant(line: &ContentLine) -> Json {
+    let address = line.value.trim();
+    let mut participant = json!({
+        "@type": "Participant",
+        "sendTo": { "imip": address },
+    });
+
+    if let Some(email) = address
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+    {
+        participant["email"] = json!(address[email.len()..]);
+    }
+
+    if let Some(name) = line.param("CN") {
+        participant["name"] = json!(name);
+    }
+
+    participant
+}
+
+/// Builds the JSCalendar alert of the VALARM properties `alarm`.
+fn alert(alarm: &[ContentLine]) -> Option<Json> {
+    let trigger = alarm.iter().find(|line| line.is("TRIGGER"))?;
+    let value = trigger.value.trim();
+
+    let is_absolute = trigger
+        .param("VALUE")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("DATE-TIME"));
+
+    let trigger = if is_absolute {
+        let (when, _) = trigger.date_time().ok()?;
+        json!({
+            "@type": "AbsoluteTrigger",
+            "when": format!("{}Z", when.format(LOCAL_DATE_TIME)),
+        })
+    } else {
+        let relative_to = match trigger.param("RELATED") {
+            Some(related) if related.eq_ignore_ascii_case("END") => "end",
+            _ => "start",
+        };
+        json!({
+            "@type": "OffsetTrigger",
+            "offset": value.trim_start_matches('+').to_ascii_uppercase(),
+            "relativeTo": relative_to,
+        })
+    };
+
+    let action = alarm
+        .iter()
+        .find(|line| line.is("ACTION"))
+        .filter(|line| line.value.trim().eq_ignore_ascii_case("EMAIL"))
+        .map_or("display", |_| "email");
+
+    Some(json!({ "@type": "Alert", "trigger": trigger, "action": action }))
+}
+
+/// Builds the JSCalendar recurrence rule of the RRULE value `rrule` of
+/// an event in `zone`.
+fn recurrence_rule(rrule: &str, zone: Option<&str>) -> Json {
+    let mut rule = json!({ "@type": "RecurrenceRule" });
+
+    for (key, value) in rrule.split(';').filter_map(|part| part.split_once('=')) {
+        let key = key.trim().to_ascii_uppercase();
+        let value = value.trim();
+        let numbers = || -> Vec<i64> {
+            value
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect()
+        };
+
+        match key.as_str() {
+            "FREQ" => rule["frequency"] = json!(value.to_ascii_lowercase()),
+            "INTERVAL" | "COUNT" => {
+                if let Ok(n) = value.parse::<u32>() {
+                    rule[key.to_ascii_lowercase()] = json!(n);
+                }
+            }
+            "UNTIL" => {
+                let line = ContentLine {
+                    name: key.clone(),
+                    params: Vec::new(),
+                    value: value.to_owned(),
+                };
+                if let Ok((until, _)) = line.date_time() {
+                    let until = match value.ends_with(['z', 'Z']) {
+                        true => from_utc(until, zone),
+                        false => until,
+                    };
+                    rule["until"] = json!(until.format(LOCAL_DATE_TIME).to_string());
+                }
+            }
+            "WKST" => rule["firstDayOfWeek"] = json!(value.to_ascii_lowercase()),
+            "BYDAY" => {
+                let days: Vec<Json> = value
+                    .split(',')
+                    .filter(|day| day.is_ascii() && day.len() >= 2)
+                    .map(|day| {
+                        let (nth, day) = day.split_at(day.len() - 2);
+                        let mut nday = json!({ "@type": "NDay", "day": day.to_ascii_lowercase() });
+                        if let Ok(nth) = nth.trim_start_matches('+').parse::<i32>() {
+                            nday["nthOfPeriod"] = json!(nth);
+                        }
+                        nday
+                    })
+                    .collect();
+                rule["byDay"] = json!(days);
+            }
+            "BYMONTH" => {
+                let months: Vec<&str> = value.split(',').map(str::trim).collect();
+                rule["byMonth"] = json!(months);
+            }
+            key => {
+                if let Some((_, name)) = RULE_NUMBERS.iter().find(|(part, _)| *part == key) {
+                    rule[*name] = json!(numbers());
+                }
+            }
+        }
+    }
+
+    rule
+}
+
+/// Renders the JSCalendar recurrence `rule` of an event in `zone` as
+/// an RRULE value.
+fn rrule(rule: &Json, all_day: bool, zone: Option<&str>) -> Option<String> {
+    let freq = rule["frequency"].as_str()?.to_ascii_uppercase();
+    let mut parts = vec![format!("FREQ={freq}")];
+
+    if let Some(interval) = rule["interval"].as_u64().filter(|n| *n > 1) {
+        parts.push(format!("INTERVAL={interval}"));
+    }
+
+    if let Some(count) = rule["count"].as_u64() {
+        parts.push(format!("COUNT={count}"));
+    }
+
+    if let Some(until) = rule["until"].as_str().and_then(parse_local) {
+        let until = match (all_day, zone) {
+            (true, _) => until.format("%Y%m%d").to_string(),
+            (false, Some(zone)) => match to_utc(until, zone) {
+                Some(until) => until.format("%Y%m%dT%H%M%SZ").to_string(),
+                None => until.format("%Y%m%dT%H%M%S").to_string(),
+            },
+            (false, None) => until.format("%Y%m%dT%H%M%S").to_string(),
+        };
+        parts.push(format!("UNTIL={until}"));
+    }
+
+    let days: Vec<String> = rule["byDay"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|nday| {
+            let day = nday["day"].as_str()?.to_ascii_uppercase();
+            match nday["nthOfPeriod"].as_i64() {
+                Some(nth) => Some(format!("{nth}{day}")),
+                None => Some(day),
+            }
+        })
+        .collect();
+
+    if !days.is_empty() {
+        parts.push(format!("BYDAY={}", days.join(",")));
+    }
+
+    let months: Vec<&str> = rule["byMonth"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Json::as_str)
+        .collect();
+
+    if !months.is_empty() {
+        parts.push(format!("BYMONTH={}", months.join(",")));
+    }
+
+    for (part, name) in RULE_NUMBERS {
+        let numbers: Vec<String> = rule[name]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Json::as_i64)
+            .map(|n| n.to_string())
+            .collect();
+
+        if !numbers.is_empty() {
+            parts.push(format!("{part}={}", numbers.join(",")));
+        }
+    }
+
+    if let Some(wkst) = rule["firstDayOfWeek"].as_str() {
+        parts.push(format!("WKST={}", wkst.to_ascii_uppercase()));
+    }
+
+    Some(parts.join(";"))
+}
+
+/// Writes the JSCalendar participant `participant` as an ORGANIZER
+/// and/or an ATTENDEE, according to its roles.
+fn write_participant(writer: &mut IcalWriter, participant: &Json) {
+    let address = match (participant.pointer("/sendTo/imip"), &participant["email"]) {
+        (Some(Json::String(imip)), _) => imip.clone(),
+        (_, Json::String(email)) => format!("mailto:{email}"),
+        _ => return,
+    };
+
+    let roles = &participant["roles"];
+    let role = |name: &str| roles[name].as_bool() == Some(true);
+    let name = participant["name"].as_str().filter(|name| !name.is_empty());
+
+    let mut params = Vec::new();
+    if let Some(name) = name {
+        params.push(("CN", name));
+    }
+
+    if role("owner") {
+        writer.property("ORGANIZER", &params, &address);
+    }
+
+    if !(role("attendee") || role("optional") || role("chair") || role("informational")) {
+        return;
+    }
+
+    let role = if role("chair") {
+        "CHAIR"
+    } else if role("optional") {
+        "OPT-PARTICIPANT"
+    } else if role("attendee") {
+        "REQ-PARTICIPANT"
+    } else {
+        "NON-PARTICIPANT"
+    };
+    params.push(("ROLE", role));
+
+    let status = participant["participationStatus"]
+        .as_str()
+        .unwrap_or("needs-action")
+        .to_ascii_uppercase();
+    params.push(("PARTSTAT", &status));
+
+    let kind = participant["kind"].as_str().map(str::to_ascii_uppercase);
+    if let Some(kind) = &kind {
+        params.push(("CUTYPE", kind));
+    }
+
+    if participant["expectReply"].as_bool() == Some(true) {
+        params.push(("RSVP", "TRUE"));
+    }
+
+    writer.property("ATTENDEE", &params, &address);
+}
+
+/// Writes the JSCalendar `alert` as a VALARM.
+fn write_alert(writer: &mut IcalWriter, alert: &Json, description: &str) {
+    let trigger = &alert["trigger"];
+
+    let (params, value): (&[(&str, &str)], String) = match trigger["@type"].as_str() {
+        Some("AbsoluteTrigger") => {
+            let Some(when) = trigger["when"].as_str().and_then(parse_instant) else {
+                return;
+            };
+            (&[("VALUE", "DATE-TIME")], format_instant(when))
+        }
+        _ => {
+            let offset = trigger["offset"].as_str().unwrap_or("PT0S").to_owned();
+            match trigger["relativeTo"].as_str() {
+                Some("end") => (&[("RELATED", "END")], offset),
+                _ => (&[], offset),
+            }
+        }
+    };
+
+    let action = match alert["action"].as_str() {
+        Some("email") => "EMAIL",
+        _ => "DISPLAY",
+    };
+
+    writer
+        .begin("VALARM")
+        .property("ACTION", &[], action)
+        .text("DESCRIPTION", description)
+        .property("TRIGGER", params, &value)
+        .end("VALARM");
+}
+
+/// Writes the local date-time `dt` of an event in `zone` as the DATE
+/// or DATE-TIME property `name`.
+fn write_local(
+    writer: &mut IcalWriter,
+    name: &str,
+    dt: NaiveDateTime,
+    all_day: bool,
+    zone: Option<&str>,
+) {
+    match zone {
+        _ if all_day => {
+            writer.property(name, &[("VALUE", "DATE")], &dt.format("%Y%m%d").to_string())
+        }
+        Some("Etc/UTC" | "UTC") => {
+            writer.property(name, &[], &dt.format("%Y%m%dT%H%M%SZ").to_string())
+        }
+        Some(zone) => writer.property(
+            name,
+            &[("TZID", zone)],
+            &dt.format("%Y%m%dT%H%M%S").to_string(),
+        ),
+        None => writer.property(name, &[], &dt.format("%Y%m%dT%H%M%S").to_string()),
+    };
+}
+
+/// Turns the local `dt` of `zone` into UTC, [`None`] for unknown
+/// zones.
+fn to_utc(dt: NaiveDateTime, zone: &str) -> Option<NaiveDateTime> {
+    let tz: Tz = zone.parse().ok()?;
+    let dt = tz.from_local_datetime(&dt).earliest()?;
+    Some(dt.naive_utc())
+}
+
+/// Turns the UTC `dt` into the local time of `zone`, unchanged for
+/// floating and unknown zones.
+fn from_utc(dt: NaiveDateTime, zone: Option<&str>) -> NaiveDateTime {
+    match zone.and_then(|zone| zone.parse::<Tz>().ok()) {
+        Some(tz) => tz.from_utc_datetime(&dt).naive_local(),
+        None => dt,
+    }
+}
+
+fn parse_local(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, LOCAL_DATE_TIME).ok()
+}
+
+/// Parses a JSCalendar `UTCDateTime` (`2026-03-16T09:00:00Z`).
+fn parse_instant(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn format_instant(instant: DateTime<Utc>) -> String {
+    instant.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// `map` as a JSON object, or `null` when empty.
+fn non_empty(map: Map<String, Json>) -> Json {
+    if map.is_empty() {
+        Json::Null
+    } else {
+        Json::Object(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use super::{to_ical, to_jscal};
+
+    #[test]
+    fn jscal_event_to_ical() {
+        let event = json!({
+            "@type": "Event",
+            "id": "e1",
+            "uid": "abc@example.com",
+            "updated": "2026-03-01T08:30:00Z",
+            "title": "Standup",
+            "start": "2026-03-16T09:00:00",
+            "timeZone": "Europe/Paris",
+            "duration": "PT15M",
+            "keywords": { "Work": true },
+            "locations": { "1": { "@type": "Location", "name": "Room 1" } },
+            "freeBusyStatus": "busy",
+            "recurrenceRules": [{
+                "@type": "RecurrenceRule",
+                "frequency": "weekly",
+                "byDay": [{ "@type": "NDay", "day": "mo" }, { "@type": "NDay", "day": "th" }],
+                "until": "2026-04-30T09:00:00",
+            }],
+            "recurrenceOverrides": { "2026-03-19T09:00:00": { "excluded": true } },
+            "participants": {
+                "1": {
+                    "@type": "Participant",
+                    "name": "Jane",
+                    "sendTo": { "imip": "mailto:jane@ex.org" },
+                    "roles": { "owner": true, "attendee": true },
+                    "participationStatus": "accepted",
+                },
+            },
+            "alerts": {
+                "1": { "@type": "Alert", "trigger": { "@type": "OffsetTrigger", "offset": "-PT10M" } },
+            },
+        });
+
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let ical = String::from_utf8(to_ical(&event, now)).unwrap();
+
+        for line in [
+            "UID:abc@example.com",
+            "DTSTAMP:20260301T083000Z",
+            "DTSTART;TZID=Europe/Paris:20260316T090000",
+            "DURATION:PT15M",
+            "RRULE:FREQ=WEEKLY;UNTIL=20260430T070000Z;BYDAY=MO,TH",
+            "EXDATE;TZID=Europe/Paris:20260319T090000",
+            "SUMMARY:Standup",
+            "LOCATION:Room 1",
+            "CATEGORIES:Work",
+            "TRANSP:OPAQUE",
+            "ORGANIZER;CN=Jane:mailto:jane@ex.org",
+            "ATTENDEE;CN=Jane;ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED:mailto:jane@ex.org",
+            "TRIGGER:-PT10M",
+        ] {
+            assert!(
+                ical.contains(&format!("{line}\r\n")),
+                "missing {line} in {ical}"
+            );
+        }
+    }
+
+    #[test]
+    fn ical_event_to_jscal() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:abc@example.com\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T090000\r\n",
+            "DTEND;TZID=Europe/Paris:20260316T103000\r\n",
+            "RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3\r\n",
+            "EXDATE:20260424T070000Z\r\n",
+            "SUMMARY:Retro\r\n",
+            "CLASS:CONFIDENTIAL\r\n",
+            "ORGANIZER;CN=Jane:mailto:jane@example.com\r\n",
+            "ATTENDEE;ROLE=OPT-PARTICIPANT;PARTSTAT=TENTATIVE:mailto:bob@example.com\r\n",
+            "BEGIN:VALARM\r\n",
+            "ACTION:EMAIL\r\n",
+            "TRIGGER;RELATED=END:PT5M\r\n",
+            "END:VALARM\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let event = to_jscal(ical.as_bytes()).unwrap();
+
+        assert_eq!(event["uid"], "abc@example.com");
+        assert_eq!(event["start"], "2026-03-16T09:00:00");
+        assert_eq!(event["timeZone"], "Europe/Paris");
+        assert_eq!(event["duration"], "PT1H30M");
+        assert_eq!(event["privacy"], "secret");
+        assert_eq!(event["locations"], json!(null));
+        assert_eq!(
+            event["recurrenceRules"],
+            json!([{
+                "@type": "RecurrenceRule",
+                "frequency": "monthly",
+                "byDay": [{ "@type": "NDay", "day": "fr", "nthOfPeriod": -1 }],
+                "count": 3,
+            }]),
+        );
+        assert_eq!(
+            event["recurrenceOverrides"],
+            json!({ "2026-04-24T09:00:00": { "excluded": true } }),
+        );
+        assert_eq!(
+            event["participants"]["1"],
+            json!({
+                "@type": "Participant",
+                "email": "bob@example.com",
+                "sendTo": { "imip": "mailto:bob@example.com" },
+                "roles": { "attendee": true, "optional": true },
+                "participationStatus": "tentative",
+            }),
+        );
+        assert_eq!(
+            event["participants"]["2"]["roles"],
+            json!({ "owner": true })
+        );
+        assert_eq!(
+            event["replyTo"],
+            json!({ "imip": "mailto:jane@example.com" })
+        );
+        assert_eq!(
+            event["alerts"]["1"],
+            json!({
+                "@type": "Alert",
+                "trigger": { "@type": "OffsetTrigger", "offset": "PT5M", "relativeTo": "end" },
+                "action": "email",
+            }),
+        );
+    }
+}
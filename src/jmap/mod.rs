@@ -0,0 +1,10 @@
+//! JMAP for Calendars backend (`jmap`), for servers like Fastmail,
+//! Stalwart or Cyrus.
+//!
+//! The API endpoint and the account are discovered from the JMAP
+//! session resource, then calendars and events go through the
+//! `Calendar/*` and `CalendarEvent/*` methods. Events are JSCalendar
+//! objects, converted to and from iCalendar at the boundary.
+
+pub mod client;
+pub mod convert;
@@ -4,9 +4,22 @@ mod backend;
 mod caldav;
 mod cli;
 mod config;
+#[cfg(feature = "file")]
+mod file;
+#[cfg(feature = "google")]
+mod google;
+#[cfg(feature = "graph")]
+mod graph;
+#[cfg(feature = "ics")]
+mod ics;
+#[cfg(feature = "jmap")]
+mod jmap;
 mod shared;
+#[cfg(all(feature = "caldav", feature = "vdir"))]
+mod sync;
 #[cfg(feature = "vdir")]
 mod vdir;
+mod watch;
 mod wizard;
 
 use anyhow::Result;
@@ -27,5 +40,7 @@ fn execute(cli: CalendulaCli, printer: &mut StdoutPrinter) -> Result<()> {
     let config = cli.config_paths.as_ref();
     let account = cli.account.name.as_deref();
     let backend = cli.backend;
-    cli.command.execute(printer, config, account, backend)
+    let timezone = cli.timezone;
+    cli.command
+        .execute(printer, config, account, backend, timezone)
 }
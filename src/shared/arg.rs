@@ -1,5 +1,8 @@
+use anyhow::Result;
 use clap::Parser;
 
+use crate::shared::client::CalendarClient;
+
 /// Shared `-k/--calendar` argument naming the calendar a shared-API
 /// command operates on. Resolve the id through the account (the flag
 /// wins, otherwise `calendar.default`, otherwise bail).
@@ -11,3 +14,39 @@ pub struct CalendarIdArg {
     #[arg(short = 'k', long = "calendar", value_name = "CALENDAR-ID")]
     pub id: Option<String>,
 }
+
+/// Repeatable `-k/--calendar` argument of the read-only commands that
+/// can overlay several calendars (`event list`, `agenda`, `upcoming`
+/// and `free`). When they span several accounts, the selection
+/// applies to each of them.
+#[derive(Debug, Parser)]
+pub struct CalendarIdsArg {
+    /// Calendar the command operates on (repeatable). Falls back to
+    /// the `calendar.default` config when omitted, otherwise the
+    /// command bails.
+    #[arg(short = 'k', long = "calendar", value_name = "CALENDAR-ID")]
+    #[arg(conflicts_with = "all_calendars")]
+    pub ids: Vec<String>,
+
+    /// Operate on every calendar of the account.
+    #[arg(long)]
+    pub all_calendars: bool,
+}
+
+impl CalendarIdsArg {
+    /// Resolves the selected calendar ids: every calendar with
+    /// `--all-calendars`, the `-k` ones otherwise, falling back to
+    /// `calendar.default`.
+    pub fn resolve(&self, client: &mut CalendarClient) -> Result<Vec<String>> {
+        if self.all_calendars {
+            let calendars = client.list_calendars()?;
+            return Ok(calendars.into_iter().map(|calendar| calendar.id).collect());
+        }
+
+        if self.ids.is_empty() {
+            return Ok(vec![client.account.calendar_id(None)?]);
+        }
+
+        Ok(self.ids.clone())
+    }
+}
@@ -0,0 +1,315 @@
+//! On-disk caches of the backends.
+//!
+//! [`CacheDir`] stores JSON documents under the XDG cache directory of
+//! one account and backend (`~/.cache/calendula/<account>/<backend>`),
+//! replaced atomically (see [`write_atomic`]) and treated as empty
+//! when missing or unreadable. The CalDAV [`ItemCache`], the ics feed
+//! cache and the Google event cache are built on it.
+//!
+//! The CalDAV [`ItemCache`] stores each calendar along with its items
+//! and the sync token they were fetched at. A run first compares the
+//! CTag of the calendar, taken from one calendar listing shared by the
+//! whole run: unchanged means the cache is served as is, without
+//! downloading anything else. Otherwise a WebDAV sync-collection
+//! REPORT (RFC 6578) from the stored token fetches only the items
+//! changed or deleted since. Servers without sync-collection support,
+//! or rejecting the token, fall back to a full listing.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt::Write as _,
+    fs::{self, Permissions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use io_calendar::{calendar::Calendar, client::CalendarClientStd, item::CalendarItem};
+use log::debug;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// Directory of the cache documents of one account and backend.
+#[derive(Clone, Debug)]
+pub struct CacheDir {
+    dir: PathBuf,
+}
+
+impl CacheDir {
+    /// Builds the cache directory of the `backend` of the account
+    /// `account`, rooted under the XDG cache directory.
+    pub fn new(account: &str, backend: &str) -> Result<Self> {
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| anyhow!("Cannot find the user cache directory"))?
+            .join(env!("CARGO_PKG_NAME"))
+            .join(sanitize(account))
+            .join(backend);
+
+        Ok(Self { dir })
+    }
+
+    /// Loads the document `id`. Missing or unreadable documents (older
+    /// formats included) are treated as empty: they are only a cache.
+    pub fn load<T: DeserializeOwned + Default>(&self, id: &str) -> T {
+        let path = self.path(id);
+
+        let Ok(json) = fs::read(&path) else {
+            return T::default();
+        };
+
+        serde_json::from_slice(&json).unwrap_or_else(|err| {
+            debug!("discarding cache {}: {err}", path.display());
+            T::default()
+        })
+    }
+
+    /// Writes the document `id`, atomically through a temporary file.
+    pub fn save<T: Serialize>(&self, id: &str, value: &T) -> Result<()> {
+        let json = serde_json::to_vec(value).context("Serialize cache error")?;
+        write_atomic(&self.path(id), &json)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", sanitize(id)))
+    }
+}
+
+/// Item cache of the CalDAV calendars of one account.
+#[derive(Clone, Debug)]
+pub struct ItemCache {
+    dir: CacheDir,
+    /// Calendars as listed by this run, fetched on first use.
+    calendars: Option<Vec<Calendar>>,
+}
+
+impl ItemCache {
+    /// Builds the item cache of the account `account`.
+    pub fn new(account: &str) -> Result<Self> {
+        Ok(Self {
+            dir: CacheDir::new(account, "caldav")?,
+            calendars: None,
+        })
+    }
+
+    /// Lists the calendars of `client`, once per run: the listing
+    /// carries the CTags every [`ItemCache::list_items`] compares.
+    pub fn list_calendars(&mut self, client: &mut CalendarClientStd) -> Result<Vec<Calendar>> {
+        if let Some(calendars) = &self.calendars {
+            return Ok(calendars.clone());
+        }
+
+        let calendars = client.list_calendars()?;
+        self.calendars = Some(calendars.clone());
+        Ok(calendars)
+    }
+
+    /// Forgets the calendar listing of this run, after a write made
+    /// its CTags stale.
+    pub fn invalidate(&mut self) {
+        self.calendars = None;
+    }
+
+    /// Returns every item of `calendar_id`, refreshing the cache from
+    /// `client` first when the calendar CTag changed.
+    pub fn list_items(
+        &mut self,
+        client: &mut CalendarClientStd,
+        calendar_id: &str,
+    ) -> Result<Vec<CalendarItem>> {
+        let mut cached: CachedCalendar = self.dir.load(calendar_id);
+
+        let calendar = self
+            .list_calendars(client)?
+            .into_iter()
+            .find(|calendar| calendar.id == calendar_id);
+
+        let ctag = calendar
+            .as_ref()
+            .and_then(|calendar| calendar.ctag.as_ref());
+        let cached_ctag = cached.calendar.as_ref().and_then(|c| c.ctag.as_ref());
+
+        if ctag.is_some() && ctag == cached_ctag {
+            debug!("calendar `{calendar_id}` unchanged since last run, serving cache");
+            return Ok(cached.items.into_values().collect());
+        }
+
+        cached.refresh(client, calendar_id)?;
+        cached.calendar = calendar;
+
+        if let Err(err) = self.dir.save(calendar_id, &cached) {
+            debug!("cannot save item cache of `{calendar_id}`: {err:?}");
+        }
+
+        Ok(cached.items.into_values().collect())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CachedCalendar {
+    /// Calendar as listed when the items were fetched, its CTag
+    /// included.
+    calendar: Option<Calendar>,
+    sync_token: Option<String>,
+    /// Cached items keyed by item id.
+    items: BTreeMap<String, CalendarItem>,
+}
+
+impl CachedCalendar {
+    /// Brings the cached items up to date: incrementally from the
+    /// stored sync token when possible, from scratch otherwise.
+    fn refresh(&mut self, client: &mut CalendarClientStd, calendar_id: &str) -> Result<()> {
+        if let Some(token) = self.sync_token.take() {
+            match client.sync_items(calendar_id, Some(&token)) {
+                Ok(changes) => {
+                    debug!(
+                        "calendar `{calendar_id}`: {} changed, {} deleted since last run",
+                        changes.updated.len(),
+                        changes.deleted.len(),
+                    );
+
+                    for id in changes.deleted {
+                        self.items.remove(&id);
+                    }
+
+                    for item in changes.updated {
+                        self.items.insert(item.id.clone(), item);
+                    }
+
+                    self.sync_token = changes.sync_token;
+                    return Ok(());
+                }
+                Err(err) => {
+                    debug!("sync token of `{calendar_id}` rejected, resyncing: {err}");
+                }
+            }
+        }
+
+        match client.sync_items(calendar_id, None) {
+            Ok(changes) => {
+                self.items = by_id(changes.updated);
+                self.sync_token = changes.sync_token;
+            }
+            Err(err) => {
+                debug!("sync-collection unsupported for `{calendar_id}`, listing all: {err}");
+                self.items = by_id(client.list_items(calendar_id, None, None, None)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn by_id(items: Vec<CalendarItem>) -> BTreeMap<String, CalendarItem> {
+    items
+        .into_iter()
+        .map(|item| (item.id.clone(), item))
+        .collect()
+}
+
+/// Replaces the file at `path` with `contents`, creating its parent
+/// directory. The contents go to a temporary file renamed over `path`,
+/// so that an interrupted write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    replace(path, contents, None)
+}
+
+/// Same as [`write_atomic`], for a file the user manages: a symlink at
+/// `path` is followed, so that its target gets replaced instead of
+/// the link, and the permissions of the replaced file are kept.
+#[cfg(feature = "file")]
+pub fn write_user_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => path.to_owned(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Resolve `{}` error", path.display()));
+        }
+    };
+
+    let permissions = fs::metadata(&path).ok().map(|meta| meta.permissions());
+    replace(&path, contents, permissions)
+}
+
+fn replace(path: &Path, contents: &[u8], permissions: Option<Permissions>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Create directory `{}` error", parent.display()))?;
+    }
+
+    let mut tmp = OsString::from(path.as_os_str());
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file =
+        fs::File::create(&tmp).with_context(|| format!("Create `{}` error", tmp.display()))?;
+    file.write_all(contents)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Write `{}` error", tmp.display()))?;
+
+    if let Some(permissions) = permissions {
+        fs::set_permissions(&tmp, permissions)
+            .with_context(|| format!("Set permissions of `{}` error", tmp.display()))?;
+    }
+
+    fs::rename(&tmp, path).with_context(|| format!("Replace `{}` error", path.display()))?;
+
+    Ok(())
+}
+
+/// Escapes account, calendar and item ids into file names: ASCII
+/// alphanumerics and `-` are kept, any other byte is written as `_`
+/// followed by its two hex digits, so that distinct ids never share a
+/// file.
+pub fn sanitize(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+
+    for byte in id.bytes() {
+        match byte {
+            b if b.is_ascii_alphanumeric() || b == b'-' => name.push(b as char),
+            b => {
+                let _ = write!(name, "_{b:02X}");
+            }
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sanitize;
+
+    #[test]
+    fn sanitize_is_injective() {
+        assert_eq!(sanitize("work-2"), "work-2");
+        assert_eq!(sanitize("a/b"), "a_2Fb");
+        assert_eq!(sanitize("a_b"), "a_5Fb");
+        assert_eq!(sanitize("été"), "_C3_A9t_C3_A9");
+    }
+
+    #[cfg(all(unix, feature = "file"))]
+    #[test]
+    fn write_user_file_through_symlink() {
+        use std::{
+            fs,
+            os::unix::fs::{PermissionsExt, symlink},
+        };
+
+        use super::write_user_file;
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("dotfiles.ics");
+        let link = dir.path().join("calendar.ics");
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        write_user_file(&link, b"new").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
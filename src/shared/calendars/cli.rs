@@ -4,7 +4,8 @@ use pimalaya_cli::printer::Printer;
 
 use crate::shared::{
     calendars::{
-        create::CalendarCreateCommand, delete::CalendarDeleteCommand, list::CalendarListCommand,
+        create::CalendarCreateCommand, delete::CalendarDeleteCommand,
+        export::CalendarExportCommand, import::CalendarImportCommand, list::CalendarListCommand,
         update::CalendarUpdateCommand,
     },
     client::CalendarClient,
@@ -18,6 +19,8 @@ pub enum CalendarCommand {
     Create(CalendarCreateCommand),
     Update(CalendarUpdateCommand),
     Delete(CalendarDeleteCommand),
+    Import(CalendarImportCommand),
+    Export(CalendarExportCommand),
 }
 
 impl CalendarCommand {
@@ -27,6 +30,8 @@ impl CalendarCommand {
             Self::Create(cmd) => cmd.execute(printer, client),
             Self::Update(cmd) => cmd.execute(printer, client),
             Self::Delete(cmd) => cmd.execute(printer, client),
+            Self::Import(cmd) => cmd.execute(printer, client),
+            Self::Export(cmd) => cmd.execute(printer, client),
         }
     }
 }
@@ -28,14 +28,14 @@ pub struct CalendarCreateCommand {
 
 impl CalendarCreateCommand {
     pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
-        client.create_calendar(
+        let id = client.create_calendar(
             &self.id,
             &self.name,
             self.description.as_deref(),
             self.color.as_deref(),
         )?;
 
-        let msg = format!("Calendar `{}` successfully created", self.id);
+        let msg = format!("Calendar `{id}` successfully created");
         printer.out(Message::new(msg))
     }
 }
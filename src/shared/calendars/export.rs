@@ -0,0 +1,226 @@
+use std::{fmt, fs, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Error, Result, bail};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::Parser;
+use io_calendar::{
+    calcard::icalendar::{ICalendarComponentType, ICalendarProperty},
+    item::CalendarItem,
+};
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdsArg,
+    client::CalendarClient,
+    events::list::build_window,
+    export::{csv_record, jcal, xcal},
+    ical::merge_objects,
+    recurrence::{self, component_text, component_values},
+    timezone::{Timezone, TzResolver, format_date_time},
+};
+
+/// Columns of the CSV export.
+const CSV_HEADER: [&str; 11] = [
+    "calendar",
+    "uid",
+    "recurrence-id",
+    "summary",
+    "start",
+    "end",
+    "all-day",
+    "location",
+    "description",
+    "status",
+    "categories",
+];
+
+/// Export calendars into a single file.
+///
+/// The items of the selected calendars are merged into one VCALENDAR,
+/// the VTIMEZONEs they share written once. Pass `--from` and/or `--to`
+/// (YYYY-MM-DD, both inclusive) to only export the items having an
+/// occurrence in that range.
+///
+/// `--format` picks the output: `ics` (default), `jcal` (RFC 7265),
+/// `xcal` (RFC 6321), or `csv` with the common fields of the events,
+/// times rendered in the `--tz` / `event.timezone` zone. The CSV holds
+/// one row per VEVENT, or one row per occurrence when a range is
+/// given. The export is printed, or written to `-o/--output`.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct CalendarExportCommand {
+    #[command(flatten)]
+    pub calendars: CalendarIdsArg,
+
+    /// Output format: `ics`, `jcal`, `xcal` or `csv`.
+    #[arg(short, long, value_name = "FORMAT", default_value_t)]
+    pub format: ExportFormat,
+
+    /// Only export items occurring on or after this date (inclusive,
+    /// YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<NaiveDate>,
+
+    /// Only export items occurring on or before this date (inclusive,
+    /// YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<NaiveDate>,
+
+    /// Write the export to this file instead of printing it.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+}
+
+impl CalendarExportCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let timezone = client.account.timezone();
+        let window = build_window(self.from, self.to);
+        let ranged = window.0.is_some() || window.1.is_some();
+
+        let mut items = Vec::new();
+
+        for calendar_id in self.calendars.resolve(&mut client)? {
+            for item in client.list_all_items(&calendar_id, None)? {
+                if !ranged || occurs_in(&item, window) {
+                    items.push((calendar_id.clone(), item));
+                }
+            }
+        }
+
+        let merged = || merge_objects(&[], items.iter().map(|(_, item)| item.contents.as_slice()));
+
+        let contents = match self.format {
+            ExportFormat::Ics => String::from_utf8_lossy(&merged()).into_owned(),
+            ExportFormat::Jcal => {
+                serde_json::to_string_pretty(&jcal(&merged())?).context("Serialize jCal error")?
+            }
+            ExportFormat::Xcal => xcal(&merged())?,
+            ExportFormat::Csv => csv(&items, timezone, ranged.then_some(window)),
+        };
+
+        let Some(path) = self.output else {
+            return printer.out(Message::new(contents));
+        };
+
+        fs::write(&path, contents)
+            .with_context(|| format!("Write export `{}` error", path.display()))?;
+
+        printer.out(Message::new(format!(
+            "Calendar successfully exported to `{}`",
+            path.display()
+        )))
+    }
+}
+
+/// Whether an event, todo or journal of `item` occurs in `window`.
+fn occurs_in(item: &CalendarItem, window: (Option<NaiveDateTime>, Option<NaiveDateTime>)) -> bool {
+    let Some(ical) = item.as_ical() else {
+        return false;
+    };
+
+    [
+        ICalendarComponentType::VEvent,
+        ICalendarComponentType::VTodo,
+        ICalendarComponentType::VJournal,
+    ]
+    .iter()
+    .any(|kind| !recurrence::expand(&ical, kind, window.0, window.1).is_empty())
+}
+
+/// Renders the VEVENTs of `items` as CSV: one row per component, or
+/// one row per occurrence in `window` when given.
+fn csv(
+    items: &[(String, CalendarItem)],
+    timezone: Timezone,
+    window: Option<(Option<NaiveDateTime>, Option<NaiveDateTime>)>,
+) -> String {
+    let mut out = csv_record(CSV_HEADER);
+    let kind = ICalendarComponentType::VEvent;
+
+    for (calendar, item) in items {
+        let Some(ical) = item.as_ical() else {
+            continue;
+        };
+
+        let resolver = TzResolver::new(&ical);
+        let occurrences = match window {
+            Some((from, to)) => recurrence::expand(&ical, &kind, from, to),
+            None => ical
+                .components
+                .iter()
+                .filter(|component| component.component_type == kind)
+                .filter_map(recurrence::single)
+                .collect(),
+        };
+
+        for occurrence in occurrences {
+            let component = occurrence.component;
+            let text = |name| component_text(component, name).unwrap_or_default();
+            let (start, end) = timezone.localize_occurrence(&resolver, &occurrence);
+
+            let uid = occurrence.uid.clone().unwrap_or_else(|| item.id.clone());
+            let status = component_values(component, &ICalendarProperty::Status);
+            let categories = component_values(component, &ICalendarProperty::Categories);
+
+            let record: [&str; 11] = [
+                calendar,
+                &uid,
+                occurrence.recurrence_id.as_deref().unwrap_or_default(),
+                &text(&ICalendarProperty::Summary),
+                &format_date_time(start, occurrence.all_day),
+                &format_date_time(end, occurrence.all_day),
+                if occurrence.all_day { "true" } else { "false" },
+                &text(&ICalendarProperty::Location),
+                &text(&ICalendarProperty::Description),
+                status.first().map(String::as_str).unwrap_or_default(),
+                &categories.join(","),
+            ];
+
+            out.push_str(&csv_record(record));
+        }
+    }
+
+    out
+}
+
+/// Output format of `calendar export`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// iCalendar (RFC 5545).
+    #[default]
+    Ics,
+    /// jCal, iCalendar as JSON (RFC 7265).
+    Jcal,
+    /// xCal, iCalendar as XML (RFC 6321).
+    Xcal,
+    /// CSV of the common event fields.
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "ics" => Ok(Self::Ics),
+            "jcal" => Ok(Self::Jcal),
+            "xcal" => Ok(Self::Xcal),
+            "csv" => Ok(Self::Csv),
+            format => {
+                bail!("Invalid export format `{format}`; expected `ics`, `jcal`, `xcal` or `csv`")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ics => write!(f, "ics"),
+            Self::Jcal => write!(f, "jcal"),
+            Self::Xcal => write!(f, "xcal"),
+            Self::Csv => write!(f, "csv"),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{Context, Error, Result, bail};
+use clap::Parser;
+use comfy_table::{Cell, Row, Table};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::{CalendarClient, item_uid},
+    ical::{IcalArg, split_objects},
+};
+
+/// Import an iCalendar file into a calendar.
+///
+/// The VCALENDAR (or the concatenated VCALENDARs) is split into one
+/// item per UID: a recurring event and its RECURRENCE-ID overrides
+/// stay together, and each item carries the VTIMEZONEs it refers to.
+/// Components without UID get one derived from their contents;
+/// METHOD is dropped.
+///
+/// Items whose UID already exists in the calendar are handled by
+/// `--on-conflict`: `skip` (default) keeps the existing item, `update`
+/// overwrites it. Importing the same file twice is therefore harmless.
+///
+/// JSON output: `{"created", "updated", "skipped"}`.
+#[derive(Debug, Parser)]
+pub struct CalendarImportCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    #[command(flatten)]
+    pub ical: IcalArg,
+
+    /// What to do with items whose UID already exists: `skip` or
+    /// `update`.
+    #[arg(long, value_name = "POLICY", default_value_t)]
+    pub on_conflict: ImportConflict,
+}
+
+impl CalendarImportCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let objects = split_objects(&self.ical.read()?);
+
+        if objects.is_empty() {
+            bail!("Cannot find any component to import");
+        }
+
+        // Existing items indexed by UID, with their id and ETag.
+        let existing: HashMap<String, (String, Option<String>)> = client
+            .list_all_items(&calendar_id, None)?
+            .into_iter()
+            .filter_map(|item| Some((item_uid(&item)?, (item.id, item.etag))))
+            .collect();
+
+        let mut report = ImportReport {
+            preset: client.account.table_preset().to_string(),
+            ..ImportReport::default()
+        };
+
+        for object in objects {
+            let uid = &object.uid;
+
+            match (existing.get(uid), self.on_conflict) {
+                (None, _) => {
+                    client
+                        .create_item(&calendar_id, object.contents)
+                        .with_context(|| format!("Import item `{uid}` error"))?;
+                    report.created += 1;
+                }
+                (Some(_), ImportConflict::Skip) => {
+                    report.skipped += 1;
+                }
+                (Some((id, etag)), ImportConflict::Update) => {
+                    client
+                        .update_item(&calendar_id, id, object.contents, etag.as_deref())
+                        .with_context(|| format!("Update item `{uid}` error"))?;
+                    report.updated += 1;
+                }
+            }
+        }
+
+        printer.out(report)
+    }
+}
+
+/// How `calendar import` handles an item whose UID already exists in
+/// the calendar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportConflict {
+    /// Keep the existing item.
+    #[default]
+    Skip,
+    /// Overwrite the existing item.
+    Update,
+}
+
+impl FromStr for ImportConflict {
+    type Err = Error;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "skip" => Ok(Self::Skip),
+            "update" => Ok(Self::Update),
+            policy => bail!("Invalid conflict policy `{policy}`; expected `skip` or `update`"),
+        }
+    }
+}
+
+impl fmt::Display for ImportConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Skip => write!(f, "skip"),
+            Self::Update => write!(f, "update"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ImportReport {
+    #[serde(skip)]
+    pub preset: String,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+
+        table
+            .load_preset(&self.preset)
+            .set_header(Row::from([
+                Cell::new("CREATED"),
+                Cell::new("UPDATED"),
+                Cell::new("SKIPPED"),
+            ]))
+            .add_row(Row::from([
+                Cell::new(self.created),
+                Cell::new(self.updated),
+                Cell::new(self.skipped),
+            ]));
+
+        writeln!(f)?;
+        write!(f, "{table}")?;
+        writeln!(f)?;
+        Ok(())
+    }
+}
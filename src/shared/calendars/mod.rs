@@ -1,5 +1,7 @@
 pub mod cli;
 pub mod create;
 pub mod delete;
+pub mod export;
+pub mod import;
 pub mod list;
 pub mod update;
@@ -1,46 +1,61 @@
 //! Cross-protocol [`CalendarClient`] for the shared subcommands
-//! (`calendars`, `events`, `items`).
+//! (`calendars`, `events`, `todos`, `items`).
 //!
-//! Wraps [`io_calendar::client::CalendarClientStd`] and bundles the
-//! active [`Account`] alongside the I/O client. Implements
-//! [`Deref`]/[`DerefMut`] onto the inner client so callers can call
-//! its methods directly.
+//! Wraps the I/O client of the single active backend and bundles the
+//! active [`Account`] alongside it. The `vdir` and `caldav` backends
+//! go through [`io_calendar::client::CalendarClientStd`]; the `file`,
+//! `graph`, `jmap`, `google` and read-only `ics` backends through their
+//! own clients, the latter serving reads and rejecting every write.
 //!
-//! [`CalendarClientStd`] is an enum over the single active backend, so
-//! construction picks the first storage backend (`vdir`, then `caldav`)
-//! allowed by the [`Backend`] flag that is configured on the account,
-//! then wraps it via the per-backend `From` impls.
+//! Construction picks the first backend (`vdir`, `caldav`, `file`,
+//! `graph`, `jmap`, `google`, then `ics`) allowed by the [`Backend`]
+//! flag that is configured on the account.
 
-use std::ops::{Deref, DerefMut};
+use std::{error, fmt};
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use io_calendar::{
+    calcard::icalendar::ICalendarProperty,
+    calendar::{Calendar, CalendarDiff},
+    client::CalendarClientStd,
+    item::{CalendarItem, PropFilter, TimeRange},
+};
 
 use crate::{
     account::context::Account,
     backend::Backend,
     config::{AccountConfig, Config},
+    shared::{cache::ItemCache, ical::set_properties, recurrence::component_text},
 };
 
 pub struct CalendarClient {
-    inner: io_calendar::client::CalendarClientStd,
+    inner: Inner,
+    /// Name of the `[accounts.<name>]` block the client was built from.
+    pub account_name: String,
     pub account: Account,
+    /// Backend actually picked (never [`Backend::Auto`]).
+    pub backend: Backend,
+    /// On-disk item cache, only set for the CalDAV backend.
+    cache: Option<ItemCache>,
 }
 
 impl CalendarClient {
     pub fn new(
         config: Config,
+        account_name: &str,
         #[allow(unused_mut)] mut account_config: AccountConfig,
         #[allow(unused)] backend: Backend,
     ) -> Result<Self> {
-        use io_calendar::client::CalendarClientStd;
-
-        let mut inner: Option<CalendarClientStd> = None;
+        let mut inner: Option<(Inner, Backend)> = None;
+        #[allow(unused_mut)]
+        let mut cache = None;
 
         #[cfg(feature = "vdir")]
         if inner.is_none() && backend.allows_vdir() {
             if let Some(vdir_config) = account_config.vdir.take() {
                 let client = crate::vdir::client::build(&vdir_config);
-                inner = Some(CalendarClientStd::from(client));
+                inner = Some((Inner::Std(client.into()), Backend::Vdir));
             }
         }
 
@@ -49,30 +64,433 @@ impl CalendarClient {
             if let Some(caldav_config) = account_config.caldav.take() {
                 let inner_client = crate::caldav::client::connect_and_resolve(&caldav_config)?;
                 let client = io_calendar::webdav::client::WebdavClientStd::new(inner_client);
-                inner = Some(CalendarClientStd::from(client));
+                inner = Some((Inner::Std(client.into()), Backend::Caldav));
+
+                if caldav_config.cache.unwrap_or(true) {
+                    cache = Some(ItemCache::new(account_name)?);
+                }
+            }
+        }
+
+        #[cfg(feature = "file")]
+        if inner.is_none() && backend.allows_file() {
+            if let Some(file_config) = account_config.file.take() {
+                let client = crate::file::client::FileClient::new(file_config);
+                inner = Some((Inner::File(client), Backend::File));
+            }
+        }
+
+        #[cfg(feature = "graph")]
+        if inner.is_none() && backend.allows_graph() {
+            if let Some(graph_config) = account_config.graph.take() {
+                let client = crate::graph::client::GraphClient::new(graph_config)?;
+                inner = Some((Inner::Graph(client), Backend::Graph));
+            }
+        }
+
+        #[cfg(feature = "jmap")]
+        if inner.is_none() && backend.allows_jmap() {
+            if let Some(jmap_config) = account_config.jmap.take() {
+                let client = crate::jmap::client::JmapClient::new(jmap_config)?;
+                inner = Some((Inner::Jmap(client), Backend::Jmap));
             }
         }
 
-        let Some(inner) = inner else {
+        #[cfg(feature = "google")]
+        if inner.is_none() && backend.allows_google() {
+            if let Some(google_config) = account_config.google.take() {
+                let client = crate::google::client::GoogleClient::new(account_name, google_config)?;
+                inner = Some((Inner::Google(client), Backend::Google));
+            }
+        }
+
+        #[cfg(feature = "ics")]
+        if inner.is_none() && backend.allows_ics() {
+            if let Some(ics_config) = account_config.ics.take() {
+                let client = crate::ics::client::IcsClient::new(account_name, ics_config)?;
+                inner = Some((Inner::Ics(client), Backend::Ics));
+            }
+        }
+
+        let Some((inner, backend)) = inner else {
             bail!("No backend matching `{backend}` is configured for this account");
         };
 
         let account = Account::from(config).merge(Account::from(account_config));
 
-        Ok(Self { inner, account })
+        Ok(Self {
+            inner,
+            account_name: account_name.to_owned(),
+            account,
+            backend,
+            cache,
+        })
     }
-}
 
-impl Deref for CalendarClient {
-    type Target = io_calendar::client::CalendarClientStd;
+    /// Lists every item of `calendar_id` overlapping `range`, without
+    /// paging.
+    ///
+    /// On CalDAV, items are served from the on-disk cache, refreshed
+    /// incrementally; the range is then left to the caller, which
+    /// filters occurrences anyway. Other backends list directly.
+    pub fn list_all_items(
+        &mut self,
+        calendar_id: &str,
+        range: Option<&TimeRange>,
+    ) -> Result<Vec<CalendarItem>> {
+        if let (Some(cache), Inner::Std(client)) = (&mut self.cache, &mut self.inner) {
+            return cache.list_items(client, calendar_id);
+        }
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+        self.list_items(calendar_id, None, None, range)
+    }
+
+    /// Finds the item of `calendar_id` holding the UID `uid`. Item ids
+    /// are backend ids on most backends (the href on CalDAV), so the
+    /// items are scanned rather than fetched by id.
+    pub fn find_item_by_uid(&mut self, calendar_id: &str, uid: &str) -> Result<CalendarItem> {
+        self.list_all_items(calendar_id, None)?
+            .into_iter()
+            .find(|item| item_uid(item).as_deref() == Some(uid))
+            .ok_or_else(|| anyhow!("Cannot find item with UID `{uid}` in calendar `{calendar_id}`"))
+    }
+
+    /// Lists the items of `calendar_id` possibly matching `range` and
+    /// every `filters` text match, without paging.
+    ///
+    /// On CalDAV, the filters go to the server as a `calendar-query`
+    /// REPORT, bypassing the cache. Other backends list everything:
+    /// the caller evaluates its query client-side anyway.
+    pub fn search_items(
+        &mut self,
+        calendar_id: &str,
+        range: Option<&TimeRange>,
+        filters: &[PropFilter],
+    ) -> Result<Vec<CalendarItem>> {
+        #[cfg(feature = "caldav")]
+        if let Inner::Std(client) = &mut self.inner
+            && self.backend == Backend::Caldav
+            && !filters.is_empty()
+        {
+            return Ok(client.search_items(calendar_id, range, filters)?);
+        }
+
+        self.list_all_items(calendar_id, range)
+    }
+
+    /// Sets `props` on the `component` blocks of the item `item_id`
+    /// (see [`set_properties`]), bumps their DTSTAMP and LAST-MODIFIED,
+    /// and writes the item back guarded by the ETag it was fetched
+    /// with. Returns the new contents of the item.
+    pub fn update_component(
+        &mut self,
+        calendar_id: &str,
+        item_id: &str,
+        component: &str,
+        props: &[(&str, Option<&str>)],
+    ) -> Result<Vec<u8>> {
+        let item = self.get_item(calendar_id, item_id)?;
+
+        let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut props: Vec<(&str, Option<&str>)> = props.to_vec();
+        props.push(("DTSTAMP", Some(now.as_str())));
+        props.push(("LAST-MODIFIED", Some(now.as_str())));
+
+        let Some(contents) = set_properties(&item.contents, component, &props) else {
+            bail!("Item `{item_id}` does not contain any {component}");
+        };
+
+        let etag = item.etag.as_deref();
+        self.update_item(calendar_id, item_id, contents.clone(), etag)?;
+
+        Ok(contents)
+    }
+
+    pub fn list_calendars(&mut self) -> Result<Vec<Calendar>> {
+        match &mut self.inner {
+            Inner::Std(client) => match &mut self.cache {
+                Some(cache) => cache.list_calendars(client),
+                None => Ok(client.list_calendars()?),
+            },
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.list_calendars(),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.list_calendars(),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.list_calendars(),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.list_calendars(),
+            #[cfg(feature = "ics")]
+            Inner::Ics(client) => Ok(client.list_calendars()),
+        }
+    }
+
+    /// Creates the calendar `calendar_id`, returning its id: the one
+    /// the backend assigned when it does not let clients choose.
+    pub fn create_calendar(
+        &mut self,
+        calendar_id: &str,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<String> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => {
+                client.create_calendar(calendar_id, name, description, color)?;
+                Ok(calendar_id.to_owned())
+            }
+            #[cfg(feature = "file")]
+            Inner::File(client) => {
+                client.create_calendar(calendar_id, name, description, color)?;
+                Ok(calendar_id.to_owned())
+            }
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.create_calendar(calendar_id, name, description, color),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.create_calendar(calendar_id, name, description, color),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.create_calendar(calendar_id, name, description, color),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("create calendars"),
+        }
+    }
+
+    pub fn update_calendar(&mut self, calendar_id: &str, patch: CalendarDiff) -> Result<()> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => {
+                client.update_calendar(calendar_id, patch)?;
+                Ok(())
+            }
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.update_calendar(calendar_id, patch),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.update_calendar(calendar_id, patch),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.update_calendar(calendar_id, patch),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.update_calendar(calendar_id, patch),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("update calendars"),
+        }
+    }
+
+    pub fn delete_calendar(&mut self, calendar_id: &str) -> Result<()> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => {
+                client.delete_calendar(calendar_id)?;
+                Ok(())
+            }
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.delete_calendar(calendar_id),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.delete_calendar(calendar_id),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.delete_calendar(calendar_id),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.delete_calendar(calendar_id),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("delete calendars"),
+        }
+    }
+
+    /// Lists one page of the items of `calendar_id`. The file, graph,
+    /// jmap, google and ics backends leave `range` to the caller, like
+    /// the CalDAV cache.
+    pub fn list_items(
+        &mut self,
+        calendar_id: &str,
+        page: Option<u32>,
+        page_size: Option<u32>,
+        range: Option<&TimeRange>,
+    ) -> Result<Vec<CalendarItem>> {
+        match &mut self.inner {
+            Inner::Std(client) => Ok(client.list_items(calendar_id, page, page_size, range)?),
+            #[cfg(feature = "file")]
+            Inner::File(client) => Ok(paginate(client.list_items(calendar_id)?, page, page_size)),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => Ok(paginate(client.list_items(calendar_id)?, page, page_size)),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => Ok(paginate(client.list_items(calendar_id)?, page, page_size)),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => Ok(paginate(client.list_items(calendar_id)?, page, page_size)),
+            #[cfg(feature = "ics")]
+            Inner::Ics(client) => Ok(paginate(client.list_items(calendar_id)?, page, page_size)),
+        }
+    }
+
+    pub fn get_item(&mut self, calendar_id: &str, item_id: &str) -> Result<CalendarItem> {
+        match &mut self.inner {
+            Inner::Std(client) => Ok(client.get_item(calendar_id, item_id)?),
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.get_item(calendar_id, item_id),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.get_item(calendar_id, item_id),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.get_item(calendar_id, item_id),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.get_item(calendar_id, item_id),
+            #[cfg(feature = "ics")]
+            Inner::Ics(client) => client.get_item(calendar_id, item_id),
+        }
+    }
+
+    /// Creates an item in `calendar_id`, returning its id.
+    pub fn create_item(&mut self, calendar_id: &str, contents: Vec<u8>) -> Result<String> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => Ok(client.create_item(calendar_id, contents)?),
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.create_item(calendar_id, contents),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.create_item(calendar_id, contents),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.create_item(calendar_id, contents),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.create_item(calendar_id, contents),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("create items"),
+        }
+    }
+
+    /// Replaces the contents of the item `item_id`, guarded by `etag`
+    /// when given.
+    pub fn update_item(
+        &mut self,
+        calendar_id: &str,
+        item_id: &str,
+        contents: Vec<u8>,
+        etag: Option<&str>,
+    ) -> Result<()> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => {
+                let Err(err) = client.update_item(calendar_id, item_id, contents, etag) else {
+                    return Ok(());
+                };
+
+                // io-calendar does not expose the status of a failed
+                // write: it conflicted when the ETag moved since.
+                if let Some(etag) = etag
+                    && let Ok(current) = client.get_item(calendar_id, item_id)
+                    && current.etag.as_deref() != Some(etag)
+                {
+                    return Err(Conflict.into());
+                }
+
+                Err(err.into())
+            }
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.update_item(calendar_id, item_id, contents, etag),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.update_item(item_id, contents, etag),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.update_item(item_id, contents, etag),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.update_item(calendar_id, item_id, contents, etag),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("update items"),
+        }
+    }
+
+    pub fn delete_item(&mut self, calendar_id: &str, item_id: &str) -> Result<()> {
+        self.invalidate_cache();
+
+        match &mut self.inner {
+            Inner::Std(client) => {
+                client.delete_item(calendar_id, item_id)?;
+                Ok(())
+            }
+            #[cfg(feature = "file")]
+            Inner::File(client) => client.delete_item(calendar_id, item_id),
+            #[cfg(feature = "graph")]
+            Inner::Graph(client) => client.delete_item(item_id),
+            #[cfg(feature = "jmap")]
+            Inner::Jmap(client) => client.delete_item(item_id),
+            #[cfg(feature = "google")]
+            Inner::Google(client) => client.delete_item(calendar_id, item_id),
+            #[cfg(feature = "ics")]
+            Inner::Ics(_) => read_only("delete items"),
+        }
+    }
+
+    /// Forgets the calendar listing the CalDAV cache took for this
+    /// run, before a write changes the CTags it carries.
+    fn invalidate_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.invalidate();
+        }
     }
 }
 
-impl DerefMut for CalendarClient {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
+/// UID of the first component of `item` carrying one.
+pub fn item_uid(item: &CalendarItem) -> Option<String> {
+    item.as_ical()?
+        .components
+        .iter()
+        .find_map(|component| component_text(component, &ICalendarProperty::Uid))
+}
+
+/// Error of a write rejected because the item changed on the backend
+/// since the ETag guarding it was fetched. Backends return it so that
+/// callers can tell a conflict from any other failure.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Conflict;
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Item changed since it was fetched")
     }
 }
+
+impl error::Error for Conflict {}
+
+/// I/O client of the active backend.
+enum Inner {
+    Std(CalendarClientStd),
+    #[cfg(feature = "file")]
+    File(crate::file::client::FileClient),
+    #[cfg(feature = "graph")]
+    Graph(crate::graph::client::GraphClient),
+    #[cfg(feature = "jmap")]
+    Jmap(crate::jmap::client::JmapClient),
+    #[cfg(feature = "google")]
+    Google(crate::google::client::GoogleClient),
+    #[cfg(feature = "ics")]
+    Ics(crate::ics::client::IcsClient),
+}
+
+/// Returns page `page` (1-indexed, defaults to 1) of `items`, or all
+/// of them without `page_size`.
+#[cfg(any(
+    feature = "file",
+    feature = "graph",
+    feature = "google",
+    feature = "ics",
+    feature = "jmap"
+))]
+fn paginate(
+    items: Vec<CalendarItem>,
+    page: Option<u32>,
+    page_size: Option<u32>,
+) -> Vec<CalendarItem> {
+    let Some(size) = page_size else {
+        return items;
+    };
+
+    let skip = page.unwrap_or(1).saturating_sub(1) as usize * size as usize;
+    items.into_iter().skip(skip).take(size as usize).collect()
+}
+
+/// Rejects a write on the read-only ics backend.
+#[cfg(feature = "ics")]
+fn read_only<T>(action: &str) -> Result<T> {
+    bail!("Cannot {action}: the ics backend is a read-only subscription")
+}
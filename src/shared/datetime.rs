@@ -0,0 +1,155 @@
+//! Human date, time and duration parsing shared by command flags.
+//!
+//! Dates are `YYYY-MM-DD`, `today` or `tomorrow`; times are `HH:MM`
+//! or `HH:MM:SS`, joined to the date by a space or a `T`. Durations
+//! are a sequence of `<n><unit>` with units `w`, `d`, `h`, `m` and `s`
+//! (`1h30m`), or an iCalendar duration (`PT1H30M`).
+
+use anyhow::{Result, bail};
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+/// Parses a date, resolving `today` and `tomorrow` against `today`.
+pub fn parse_date(s: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let s = s.trim();
+
+    if s.eq_ignore_ascii_case("today") {
+        return Ok(today);
+    }
+
+    if s.eq_ignore_ascii_case("tomorrow") {
+        return Ok(today + Days::new(1));
+    }
+
+    if s.eq_ignore_ascii_case("yesterday") {
+        return Ok(today - Days::new(1));
+    }
+
+    match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        Ok(date) => Ok(date),
+        Err(_) => bail!("Invalid date `{s}`; expected YYYY-MM-DD, `today` or `tomorrow`"),
+    }
+}
+
+/// Parses a time of day.
+pub fn parse_time(s: &str) -> Result<NaiveTime> {
+    let s = s.trim();
+
+    NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .or_else(|_| bail!("Invalid time `{s}`; expected HH:MM or HH:MM:SS"))
+}
+
+/// Parses a date followed by an optional time. A missing time means
+/// midnight; the returned flag tells whether a time was given.
+pub fn parse_date_time(s: &str, today: NaiveDate) -> Result<(NaiveDateTime, bool)> {
+    let s = s.trim();
+
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+
+    let date = parse_date(date, today)?;
+
+    match time {
+        Some(time) => Ok((date.and_time(parse_time(time)?), true)),
+        None => Ok((date.and_time(NaiveTime::MIN), false)),
+    }
+}
+
+/// Parses a positive duration.
+pub fn parse_duration(s: &str) -> Result<TimeDelta> {
+    let trimmed = s.trim();
+    let upper = trimmed.to_ascii_uppercase();
+
+    // iCalendar durations: drop the designators, the remaining units
+    // are the same as the short form except for minutes, which are
+    // only `M` after the `T`.
+    let short = match upper.strip_prefix('P') {
+        Some(rest) => match rest.split_once('T') {
+            Some((date, time)) => format!("{date}{}", time.replace('M', "I")),
+            None => rest.to_owned(),
+        },
+        None => upper.replace('M', "I"),
+    };
+
+    let mut total = TimeDelta::zero();
+    let mut digits = String::new();
+
+    for c in short.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let Ok(n) = digits.parse::<i64>() else {
+            bail!("Invalid duration `{trimmed}`; expected e.g. `1h30m` or `PT1H30M`");
+        };
+
+        total += match c {
+            'W' => TimeDelta::weeks(n),
+            'D' => TimeDelta::days(n),
+            'H' => TimeDelta::hours(n),
+            'I' => TimeDelta::minutes(n),
+            'S' => TimeDelta::seconds(n),
+            _ => bail!("Invalid duration `{trimmed}`; unknown unit `{c}`"),
+        };
+
+        digits.clear();
+    }
+
+    if !digits.is_empty() || total <= TimeDelta::zero() {
+        bail!("Invalid duration `{trimmed}`; expected e.g. `1h30m` or `PT1H30M`");
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta};
+
+    use super::{parse_date_time, parse_duration};
+
+    #[test]
+    fn date_times() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let at = |d, h, m| {
+            NaiveDate::from_ymd_opt(2026, 3, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+
+        assert_eq!(
+            parse_date_time("2026-03-20 09:30", today).unwrap(),
+            (at(20, 9, 30), true)
+        );
+        assert_eq!(
+            parse_date_time("2026-03-20T09:30:00", today).unwrap(),
+            (at(20, 9, 30), true)
+        );
+        assert_eq!(
+            parse_date_time("tomorrow 12:00", today).unwrap(),
+            (at(15, 12, 0), true)
+        );
+        assert_eq!(
+            parse_date_time("today", today).unwrap(),
+            (at(14, 0, 0), false)
+        );
+        assert!(parse_date_time("next week", today).is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90m").unwrap(), TimeDelta::minutes(90));
+        assert_eq!(parse_duration("1h30m").unwrap(), TimeDelta::minutes(90));
+        assert_eq!(parse_duration("2d").unwrap(), TimeDelta::days(2));
+        assert_eq!(parse_duration("1w").unwrap(), TimeDelta::weeks(1));
+        assert_eq!(parse_duration("P1DT2H").unwrap(), TimeDelta::hours(26));
+        assert_eq!(parse_duration("PT15M").unwrap(), TimeDelta::minutes(15));
+        assert!(parse_duration("15").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("soon").is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Round-trip edition of a calendar item in `$EDITOR`.
+//!
+//! The item is fetched, written to a temporary file and opened in the
+//! user's editor. The result is validated with the calcard parser,
+//! then written back with the fetched ETag as `If-Match`, so that a
+//! concurrent change on the server is detected instead of silently
+//! overwritten. Parse errors prompt the user to edit again or to
+//! discard the changes; conflicts to edit the server version, to
+//! overwrite it with the draft, or to discard the changes.
+
+use std::{env, fmt, fs, io::Write, path::Path, process::Command};
+
+use anyhow::{Context, Result, bail};
+use io_calendar::calcard::icalendar::{ICalendar, ICalendarComponentType};
+use log::warn;
+use pimalaya_cli::prompt;
+
+use crate::shared::{
+    cache::sanitize,
+    client::{CalendarClient, Conflict},
+};
+
+const CONFLICT_PROMPT: &str =
+    "The item changed on the server since it was fetched. What do you want to do?";
+
+/// Final state of an edit session.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edited {
+    Updated,
+    Unchanged,
+    Discarded,
+}
+
+/// Choice offered when the edited item cannot be written back.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Recovery {
+    EditAgain,
+    EditLatest,
+    Overwrite,
+    Discard,
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EditAgain => write!(f, "Edit again"),
+            Self::EditLatest => write!(f, "Drop my changes and edit the server version"),
+            Self::Overwrite => write!(f, "Overwrite the server version with my changes"),
+            Self::Discard => write!(f, "Discard changes"),
+        }
+    }
+}
+
+/// Opens the item `item_id` of `calendar_id` in `$EDITOR`, then writes
+/// it back. When `kind` is given, the edited iCalendar must contain at
+/// least one component of that kind.
+pub fn edit_item(
+    client: &mut CalendarClient,
+    calendar_id: &str,
+    item_id: &str,
+    kind: Option<ICalendarComponentType>,
+) -> Result<Edited> {
+    let item = client.get_item(calendar_id, item_id)?;
+
+    // Created with a random name and owner-only permissions, removed
+    // when dropped.
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("calendula-{}-", sanitize(item_id)))
+        .suffix(".ics")
+        .tempfile()
+        .context("Create temporary item file error")?;
+
+    file.write_all(&item.contents)
+        .and_then(|()| file.flush())
+        .with_context(|| format!("Write item to `{}` error", file.path().display()))?;
+
+    let session = EditSession {
+        editor: &editor(),
+        calendar_id,
+        item_id,
+        kind,
+        path: file.path(),
+        original: item.contents,
+        etag: item.etag,
+    };
+
+    session.run(client)
+}
+
+struct EditSession<'a> {
+    editor: &'a str,
+    calendar_id: &'a str,
+    item_id: &'a str,
+    kind: Option<ICalendarComponentType>,
+    path: &'a Path,
+    original: Vec<u8>,
+    etag: Option<String>,
+}
+
+impl EditSession<'_> {
+    fn run(mut self, client: &mut CalendarClient) -> Result<Edited> {
+        let path = self.path;
+        // Draft to write back without going through the editor again,
+        // once the user chose to overwrite a conflicting version.
+        let mut draft = None;
+
+        loop {
+            let contents = match draft.take() {
+                Some(contents) => contents,
+                None => {
+                    open_editor(self.editor, path)?;
+
+                    let contents = fs::read(path).with_context(|| {
+                        format!("Read edited item from `{}` error", path.display())
+                    })?;
+
+                    if contents == self.original {
+                        return Ok(Edited::Unchanged);
+                    }
+
+                    if let Err(err) = validate(&contents, self.kind.as_ref()) {
+                        let msg = format!("{err}. What do you want to do?");
+                        match recover(&msg, [Recovery::EditAgain, Recovery::Discard])? {
+                            Recovery::Discard => return Ok(Edited::Discarded),
+                            _ => continue,
+                        }
+                    }
+
+                    contents
+                }
+            };
+
+            let etag = self.etag.as_deref();
+            let result = client.update_item(self.calendar_id, self.item_id, contents.clone(), etag);
+
+            let Err(err) = result else {
+                return Ok(Edited::Updated);
+            };
+
+            if !err.chain().any(|cause| cause.is::<Conflict>()) {
+                return Err(err.context(format!("Update item `{}` error", self.item_id)));
+            }
+
+            warn!(
+                "item `{}` changed on the server since it was fetched",
+                self.item_id
+            );
+
+            let latest = client.get_item(self.calendar_id, self.item_id)?;
+            let choices = [Recovery::EditLatest, Recovery::Overwrite, Recovery::Discard];
+
+            match recover(CONFLICT_PROMPT, choices)? {
+                Recovery::EditLatest => {
+                    fs::write(path, &latest.contents)
+                        .with_context(|| format!("Write item to `{}` error", path.display()))?;
+                    self.original = latest.contents;
+                    self.etag = latest.etag;
+                }
+                Recovery::Overwrite => {
+                    self.etag = latest.etag;
+                    draft = Some(contents);
+                }
+                _ => return Ok(Edited::Discarded),
+            }
+        }
+    }
+}
+
+fn recover<const N: usize>(msg: &str, choices: [Recovery; N]) -> Result<Recovery> {
+    Ok(prompt::item(msg, choices, None)?)
+}
+
+/// Editor command of the user: `$VISUAL`, then `$EDITOR`, then `vi`.
+/// The variable may carry arguments (`code --wait`).
+fn editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"))
+}
+
+/// Runs the `editor` command on `path` and waits for it to exit.
+fn open_editor(editor: &str, path: &Path) -> Result<()> {
+    let mut args = editor.split_whitespace();
+
+    let Some(program) = args.next() else {
+        bail!("Editor from $VISUAL or $EDITOR is empty");
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Run editor `{editor}` error"))?;
+
+    if !status.success() {
+        bail!("Editor `{editor}` exited with {status}");
+    }
+
+    Ok(())
+}
+
+/// Checks that `contents` parses as iCalendar and, when `kind` is
+/// given, holds at least one component of that kind.
+fn validate(contents: &[u8], kind: Option<&ICalendarComponentType>) -> Result<()> {
+    let Ok(contents) = std::str::from_utf8(contents) else {
+        bail!("Edited item is not valid UTF-8");
+    };
+
+    let Ok(ical) = ICalendar::parse(contents) else {
+        bail!("Edited item is not valid iCalendar");
+    };
+
+    if let Some(kind) = kind
+        && !ical.components.iter().any(|c| &c.component_type == kind)
+    {
+        let kind = format!("{kind:?}").to_ascii_uppercase();
+        bail!("Edited item does not contain any {kind}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use io_calendar::calcard::icalendar::ICalendarComponentType;
+
+    use super::validate;
+
+    const EVENT: &str = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "PRODID:-//Test//EN\r\n",
+        "BEGIN:VEVENT\r\n",
+        "UID:standup@example.com\r\n",
+        "DTSTAMP:20260301T080000Z\r\n",
+        "DTSTART:20260316T090000Z\r\n",
+        "SUMMARY:Standup\r\n",
+        "END:VEVENT\r\n",
+        "END:VCALENDAR\r\n",
+    );
+
+    #[test]
+    fn validate_edited_item() {
+        let vevent = ICalendarComponentType::VEvent;
+        let vtodo = ICalendarComponentType::VTodo;
+
+        assert!(validate(EVENT.as_bytes(), None).is_ok());
+        assert!(validate(EVENT.as_bytes(), Some(&vevent)).is_ok());
+
+        let err = validate(b"SUMMARY:\xff\r\n", None).unwrap_err();
+        assert_eq!(err.to_string(), "Edited item is not valid UTF-8");
+
+        let err = validate(b"not an iCalendar object", None).unwrap_err();
+        assert_eq!(err.to_string(), "Edited item is not valid iCalendar");
+
+        let err = validate(EVENT.as_bytes(), Some(&vtodo)).unwrap_err();
+        assert_eq!(err.to_string(), "Edited item does not contain any VTODO");
+    }
+
+    #[cfg(all(unix, feature = "vdir"))]
+    #[test]
+    fn edit_session_outcomes() {
+        use super::{EditSession, Edited};
+        use crate::{
+            backend::Backend,
+            config::{AccountConfig, Config, VdirConfig},
+            shared::client::CalendarClient,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let account = AccountConfig {
+            vdir: Some(VdirConfig {
+                home_dir: dir.path().to_owned(),
+            }),
+            ..Default::default()
+        };
+        let mut client =
+            CalendarClient::new(Config::default(), "test", account, Backend::Vdir).unwrap();
+        client
+            .create_calendar("personal", "Personal", None, None)
+            .unwrap();
+        let id = client
+            .create_item("personal", EVENT.as_bytes().to_vec())
+            .unwrap();
+
+        let path = dir.path().join("draft.ics");
+        let mut run = |editor: &str| {
+            let item = client.get_item("personal", &id).unwrap();
+            std::fs::write(&path, &item.contents).unwrap();
+
+            let session = EditSession {
+                editor,
+                calendar_id: "personal",
+                item_id: &id,
+                kind: Some(ICalendarComponentType::VEvent),
+                path: &path,
+                original: item.contents,
+                etag: item.etag,
+            };
+            session.run(&mut client).unwrap()
+        };
+
+        // An editor leaving the file untouched changes nothing.
+        assert_eq!(run("true"), Edited::Unchanged);
+        assert_eq!(run("sed -i.orig s/Standup/Retro/"), Edited::Updated);
+
+        let item = client.get_item("personal", &id).unwrap();
+        let contents = String::from_utf8(item.contents).unwrap();
+        assert!(contents.contains("SUMMARY:Retro\r\n"));
+    }
+}
@@ -1,19 +1,26 @@
 use std::{
-    borrow::Cow,
     collections::HashMap,
     fmt::{self, Write},
 };
 
 use anyhow::{Result, bail};
-use chrono::{Datelike, Local, NaiveDateTime};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use clap::Parser;
-use io_calendar::calcard::icalendar::{
-    ICalendar, ICalendarComponentType, ICalendarProperty, ICalendarValue,
-};
+use io_calendar::calcard::icalendar::{ICalendar, ICalendarComponentType, ICalendarProperty};
 use pimalaya_cli::printer::Printer;
 use serde::{Serialize, Serializer};
 
-use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
+use crate::{
+    account::context::Account,
+    shared::{
+        arg::CalendarIdsArg,
+        client::CalendarClient,
+        datetime::parse_date,
+        events::grid::{GridEvent, GridSlots, TimeGrid},
+        recurrence::{self, Localized, component_text},
+        timezone::Timezone,
+    },
+};
 
 const DAYS_IN_WEEK: usize = 7;
 const MAXDAYS: usize = 42;
@@ -30,14 +37,32 @@ const DEFAULT_REFORM_YEAR: i32 = 1752;
 /// Display a calendar view alla cal.
 ///
 /// This command allows you to display a calendar/agenda view like
-/// does the Unix cal tool.
+/// does the Unix cal tool. Recurring events are expanded over the
+/// painted months, so every occurrence highlights its own day, and
+/// times are shown in the `--tz` / `event.timezone` zone.
+///
+/// Repeat `-k` or pass `--all-calendars` to overlay several
+/// calendars on the same grid, and pass `--account all`, a comma list
+/// of accounts or an account group to overlay several accounts;
+/// summaries are then prefixed by the account and/or calendar they
+/// come from.
+///
+/// Pass `--day` or `--week-grid` to render the day (or week) of the
+/// DATE (`YYYY-MM-DD`, `today`, etc.) as an hourly time grid instead:
+/// overlapping events sit side by side, all-day events go to a header
+/// band, and the table fits the terminal like the lists do. The hour
+/// range and slot size come from `--start-hour`, `--end-hour` and
+/// `--slot`, falling back to the `event.agenda.*` configs (8, 20 and
+/// 30 minutes by default); events outside the range are pinned to its
+/// first or last slot.
 ///
 /// JSON output: an object mapping each event's start datetime to its
-/// summary.
+/// summary. With `--day`/`--week-grid`: `{"days": [{"date",
+/// "all-day", "events": [{"summary", "start", "end", "lane"}]}]}`.
 #[derive(Debug, Parser)]
 pub struct EventAgendaCommand {
     #[command(flatten)]
-    calendar: CalendarIdArg,
+    calendars: CalendarIdsArg,
 
     /// Show the calendar at the given date.
     #[arg(name = "DATE")]
@@ -107,15 +132,85 @@ pub struct EventAgendaCommand {
     /// Display using a vertical layout (aka ncal(1) mode).
     #[arg(short = 'v', long)]
     vertical: bool,
+
+    /// Display the day of the date as an hourly time grid.
+    #[arg(long, conflicts_with = "week_grid")]
+    day: bool,
+
+    /// Display the week of the date as an hourly time grid.
+    #[arg(long)]
+    week_grid: bool,
+
+    /// First hour of the time grid.
+    #[arg(long, value_name = "HOUR")]
+    start_hour: Option<u32>,
+
+    /// Hour the time grid ends at.
+    #[arg(long, value_name = "HOUR")]
+    end_hour: Option<u32>,
+
+    /// Size of the time grid slots, in minutes.
+    #[arg(long, value_name = "MINUTES")]
+    slot: Option<u32>,
+
+    /// Maximum width of the time grid, in terminal columns.
+    #[arg(long = "max-width", value_name = "COLUMNS")]
+    max_width: Option<u16>,
 }
 
 impl EventAgendaCommand {
-    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+    pub fn execute(self, printer: &mut impl Printer, client: CalendarClient) -> Result<()> {
+        self.execute_many(printer, vec![client])
+    }
+
+    /// Paints the events of every client, one per account selected by
+    /// `--account`, on the same grid.
+    pub fn execute_many(
+        self,
+        printer: &mut impl Printer,
+        mut clients: Vec<CalendarClient>,
+    ) -> Result<()> {
         let now = Local::now();
 
-        let calendar_id = client.account.calendar_id(self.calendar.id)?;
-        let items = client.list_items(&calendar_id, None, None, None)?;
-        let all_events: Vec<ICalendar> = items.iter().filter_map(|item| item.as_ical()).collect();
+        let Some(timezone) = clients.first().map(|client| client.account.timezone()) else {
+            bail!("Cannot paint the agenda without any account");
+        };
+
+        let multi_account = clients.len() > 1;
+        let mut sources = Vec::new();
+
+        for client in &mut clients {
+            for calendar_id in self.calendars.resolve(client)? {
+                let items = client.list_all_items(&calendar_id, None)?;
+                sources.push((client.account_name.clone(), calendar_id, items));
+            }
+        }
+
+        // Overlaid summaries are prefixed by the account and/or the
+        // calendar they come from.
+        let multi_calendar = clients.iter().any(|client| {
+            let calendars = sources
+                .iter()
+                .filter(|(name, ..)| *name == client.account_name);
+            calendars.count() > 1
+        });
+        let mut icals: Vec<(Option<String>, ICalendar)> = Vec::new();
+
+        for (account_name, calendar_id, items) in &sources {
+            let label = match (multi_account, multi_calendar) {
+                (true, true) => Some(format!("{account_name}/{calendar_id}")),
+                (true, false) => Some(account_name.clone()),
+                (false, true) => Some(calendar_id.clone()),
+                (false, false) => None,
+            };
+
+            let parsed = items.iter().filter_map(|item| item.as_ical());
+            icals.extend(parsed.map(|ical| (label.clone(), ical)));
+        }
+
+        if self.day || self.week_grid {
+            return self.time_grid(printer, &clients[0].account, timezone, &icals);
+        }
 
         let mut ctl = CalControl {
             reform_year: DEFAULT_REFORM_YEAR,
@@ -137,7 +232,7 @@ impl EventAgendaCommand {
                 year: 0,
                 start_month: 0,
             },
-            all_events,
+            all_events: Vec::new(),
             events: HashMap::new(),
         };
 
@@ -258,6 +353,9 @@ impl EventAgendaCommand {
 
         headers_init(&mut ctl);
 
+        let (from, to) = painted_window(&ctl);
+        ctl.all_events = expand_events(&icals, from, to, timezone);
+
         let mut grid = String::new();
 
         if yflag || yflag_cap {
@@ -271,6 +369,93 @@ impl EventAgendaCommand {
             events: ctl.events,
         })
     }
+
+    /// Renders the `--day`/`--week-grid` time grid.
+    fn time_grid(
+        self,
+        printer: &mut impl Printer,
+        account: &Account,
+        timezone: Timezone,
+        icals: &[(Option<String>, ICalendar)],
+    ) -> Result<()> {
+        let today = timezone.from_utc(Utc::now()).date();
+
+        let date = match self.date_args.as_slice() {
+            [] => today,
+            [date] => parse_date(date, today)?,
+            _ => bail!("The --day and --week-grid views take a single date"),
+        };
+
+        let dates: Vec<NaiveDate> = if self.week_grid {
+            let weekday = date.weekday();
+            let offset = if self.monday {
+                weekday.num_days_from_monday()
+            } else {
+                weekday.num_days_from_sunday()
+            };
+            let first = date - Days::new(offset as u64);
+            (0..DAYS_IN_WEEK as u64)
+                .map(|day| first + Days::new(day))
+                .collect()
+        } else {
+            vec![date]
+        };
+
+        let slots = GridSlots::new(
+            self.start_hour
+                .unwrap_or(account.events_agenda_start_hour()),
+            self.end_hour.unwrap_or(account.events_agenda_end_hour()),
+            self.slot.unwrap_or(account.events_agenda_slot()),
+        )?;
+
+        let from = dates[0].and_time(NaiveTime::MIN);
+        let to = dates[dates.len() - 1].and_time(NaiveTime::MIN) + Days::new(1);
+
+        let events: Vec<GridEvent> = expand_events(icals, Some(from), Some(to), timezone)
+            .into_iter()
+            .map(|event| GridEvent {
+                start: event.start,
+                end: event.end,
+                all_day: event.all_day,
+                summary: event.summary,
+            })
+            .collect();
+
+        printer.out(TimeGrid::new(
+            account.table_preset().to_string(),
+            account.table_arrangement(),
+            self.max_width,
+            slots,
+            &dates,
+            &events,
+        ))
+    }
+}
+
+/// Expands the VEVENTs of `icals` over the `[from, to)` window of
+/// `timezone` into agenda entries, prefixing summaries with their
+/// source label.
+fn expand_events(
+    icals: &[(Option<String>, ICalendar)],
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    timezone: Timezone,
+) -> Vec<AgendaEvent> {
+    icals
+        .iter()
+        .flat_map(|(label, ical)| {
+            recurrence::occurrences_in(ical, &ICalendarComponentType::VEvent, from, to, timezone)
+                .iter()
+                .map(|localized| {
+                    let mut event = AgendaEvent::new(localized);
+                    if let Some(label) = label {
+                        event.summary = format!("[{label}] {}", event.summary);
+                    }
+                    event
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
 }
 
 #[derive(Clone)]
@@ -289,10 +474,37 @@ struct CalControl {
     header_hint: bool,
     vertical: bool,
     req: CalRequest,
-    all_events: Vec<ICalendar>,
+    all_events: Vec<AgendaEvent>,
     events: HashMap<NaiveDateTime, String>,
 }
 
+/// One VEVENT occurrence falling inside the painted window.
+#[derive(Clone)]
+struct AgendaEvent {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    all_day: bool,
+    /// SUMMARY, falling back to DESCRIPTION.
+    summary: String,
+}
+
+impl AgendaEvent {
+    /// Builds the agenda entry of the `localized` occurrence.
+    fn new(localized: &Localized<'_>) -> Self {
+        let component = localized.occurrence.component;
+        let summary = component_text(component, &ICalendarProperty::Summary)
+            .or_else(|| component_text(component, &ICalendarProperty::Description))
+            .unwrap_or_default();
+
+        Self {
+            start: localized.start,
+            end: localized.end,
+            all_day: localized.occurrence.all_day,
+            summary,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct CalRequest {
     day: i32,
@@ -813,69 +1025,38 @@ fn cal_vert_output_months(
     Ok(())
 }
 
-/// Walks every parsed VEVENT in `ctl.all_events` looking for a DTSTART
-/// matching `(y, m, d)`; when found, records the SUMMARY (falling back
-/// to DESCRIPTION) into `ctl.events` and returns true.
+/// Walks every expanded VEVENT occurrence in `ctl.all_events` looking
+/// for a start matching `(y, m, d)`; when found, records its summary
+/// into `ctl.events` and returns true. Summaries of events starting
+/// at the same time are joined by `; `.
 fn collect_events(ctl: &mut CalControl, y: i32, m: u32, d: u32) -> bool {
-    let mut has_event = false;
-
-    for ical in &ctl.all_events {
-        for component in &ical.components {
-            if component.component_type != ICalendarComponentType::VEvent {
-                continue;
-            }
-
-            let Some(prop) = component.property(&ICalendarProperty::Dtstart) else {
-                continue;
-            };
-
-            for value in &prop.values {
-                let ICalendarValue::PartialDateTime(pdt) = value else {
-                    continue;
-                };
-
-                if pdt.year != Some(y as u16) {
-                    continue;
-                }
-                if pdt.month != Some(m as u8) {
-                    continue;
-                }
-                if pdt.day != Some(d as u8) {
-                    continue;
-                }
-
-                has_event = true;
+    let Some(date) = NaiveDate::from_ymd_opt(y, m, d) else {
+        return false;
+    };
 
-                let summary = component
-                    .property(&ICalendarProperty::Summary)
-                    .and_then(|p| p.values.iter().find_map(text_value));
-                let desc = component
-                    .property(&ICalendarProperty::Description)
-                    .and_then(|p| p.values.iter().find_map(text_value));
+    let mut has_event = false;
 
-                let summary_or_desc = summary.or(desc).unwrap_or_default().into_owned();
+    for event in &ctl.all_events {
+        if event.start.date() != date {
+            continue;
+        }
 
-                if let Some(dt) = pdt.to_date_time() {
-                    ctl.events.insert(dt.date_time, summary_or_desc);
-                }
+        has_event = true;
 
-                break;
+        let summary = ctl.events.entry(event.start).or_default();
+        if summary.split("; ").all(|s| s != event.summary) {
+            if !summary.is_empty() {
+                summary.push_str("; ");
             }
+            summary.push_str(&event.summary);
         }
     }
 
     has_event
 }
 
-fn text_value(value: &ICalendarValue) -> Option<Cow<'_, str>> {
-    if let ICalendarValue::Text(text) = value {
-        Some(Cow::Borrowed(text))
-    } else {
-        None
-    }
-}
-
-fn monthly(grid: &mut String, ctl: &mut CalControl) -> fmt::Result {
+/// Returns the first month painted by [`monthly`], as `(month, year)`.
+fn first_month(ctl: &CalControl) -> (usize, i32) {
     let mut month = if ctl.req.start_month > 0 {
         ctl.req.start_month
     } else {
@@ -898,6 +1079,22 @@ fn monthly(grid: &mut String, ctl: &mut CalControl) -> fmt::Result {
         }
     }
 
+    (month, year)
+}
+
+/// Naive `[from, to)` window covering every painted month, used to
+/// bound recurrence expansion.
+fn painted_window(ctl: &CalControl) -> (Option<NaiveDateTime>, Option<NaiveDateTime>) {
+    let (month, year) = first_month(ctl);
+    let from = NaiveDate::from_ymd_opt(year, month as u32, 1);
+    let to = from.and_then(|from| from.checked_add_months(Months::new(ctl.num_months as u32)));
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0);
+    (from.and_then(midnight), to.and_then(midnight))
+}
+
+fn monthly(grid: &mut String, ctl: &mut CalControl) -> fmt::Result {
+    let (mut month, mut year) = first_month(ctl);
+
     let rows = (ctl.num_months - 1) / ctl.months_in_row;
 
     for i in 0..=rows {
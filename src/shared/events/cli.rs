@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use clap::Subcommand;
 use pimalaya_cli::printer::Printer;
 
@@ -6,21 +6,31 @@ use crate::shared::{
     client::CalendarClient,
     events::{
         agenda::EventAgendaCommand, create::EventCreateCommand, delete::EventDeleteCommand,
-        list::EventListCommand, read::EventReadCommand, update::EventUpdateCommand,
+        edit::EventEditCommand, free::EventFreeCommand, import_reply::EventImportReplyCommand,
+        invite::EventInviteCommand, list::EventListCommand, read::EventReadCommand,
+        reply::EventReplyCommand, upcoming::EventUpcomingCommand, update::EventUpdateCommand,
     },
 };
 
-/// Shared API to manage VEVENT items: agenda, list, read, create,
-/// update, delete.
+/// Shared API to manage VEVENT items: agenda, list, upcoming, free,
+/// read, create, update, edit, delete, plus the iTIP scheduling
+/// commands invite, reply and import-reply.
 #[derive(Debug, Subcommand)]
 pub enum EventCommand {
     Agenda(EventAgendaCommand),
     #[command(visible_alias = "ls")]
     List(EventListCommand),
+    #[command(visible_alias = "next")]
+    Upcoming(EventUpcomingCommand),
+    Free(EventFreeCommand),
     Read(EventReadCommand),
     Create(EventCreateCommand),
     Update(EventUpdateCommand),
+    Edit(EventEditCommand),
     Delete(EventDeleteCommand),
+    Invite(EventInviteCommand),
+    Reply(EventReplyCommand),
+    ImportReply(EventImportReplyCommand),
 }
 
 impl EventCommand {
@@ -28,10 +38,41 @@ impl EventCommand {
         match self {
             Self::Agenda(cmd) => cmd.execute(printer, client),
             Self::List(cmd) => cmd.execute(printer, client),
+            Self::Upcoming(cmd) => cmd.execute(printer, client),
+            Self::Free(cmd) => cmd.execute(printer, client),
             Self::Read(cmd) => cmd.execute(printer, client),
             Self::Create(cmd) => cmd.execute(printer, client),
             Self::Update(cmd) => cmd.execute(printer, client),
+            Self::Edit(cmd) => cmd.execute(printer, client),
             Self::Delete(cmd) => cmd.execute(printer, client),
+            Self::Invite(cmd) => cmd.execute(printer, client),
+            Self::Reply(cmd) => cmd.execute(printer, client),
+            Self::ImportReply(cmd) => cmd.execute(printer, client),
+        }
+    }
+
+    /// Whether the command can aggregate several accounts selected by
+    /// `--account` (`all`, a comma list or a group).
+    pub fn spans_accounts(&self) -> bool {
+        matches!(
+            self,
+            Self::Agenda(_) | Self::List(_) | Self::Upcoming(_) | Self::Free(_)
+        )
+    }
+
+    pub fn execute_many(
+        self,
+        printer: &mut impl Printer,
+        clients: Vec<CalendarClient>,
+    ) -> Result<()> {
+        match self {
+            Self::Agenda(cmd) => cmd.execute_many(printer, clients),
+            Self::List(cmd) => cmd.execute_many(printer, clients),
+            Self::Upcoming(cmd) => cmd.execute_many(printer, clients),
+            Self::Free(cmd) => cmd.execute_many(printer, clients),
+            _ => bail!(
+                "Only `event list`, `agenda`, `upcoming` and `free` can operate on several accounts"
+            ),
         }
     }
 }
@@ -1,10 +1,26 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Datelike, Days, NaiveDateTime, TimeDelta, Utc};
 use clap::Parser;
 use pimalaya_cli::printer::{Message, Printer};
 
-use crate::shared::{arg::CalendarIdArg, client::CalendarClient, ical::IcalArg};
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    datetime::{parse_date_time, parse_duration, parse_time},
+    ical::{IcalArg, IcalWriter, PRODID, format_duration, generate_uid},
+    timezone::{Timezone, write_vtimezone},
+};
 
-/// Create a new event from an iCalendar source.
+/// Create a new event.
+///
+/// Either pass an iCalendar source, or build the event from flags:
+/// `--summary` and `--start` are required, the rest is optional.
+/// Dates and times are read in the zone picked by the global `--tz`
+/// flag (falling back to `event.timezone`, then the local zone); the
+/// generated VCALENDAR carries a TZID and its VTIMEZONE, the local
+/// zone being resolved to its IANA name. When the system does not
+/// name it, times are written in UTC, and `--rrule` is refused since
+/// the rule would not follow the DST changes of the zone.
 ///
 /// JSON output: `{"message": "..."}`.
 #[derive(Debug, Parser)]
@@ -12,16 +28,364 @@ pub struct EventCreateCommand {
     #[command(flatten)]
     pub calendar: CalendarIdArg,
 
+    /// A path to an iCalendar file, raw iCalendar contents, or `-` for
+    /// stdin. Omit it to build the event from flags.
+    #[arg(value_name = "ICAL", required_unless_present = "summary")]
+    pub ical: Option<String>,
+
     #[command(flatten)]
-    pub ical: IcalArg,
+    pub fields: EventFields,
 }
 
 impl EventCreateCommand {
     pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
         let calendar_id = client.account.calendar_id(self.calendar.id)?;
-        let contents = self.ical.read()?;
+
+        let contents = match self.ical {
+            Some(ical) => IcalArg { ical }.read()?,
+            None => {
+                let email = client.account.email.as_deref();
+                self.fields
+                    .build(client.account.timezone(), email, Utc::now())?
+            }
+        };
 
         let id = client.create_item(&calendar_id, contents)?;
         printer.out(Message::new(format!("Event `{id}` successfully created")))
     }
 }
+
+/// Flags describing an event built without hand-written iCalendar.
+#[derive(Debug, Parser)]
+pub struct EventFields {
+    /// Title of the event.
+    #[arg(long, value_name = "TEXT", conflicts_with = "ical", requires = "start")]
+    pub summary: Option<String>,
+
+    /// Start of the event: `YYYY-MM-DD HH:MM`, `tomorrow 12:00`, etc.
+    ///
+    /// A date without time makes an all-day event.
+    #[arg(long, value_name = "DATETIME", requires = "summary")]
+    pub start: Option<String>,
+
+    /// End of the event: a date time, or a time on the start day
+    /// (`13:00`).
+    ///
+    /// For all-day events, the last day of the event (inclusive).
+    /// Defaults to one hour after the start, or one day for all-day
+    /// events.
+    #[arg(long, value_name = "DATETIME", requires = "summary")]
+    #[arg(conflicts_with = "duration")]
+    pub end: Option<String>,
+
+    /// Length of the event: `1h30m`, `2d`, `PT45M`, etc.
+    #[arg(long, value_name = "DURATION", requires = "summary")]
+    #[arg(value_parser = parse_duration)]
+    pub duration: Option<TimeDelta>,
+
+    /// Make it an all-day event: times are ignored.
+    #[arg(long, requires = "summary")]
+    pub all_day: bool,
+
+    /// Where the event takes place.
+    #[arg(long, value_name = "TEXT", requires = "summary")]
+    pub location: Option<String>,
+
+    /// Free-form description of the event.
+    #[arg(long, value_name = "TEXT", requires = "summary")]
+    pub description: Option<String>,
+
+    /// Recurrence rule, e.g. `FREQ=WEEKLY;BYDAY=MO,WE`.
+    #[arg(long, value_name = "RULE", requires = "summary")]
+    pub rrule: Option<String>,
+
+    /// Display a reminder this long before the start (repeatable).
+    #[arg(long = "alarm", value_name = "DURATION", requires = "summary")]
+    #[arg(value_parser = parse_duration)]
+    pub alarms: Vec<TimeDelta>,
+
+    /// Invite an attendee: `jane@example.com` or `Jane <jane@example.com>`
+    /// (repeatable).
+    #[arg(long = "attendee", value_name = "ADDRESS", requires = "summary")]
+    pub attendees: Vec<String>,
+
+    /// Organizer of the invitation sent to attendees: `jane@example.com`
+    /// or `Jane <jane@example.com>`. Defaults to the `event.email`
+    /// config.
+    #[arg(long, value_name = "ADDRESS", requires = "attendees")]
+    pub organizer: Option<String>,
+}
+
+impl EventFields {
+    /// Generates a VCALENDAR holding a single VEVENT, with times read
+    /// in the `timezone` wall clock. Attendees come with an ORGANIZER
+    /// (RFC 5545 §3.8.4.3): `--organizer`, or the `email` address.
+    pub fn build(
+        &self,
+        timezone: Timezone,
+        email: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<u8>> {
+        let (Some(summary), Some(start)) = (&self.summary, &self.start) else {
+            bail!("Missing --summary and --start, or an iCalendar source");
+        };
+
+        let today = timezone.from_utc(now).date();
+        let (start, has_time) = parse_date_time(start, today)?;
+        let all_day = self.all_day || !has_time;
+
+        let end = match (&self.end, self.duration) {
+            (Some(end), _) => match parse_time(end) {
+                Ok(time) if !all_day => start.date().and_time(time),
+                _ => {
+                    let (end, _) = parse_date_time(end, today)?;
+                    if all_day { end + Days::new(1) } else { end }
+                }
+            },
+            (None, Some(duration)) if all_day => {
+                if duration.num_seconds() % 86_400 != 0 {
+                    bail!("Duration of all-day events must be a whole number of days");
+                }
+                start + duration
+            }
+            (None, Some(duration)) => start + duration,
+            (None, None) if all_day => start + Days::new(1),
+            (None, None) => start + TimeDelta::hours(1),
+        };
+
+        let start = if all_day { start.date().into() } else { start };
+        let end = if all_day { end.date().into() } else { end };
+
+        if end <= start {
+            bail!("End of the event must be after its start");
+        }
+
+        let zone = timezone.iana();
+
+        if self.rrule.is_some() && !all_day && zone.is_none() {
+            bail!("Cannot find the name of the local zone for --rrule; pass --tz");
+        }
+
+        let mut ical = IcalWriter::new();
+
+        ical.begin("VCALENDAR")
+            .property("VERSION", &[], "2.0")
+            .text("PRODID", PRODID);
+
+        if let Some(tz) = zone
+            && !all_day
+        {
+            write_vtimezone(&mut ical, tz, start.year());
+        }
+
+        ical.begin("VEVENT")
+            .text("UID", &generate_uid(now))
+            .property("DTSTAMP", &[], &now.format("%Y%m%dT%H%M%SZ").to_string());
+
+        date_time(&mut ical, "DTSTART", start, all_day, timezone, zone);
+        date_time(&mut ical, "DTEND", end, all_day, timezone, zone);
+
+        ical.text("SUMMARY", summary);
+
+        if let Some(location) = &self.location {
+            ical.text("LOCATION", location);
+        }
+
+        if let Some(description) = &self.description {
+            ical.text("DESCRIPTION", description);
+        }
+
+        if let Some(rrule) = &self.rrule {
+            let rrule = rrule.trim().trim_start_matches("RRULE:");
+
+            if !rrule.to_ascii_uppercase().contains("FREQ=") {
+                bail!("Invalid recurrence rule `{rrule}`: missing FREQ");
+            }
+
+            ical.property("RRULE", &[], rrule);
+        }
+
+        if !self.attendees.is_empty() {
+            let Some(organizer) = self.organizer.as_deref().or(email) else {
+                bail!("Cannot invite attendees without organizer; pass --organizer");
+            };
+
+            let (name, address) = parse_address(organizer)?;
+            let address = format!("mailto:{address}");
+            let params: Vec<_> = name.map(|name| ("CN", name)).into_iter().collect();
+            ical.property("ORGANIZER", &params, &address);
+        }
+
+        for attendee in &self.attendees {
+            let (name, address) = parse_address(attendee)?;
+            let address = format!("mailto:{address}");
+            let mut params = vec![
+                ("ROLE", "REQ-PARTICIPANT"),
+                ("PARTSTAT", "NEEDS-ACTION"),
+                ("RSVP", "TRUE"),
+            ];
+
+            if let Some(name) = name {
+                params.push(("CN", name));
+            }
+
+            ical.property("ATTENDEE", &params, &address);
+        }
+
+        for alarm in &self.alarms {
+            ical.begin("VALARM")
+                .property("ACTION", &[], "DISPLAY")
+                .property("TRIGGER", &[], &format_duration(-*alarm))
+                .text("DESCRIPTION", summary)
+                .end("VALARM");
+        }
+
+        ical.end("VEVENT").end("VCALENDAR");
+
+        Ok(ical.finish())
+    }
+}
+
+/// Writes a DTSTART/DTEND property: a DATE for all-day events, a TZID
+/// time when `timezone` resolves to the IANA `zone`, a UTC time
+/// otherwise.
+fn date_time(
+    ical: &mut IcalWriter,
+    name: &str,
+    dt: NaiveDateTime,
+    all_day: bool,
+    timezone: Timezone,
+    zone: Option<chrono_tz::Tz>,
+) {
+    if all_day {
+        let date = dt.format("%Y%m%d").to_string();
+        ical.property(name, &[("VALUE", "DATE")], &date);
+        return;
+    }
+
+    match zone {
+        Some(tz) => {
+            let dt = dt.format("%Y%m%dT%H%M%S").to_string();
+            ical.property(name, &[("TZID", tz.name())], &dt);
+        }
+        None => {
+            let dt = timezone.to_utc(dt).format("%Y%m%dT%H%M%SZ").to_string();
+            ical.property(name, &[], &dt);
+        }
+    }
+}
+
+/// Splits `Jane <jane@example.com>` into its display name and address.
+fn parse_address(input: &str) -> Result<(Option<&str>, &str)> {
+    let input = input.trim();
+
+    let (name, address) = match input.rsplit_once('<') {
+        Some((name, address)) => {
+            let name = name.trim().trim_matches('"');
+            let address = address.trim_end_matches('>').trim();
+            ((!name.is_empty()).then_some(name), address)
+        }
+        None => (None, input),
+    };
+
+    let address = address.trim_start_matches("mailto:");
+
+    if !address.contains('@') {
+        bail!("Invalid address `{input}`: expected an email address");
+    }
+
+    Ok((name, address))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn event_fields(start: &str) -> EventFields {
+        EventFields {
+            summary: Some("Standup".into()),
+            start: Some(start.into()),
+            end: None,
+            duration: None,
+            all_day: false,
+            location: None,
+            description: None,
+            rrule: None,
+            alarms: Vec::new(),
+            attendees: Vec::new(),
+            organizer: None,
+        }
+    }
+
+    fn build(fields: &EventFields, email: Option<&str>) -> Result<String> {
+        let paris = Timezone::Tz(chrono_tz::Europe::Paris);
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap();
+        let contents = fields.build(paris, email, now)?;
+        // Unfold the long lines.
+        Ok(String::from_utf8(contents).unwrap().replace("\r\n ", ""))
+    }
+
+    #[test]
+    fn build_timed_event() {
+        let mut fields = event_fields("2026-03-16 09:00");
+        fields.rrule = Some("RRULE:FREQ=WEEKLY;BYDAY=MO".into());
+        fields.alarms = vec![TimeDelta::minutes(15)];
+
+        let ical = build(&fields, None).unwrap();
+
+        assert!(ical.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Paris\r\n"));
+        assert!(ical.contains("DTSTAMP:20260301T080000Z\r\n"));
+        assert!(ical.contains("DTSTART;TZID=Europe/Paris:20260316T090000\r\n"));
+        assert!(ical.contains("DTEND;TZID=Europe/Paris:20260316T100000\r\n"));
+        assert!(ical.contains("SUMMARY:Standup\r\n"));
+        assert!(ical.contains("RRULE:FREQ=WEEKLY;BYDAY=MO\r\n"));
+        assert!(ical.contains("TRIGGER:-PT15M\r\n"));
+        assert!(!ical.contains("ORGANIZER"));
+    }
+
+    #[test]
+    fn build_all_day_event() {
+        let mut fields = event_fields("tomorrow");
+        fields.end = Some("2026-03-04".into());
+
+        let ical = build(&fields, None).unwrap();
+
+        assert!(!ical.contains("VTIMEZONE"));
+        assert!(ical.contains("DTSTART;VALUE=DATE:20260302\r\n"));
+        assert!(ical.contains("DTEND;VALUE=DATE:20260305\r\n"));
+
+        fields.end = None;
+        fields.duration = Some(TimeDelta::hours(3));
+        assert!(build(&fields, None).is_err());
+    }
+
+    #[test]
+    fn build_invitation() {
+        let mut fields = event_fields("2026-03-16 09:00");
+        fields.attendees = vec!["Bob <bob@example.com>".into()];
+
+        assert!(build(&fields, None).is_err());
+
+        let ical = build(&fields, Some("jane@example.com")).unwrap();
+        assert!(ical.contains("ORGANIZER:mailto:jane@example.com\r\n"));
+        assert!(ical.contains("mailto:bob@example.com\r\n"));
+        assert!(ical.contains(";CN=Bob"));
+
+        fields.organizer = Some("Jane <jane@work.example.com>".into());
+        let ical = build(&fields, Some("jane@example.com")).unwrap();
+        assert!(ical.contains("ORGANIZER;CN=Jane:mailto:jane@work.example.com\r\n"));
+    }
+
+    #[test]
+    fn build_rejects_invalid_fields() {
+        let mut fields = event_fields("2026-03-16 09:00");
+        fields.end = Some("08:00".into());
+        assert!(build(&fields, None).is_err());
+
+        let mut fields = event_fields("2026-03-16 09:00");
+        fields.rrule = Some("BYDAY=MO".into());
+        assert!(build(&fields, None).is_err());
+    }
+}
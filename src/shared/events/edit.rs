@@ -0,0 +1,43 @@
+use anyhow::Result;
+use clap::Parser;
+use io_calendar::calcard::icalendar::ICalendarComponentType;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    editor::{Edited, edit_item},
+};
+
+/// Edit an existing event in `$EDITOR`.
+///
+/// The event is fetched, opened in `$VISUAL` or `$EDITOR` (falling
+/// back to `vi`), validated, then written back with the fetched ETag
+/// as `If-Match`. Invalid iCalendar or a concurrent change on the
+/// server prompts to edit again or to discard the changes.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct EventEditCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Stable event identifier (iCal `UID`).
+    #[arg(value_name = "EVENT-ID")]
+    pub event_id: String,
+}
+
+impl EventEditCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let kind = Some(ICalendarComponentType::VEvent);
+
+        let msg = match edit_item(&mut client, &calendar_id, &self.event_id, kind)? {
+            Edited::Updated => "Event successfully updated",
+            Edited::Unchanged => "Event unchanged, nothing to update",
+            Edited::Discarded => "Event changes discarded",
+        };
+
+        printer.out(Message::new(msg))
+    }
+}
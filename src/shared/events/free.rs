@@ -0,0 +1,446 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use clap::Parser;
+use comfy_table::{Cell, ContentArrangement, Row, Table};
+use io_calendar::{
+    calcard::icalendar::{ICalendarComponentType, ICalendarProperty},
+    item::CalendarItem,
+};
+use pimalaya_cli::printer::{Message, Printer};
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdsArg,
+    client::CalendarClient,
+    datetime::{parse_date, parse_duration},
+    ical::{IcalWriter, PRODID, generate_uid},
+    recurrence::{self, Localized, component_values},
+    timezone::Timezone,
+};
+
+/// Busy interval, in the rendering zone, tentative when it comes from
+/// tentative events.
+type Busy = (NaiveDateTime, NaiveDateTime, bool);
+
+/// Find free time slots.
+///
+/// Busy intervals are computed from the VEVENTs of the selected
+/// calendars, recurrences expanded. Events marked `TRANSP:TRANSPARENT`
+/// or `STATUS:CANCELLED` do not block time; all-day events do, unless
+/// transparent. The free slots of every day from `--from` (today by
+/// default) to `--to` (inclusive, a week by default) are then listed,
+/// restricted to the `--start-hour`/`--end-hour` working hours and
+/// to the future, and only kept when they last at least `--duration`.
+///
+/// Pass `--vfreebusy` to print the busy intervals of the range as an
+/// iCalendar VFREEBUSY instead (RFC 5545 §3.6.4), tentative events
+/// being reported as BUSY-TENTATIVE.
+///
+/// Repeat `-k`, pass `--all-calendars` and/or select several accounts
+/// with `--account` to merge the availability of several calendars.
+///
+/// JSON output: `{"slots": [{"start", "end", "minutes"}]}`, or
+/// `{"message": "..."}` carrying the VFREEBUSY with `--vfreebusy`.
+#[derive(Debug, Parser)]
+pub struct EventFreeCommand {
+    #[command(flatten)]
+    pub calendars: CalendarIdsArg,
+
+    /// Minimum length of the free slots, e.g. `45m` or `1h30m`.
+    #[arg(short, long, value_name = "DURATION", value_parser = parse_duration)]
+    pub duration: Option<TimeDelta>,
+
+    /// First day to look at: `YYYY-MM-DD`, `today`, etc. Defaults to
+    /// today.
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<String>,
+
+    /// Last day to look at (inclusive). Defaults to 6 days after
+    /// `--from`.
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<String>,
+
+    /// Start of the working hours.
+    #[arg(long, value_name = "HOUR", default_value_t = 0)]
+    pub start_hour: u32,
+
+    /// End of the working hours.
+    #[arg(long, value_name = "HOUR", default_value_t = 24)]
+    pub end_hour: u32,
+
+    /// Print the busy intervals as an iCalendar VFREEBUSY.
+    #[arg(long)]
+    pub vfreebusy: bool,
+
+    /// Maximum width of the rendered table, in terminal columns.
+    #[arg(long = "max-width", short = 'w', value_name = "COLUMNS")]
+    pub max_width: Option<u16>,
+}
+
+impl EventFreeCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CalendarClient) -> Result<()> {
+        self.execute_many(printer, vec![client])
+    }
+
+    /// Computes the availability across every client, one per account
+    /// selected by `--account`, in the zone of the first one.
+    pub fn execute_many(
+        self,
+        printer: &mut impl Printer,
+        mut clients: Vec<CalendarClient>,
+    ) -> Result<()> {
+        let Some(timezone) = clients.first().map(|client| client.account.timezone()) else {
+            bail!("Cannot compute free slots without any account");
+        };
+
+        if self.start_hour >= self.end_hour || self.end_hour > 24 {
+            bail!(
+                "Invalid working hours {}-{}; expected 0 <= start < end <= 24",
+                self.start_hour,
+                self.end_hour
+            );
+        }
+
+        let now = Utc::now();
+        let today = timezone.from_utc(now).date();
+
+        let from = match &self.from {
+            Some(date) => parse_date(date, today)?,
+            None => today,
+        };
+
+        let to = match &self.to {
+            Some(date) => parse_date(date, today)?,
+            None => from + Days::new(6),
+        };
+
+        if to < from {
+            bail!("The --to date must not be before the --from one");
+        }
+
+        let range = (
+            from.and_time(NaiveTime::MIN),
+            to.and_time(NaiveTime::MIN) + Days::new(1),
+        );
+
+        let mut busy = Vec::new();
+
+        for client in &mut clients {
+            for calendar_id in self.calendars.resolve(client)? {
+                let items = client.list_all_items(&calendar_id, None)?;
+                busy.extend(busy_intervals(&items, range, timezone));
+            }
+        }
+
+        let busy = merge(busy);
+
+        if self.vfreebusy {
+            let ical = vfreebusy(&busy, range, timezone, now);
+            let ical = String::from_utf8_lossy(&ical).into_owned();
+            return printer.out(Message::new(ical));
+        }
+
+        let now = timezone.from_utc(now);
+        let hours = (
+            TimeDelta::hours(self.start_hour as i64),
+            TimeDelta::hours(self.end_hour as i64),
+        );
+        let min = self.duration.unwrap_or(TimeDelta::zero());
+
+        let slots = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .flat_map(|date| {
+                let midnight = date.and_time(NaiveTime::MIN);
+                let window = ((midnight + hours.0).max(now), midnight + hours.1);
+                free_slots(window, &busy, min)
+            })
+            .map(|(start, end)| FreeSlot::new(start, end))
+            .collect();
+
+        let account = &clients[0].account;
+
+        printer.out(FreeSlots {
+            preset: account.table_preset().to_string(),
+            arrangement: account.table_arrangement(),
+            max_width: self.max_width,
+            slots,
+        })
+    }
+}
+
+/// Expands the VEVENTs of `items` into the busy intervals overlapping
+/// `range`, converted into `timezone`. Transparent and cancelled
+/// events are skipped.
+fn busy_intervals(
+    items: &[CalendarItem],
+    range: (NaiveDateTime, NaiveDateTime),
+    timezone: Timezone,
+) -> Vec<Busy> {
+    let kind = ICalendarComponentType::VEvent;
+    let mut busy = Vec::new();
+
+    for item in items {
+        let Some(ical) = item.as_ical() else {
+            continue;
+        };
+
+        let occurrences =
+            recurrence::occurrences_in(&ical, &kind, Some(range.0), Some(range.1), timezone);
+
+        for Localized {
+            occurrence,
+            start,
+            end,
+        } in occurrences
+        {
+            let has = |name: ICalendarProperty, value: &str| {
+                component_values(occurrence.component, &name)
+                    .iter()
+                    .any(|v| v.eq_ignore_ascii_case(value))
+            };
+
+            if has(ICalendarProperty::Transp, "TRANSPARENT")
+                || has(ICalendarProperty::Status, "CANCELLED")
+            {
+                continue;
+            }
+
+            let tentative = has(ICalendarProperty::Status, "TENTATIVE");
+
+            if end > start {
+                busy.push((start, end, tentative));
+            }
+        }
+    }
+
+    busy
+}
+
+/// Merges overlapping or adjacent busy intervals of the same kind,
+/// so that BUSY and BUSY-TENTATIVE time stay apart, then sorts them
+/// by start.
+fn merge(mut busy: Vec<Busy>) -> Vec<Busy> {
+    busy.sort_by_key(|(start, end, tentative)| (*tentative, *start, *end));
+
+    let mut merged: Vec<Busy> = Vec::new();
+
+    for (start, end, tentative) in busy {
+        match merged.last_mut() {
+            Some(last) if last.2 == tentative && start <= last.1 => {
+                last.1 = last.1.max(end);
+            }
+            _ => merged.push((start, end, tentative)),
+        }
+    }
+
+    merged.sort_by_key(|(start, end, _)| (*start, *end));
+    merged
+}
+
+/// Subtracts the `busy` intervals, sorted by start, from `window` and
+/// keeps the free slots lasting at least `min`.
+fn free_slots(
+    window: (NaiveDateTime, NaiveDateTime),
+    busy: &[Busy],
+    min: TimeDelta,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let (mut cursor, end) = window;
+    let mut slots = Vec::new();
+
+    for (busy_start, busy_end, _) in busy {
+        if *busy_end <= cursor {
+            continue;
+        }
+
+        if *busy_start >= end {
+            break;
+        }
+
+        if *busy_start > cursor {
+            slots.push((cursor, *busy_start));
+        }
+
+        cursor = cursor.max(*busy_end);
+    }
+
+    if cursor < end {
+        slots.push((cursor, end));
+    }
+
+    slots.retain(|(start, end)| *end - *start >= min && end > start);
+    slots
+}
+
+/// Writes the busy intervals of `range` as a VFREEBUSY, in UTC.
+fn vfreebusy(
+    busy: &[Busy],
+    range: (NaiveDateTime, NaiveDateTime),
+    timezone: Timezone,
+    now: DateTime<Utc>,
+) -> Vec<u8> {
+    let utc = |dt: NaiveDateTime| timezone.to_utc(dt).format("%Y%m%dT%H%M%SZ").to_string();
+    let mut ical = IcalWriter::new();
+
+    ical.begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .text("PRODID", PRODID)
+        .begin("VFREEBUSY")
+        .text("UID", &generate_uid(now))
+        .property("DTSTAMP", &[], &now.format("%Y%m%dT%H%M%SZ").to_string())
+        .property("DTSTART", &[], &utc(range.0))
+        .property("DTEND", &[], &utc(range.1));
+
+    for (start, end, tentative) in busy {
+        let start = (*start).max(range.0);
+        let end = (*end).min(range.1);
+        let fbtype = if *tentative { "BUSY-TENTATIVE" } else { "BUSY" };
+        let period = format!("{}/{}", utc(start), utc(end));
+        ical.property("FREEBUSY", &[("FBTYPE", fbtype)], &period);
+    }
+
+    ical.end("VFREEBUSY").end("VCALENDAR");
+    ical.finish()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FreeSlot {
+    pub start: String,
+    pub end: String,
+    pub minutes: i64,
+    #[serde(skip)]
+    date: NaiveDate,
+    #[serde(skip)]
+    span: (NaiveDateTime, NaiveDateTime),
+}
+
+impl FreeSlot {
+    fn new(start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        Self {
+            start: start.format("%Y-%m-%d %H:%M").to_string(),
+            end: end.format("%Y-%m-%d %H:%M").to_string(),
+            minutes: (end - start).num_minutes(),
+            date: start.date(),
+            span: (start, end),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FreeSlots {
+    #[serde(skip)]
+    pub preset: String,
+    #[serde(skip)]
+    pub arrangement: ContentArrangement,
+    #[serde(skip)]
+    pub max_width: Option<u16>,
+    pub slots: Vec<FreeSlot>,
+}
+
+impl fmt::Display for FreeSlots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+
+        table
+            .load_preset(&self.preset)
+            .set_content_arrangement(self.arrangement.clone())
+            .set_header(Row::from([
+                Cell::new("DAY"),
+                Cell::new("START"),
+                Cell::new("END"),
+                Cell::new("DURATION"),
+            ]))
+            .add_rows(self.slots.iter().map(|slot| {
+                let (start, end) = slot.span;
+                // A slot reaching the end of the day ends at midnight.
+                let end = if end.date() > slot.date {
+                    String::from("24:00")
+                } else {
+                    end.format("%H:%M").to_string()
+                };
+
+                let mut row = Row::new();
+                row.max_height(1);
+                row.add_cell(Cell::new(slot.date.format("%a %d %b")));
+                row.add_cell(Cell::new(start.format("%H:%M")));
+                row.add_cell(Cell::new(end));
+                row.add_cell(Cell::new(format_minutes(slot.minutes)));
+                row
+            }));
+
+        if let Some(width) = self.max_width {
+            table.set_width(width);
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{table}")
+    }
+}
+
+/// Renders a number of minutes as `45m`, `2h` or `1h30m`.
+fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+
+    use super::{free_slots, merge};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 16)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn merges_busy_intervals() {
+        let busy = vec![
+            (at(10, 0), at(11, 0), true),
+            (at(9, 0), at(10, 0), false),
+            (at(14, 0), at(15, 0), true),
+            (at(14, 30), at(14, 45), true),
+        ];
+
+        assert_eq!(
+            merge(busy),
+            [
+                (at(9, 0), at(10, 0), false),
+                (at(10, 0), at(11, 0), true),
+                (at(14, 0), at(15, 0), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_free_slots() {
+        let busy = [
+            (at(8, 0), at(9, 30), false),
+            (at(10, 0), at(11, 0), false),
+            (at(12, 0), at(13, 0), false),
+            (at(19, 0), at(20, 0), false),
+        ];
+        let window = (at(9, 0), at(18, 0));
+
+        assert_eq!(
+            free_slots(window, &busy, TimeDelta::minutes(45)),
+            [(at(11, 0), at(12, 0)), (at(13, 0), at(18, 0))]
+        );
+        assert_eq!(
+            free_slots(window, &busy, TimeDelta::zero()),
+            [
+                (at(9, 30), at(10, 0)),
+                (at(11, 0), at(12, 0)),
+                (at(13, 0), at(18, 0))
+            ]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Time-slotted `--day` and `--week-grid` layouts of `event agenda`.
+//!
+//! Each day of the view is split into slots of a fixed number of
+//! minutes between a start and an end hour. Timed events are snapped
+//! to the slots they touch and spread over side-by-side lanes when
+//! they overlap; all-day events, and timed ones covering the whole
+//! day, go to a header band instead. Events outside the hour range
+//! are pinned to the first or last slot so none gets lost.
+
+use std::{cmp::Reverse, fmt};
+
+use anyhow::{Result, bail};
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use comfy_table::{Cell, ContentArrangement, Row, Table};
+use serde::Serialize;
+
+use crate::shared::timezone::format_date_time;
+
+/// Event placed on the grid, with times in the rendering zone, as
+/// returned by [`occurrences_in`](crate::shared::recurrence::occurrences_in).
+#[derive(Clone, Debug)]
+pub struct GridEvent {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+    pub summary: String,
+}
+
+/// Hour range and slot size of the grid.
+#[derive(Clone, Copy, Debug)]
+pub struct GridSlots {
+    pub start_hour: u32,
+    pub end_hour: u32,
+    /// Slot size, in minutes.
+    pub minutes: u32,
+}
+
+impl GridSlots {
+    /// Validates the hour range and slot size.
+    pub fn new(start_hour: u32, end_hour: u32, minutes: u32) -> Result<Self> {
+        if start_hour >= end_hour || end_hour > 24 {
+            bail!("Invalid agenda hours {start_hour}-{end_hour}; expected 0 <= start < end <= 24");
+        }
+
+        if minutes == 0 || minutes > (end_hour - start_hour) * 60 {
+            bail!("Invalid agenda slot of {minutes} minutes for hours {start_hour}-{end_hour}");
+        }
+
+        Ok(Self {
+            start_hour,
+            end_hour,
+            minutes,
+        })
+    }
+
+    fn count(&self) -> usize {
+        ((self.end_hour - self.start_hour) * 60).div_ceil(self.minutes) as usize
+    }
+
+    fn slot(&self) -> TimeDelta {
+        TimeDelta::minutes(self.minutes as i64)
+    }
+
+    fn bounds(&self, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+        let midnight = date.and_time(NaiveTime::MIN);
+        let lo = midnight + TimeDelta::hours(self.start_hour as i64);
+        let hi = midnight + TimeDelta::hours(self.end_hour as i64);
+        (lo, hi)
+    }
+
+    fn label(&self, index: usize) -> String {
+        let minutes = self.start_hour * 60 + index as u32 * self.minutes;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    /// Snaps `[start, end)` to the `[first, last)` slot indices of
+    /// `date` it touches, pinned inside the hour range.
+    fn span(&self, date: NaiveDate, start: NaiveDateTime, end: NaiveDateTime) -> (usize, usize) {
+        let (lo, hi) = self.bounds(date);
+        let slot = self.slot();
+        let start = start.clamp(lo, hi - slot);
+        let end = end.max(start + slot).clamp(start + slot, hi);
+
+        let minutes = |dt: NaiveDateTime| (dt - lo).num_minutes() as u32;
+        let first = minutes(start) / self.minutes;
+        let last = minutes(end).div_ceil(self.minutes);
+
+        (first as usize, (last as usize).min(self.count()))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GridDay {
+    pub date: String,
+    pub all_day: Vec<String>,
+    pub events: Vec<GridEntry>,
+    #[serde(skip)]
+    label: String,
+    #[serde(skip)]
+    lanes: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GridEntry {
+    pub summary: String,
+    pub start: String,
+    pub end: String,
+    pub lane: usize,
+    #[serde(skip)]
+    slots: (usize, usize),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimeGrid {
+    #[serde(skip)]
+    pub preset: String,
+    #[serde(skip)]
+    pub arrangement: ContentArrangement,
+    #[serde(skip)]
+    pub max_width: Option<u16>,
+    #[serde(skip)]
+    slots: GridSlots,
+    pub days: Vec<GridDay>,
+}
+
+impl TimeGrid {
+    /// Lays `events` out over `dates`.
+    pub fn new(
+        preset: String,
+        arrangement: ContentArrangement,
+        max_width: Option<u16>,
+        slots: GridSlots,
+        dates: &[NaiveDate],
+        events: &[GridEvent],
+    ) -> Self {
+        let days = dates
+            .iter()
+            .map(|date| layout_day(*date, &slots, events))
+            .collect();
+
+        Self {
+            preset,
+            arrangement,
+            max_width,
+            slots,
+            days,
+        }
+    }
+}
+
+fn layout_day(date: NaiveDate, slots: &GridSlots, events: &[GridEvent]) -> GridDay {
+    let midnight = date.and_time(NaiveTime::MIN);
+    let next_midnight = midnight + Days::new(1);
+
+    let mut all_day = Vec::new();
+    let mut timed = Vec::new();
+
+    for event in events {
+        let overlaps =
+            event.start < next_midnight && (event.end > midnight || event.start >= midnight);
+
+        if !overlaps {
+            continue;
+        }
+
+        if event.all_day || (event.start <= midnight && event.end >= next_midnight) {
+            all_day.push(event.summary.clone());
+        } else {
+            let start = event.start.max(midnight);
+            let end = event.end.min(next_midnight);
+            timed.push((event, slots.span(date, start, end)));
+        }
+    }
+
+    let spans: Vec<(usize, usize)> = timed.iter().map(|(_, span)| *span).collect();
+    let lanes = assign_lanes(&spans);
+
+    let mut entries: Vec<GridEntry> = timed
+        .into_iter()
+        .zip(&lanes)
+        .map(|((event, slots), lane)| GridEntry {
+            summary: event.summary.clone(),
+            start: format_date_time(event.start, false),
+            end: format_date_time(event.end, false),
+            lane: *lane,
+            slots,
+        })
+        .collect();
+
+    entries.sort_by_key(|entry| (entry.slots.0, entry.lane));
+
+    GridDay {
+        date: date.format("%Y-%m-%d").to_string(),
+        all_day,
+        label: date.format("%a %d %b").to_string(),
+        lanes: lanes.iter().max().map_or(1, |lane| lane + 1),
+        events: entries,
+    }
+}
+
+/// Assigns each `[first, last)` slot span the lowest lane free over
+/// its whole span, longest spans first among those starting together.
+fn assign_lanes(spans: &[(usize, usize)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| (spans[i].0, Reverse(spans[i].1)));
+
+    // End of the last span placed in each lane.
+    let mut ends: Vec<usize> = Vec::new();
+    let mut lanes = vec![0; spans.len()];
+
+    for i in order {
+        let (first, last) = spans[i];
+
+        let lane = match ends.iter().position(|end| *end <= first) {
+            Some(lane) => lane,
+            None => {
+                ends.push(0);
+                ends.len() - 1
+            }
+        };
+
+        ends[lane] = last;
+        lanes[i] = lane;
+    }
+
+    lanes
+}
+
+impl fmt::Display for TimeGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+
+        let mut header = vec![Cell::new("")];
+        for day in &self.days {
+            header.push(Cell::new(&day.label));
+            header.extend((1..day.lanes).map(|_| Cell::new("")));
+        }
+
+        table
+            .load_preset(&self.preset)
+            .set_content_arrangement(self.arrangement.clone())
+            .set_header(Row::from(header));
+
+        if self.days.iter().any(|day| !day.all_day.is_empty()) {
+            let mut row = Row::new();
+            row.add_cell(Cell::new("all-day"));
+
+            for day in &self.days {
+                row.add_cell(Cell::new(day.all_day.join("\n")));
+                for _ in 1..day.lanes {
+                    row.add_cell(Cell::new(""));
+                }
+            }
+
+            table.add_row(row);
+        }
+
+        for index in 0..self.slots.count() {
+            let mut row = Row::new();
+            row.max_height(1);
+            row.add_cell(Cell::new(self.slots.label(index)));
+
+            for day in &self.days {
+                for lane in 0..day.lanes {
+                    let entry = day.events.iter().find(|entry| {
+                        entry.lane == lane && entry.slots.0 <= index && index < entry.slots.1
+                    });
+
+                    let text = match entry {
+                        Some(entry) if entry.slots.0 == index => entry.summary.as_str(),
+                        Some(_) => "│",
+                        None => "",
+                    };
+
+                    row.add_cell(Cell::new(text));
+                }
+            }
+
+            table.add_row(row);
+        }
+
+        if let Some(width) = self.max_width {
+            table.set_width(width);
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{table}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{GridSlots, assign_lanes};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 16)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn spreads_overlapping_spans_over_lanes() {
+        // 9-11, 10-12, 11-12 and 12-13 in hourly slots.
+        let spans = [(1, 3), (2, 4), (3, 4), (4, 5)];
+        assert_eq!(assign_lanes(&spans), [0, 1, 0, 0]);
+        assert_eq!(assign_lanes(&[(0, 2), (0, 4), (1, 2)]), [1, 0, 2]);
+    }
+
+    #[test]
+    fn snaps_and_pins_spans() {
+        let slots = GridSlots::new(8, 20, 30).unwrap();
+        let date = at(0, 0).date();
+
+        assert_eq!(slots.span(date, at(9, 10), at(9, 40)), (2, 4));
+        assert_eq!(slots.span(date, at(9, 0), at(9, 0)), (2, 3));
+        assert_eq!(slots.span(date, at(6, 0), at(7, 0)), (0, 1));
+        assert_eq!(slots.span(date, at(19, 45), at(23, 0)), (23, 24));
+        assert_eq!(slots.label(3), "09:30");
+        assert!(GridSlots::new(20, 8, 30).is_err());
+        assert!(GridSlots::new(8, 9, 90).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::{Result, bail};
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{arg::CalendarIdArg, client::CalendarClient, ical::IcalArg, itip};
+
+/// Process the iTIP replies of attendees.
+///
+/// Reads an RFC 5546 METHOD:REPLY message, as received by mail
+/// (iMIP), and sets the PARTSTAT each attendee answered in the
+/// organizer copy of the events of the same UID, written back guarded
+/// by their ETag. Answers for an overridden occurrence only update that
+/// override.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct EventImportReplyCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    #[command(flatten)]
+    pub ical: IcalArg,
+}
+
+impl EventImportReplyCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let updates = import_replies(&mut client, &calendar_id, &self.ical.read()?)?;
+
+        let message = format!("Reply successfully imported\n{}", updates.join("\n"));
+        printer.out(Message::new(message))
+    }
+}
+
+/// Applies the REPLY `contents` to the events of `calendar_id` they
+/// answer, found by UID. Returns one line per updated attendee.
+fn import_replies(
+    client: &mut CalendarClient,
+    calendar_id: &str,
+    contents: &[u8],
+) -> Result<Vec<String>> {
+    let replies = itip::read_replies(contents)?;
+
+    let mut uids: Vec<&str> = Vec::new();
+    for reply in &replies {
+        if !uids.contains(&reply.uid.as_str()) {
+            uids.push(&reply.uid);
+        }
+    }
+
+    let mut updates = Vec::new();
+
+    for uid in uids {
+        let item = client.find_item_by_uid(calendar_id, uid)?;
+        let mut contents = item.contents.clone();
+
+        for reply in replies.iter().filter(|reply| reply.uid == uid) {
+            let recurrence_id = reply.recurrence_id.as_deref();
+            let partstat = &reply.partstat;
+
+            let Some(updated) =
+                itip::set_partstat(&contents, &reply.attendee, partstat, recurrence_id)
+            else {
+                bail!(
+                    "Attendee `{}` is not invited to event `{uid}`",
+                    reply.attendee
+                );
+            };
+
+            contents = updated;
+            updates.push(format!("{}: {partstat} (event `{uid}`)", reply.attendee));
+        }
+
+        let etag = item.etag.as_deref();
+        client.update_item(calendar_id, &item.id, contents, etag)?;
+    }
+
+    Ok(updates)
+}
+
+#[cfg(all(test, feature = "vdir"))]
+mod tests {
+    use super::import_replies;
+    use crate::{
+        backend::Backend,
+        config::{AccountConfig, Config, VdirConfig},
+        shared::client::CalendarClient,
+    };
+
+    #[test]
+    fn import_reply_by_uid() {
+        let dir = tempfile::tempdir().unwrap();
+        let account = AccountConfig {
+            vdir: Some(VdirConfig {
+                home_dir: dir.path().to_owned(),
+            }),
+            ..Default::default()
+        };
+        let mut client =
+            CalendarClient::new(Config::default(), "test", account, Backend::Vdir).unwrap();
+
+        client
+            .create_calendar("personal", "Personal", None, None)
+            .unwrap();
+        let event = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Test//EN\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:review@example.com\r\n",
+            "DTSTAMP:20260301T080000Z\r\n",
+            "DTSTART:20260316T090000Z\r\n",
+            "SUMMARY:Review\r\n",
+            "ORGANIZER:mailto:jane@example.com\r\n",
+            "ATTENDEE;PARTSTAT=NEEDS-ACTION:mailto:bob@example.com\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+        client
+            .create_item("personal", event.as_bytes().to_vec())
+            .unwrap();
+
+        let reply = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Test//EN\r\n",
+            "METHOD:REPLY\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:review@example.com\r\n",
+            "DTSTAMP:20260302T080000Z\r\n",
+            "ORGANIZER:mailto:jane@example.com\r\n",
+            "ATTENDEE;PARTSTAT=ACCEPTED:mailto:bob@example.com\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+        let updates = import_replies(&mut client, "personal", reply.as_bytes()).unwrap();
+        assert_eq!(
+            updates,
+            ["bob@example.com: ACCEPTED (event `review@example.com`)"]
+        );
+
+        let item = client
+            .find_item_by_uid("personal", "review@example.com")
+            .unwrap();
+        let contents = String::from_utf8(item.contents).unwrap();
+        assert!(contents.contains("PARTSTAT=ACCEPTED:mailto:bob@example.com\r\n"));
+
+        let unknown = reply.replace("UID:review@", "UID:unknown@");
+        assert!(import_replies(&mut client, "personal", unknown.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Result, bail};
+use clap::Parser;
+use pimalaya_cli::printer::Printer;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    itip::{self, ItipSaveArg},
+};
+
+/// Generate the iTIP invitation of an event.
+///
+/// Wraps the event into an RFC 5546 METHOD:REQUEST message to send to
+/// its ATTENDEEs by mail (iMIP). Events without ORGANIZER get one from
+/// `--organizer`, falling back to the `event.email` config. Pass
+/// `--cancel` to call the event off instead: the stored copy gets
+/// STATUS:CANCELLED and a bumped SEQUENCE, and a METHOD:CANCEL message
+/// is generated.
+///
+/// The message is printed to stdout, ready to be piped to a mail
+/// client, or saved under the downloads directory with `--save`.
+///
+/// JSON output: `{"message": "..."}`, carrying either the message or
+/// the path it was saved at.
+#[derive(Debug, Parser)]
+pub struct EventInviteCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Stable event identifier (iCal `UID`).
+    #[arg(value_name = "EVENT-ID")]
+    pub event_id: String,
+
+    /// Address of the organizer, when the event has none. Defaults to
+    /// the `event.email` config.
+    #[arg(long, value_name = "ADDRESS")]
+    pub organizer: Option<String>,
+
+    /// Cancel the event instead of inviting attendees.
+    #[arg(long)]
+    pub cancel: bool,
+
+    #[command(flatten)]
+    pub save: ItipSaveArg,
+}
+
+impl EventInviteCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let item = client.find_item_by_uid(&calendar_id, &self.event_id)?;
+
+        if itip::attendees(&item.contents).is_empty() {
+            bail!("Event `{}` has no ATTENDEE to invite", self.event_id);
+        }
+
+        let mut props = Vec::new();
+
+        let organizer = match itip::organizer(&item.contents) {
+            Some(_) => None,
+            None => match self.organizer.as_ref().or(client.account.email.as_ref()) {
+                Some(address) => Some(format!("mailto:{}", itip::normalize_address(address))),
+                None => bail!(
+                    "Event `{}` has no ORGANIZER; pass --organizer",
+                    self.event_id
+                ),
+            },
+        };
+
+        if let Some(organizer) = &organizer {
+            props.push(("ORGANIZER", Some(organizer.as_str())));
+        }
+
+        let sequence = (itip::sequence(&item.contents) + 1).to_string();
+
+        if self.cancel {
+            props.push(("STATUS", Some("CANCELLED")));
+            props.push(("SEQUENCE", Some(sequence.as_str())));
+        }
+
+        let contents = if props.is_empty() {
+            item.contents
+        } else {
+            client.update_component(&calendar_id, &item.id, "VEVENT", &props)?
+        };
+
+        let method = if self.cancel { "CANCEL" } else { "REQUEST" };
+        let message = itip::with_method(&contents, method);
+
+        self.save
+            .out(printer, &client.account, &self.event_id, method, message)
+    }
+}
@@ -1,22 +1,23 @@
-use std::fmt::{self, Write};
+use std::fmt;
 
-use anyhow::{Result, anyhow};
-use chrono::{Days, NaiveDate};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
 use clap::Parser;
 use comfy_table::{Cell, Color, ContentArrangement, Row, Table};
 use io_calendar::{
-    calcard::{
-        common::PartialDateTime,
-        icalendar::{
-            ICalendarComponent, ICalendarComponentType, ICalendarProperty, ICalendarValue,
-        },
-    },
-    item::{CalendarItem, TimeRange},
+    calcard::icalendar::{ICalendarComponent, ICalendarComponentType, ICalendarProperty},
+    item::{CalendarItem, PropFilter, TimeRange},
 };
 use pimalaya_cli::printer::Printer;
 use serde::Serialize;
 
-use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
+use crate::shared::{
+    arg::CalendarIdsArg,
+    client::CalendarClient,
+    query::Query,
+    recurrence::{self, Localized, component_text, component_values},
+    timezone::{Timezone, TzResolver, format_date_time},
+};
 
 /// List VEVENT items inside a calendar.
 ///
@@ -25,13 +26,39 @@ use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
 ///
 /// Pass `--from` and/or `--to` (YYYY-MM-DD, both inclusive) to filter
 /// by date range: server-side on CalDAV, client-side on vdir. A range
-/// lifts the default page-size cap so every match is returned.
+/// lifts the default page-size cap so every match is returned, and
+/// expands recurring events (RRULE, RDATE, EXDATE and RECURRENCE-ID
+/// overrides) into one row per occurrence inside the range, sorted by
+/// start. Each occurrence row keeps the id of its item, and carries
+/// its `uid` and `recurrence-id`.
+///
+/// Pass a trailing query to only list the events matching it, e.g.
+/// `summary~standup and category=work and not status=cancelled`.
+/// Conditions are `<field><op><value>` over `summary`, `description`,
+/// `location`, `category`, `attendee`, `organizer`, `status` and `uid`,
+/// with `=`, `!=`, `~` (contains) and `!~`, all case-insensitive; they
+/// combine with `and`, `or`, `not` and parentheses. Queries are
+/// evaluated client-side, after their top-level positive conditions
+/// have been pushed down to the CalDAV server as a `calendar-query`.
+/// Paging then applies to the matching events.
+///
+/// Repeat `-k` or pass `--all-calendars` to list several calendars at
+/// once, and pass `--account all`, a comma list of accounts or an
+/// account group to list several accounts at once: rows are merged by
+/// start, paged client-side, and CALENDAR/ACCOUNT columns tell them
+/// apart. Rendering settings then come from the first account.
 ///
-/// JSON output: `{"events": [{"id", "summary", "start", "end"}]}`.
+/// Start and end are rendered in the zone picked by the global `--tz`
+/// flag, falling back to `event.timezone`, then the local zone. TZIDs
+/// resolve through the embedded VTIMEZONE or their IANA name; floating
+/// times and all-day dates are shown as is.
+///
+/// JSON output: `{"events": [{"id", "uid", "account", "calendar",
+/// "recurrence-id", "summary", "start", "end"}]}`.
 #[derive(Debug, Parser)]
 pub struct EventListCommand {
     #[command(flatten)]
-    pub calendar: CalendarIdArg,
+    pub calendars: CalendarIdsArg,
 
     /// 1-indexed page number. Defaults to 1.
     #[arg(short, long, value_name = "N")]
@@ -52,12 +79,42 @@ pub struct EventListCommand {
     /// Maximum width of the rendered table, in terminal columns.
     #[arg(long = "max-width", short = 'w', value_name = "COLUMNS")]
     pub max_width: Option<u16>,
+
+    /// Filter expression, e.g. `summary~standup and not
+    /// status=cancelled`.
+    #[arg(value_name = "QUERY", trailing_var_arg = true)]
+    pub query: Vec<String>,
 }
 
 impl EventListCommand {
-    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
-        let calendar_id = client.account.calendar_id(self.calendar.id)?;
-        let time_range = build_time_range(self.from, self.to)?;
+    pub fn execute(self, printer: &mut impl Printer, client: CalendarClient) -> Result<()> {
+        self.execute_many(printer, vec![client])
+    }
+
+    /// Lists the events of every client, one per account selected by
+    /// `--account`. Rendering settings come from the first one.
+    pub fn execute_many(
+        self,
+        printer: &mut impl Printer,
+        mut clients: Vec<CalendarClient>,
+    ) -> Result<()> {
+        let Some(first) = clients.first() else {
+            bail!("Cannot list events without any account");
+        };
+
+        let multi_account = clients.len() > 1;
+        let timezone = first.account.timezone();
+        let window = build_window(self.from, self.to);
+        if self.to.is_some() && window.1.is_none() {
+            bail!("The --to date is out of range");
+        }
+        let time_range = build_time_range(window, timezone)?;
+
+        let query = if self.query.is_empty() {
+            None
+        } else {
+            Some(self.query.join(" ").parse::<Query>()?)
+        };
 
         // A date range should return every match, so the default
         // page-size cap only applies to the unfiltered listing.
@@ -65,26 +122,99 @@ impl EventListCommand {
             Some(_) => self.page_size,
             None => self
                 .page_size
-                .or(Some(client.account.events_list_page_size())),
+                .or(Some(first.account.events_list_page_size())),
         };
 
-        let raw_items =
-            client.list_items(&calendar_id, self.page, page_size, time_range.as_ref())?;
+        let mut sources = Vec::new();
 
-        let events: Vec<EventRow> = raw_items
-            .into_iter()
-            .filter_map(extract_event_row)
-            .collect();
+        for (index, client) in clients.iter_mut().enumerate() {
+            for calendar_id in self.calendars.resolve(client)? {
+                sources.push((index, calendar_id));
+            }
+        }
+
+        // Calendars are told apart as soon as one account contributes
+        // several of them.
+        let multi_calendar =
+            (0..clients.len()).any(|index| sources.iter().filter(|(i, _)| *i == index).count() > 1);
+
+        // Queries and merged listings are paged client-side, once
+        // evaluated and sorted.
+        let client_paging = query.is_some() || sources.len() > 1;
+        let unpaged = client_paging || (self.page.is_none() && page_size.is_none());
+        let mut rows: Vec<(NaiveDateTime, EventRow)> = Vec::new();
+
+        for (index, calendar_id) in &sources {
+            let client = &mut clients[*index];
+            let account_name = client.account_name.clone();
+            let source = Source {
+                account: &account_name,
+                calendar: calendar_id,
+            };
+
+            // Unpaged listings go through the item cache, when any.
+            let raw_items = match &query {
+                Some(query) => {
+                    let filters = prop_filters(query);
+                    client.search_items(calendar_id, time_range.as_ref(), &filters)?
+                }
+                None if unpaged => client.list_all_items(calendar_id, time_range.as_ref())?,
+                None => {
+                    client.list_items(calendar_id, self.page, page_size, time_range.as_ref())?
+                }
+            };
+
+            let query = query.as_ref();
+            match time_range {
+                Some(_) => {
+                    let (from, to) = window;
+                    rows.extend(raw_items.iter().flat_map(|item| {
+                        expand_event_rows(item, &source, from, to, timezone, query)
+                    }));
+                }
+                None => rows.extend(
+                    raw_items
+                        .iter()
+                        .filter_map(|item| extract_event_row(item, &source, timezone, query)),
+                ),
+            }
+        }
+
+        // Expanded occurrences are always sorted by start; plain
+        // listings keep the backend order unless several sources need
+        // to be merged.
+        if time_range.is_some() || sources.len() > 1 {
+            rows.sort_by_key(|(start, _)| *start);
+        }
+
+        let mut events: Vec<EventRow> = rows.into_iter().map(|(_, row)| row).collect();
+
+        if client_paging && let Some(page_size) = page_size {
+            let page = self.page.unwrap_or(1).max(1) - 1;
+            let skip = page as usize * page_size as usize;
+            events = events
+                .into_iter()
+                .skip(skip)
+                .take(page_size as usize)
+                .collect();
+        }
+
+        let account = &clients[0].account;
 
         let table = Events {
-            preset: client.account.table_preset().to_string(),
-            arrangement: client.account.table_arrangement(),
+            preset: account.table_preset().to_string(),
+            arrangement: account.table_arrangement(),
             max_width: self.max_width,
+            multi_account,
+            multi_calendar,
             colors: EventColors {
-                id: client.account.events_list_table_id_color(),
-                summary: client.account.events_list_table_summary_color(),
-                start: client.account.events_list_table_start_color(),
-                end: client.account.events_list_table_end_color(),
+                id: account.events_list_table_id_color(),
+                account: account.events_list_table_account_color(),
+                calendar: account.events_list_table_calendar_color(),
+                recurrence_id: account.events_list_table_recurrence_id_color(),
+                summary: account.events_list_table_summary_color(),
+                start: account.events_list_table_start_color(),
+                end: account.events_list_table_end_color(),
             },
             events,
         };
@@ -93,17 +223,37 @@ impl EventListCommand {
     }
 }
 
+/// Account and calendar the listed items come from.
+struct Source<'a> {
+    account: &'a str,
+    calendar: &'a str,
+}
+
 #[derive(Clone, Copy, Debug)]
 struct EventColors {
     id: Color,
+    account: Color,
+    calendar: Color,
+    recurrence_id: Color,
     summary: Color,
     start: Color,
     end: Color,
 }
 
 #[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct EventRow {
     pub id: String,
+    /// UID of the event, which backends with opaque ids do not reuse
+    /// as item id.
+    pub uid: Option<String>,
+    /// Account the event belongs to.
+    pub account: String,
+    /// Calendar the event belongs to.
+    pub calendar: String,
+    /// RECURRENCE-ID of the occurrence, set on rows expanded from a
+    /// recurring event.
+    pub recurrence_id: Option<String>,
     pub summary: String,
     pub start: String,
     pub end: String,
@@ -117,6 +267,14 @@ pub struct Events {
     pub arrangement: ContentArrangement,
     #[serde(skip)]
     pub max_width: Option<u16>,
+    /// Whether rows come from several accounts, which adds an ACCOUNT
+    /// column.
+    #[serde(skip)]
+    pub multi_account: bool,
+    /// Whether rows come from several calendars of an account, which
+    /// adds a CALENDAR column.
+    #[serde(skip)]
+    pub multi_calendar: bool,
     #[serde(skip)]
     colors: EventColors,
     pub events: Vec<EventRow>,
@@ -126,19 +284,52 @@ impl fmt::Display for Events {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut table = Table::new();
 
+        // The RECURRENCE-ID column only shows up once a recurring
+        // event has been expanded, so plain listings stay narrow.
+        let recurring = self.events.iter().any(|e| e.recurrence_id.is_some());
+        // Same for the UID column, only useful when ids differ from it.
+        let uids = self
+            .events
+            .iter()
+            .any(|e| e.uid.as_ref().is_some_and(|uid| *uid != e.id));
+
+        let mut header = vec![Cell::new("ID")];
+        if uids {
+            header.push(Cell::new("UID"));
+        }
+        if self.multi_account {
+            header.push(Cell::new("ACCOUNT"));
+        }
+        if self.multi_calendar {
+            header.push(Cell::new("CALENDAR"));
+        }
+        if recurring {
+            header.push(Cell::new("RECURRENCE-ID"));
+        }
+        header.extend([Cell::new("SUMMARY"), Cell::new("START"), Cell::new("END")]);
+
         table
             .load_preset(&self.preset)
             .set_content_arrangement(self.arrangement.clone())
-            .set_header(Row::from(vec![
-                Cell::new("ID"),
-                Cell::new("SUMMARY"),
-                Cell::new("START"),
-                Cell::new("END"),
-            ]))
+            .set_header(Row::from(header))
             .add_rows(self.events.iter().map(|e| {
                 let mut row = Row::new();
                 row.max_height(1);
                 row.add_cell(Cell::new(&e.id).fg(self.colors.id));
+                if uids {
+                    let uid = e.uid.as_deref().unwrap_or("");
+                    row.add_cell(Cell::new(uid).fg(self.colors.id));
+                }
+                if self.multi_account {
+                    row.add_cell(Cell::new(&e.account).fg(self.colors.account));
+                }
+                if self.multi_calendar {
+                    row.add_cell(Cell::new(&e.calendar).fg(self.colors.calendar));
+                }
+                if recurring {
+                    let recurrence_id = e.recurrence_id.as_deref().unwrap_or("");
+                    row.add_cell(Cell::new(recurrence_id).fg(self.colors.recurrence_id));
+                }
                 row.add_cell(Cell::new(&e.summary).fg(self.colors.summary));
                 row.add_cell(Cell::new(&e.start).fg(self.colors.start));
                 row.add_cell(Cell::new(&e.end).fg(self.colors.end));
@@ -155,79 +346,131 @@ impl fmt::Display for Events {
 }
 
 /// Parses `item` and pulls out the first VEVENT component's SUMMARY,
-/// DTSTART, and DTEND. Returns [`None`] when the item is not a
-/// recognisable VEVENT.
-fn extract_event_row(item: CalendarItem) -> Option<EventRow> {
+/// DTSTART, and DTEND, converted into `timezone`, the row paired with
+/// its converted start for sorting. Returns [`None`] when the item is
+/// not a recognisable VEVENT, or does not match `query`.
+fn extract_event_row(
+    item: &CalendarItem,
+    source: &Source,
+    timezone: Timezone,
+    query: Option<&Query>,
+) -> Option<(NaiveDateTime, EventRow)> {
     let ical = item.as_ical()?;
-    let vevent = ical
-        .components
-        .iter()
-        .find(|c| c.component_type == ICalendarComponentType::VEvent)?;
-
-    let summary = component_text(vevent, &ICalendarProperty::Summary).unwrap_or_default();
-    let start = component_date(vevent, &ICalendarProperty::Dtstart).unwrap_or_default();
-    let end = component_date(vevent, &ICalendarProperty::Dtend).unwrap_or_default();
-
-    Some(EventRow {
-        id: item.id,
-        summary,
-        start,
-        end,
-    })
-}
+    let resolver = TzResolver::new(&ical);
+    let occurrence = recurrence::first(&ical, &ICalendarComponentType::VEvent)?;
 
-fn component_text(component: &ICalendarComponent, name: &ICalendarProperty) -> Option<String> {
-    let prop = component.property(name)?;
-    prop.values.iter().find_map(|v| match v {
-        ICalendarValue::Text(text) => Some(text.clone()),
-        _ => None,
-    })
-}
+    if !matches(query, occurrence.component) {
+        return None;
+    }
+    let (start, end) = timezone.localize_occurrence(&resolver, &occurrence);
+    let summary = component_text(occurrence.component, &ICalendarProperty::Summary);
+
+    let row = EventRow {
+        id: item.id.clone(),
+        uid: occurrence.uid,
+        account: source.account.to_owned(),
+        calendar: source.calendar.to_owned(),
+        recurrence_id: None,
+        summary: summary.unwrap_or_default(),
+        start: format_date_time(start, occurrence.all_day),
+        end: format_date_time(end, occurrence.all_day),
+    };
 
-fn component_date(component: &ICalendarComponent, name: &ICalendarProperty) -> Option<String> {
-    let prop = component.property(name)?;
-    prop.values.iter().find_map(|v| match v {
-        ICalendarValue::PartialDateTime(pdt) => Some(format_partial_datetime(pdt)),
-        _ => None,
-    })
+    Some((start, row))
 }
 
-/// Renders a [`PartialDateTime`] as a YYYYMMDD or YYYYMMDDTHHMMSS
-/// string, mirroring the iCalendar wire format used by upstream
-/// consumers of `events list`.
-fn format_partial_datetime(pdt: &PartialDateTime) -> String {
-    let mut out = String::with_capacity(16);
+/// Parses `item` and expands its VEVENTs into one row per occurrence
+/// overlapping the `[from, to)` window of the `timezone` wall clock
+/// and matching `query`, each paired with its converted start for
+/// sorting.
+fn expand_event_rows(
+    item: &CalendarItem,
+    source: &Source,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    timezone: Timezone,
+    query: Option<&Query>,
+) -> Vec<(NaiveDateTime, EventRow)> {
+    let Some(ical) = item.as_ical() else {
+        return Vec::new();
+    };
 
-    if let (Some(y), Some(m), Some(d)) = (pdt.year, pdt.month, pdt.day) {
-        let _ = write!(&mut out, "{y:04}{m:02}{d:02}");
-    }
+    let kind = ICalendarComponentType::VEvent;
+
+    recurrence::occurrences_in(&ical, &kind, from, to, timezone)
+        .into_iter()
+        .filter(|localized| matches(query, localized.occurrence.component))
+        .map(|localized| {
+            let Localized {
+                occurrence,
+                start,
+                end,
+            } = localized;
+            let summary = component_text(occurrence.component, &ICalendarProperty::Summary);
+            let row = EventRow {
+                id: item.id.clone(),
+                uid: occurrence.uid,
+                account: source.account.to_owned(),
+                calendar: source.calendar.to_owned(),
+                recurrence_id: occurrence.recurrence_id,
+                summary: summary.unwrap_or_default(),
+                start: format_date_time(start, occurrence.all_day),
+                end: format_date_time(end, occurrence.all_day),
+            };
+
+            (start, row)
+        })
+        .collect()
+}
 
-    if let (Some(h), Some(min), Some(s)) = (pdt.hour, pdt.minute, pdt.second) {
-        let _ = write!(&mut out, "T{h:02}{min:02}{s:02}");
-    }
+/// Evaluates `query` against the properties of `component`. No query
+/// matches everything.
+fn matches(query: Option<&Query>, component: &ICalendarComponent) -> bool {
+    query.is_none_or(|query| query.matches(&|field| component_values(component, &field.property())))
+}
 
-    out
+/// Turns the conditions of `query` every match satisfies into VEVENT
+/// `text-match` filters for the CalDAV `calendar-query`.
+fn prop_filters(query: &Query) -> Vec<PropFilter> {
+    query
+        .text_matches()
+        .into_iter()
+        .map(|(field, text)| PropFilter::text_match("VEVENT", field.name(), text))
+        .collect()
 }
 
-/// Builds a [`TimeRange`] from optional inclusive `--from` / `--to`
-/// dates. The `--to` bound is inclusive, so it is shifted one day
-/// forward to produce the exclusive upper bound the filter expects.
-fn build_time_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Option<TimeRange>> {
+/// Builds the `[from, to)` expansion window: `--from` at midnight,
+/// `--to` at the next midnight, as wall-clock times of the display
+/// zone.
+pub fn build_window(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> (Option<NaiveDateTime>, Option<NaiveDateTime>) {
+    let from = from.and_then(|date| date.and_hms_opt(0, 0, 0));
+    let to = to
+        .and_then(|date| date.checked_add_days(Days::new(1)))
+        .and_then(|date| date.and_hms_opt(0, 0, 0));
+    (from, to)
+}
+
+/// Builds the server-side [`TimeRange`] covering the `window` of
+/// `timezone` (see [`build_window`]). Each bound is widened to cover
+/// both its instant in `timezone` and the same wall clock read as UTC,
+/// so floating times pass too; the expansion then filters the
+/// occurrences exactly.
+fn build_time_range(
+    (from, to): (Option<NaiveDateTime>, Option<NaiveDateTime>),
+    timezone: Timezone,
+) -> Result<Option<TimeRange>> {
     if from.is_none() && to.is_none() {
         return Ok(None);
     }
 
-    let stamp = |date: NaiveDate| format!("{}T000000Z", date.format("%Y%m%d"));
-
-    let end = match to {
-        Some(date) => Some(
-            date.checked_add_days(Days::new(1))
-                .ok_or_else(|| anyhow!("The --to date is out of range"))?,
-        ),
-        None => None,
-    };
+    let stamp = |dt: DateTime<Utc>| dt.format("%Y%m%dT%H%M%SZ").to_string();
+    let start = from.map(|from| stamp(timezone.to_utc(from).min(from.and_utc())));
+    let end = to.map(|to| stamp(timezone.to_utc(to).max(to.and_utc())));
 
-    TimeRange::new(from.map(stamp).as_deref(), end.map(stamp).as_deref())
+    TimeRange::new(start.as_deref(), end.as_deref())
         .map(Some)
         .ok_or_else(|| anyhow!("Invalid --from/--to date range"))
 }
@@ -240,25 +483,25 @@ mod tests {
         NaiveDate::from_ymd_opt(year, month, day).unwrap()
     }
 
+    fn range(from: Option<NaiveDate>, to: Option<NaiveDate>, tz: &str) -> Option<TimeRange> {
+        build_time_range(build_window(from, to), tz.parse().unwrap()).unwrap()
+    }
+
     #[test]
     fn both_none_returns_none() {
-        assert!(build_time_range(None, None).unwrap().is_none());
+        assert!(range(None, None, "UTC").is_none());
     }
 
     #[test]
     fn from_only() {
-        let range = build_time_range(Some(date(2026, 2, 14)), None)
-            .unwrap()
-            .unwrap();
+        let range = range(Some(date(2026, 2, 14)), None, "UTC").unwrap();
         assert_eq!(range.start(), Some("20260214T000000Z"));
         assert_eq!(range.end(), None);
     }
 
     #[test]
     fn to_only_is_inclusive() {
-        let range = build_time_range(None, Some(date(2026, 2, 21)))
-            .unwrap()
-            .unwrap();
+        let range = range(None, Some(date(2026, 2, 21)), "UTC").unwrap();
         assert_eq!(range.start(), None);
         // `--to 2026-02-21` covers the whole day, so the exclusive end
         // bound is the next day.
@@ -267,26 +510,36 @@ mod tests {
 
     #[test]
     fn both_from_and_to() {
-        let range = build_time_range(Some(date(2026, 2, 14)), Some(date(2026, 2, 21)))
-            .unwrap()
-            .unwrap();
+        let range = range(Some(date(2026, 2, 14)), Some(date(2026, 2, 21)), "UTC").unwrap();
         assert_eq!(range.start(), Some("20260214T000000Z"));
         assert_eq!(range.end(), Some("20260222T000000Z"));
     }
 
     #[test]
     fn to_at_month_boundary() {
-        let range = build_time_range(None, Some(date(2026, 1, 31)))
-            .unwrap()
-            .unwrap();
+        let range = range(None, Some(date(2026, 1, 31)), "UTC").unwrap();
         assert_eq!(range.end(), Some("20260201T000000Z"));
     }
 
     #[test]
     fn to_at_year_boundary() {
-        let range = build_time_range(None, Some(date(2026, 12, 31)))
-            .unwrap()
-            .unwrap();
+        let range = range(None, Some(date(2026, 12, 31)), "UTC").unwrap();
         assert_eq!(range.end(), Some("20270101T000000Z"));
     }
+
+    #[test]
+    fn widened_by_zone_offset() {
+        let from = Some(date(2026, 2, 14));
+        let to = Some(date(2026, 2, 21));
+
+        // Paris midnight is 23:00 UTC the day before
+        let paris = range(from, to, "Europe/Paris").unwrap();
+        assert_eq!(paris.start(), Some("20260213T230000Z"));
+        assert_eq!(paris.end(), Some("20260222T000000Z"));
+
+        // New York midnight is 05:00 UTC the same day
+        let new_york = range(from, to, "America/New_York").unwrap();
+        assert_eq!(new_york.start(), Some("20260214T000000Z"));
+        assert_eq!(new_york.end(), Some("20260222T050000Z"));
+    }
 }
@@ -2,6 +2,13 @@ pub mod agenda;
 pub mod cli;
 pub mod create;
 pub mod delete;
+pub mod edit;
+pub mod free;
+pub mod grid;
+pub mod import_reply;
+pub mod invite;
 pub mod list;
 pub mod read;
+pub mod reply;
+pub mod upcoming;
 pub mod update;
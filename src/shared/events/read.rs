@@ -1,12 +1,28 @@
-use anyhow::Result;
+use std::fmt;
+
+use anyhow::{Result, bail};
 use clap::Parser;
+use io_calendar::calcard::icalendar::{ICalendarComponentType, ICalendarProperty};
 use pimalaya_cli::printer::{Message, Printer};
+use serde::Serialize;
 
-use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    recurrence::{self, component_text},
+    timezone::{Timezone, TzResolver, format_date_time},
+};
 
-/// Read a single event (raw iCalendar bytes).
+/// Read a single event.
+///
+/// Renders the SUMMARY, start, end, LOCATION and DESCRIPTION of the
+/// event, with times converted into the zone picked by the global
+/// `--tz` flag (falling back to `event.timezone`, then the local
+/// zone). Pass `--raw` to print the raw iCalendar bytes instead.
 ///
-/// JSON output: `{"message": "..."}`, carrying the raw iCalendar.
+/// JSON output: `{"id", "summary", "start", "end", "timezone",
+/// "location", "description"}`, or `{"message": "..."}` carrying the
+/// raw iCalendar with `--raw`.
 #[derive(Debug, Parser)]
 pub struct EventReadCommand {
     #[command(flatten)]
@@ -15,13 +31,73 @@ pub struct EventReadCommand {
     /// Stable event identifier (iCal `UID`).
     #[arg(value_name = "EVENT-ID")]
     pub event_id: String,
+
+    /// Print the raw iCalendar bytes, untouched.
+    #[arg(short, long)]
+    pub raw: bool,
 }
 
 impl EventReadCommand {
     pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
         let calendar_id = client.account.calendar_id(self.calendar.id)?;
         let item = client.get_item(&calendar_id, &self.event_id)?;
-        let contents = String::from_utf8_lossy(&item.contents).into_owned();
-        printer.out(Message::new(contents))
+
+        if self.raw {
+            let contents = String::from_utf8_lossy(&item.contents).into_owned();
+            return printer.out(Message::new(contents));
+        }
+
+        let Some(ical) = item.as_ical() else {
+            bail!("Event `{}` is not valid iCalendar", self.event_id);
+        };
+
+        let Some(occurrence) = recurrence::first(&ical, &ICalendarComponentType::VEvent) else {
+            bail!("Item `{}` does not contain any VEVENT", self.event_id);
+        };
+
+        let timezone = client.account.timezone();
+        let resolver = TzResolver::new(&ical);
+        let (start, end) = timezone.localize_occurrence(&resolver, &occurrence);
+        let text = |name| component_text(occurrence.component, &name);
+
+        printer.out(Event {
+            id: item.id,
+            summary: text(ICalendarProperty::Summary),
+            start: format_date_time(start, occurrence.all_day),
+            end: format_date_time(end, occurrence.all_day),
+            timezone,
+            location: text(ICalendarProperty::Location),
+            description: text(ICalendarProperty::Description),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    pub id: String,
+    pub summary: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub timezone: Timezone,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Summary: {}", self.summary.as_deref().unwrap_or(""))?;
+        writeln!(f, "Start: {} ({})", self.start, self.timezone)?;
+        writeln!(f, "End: {} ({})", self.end, self.timezone)?;
+
+        if let Some(location) = &self.location {
+            writeln!(f, "Location: {location}")?;
+        }
+
+        if let Some(description) = &self.description {
+            writeln!(f)?;
+            writeln!(f, "{description}")?;
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,99 @@
+use anyhow::{Result, bail};
+use chrono::Utc;
+use clap::{ArgGroup, Parser};
+use pimalaya_cli::printer::Printer;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    itip::{self, ItipSaveArg},
+};
+
+/// Answer the iTIP invitation of an event.
+///
+/// Sets the PARTSTAT of the attendee (`--attendee`, falling back to
+/// the `event.email` config) in the stored copy of the event, then
+/// generates the RFC 5546 METHOD:REPLY message to send back to the
+/// ORGANIZER by mail (iMIP). Pass `--recurrence-id` to only answer
+/// for one overridden occurrence.
+///
+/// The message is printed to stdout, ready to be piped to a mail
+/// client, or saved under the downloads directory with `--save`.
+///
+/// JSON output: `{"message": "..."}`, carrying either the message or
+/// the path it was saved at.
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("partstat").required(true)))]
+pub struct EventReplyCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Stable event identifier (iCal `UID`).
+    #[arg(value_name = "EVENT-ID")]
+    pub event_id: String,
+
+    /// Accept the invitation.
+    #[arg(long, group = "partstat")]
+    pub accept: bool,
+
+    /// Decline the invitation.
+    #[arg(long, group = "partstat")]
+    pub decline: bool,
+
+    /// Tentatively accept the invitation.
+    #[arg(long, group = "partstat")]
+    pub tentative: bool,
+
+    /// Address of the answering attendee. Defaults to the
+    /// `event.email` config.
+    #[arg(long, value_name = "ADDRESS")]
+    pub attendee: Option<String>,
+
+    /// Only answer for the occurrence overridden with this
+    /// RECURRENCE-ID value, e.g. `20260316T090000Z`.
+    #[arg(long, value_name = "RECURRENCE-ID")]
+    pub recurrence_id: Option<String>,
+
+    #[command(flatten)]
+    pub save: ItipSaveArg,
+}
+
+impl EventReplyCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+
+        let Some(attendee) = self.attendee.or_else(|| client.account.email.clone()) else {
+            bail!("Cannot find the attendee to reply as; pass --attendee");
+        };
+
+        let partstat = match (self.accept, self.decline) {
+            (true, _) => "ACCEPTED",
+            (_, true) => "DECLINED",
+            _ => "TENTATIVE",
+        };
+
+        let recurrence_id = self.recurrence_id.as_deref();
+        let item = client.find_item_by_uid(&calendar_id, &self.event_id)?;
+        let message = itip::reply(
+            &item.contents,
+            &attendee,
+            partstat,
+            recurrence_id,
+            Utc::now(),
+        )?;
+
+        let Some(contents) = itip::set_partstat(&item.contents, &attendee, partstat, recurrence_id)
+        else {
+            bail!(
+                "Cannot find attendee `{attendee}` in event `{}`",
+                self.event_id
+            );
+        };
+
+        let etag = item.etag.as_deref();
+        client.update_item(&calendar_id, &item.id, contents, etag)?;
+
+        self.save
+            .out(printer, &client.account, &self.event_id, "REPLY", message)
+    }
+}
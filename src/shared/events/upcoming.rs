@@ -0,0 +1,267 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use clap::Parser;
+use io_calendar::calcard::icalendar::{ICalendarComponentType, ICalendarProperty};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdsArg,
+    client::CalendarClient,
+    datetime::parse_duration,
+    recurrence::{self, Localized, component_text},
+    timezone::format_date_time,
+};
+
+/// Number of occurrences listed when neither `--count` nor `--within`
+/// is given.
+const DEFAULT_COUNT: usize = 10;
+
+/// How far ahead occurrences are looked up without `--within`.
+const DEFAULT_HORIZON_DAYS: i64 = 366;
+
+/// List the next occurrences of events.
+///
+/// Recurring events are expanded, and occurrences still in progress
+/// or starting later are listed chronologically, grouped by day under
+/// relative headings ("Today", "Tomorrow", then the weekday and date).
+/// Lists the next 10 occurrences within a year by default; pass
+/// `-n/--count` to change the number, and/or `--within` (e.g. `7d`,
+/// `12h`) to bound the lookup window instead. The output is plain
+/// text, suitable for shell prompts and login banners.
+///
+/// Repeat `-k`, pass `--all-calendars` and/or select several accounts
+/// with `--account` to merge several calendars; summaries are then
+/// prefixed by the account and/or calendar they come from.
+///
+/// JSON output: `{"days": [{"date", "heading", "events": [{"id",
+/// "recurrence-id", "account", "calendar", "summary", "start", "end",
+/// "all-day"}]}]}`.
+#[derive(Debug, Parser)]
+pub struct EventUpcomingCommand {
+    #[command(flatten)]
+    pub calendars: CalendarIdsArg,
+
+    /// Maximum number of occurrences to list.
+    #[arg(short = 'n', long, value_name = "N")]
+    pub count: Option<usize>,
+
+    /// Only list occurrences starting within this duration, e.g. `7d`
+    /// or `1w2d`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub within: Option<TimeDelta>,
+}
+
+impl EventUpcomingCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CalendarClient) -> Result<()> {
+        self.execute_many(printer, vec![client])
+    }
+
+    /// Lists the upcoming occurrences of every client, one per account
+    /// selected by `--account`, in the zone of the first one.
+    pub fn execute_many(
+        self,
+        printer: &mut impl Printer,
+        mut clients: Vec<CalendarClient>,
+    ) -> Result<()> {
+        let Some(timezone) = clients.first().map(|client| client.account.timezone()) else {
+            bail!("Cannot list upcoming events without any account");
+        };
+
+        let now = timezone.from_utc(Utc::now());
+        let until = now + self.within.unwrap_or(TimeDelta::days(DEFAULT_HORIZON_DAYS));
+        let count = match (self.count, self.within) {
+            (Some(count), _) => Some(count),
+            (None, Some(_)) => None,
+            (None, None) => Some(DEFAULT_COUNT),
+        };
+
+        let multi_account = clients.len() > 1;
+        let mut sources = Vec::new();
+
+        for client in &mut clients {
+            for calendar_id in self.calendars.resolve(client)? {
+                let items = client.list_all_items(&calendar_id, None)?;
+                sources.push((client.account_name.clone(), calendar_id, items));
+            }
+        }
+
+        let multi_calendar = clients.iter().any(|client| {
+            let calendars = sources
+                .iter()
+                .filter(|(name, ..)| *name == client.account_name);
+            calendars.count() > 1
+        });
+
+        let kind = ICalendarComponentType::VEvent;
+        let mut events = Vec::new();
+
+        for (account, calendar, items) in &sources {
+            let label = match (multi_account, multi_calendar) {
+                (true, true) => Some(format!("{account}/{calendar}")),
+                (true, false) => Some(account.clone()),
+                (false, true) => Some(calendar.clone()),
+                (false, false) => None,
+            };
+
+            for item in items {
+                let Some(ical) = item.as_ical() else {
+                    continue;
+                };
+
+                let occurrences =
+                    recurrence::occurrences_in(&ical, &kind, Some(now), Some(until), timezone);
+
+                for localized in occurrences {
+                    let Localized {
+                        occurrence,
+                        start,
+                        end,
+                    } = localized;
+                    let summary = component_text(occurrence.component, &ICalendarProperty::Summary)
+                        .unwrap_or_default();
+
+                    events.push(UpcomingEvent {
+                        id: occurrence.uid.unwrap_or_else(|| item.id.clone()),
+                        recurrence_id: occurrence.recurrence_id,
+                        account: account.clone(),
+                        calendar: calendar.clone(),
+                        summary,
+                        start: format_date_time(start, occurrence.all_day),
+                        end: format_date_time(end, occurrence.all_day),
+                        all_day: occurrence.all_day,
+                        label: label.clone(),
+                        span: (start, end),
+                    });
+                }
+            }
+        }
+
+        events.sort_by_key(|event| event.span);
+
+        if let Some(count) = count {
+            events.truncate(count);
+        }
+
+        printer.out(Upcoming {
+            days: group_by_day(events, now),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpcomingEvent {
+    pub id: String,
+    pub recurrence_id: Option<String>,
+    pub account: String,
+    pub calendar: String,
+    pub summary: String,
+    pub start: String,
+    pub end: String,
+    pub all_day: bool,
+    /// Source shown before the summary when several are merged.
+    #[serde(skip)]
+    label: Option<String>,
+    #[serde(skip)]
+    span: (NaiveDateTime, NaiveDateTime),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpcomingDay {
+    pub date: String,
+    pub heading: String,
+    pub events: Vec<UpcomingEvent>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Upcoming {
+    pub days: Vec<UpcomingDay>,
+}
+
+/// Groups chronologically sorted `events` by the day they start on,
+/// occurrences in progress counting for today.
+fn group_by_day(events: Vec<UpcomingEvent>, now: NaiveDateTime) -> Vec<UpcomingDay> {
+    let today = now.date();
+    let mut days: Vec<UpcomingDay> = Vec::new();
+
+    for event in events {
+        let date = event.span.0.max(now).date();
+        let key = date.format("%Y-%m-%d").to_string();
+
+        match days.last_mut() {
+            Some(day) if day.date == key => day.events.push(event),
+            _ => days.push(UpcomingDay {
+                date: key,
+                heading: heading(date, today),
+                events: vec![event],
+            }),
+        }
+    }
+
+    days
+}
+
+/// Relative heading of `date`: "Today", "Tomorrow", otherwise the
+/// weekday and date.
+fn heading(date: NaiveDate, today: NaiveDate) -> String {
+    if date == today {
+        return String::from("Today");
+    }
+
+    if today.succ_opt() == Some(date) {
+        return String::from("Tomorrow");
+    }
+
+    date.format("%A %d %B").to_string()
+}
+
+impl fmt::Display for Upcoming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, day) in self.days.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "{}", day.heading)?;
+
+            for event in &day.events {
+                let (start, end) = event.span;
+
+                let time = if event.all_day {
+                    String::from("all day")
+                } else if end.date() == start.date() {
+                    format!("{}-{}", start.format("%H:%M"), end.format("%H:%M"))
+                } else {
+                    format!("{}-{}", start.format("%H:%M"), end.format("%d/%m %H:%M"))
+                };
+
+                match &event.label {
+                    Some(label) => writeln!(f, "  {time:<11}  [{label}] {}", event.summary)?,
+                    None => writeln!(f, "  {time:<11}  {}", event.summary)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::heading;
+
+    #[test]
+    fn relative_headings() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 16).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+
+        assert_eq!(heading(date(16), today), "Today");
+        assert_eq!(heading(date(17), today), "Tomorrow");
+        assert_eq!(heading(date(18), today), "Wednesday 18 March");
+    }
+}
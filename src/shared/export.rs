@@ -0,0 +1,539 @@
+//! Alternative representations of iCalendar objects, for `calendar
+//! export`: jCal (RFC 7265), xCal (RFC 6321) and CSV.
+//!
+//! Like [`split_objects`](crate::shared::ical::split_objects), objects
+//! are read at the content-line level, so that properties calcard does
+//! not model are converted too. Value types follow the RFC 5545
+//! defaults of each property, overridden by the VALUE parameter.
+
+use anyhow::{Result, bail};
+use serde_json::{Map, Value as Json, json};
+
+use crate::shared::ical::{
+    content_lines, split_all_unquoted, split_unescaped, split_unquoted, unescape_text, unfold,
+};
+
+/// XML namespace of xCal.
+const XCAL_NS: &str = "urn:ietf:params:xml:ns:icalendar-2.0";
+
+/// RECUR rule parts holding integers.
+const RECUR_INTEGERS: [&str; 10] = [
+    "count",
+    "interval",
+    "bysecond",
+    "byminute",
+    "byhour",
+    "bymonthday",
+    "byyearday",
+    "byweekno",
+    "bymonth",
+    "bysetpos",
+];
+
+/// Component of the iCalendar tree, names in lowercase.
+#[derive(Clone, Debug, Default)]
+struct Component {
+    name: String,
+    properties: Vec<Property>,
+    components: Vec<Component>,
+}
+
+#[derive(Clone, Debug)]
+struct Property {
+    name: String,
+    /// Parameters but VALUE, keys in lowercase, values unquoted.
+    params: Vec<(String, String)>,
+    /// Value type, as named by the VALUE parameter.
+    kind: &'static str,
+    values: Vec<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Text(String),
+    Integer(i64),
+    Boolean(bool),
+    /// GEO latitude and longitude.
+    Geo(f64, f64),
+    /// Start, then end or duration.
+    Period(String, String),
+    /// Rule parts, keys in lowercase.
+    Recur(Vec<(String, Vec<String>)>),
+}
+
+/// Converts the VCALENDAR `contents` into jCal.
+pub fn jcal(contents: &[u8]) -> Result<Json> {
+    Ok(component_to_jcal(&parse(contents)?))
+}
+
+/// Converts the VCALENDAR `contents` into an xCal document.
+pub fn xcal(contents: &[u8]) -> Result<String> {
+    let calendar = parse(contents)?;
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str(&format!("<icalendar xmlns=\"{XCAL_NS}\">\n"));
+    component_to_xcal(&mut out, &calendar, 1);
+    out.push_str("</icalendar>\n");
+
+    Ok(out)
+}
+
+/// Renders one CSV record (RFC 4180), CRLF included: fields holding a
+/// comma, a double quote or a line break are quoted.
+pub fn csv_record<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        })
+        .collect();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Parses the first VCALENDAR of `contents` into a tree.
+fn parse(contents: &[u8]) -> Result<Component> {
+    let contents = String::from_utf8_lossy(contents);
+    let mut stack: Vec<Component> = Vec::new();
+
+    for line in content_lines(&contents) {
+        let line = unfold(&line);
+        let (head, value) = split_unquoted(&line, ':').unwrap_or((&line, ""));
+        let mut parts = split_all_unquoted(head, ';').into_iter();
+        let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+
+        match name.as_str() {
+            "" => continue,
+            "begin" => stack.push(Component {
+                name: value.trim().to_ascii_lowercase(),
+                ..Component::default()
+            }),
+            "end" => {
+                let Some(component) = stack.pop() else {
+                    bail!("Unexpected END:{value} line");
+                };
+
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => return Ok(component),
+                }
+            }
+            _ => {
+                let Some(component) = stack.last_mut() else {
+                    continue;
+                };
+
+                let params: Vec<(String, String)> = parts
+                    .filter_map(|param| param.split_once('='))
+                    .map(|(key, val)| (key.trim().to_ascii_lowercase(), val.replace('"', "")))
+                    .collect();
+
+                component.properties.push(property(name, params, value));
+            }
+        }
+    }
+
+    bail!("Cannot find any complete VCALENDAR to convert")
+}
+
+fn property(name: String, mut params: Vec<(String, String)>, value: &str) -> Property {
+    let explicit = params
+        .iter()
+        .position(|(key, _)| key == "value")
+        .map(|index| params.remove(index).1);
+
+    let mut kind = match explicit {
+        Some(kind) => explicit_type(&kind),
+        None => default_type(&name),
+    };
+
+    // DATE values without VALUE=DATE are common enough.
+    if kind == "date-time" && !value.contains('T') {
+        kind = "date";
+    }
+
+    let values = match kind {
+        "text" if matches!(name.as_str(), "categories" | "resources") => {
+            split_unescaped(value, ',')
+                .into_iter()
+                .map(|text| Value::Text(unescape_text(text)))
+                .collect()
+        }
+        "text" => vec![Value::Text(unescape_text(value))],
+        "date" | "date-time" => value
+            .split(',')
+            .map(|dt| Value::Text(date_time(dt.trim())))
+            .collect(),
+        "period" => value
+            .split(',')
+            .map(|period| match period.split_once('/') {
+                Some((start, end)) => Value::Period(date_time(start), date_time(end)),
+                None => Value::Text(period.to_owned()),
+            })
+            .collect(),
+        "integer" => vec![match value.trim().parse() {
+            Ok(n) => Value::Integer(n),
+            Err(_) => Value::Text(value.to_owned()),
+        }],
+        "float" => {
+            let geo = value
+                .split_once(';')
+                .and_then(|(lat, lon)| Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?)));
+
+            match geo {
+                Some((lat, lon)) => vec![Value::Geo(lat, lon)],
+                None => vec![Value::Text(value.to_owned())],
+            }
+        }
+        "boolean" => vec![Value::Boolean(value.trim().eq_ignore_ascii_case("TRUE"))],
+        "recur" => vec![Value::Recur(recur(value))],
+        "utc-offset" => vec![Value::Text(utc_offset(value.trim()))],
+        _ => vec![Value::Text(value.to_owned())],
+    };
+
+    Property {
+        name,
+        params,
+        kind,
+        values,
+    }
+}
+
+fn explicit_type(kind: &str) -> &'static str {
+    match kind.to_ascii_lowercase().as_str() {
+        "binary" => "binary",
+        "boolean" => "boolean",
+        "cal-address" => "cal-address",
+        "date" => "date",
+        "date-time" => "date-time",
+        "duration" => "duration",
+        "float" => "float",
+        "integer" => "integer",
+        "period" => "period",
+        "recur" => "recur",
+        "text" => "text",
+        "time" => "time",
+        "uri" => "uri",
+        "utc-offset" => "utc-offset",
+        _ => "unknown",
+    }
+}
+
+fn default_type(name: &str) -> &'static str {
+    match name {
+        "dtstart" | "dtend" | "due" | "recurrence-id" | "exdate" | "rdate" | "dtstamp"
+        | "created" | "last-modified" | "completed" => "date-time",
+        "duration" | "trigger" => "duration",
+        "rrule" | "exrule" => "recur",
+        "priority" | "sequence" | "percent-complete" | "repeat" => "integer",
+        "geo" => "float",
+        "attendee" | "organizer" => "cal-address",
+        "url" | "tzurl" | "attach" | "source" => "uri",
+        "tzoffsetfrom" | "tzoffsetto" => "utc-offset",
+        "freebusy" => "period",
+        name if name.starts_with("x-") => "unknown",
+        _ => "text",
+    }
+}
+
+/// Converts a DATE (`20260316`) or DATE-TIME (`20260316T100000Z`)
+/// value into its RFC 3339-like form (`2026-03-16`,
+/// `2026-03-16T10:00:00Z`). Other values are returned as is.
+fn date_time(value: &str) -> String {
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+    if date.len() != 8 || !digits(date) {
+        return value.to_owned();
+    }
+
+    let date = format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]);
+
+    let Some(time) = time else {
+        return date;
+    };
+
+    let (time, utc) = match time.strip_suffix('Z') {
+        Some(time) => (time, "Z"),
+        None => (time, ""),
+    };
+
+    if time.len() != 6 || !digits(time) {
+        return value.to_owned();
+    }
+
+    format!("{date}T{}:{}:{}{utc}", &time[..2], &time[2..4], &time[4..])
+}
+
+/// Converts a UTC-OFFSET value (`+0100`, `-013015`) into its
+/// colon-separated form (`+01:00`, `-01:30:15`).
+fn utc_offset(value: &str) -> String {
+    let (sign, digits) = value.split_at(value.len().min(1));
+
+    match digits.len() {
+        4 => format!("{sign}{}:{}", &digits[..2], &digits[2..]),
+        6 => format!("{sign}{}:{}:{}", &digits[..2], &digits[2..4], &digits[4..]),
+        _ => value.to_owned(),
+    }
+}
+
+/// Splits a RECUR value into its rule parts.
+fn recur(value: &str) -> Vec<(String, Vec<String>)> {
+    value
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, values)| {
+            let key = key.trim().to_ascii_lowercase();
+            let values = values
+                .split(',')
+                .map(|value| match key.as_str() {
+                    "until" => date_time(value.trim()),
+                    _ => value.trim().to_owned(),
+                })
+                .collect();
+            (key, values)
+        })
+        .collect()
+}
+
+/// Parameters holding a list of values.
+fn multi_valued(param: &str) -> bool {
+    matches!(param, "member" | "delegated-to" | "delegated-from")
+}
+
+fn component_to_jcal(component: &Component) -> Json {
+    let properties: Vec<Json> = component.properties.iter().map(property_to_jcal).collect();
+    let components: Vec<Json> = component.components.iter().map(component_to_jcal).collect();
+    json!([component.name, properties, components])
+}
+
+fn property_to_jcal(property: &Property) -> Json {
+    let mut params = Map::new();
+
+    for (key, val) in &property.params {
+        let val = if multi_valued(key) && val.contains(',') {
+            json!(val.split(',').collect::<Vec<_>>())
+        } else {
+            json!(val)
+        };
+
+        params.insert(key.clone(), val);
+    }
+
+    let mut out = vec![
+        json!(property.name),
+        Json::Object(params),
+        json!(property.kind),
+    ];
+
+    out.extend(property.values.iter().map(|value| match value {
+        Value::Text(text) => json!(text),
+        Value::Integer(n) => json!(n),
+        Value::Boolean(b) => json!(b),
+        Value::Geo(lat, lon) => json!([lat, lon]),
+        Value::Period(start, end) => json!(format!("{start}/{end}")),
+        Value::Recur(parts) => {
+            let mut rule = Map::new();
+
+            for (key, values) in parts {
+                let values: Vec<Json> = values
+                    .iter()
+                    .map(|value| match value.parse::<i64>() {
+                        Ok(n) if RECUR_INTEGERS.contains(&key.as_str()) => json!(n),
+                        _ => json!(value),
+                    })
+                    .collect();
+
+                let value = match <[Json; 1]>::try_from(values) {
+                    Ok([value]) => value,
+                    Err(values) => Json::Array(values),
+                };
+
+                rule.insert(key.clone(), value);
+            }
+
+            Json::Object(rule)
+        }
+    }));
+
+    Json::Array(out)
+}
+
+fn component_to_xcal(out: &mut String, component: &Component, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let name = &component.name;
+
+    out.push_str(&format!("{indent}<{name}>\n"));
+
+    if !component.properties.is_empty() {
+        out.push_str(&format!("{indent}  <properties>\n"));
+        for property in &component.properties {
+            property_to_xcal(out, property, depth + 2);
+        }
+        out.push_str(&format!("{indent}  </properties>\n"));
+    }
+
+    if !component.components.is_empty() {
+        out.push_str(&format!("{indent}  <components>\n"));
+        for child in &component.components {
+            component_to_xcal(out, child, depth + 2);
+        }
+        out.push_str(&format!("{indent}  </components>\n"));
+    }
+
+    out.push_str(&format!("{indent}</{name}>\n"));
+}
+
+fn property_to_xcal(out: &mut String, property: &Property, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let name = &property.name;
+    let kind = property.kind;
+
+    out.push_str(&format!("{indent}<{name}>"));
+
+    if !property.params.is_empty() {
+        out.push_str("<parameters>");
+
+        for (key, val) in &property.params {
+            let kind = match key.as_str() {
+                "member" | "delegated-to" | "delegated-from" | "sent-by" => "cal-address",
+                "altrep" | "dir" => "uri",
+                _ => "text",
+            };
+
+            let vals: Vec<&str> = if multi_valued(key) {
+                val.split(',').collect()
+            } else {
+                vec![val]
+            };
+
+            out.push_str(&format!("<{key}>"));
+            for val in vals {
+                out.push_str(&format!("<{kind}>{}</{kind}>", escape_xml(val)));
+            }
+            out.push_str(&format!("</{key}>"));
+        }
+
+        out.push_str("</parameters>");
+    }
+
+    for value in &property.values {
+        match value {
+            Value::Text(text) => {
+                out.push_str(&format!("<{kind}>{}</{kind}>", escape_xml(text)));
+            }
+            Value::Integer(n) => out.push_str(&format!("<{kind}>{n}</{kind}>")),
+            Value::Boolean(b) => out.push_str(&format!("<{kind}>{b}</{kind}>")),
+            Value::Geo(lat, lon) => out.push_str(&format!(
+                "<latitude>{lat}</latitude><longitude>{lon}</longitude>"
+            )),
+            Value::Period(start, end) => {
+                let end_kind = if end.trim_start_matches(['+', '-']).starts_with('P') {
+                    "duration"
+                } else {
+                    "end"
+                };
+
+                out.push_str(&format!(
+                    "<period><start>{}</start><{end_kind}>{}</{end_kind}></period>",
+                    escape_xml(start),
+                    escape_xml(end)
+                ));
+            }
+            Value::Recur(parts) => {
+                out.push_str("<recur>");
+                for (key, values) in parts {
+                    for value in values {
+                        out.push_str(&format!("<{key}>{}</{key}>", escape_xml(value)));
+                    }
+                }
+                out.push_str("</recur>");
+            }
+        }
+    }
+
+    out.push_str(&format!("</{name}>\n"));
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{csv_record, jcal, xcal};
+
+    const ICAL: &str = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "BEGIN:VEVENT\r\n",
+        "UID:a\r\n",
+        "DTSTART;TZID=Europe/Paris:20260316T100000\r\n",
+        "DTEND;VALUE=DATE:20260317\r\n",
+        "SUMMARY:Lunch\\, then <coffee>\r\n",
+        "CATEGORIES:work,food\r\n",
+        "RRULE:FREQ=WEEKLY;COUNT=3;BYDAY=MO,TU\r\n",
+        "GEO:48.85;2.35\r\n",
+        "END:VEVENT\r\n",
+        "END:VCALENDAR\r\n",
+    );
+
+    #[test]
+    fn to_jcal() {
+        assert_eq!(
+            jcal(ICAL.as_bytes()).unwrap(),
+            json!([
+                "vcalendar",
+                [["version", {}, "text", "2.0"]],
+                [[
+                    "vevent",
+                    [
+                        ["uid", {}, "text", "a"],
+                        ["dtstart", {"tzid": "Europe/Paris"}, "date-time", "2026-03-16T10:00:00"],
+                        ["dtend", {}, "date", "2026-03-17"],
+                        ["summary", {}, "text", "Lunch, then <coffee>"],
+                        ["categories", {}, "text", "work", "food"],
+                        ["rrule", {}, "recur", {"freq": "WEEKLY", "count": 3, "byday": ["MO", "TU"]}],
+                        ["geo", {}, "float", [48.85, 2.35]],
+                    ],
+                    [],
+                ]],
+            ])
+        );
+    }
+
+    #[test]
+    fn to_xcal() {
+        let xml = xcal(ICAL.as_bytes()).unwrap();
+
+        assert!(xml.contains(concat!(
+            "<dtstart><parameters><tzid><text>Europe/Paris</text></tzid></parameters>",
+            "<date-time>2026-03-16T10:00:00</date-time></dtstart>"
+        )));
+        assert!(xml.contains("<summary><text>Lunch, then &lt;coffee&gt;</text></summary>"));
+        assert!(xml.contains(
+            "<rrule><recur><freq>WEEKLY</freq><count>3</count><byday>MO</byday><byday>TU</byday></recur></rrule>"
+        ));
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(
+            csv_record(["a", "b,c", "say \"hi\"", "x\ny"]),
+            "a,\"b,c\",\"say \"\"hi\"\"\",\"x\ny\"\r\n"
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use std::{
+    collections::HashMap,
     fs,
     io::{Read, stdin},
     path::PathBuf,
 };
 
 use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
 use clap::Parser;
 
 /// Positional iCalendar source shared by the `event`/`item` create and
-/// update commands.
+/// update commands and `calendar import`.
 #[derive(Debug, Parser)]
 pub struct IcalArg {
     /// A path to an iCalendar file, raw iCalendar contents, or `-` for
@@ -49,3 +51,927 @@ impl IcalArg {
         )
     }
 }
+
+/// PRODID of the iCalendar objects generated by calendula.
+pub const PRODID: &str = concat!("-//Pimalaya//Calendula ", env!("CARGO_PKG_VERSION"), "//EN");
+
+/// Minimal iCalendar content-line writer used to generate objects
+/// from command flags.
+///
+/// Values given to [`IcalWriter::text`] are escaped; every line is
+/// folded at 75 octets and terminated by CRLF, as RFC 5545 requires.
+#[derive(Debug, Default)]
+pub struct IcalWriter {
+    buf: String,
+}
+
+impl IcalWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin(&mut self, component: &str) -> &mut Self {
+        self.line(&format!("BEGIN:{component}"))
+    }
+
+    pub fn end(&mut self, component: &str) -> &mut Self {
+        self.line(&format!("END:{component}"))
+    }
+
+    /// Writes a property whose value is already in its wire format.
+    /// Parameter values containing `:`, `;` or `,` are quoted.
+    pub fn property(&mut self, name: &str, params: &[(&str, &str)], value: &str) -> &mut Self {
+        let mut line = String::from(name);
+
+        for (key, val) in params {
+            line.push(';');
+            line.push_str(key);
+            line.push('=');
+
+            if val.contains([':', ';', ',']) {
+                line.push('"');
+                line.push_str(&val.replace('"', ""));
+                line.push('"');
+            } else {
+                line.push_str(val);
+            }
+        }
+
+        line.push(':');
+        line.push_str(value);
+        self.line(&line)
+    }
+
+    /// Writes a TEXT property, escaping its value.
+    pub fn text(&mut self, name: &str, value: &str) -> &mut Self {
+        self.property(name, &[], &escape_text(value))
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf.into_bytes()
+    }
+
+    fn line(&mut self, line: &str) -> &mut Self {
+        let mut width = 0;
+
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                self.buf.push_str("\r\n ");
+                width = 1;
+            }
+
+            self.buf.push(c);
+            width += c.len_utf8();
+        }
+
+        self.buf.push_str("\r\n");
+        self
+    }
+}
+
+/// Generates a UID unique enough for the calendars of one user: the
+/// creation instant plus the process id.
+pub fn generate_uid(now: DateTime<Utc>) -> String {
+    let nanos = now.timestamp_nanos_opt().unwrap_or_default();
+    format!("{nanos:x}-{:x}@calendula", std::process::id())
+}
+
+/// Content hash (64-bit FNV-1a), stable across runs and builds.
+pub fn content_hash(contents: &[u8]) -> String {
+    let hash = contents
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    format!("{hash:016x}")
+}
+
+/// Escapes a TEXT value: backslashes, `;`, `,` and newlines.
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Unescapes a TEXT value, the reverse of [`escape_text`].
+pub fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+
+    out
+}
+
+/// Sets properties of every top-level `component` block of `contents`
+/// (nested components such as VALARM are left alone): existing lines
+/// named after a `props` entry are dropped, then the entries holding a
+/// value are appended before `END:<component>`. Keys may carry
+/// parameters (`DTSTART;VALUE=DATE`), ignored when matching existing
+/// lines. Values are written as is, so TEXT values must be escaped
+/// beforehand. Returns [`None`] when `contents` holds no `component`
+/// block.
+pub fn set_properties(
+    contents: &[u8],
+    component: &str,
+    props: &[(&str, Option<&str>)],
+) -> Option<Vec<u8>> {
+    let contents = String::from_utf8_lossy(contents);
+
+    let lines = content_lines(&contents);
+
+    let mut out = String::with_capacity(contents.len());
+    let mut depth: Option<usize> = None;
+    let mut found = false;
+
+    let replaced = |name: &str| {
+        props.iter().any(|(prop, _)| {
+            let prop = prop.split(';').next().unwrap_or(prop);
+            prop.eq_ignore_ascii_case(name)
+        })
+    };
+
+    for line in lines {
+        let (name, value) = line
+            .trim_end()
+            .split_once(':')
+            .map(|(name, value)| (name.split(';').next().unwrap_or(name), value))
+            .unwrap_or_default();
+        let is = |expected: &str| name.eq_ignore_ascii_case(expected);
+        let matches = |value: &str| value.eq_ignore_ascii_case(component);
+
+        match depth {
+            None if is("BEGIN") && matches(value) => depth = Some(0),
+            Some(0) if is("END") && matches(value) => {
+                let mut writer = IcalWriter::new();
+                for (name, value) in props {
+                    if let Some(value) = value {
+                        writer.property(name, &[], value);
+                    }
+                }
+                out.push_str(&String::from_utf8_lossy(&writer.finish()));
+                depth = None;
+                found = true;
+            }
+            Some(0) if replaced(name) => continue,
+            Some(n) if is("BEGIN") => depth = Some(n + 1),
+            Some(n) if is("END") => depth = Some(n.saturating_sub(1)),
+            _ => (),
+        }
+
+        out.push_str(&line);
+    }
+
+    found.then(|| out.into_bytes())
+}
+
+/// Groups the physical lines of `contents` into content lines, their
+/// folded continuations and line endings kept.
+pub fn content_lines(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in contents.split_inclusive('\n') {
+        match lines.last_mut() {
+            Some(last) if line.starts_with([' ', '\t']) => last.push_str(line),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    lines
+}
+
+/// Joins the folded continuations of the content line `line`, its
+/// line ending dropped.
+pub fn unfold(line: &str) -> String {
+    line.lines()
+        .enumerate()
+        .map(|(i, part)| match i {
+            0 => part,
+            _ => part.strip_prefix([' ', '\t']).unwrap_or(part),
+        })
+        .collect()
+}
+
+/// iCalendar object holding the components of one UID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcalObject {
+    pub uid: String,
+    pub contents: Vec<u8>,
+}
+
+/// Top-level component of a VCALENDAR, as content lines.
+#[derive(Debug, Default)]
+struct Block {
+    lines: Vec<String>,
+    uid: Option<String>,
+    /// TZID of a VTIMEZONE block.
+    tzid: Option<String>,
+    /// TZID parameters referenced by the block.
+    tzids: Vec<String>,
+}
+
+/// Splits `contents`, made of one or several VCALENDARs, into one
+/// object per UID: the components sharing it (a recurring master and
+/// its RECURRENCE-ID overrides), preceded by the properties of the
+/// first VCALENDAR (except METHOD) and the VTIMEZONEs their TZID
+/// parameters refer to. Content lines are copied as is, so unknown
+/// properties survive. Components without UID get one derived from
+/// their contents, the same on every read. Objects keep the order of
+/// their first component.
+pub fn split_objects(contents: &[u8]) -> Vec<IcalObject> {
+    let (header, blocks) = parse_blocks(contents);
+
+    let prelude = prelude(&header);
+
+    let mut timezones: HashMap<String, Block> = HashMap::new();
+    let mut objects: Vec<(String, Vec<Block>)> = Vec::new();
+
+    for mut block in blocks {
+        if let Some(tzid) = block.timezone() {
+            timezones.entry(tzid.to_owned()).or_insert(block);
+            continue;
+        }
+
+        let uid = match block.uid.clone() {
+            Some(uid) => uid,
+            None => {
+                let uid = fallback_uid(&block, &objects);
+                block.lines.insert(1, format!("UID:{uid}"));
+                uid
+            }
+        };
+
+        match objects.iter_mut().find(|(id, _)| *id == uid) {
+            Some((_, blocks)) => blocks.push(block),
+            None => objects.push((uid, vec![block])),
+        }
+    }
+
+    objects
+        .into_iter()
+        .map(|(uid, blocks)| {
+            let mut tzids: Vec<&str> = Vec::new();
+            for tzid in blocks.iter().flat_map(|block| &block.tzids) {
+                if !tzids.contains(&tzid.as_str()) {
+                    tzids.push(tzid);
+                }
+            }
+
+            let timezones = tzids.into_iter().filter_map(|tzid| timezones.get(tzid));
+            let lines = prelude
+                .iter()
+                .chain(timezones.chain(&blocks).flat_map(|block| &block.lines))
+                .map(String::as_str)
+                .chain(["END:VCALENDAR"]);
+
+            IcalObject {
+                uid,
+                contents: join_lines(lines),
+            }
+        })
+        .collect()
+}
+
+/// Derives the UID of a `block` without one from the hash of its
+/// content lines, suffixed when identical components were already
+/// met among `objects`.
+fn fallback_uid(block: &Block, objects: &[(String, Vec<Block>)]) -> String {
+    let hash = content_hash(block.lines.join("\r\n").as_bytes());
+    let uid = format!("{hash}@calendula");
+
+    let mut n = 1;
+    let mut candidate = uid.clone();
+    while objects.iter().any(|(id, _)| *id == candidate) {
+        n += 1;
+        candidate = format!("{uid}-{n}");
+    }
+
+    candidate
+}
+
+/// Merges the VCALENDARs of `objects` into a single one holding all
+/// their top-level components, VTIMEZONEs deduplicated by TZID (the
+/// first one wins), after the calendar `properties` (see
+/// [`calendar_properties`]). Content lines are copied as is.
+pub fn merge_objects<'a>(
+    properties: &[String],
+    objects: impl IntoIterator<Item = &'a [u8]>,
+) -> Vec<u8> {
+    let mut timezones: Vec<Block> = Vec::new();
+    let mut components: Vec<Block> = Vec::new();
+
+    for contents in objects {
+        for block in parse_blocks(contents).1 {
+            match block.timezone() {
+                Some(tzid) if timezones.iter().any(|tz| tz.timezone() == Some(tzid)) => (),
+                Some(_) => timezones.push(block),
+                None => components.push(block),
+            }
+        }
+    }
+
+    let prelude = prelude(properties);
+
+    let lines = prelude
+        .iter()
+        .chain(timezones.iter().chain(&components).flat_map(|b| &b.lines))
+        .map(String::as_str)
+        .chain(["END:VCALENDAR"]);
+
+    join_lines(lines)
+}
+
+/// Returns the properties of the first VCALENDAR of `contents`
+/// (except METHOD), as content lines kept folded.
+pub fn calendar_properties(contents: &[u8]) -> Vec<String> {
+    parse_blocks(contents).0
+}
+
+/// Opens a VCALENDAR with the calendar `properties`, adding VERSION
+/// and PRODID when missing.
+fn prelude(properties: &[String]) -> Vec<String> {
+    let has = |name: &str| {
+        properties.iter().any(|line| {
+            let (head, _) = split_unquoted(line, ':').unwrap_or((line, ""));
+            head.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .eq_ignore_ascii_case(name)
+        })
+    };
+
+    let mut prelude = vec![String::from("BEGIN:VCALENDAR")];
+    if !has("VERSION") {
+        prelude.push(String::from("VERSION:2.0"));
+    }
+    if !has("PRODID") {
+        prelude.push(format!("PRODID:{PRODID}"));
+    }
+    prelude.extend(properties.iter().cloned());
+    prelude
+}
+
+impl Block {
+    /// TZID of a VTIMEZONE block.
+    fn timezone(&self) -> Option<&str> {
+        match &self.uid {
+            Some(_) => None,
+            None => self.tzid.as_deref(),
+        }
+    }
+}
+
+/// Parses the VCALENDARs of `contents` into the properties of the
+/// first one (except METHOD) and the top-level components of all,
+/// CRLF-terminated content lines kept folded.
+fn parse_blocks(contents: &[u8]) -> (Vec<String>, Vec<Block>) {
+    let contents = String::from_utf8_lossy(contents);
+
+    let lines = content_lines(&contents);
+
+    let mut header: Option<Vec<String>> = None;
+    let mut properties = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut block: Option<Block> = None;
+    let mut depth = 0usize;
+
+    for line in lines {
+        let line = line
+            .trim_end_matches(['\r', '\n'])
+            .replace("\r\n", "\n")
+            .replace('\n', "\r\n");
+        let unfolded = unfold(&line);
+        let (head, value) = split_unquoted(&unfolded, ':').unwrap_or((&unfolded, ""));
+        let mut params = head.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        let is = |expected: &str| name.eq_ignore_ascii_case(expected);
+
+        if let Some(current) = block.as_mut() {
+            if depth == 2 && is("UID") {
+                current.uid = Some(value.trim().to_owned());
+            }
+
+            if depth == 2 && is("TZID") {
+                current.tzid = Some(value.trim().to_owned());
+            }
+
+            for param in params {
+                if let Some((key, tzid)) = param.split_once('=')
+                    && key.trim().eq_ignore_ascii_case("TZID")
+                {
+                    current.tzids.push(tzid.trim_matches('"').to_owned());
+                }
+            }
+
+            current.lines.push(line);
+
+            if is("BEGIN") {
+                depth += 1;
+            } else if is("END") {
+                depth -= 1;
+            }
+
+            if depth == 1 {
+                blocks.extend(block.take());
+            }
+
+            continue;
+        }
+
+        match depth {
+            0 if is("BEGIN") && value.eq_ignore_ascii_case("VCALENDAR") => depth = 1,
+            1 if is("END") => {
+                depth = 0;
+                header.get_or_insert_with(|| properties.clone());
+                properties.clear();
+            }
+            1 if is("BEGIN") => {
+                depth = 2;
+                block = Some(Block {
+                    lines: vec![line],
+                    ..Block::default()
+                });
+            }
+            1 if !is("METHOD") => properties.push(line),
+            _ => (),
+        }
+    }
+
+    (header.unwrap_or(properties), blocks)
+}
+
+/// Joins content lines, each terminated by CRLF.
+fn join_lines<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+    let mut contents = String::new();
+
+    for line in lines {
+        contents.push_str(line);
+        contents.push_str("\r\n");
+    }
+
+    contents.into_bytes()
+}
+
+/// Unfolded content line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentLine {
+    pub name: String,
+    /// Parameters, keys in uppercase, values unquoted.
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl ContentLine {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (head, value) = split_unquoted(line, ':')?;
+        let mut parts = split_all_unquoted(head, ';').into_iter();
+        let name = parts.next()?.trim().to_ascii_uppercase();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, val)| (key.trim().to_ascii_uppercase(), val.replace('"', "")))
+            .collect();
+
+        Some(Self {
+            name,
+            params,
+            value: value.to_owned(),
+        })
+    }
+
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name
+    }
+
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// Sets the parameter `key` in place (appended when missing), or
+    /// removes it when `value` is [`None`].
+    pub fn set_param(&mut self, key: &str, value: Option<&str>) {
+        let Some(value) = value else {
+            self.params.retain(|(k, _)| k != key);
+            return;
+        };
+
+        match self.params.iter_mut().find(|(k, _)| k == key) {
+            Some((_, val)) => *val = value.to_owned(),
+            None => self.params.push((key.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Parses the value of a DATE or DATE-TIME property, returning
+    /// whether it is a DATE. The TZID parameter and the UTC designator
+    /// are left to the caller.
+    pub fn date_time(&self) -> Result<(NaiveDateTime, bool)> {
+        let value = self.value.trim().trim_end_matches(['z', 'Z']);
+
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+            return Ok((date.and_time(Default::default()), true));
+        }
+
+        match NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+            Ok(dt) => Ok((dt, false)),
+            Err(_) => bail!("Invalid {} `{}`", self.name, self.value),
+        }
+    }
+
+    pub fn write(&self, writer: &mut IcalWriter) {
+        let params: Vec<(&str, &str)> = self
+            .params
+            .iter()
+            .map(|(key, val)| (key.as_str(), val.as_str()))
+            .collect();
+
+        writer.property(&self.name, &params, &self.value);
+    }
+}
+
+/// Splits `s` at its first `sep` outside double quotes.
+pub fn split_unquoted(s: &str, sep: char) -> Option<(&str, &str)> {
+    let mut quoted = false;
+
+    let index = s.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+
+        (!quoted && c == sep).then_some(i)
+    })?;
+
+    Some((&s[..index], &s[index + sep.len_utf8()..]))
+}
+
+/// Splits `s` at every `sep` outside double quotes.
+pub fn split_all_unquoted(mut s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+
+    while let Some((part, rest)) = split_unquoted(s, sep) {
+        parts.push(part);
+        s = rest;
+    }
+
+    parts.push(s);
+    parts
+}
+
+/// Unfolds and parses the content lines of `contents`.
+pub fn parse_lines(contents: &[u8]) -> Vec<ContentLine> {
+    let contents = String::from_utf8_lossy(contents);
+
+    content_lines(&contents)
+        .iter()
+        .filter_map(|line| ContentLine::parse(&unfold(line)))
+        .collect()
+}
+
+/// VEVENT of an iCalendar object, as content lines.
+#[derive(Debug, Default)]
+pub struct VEvent {
+    /// Own properties, sub-components excluded.
+    pub props: Vec<ContentLine>,
+    /// Own properties of each VALARM.
+    pub alarms: Vec<Vec<ContentLine>>,
+}
+
+/// First VEVENT of `lines` without RECURRENCE-ID, or first VEVENT:
+/// the master of a recurring event.
+pub fn master_event(lines: Vec<ContentLine>) -> Option<VEvent> {
+    let mut events = events(lines);
+
+    let master = events
+        .iter()
+        .position(|event| !event.props.iter().any(|line| line.is("RECURRENCE-ID")))
+        .unwrap_or_default();
+
+    (master < events.len()).then(|| events.swap_remove(master))
+}
+
+/// Every VEVENT of `lines`, in order.
+pub fn events(lines: Vec<ContentLine>) -> Vec<VEvent> {
+    let mut events: Vec<VEvent> = Vec::new();
+    let mut current: Option<VEvent> = None;
+    let mut alarm: Option<Vec<ContentLine>> = None;
+    let mut depth = 0usize;
+
+    for line in lines {
+        if line.is("BEGIN") {
+            depth += 1;
+            match depth {
+                2 if line.value.eq_ignore_ascii_case("VEVENT") => {
+                    current = Some(VEvent::default());
+                }
+                3 if current.is_some() && line.value.eq_ignore_ascii_case("VALARM") => {
+                    alarm = Some(Vec::new());
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        if line.is("END") {
+            match depth {
+                2 => events.extend(current.take()),
+                3 => {
+                    if let (Some(event), Some(alarm)) = (current.as_mut(), alarm.take()) {
+                        event.alarms.push(alarm);
+                    }
+                }
+                _ => (),
+            }
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+
+        match (depth, current.as_mut(), alarm.as_mut()) {
+            (2, Some(event), _) => event.props.push(line),
+            (3, _, Some(alarm)) => alarm.push(line),
+            _ => (),
+        }
+    }
+
+    events
+}
+
+/// Splits the TEXT list `s` at every `sep` not escaped by a backslash.
+pub fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == sep => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Formats a duration as an iCalendar DURATION value (`PT1H30M`,
+/// `-P1D`).
+pub fn format_duration(delta: TimeDelta) -> String {
+    let sign = if delta < TimeDelta::zero() { "-" } else { "" };
+    let mut secs = delta.num_seconds().abs();
+
+    let days = secs / 86_400;
+    secs %= 86_400;
+    let (hours, minutes, seconds) = (secs / 3_600, secs % 3_600 / 60, secs % 60);
+
+    let mut out = format!("{sign}P");
+
+    if days > 0 {
+        out.push_str(&format!("{days}D"));
+    }
+
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        out.push('T');
+
+        if hours > 0 {
+            out.push_str(&format!("{hours}H"));
+        }
+
+        if minutes > 0 {
+            out.push_str(&format!("{minutes}M"));
+        }
+
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            out.push_str(&format!("{seconds}S"));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, Utc};
+
+    use super::{
+        IcalWriter, content_lines, format_duration, merge_objects, set_properties, split_objects,
+        unfold,
+    };
+
+    #[test]
+    fn escape_and_fold() {
+        let mut writer = IcalWriter::new();
+        writer.text("SUMMARY", "Lunch; then coffee, maybe\nor not");
+        writer.text("DESCRIPTION", &"x".repeat(80));
+        writer.property(
+            "ATTENDEE",
+            &[("CN", "Doe, Jane")],
+            "mailto:jane@example.com",
+        );
+
+        let lines = String::from_utf8(writer.finish()).unwrap();
+        let mut lines = lines.split("\r\n");
+
+        assert_eq!(
+            lines.next(),
+            Some("SUMMARY:Lunch\\; then coffee\\, maybe\\nor not")
+        );
+        assert_eq!(lines.next().map(str::len), Some(75));
+        assert_eq!(lines.next(), Some(&*format!(" {}", "x".repeat(17))));
+        assert_eq!(
+            lines.next(),
+            Some("ATTENDEE;CN=\"Doe, Jane\":mailto:jane@example.com")
+        );
+    }
+
+    #[test]
+    fn unfold_content_lines() {
+        let contents = "SUMMARY:Long\r\n  title\r\nDESCRIPTION:a\n\tb\nUID:1";
+        let lines: Vec<String> = content_lines(contents).iter().map(|l| unfold(l)).collect();
+        assert_eq!(lines, ["SUMMARY:Long title", "DESCRIPTION:ab", "UID:1"]);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(TimeDelta::minutes(-15)), "-PT15M");
+        assert_eq!(format_duration(TimeDelta::minutes(90)), "PT1H30M");
+        assert_eq!(format_duration(TimeDelta::days(1)), "P1D");
+        assert_eq!(format_duration(TimeDelta::hours(26)), "P1DT2H");
+        assert_eq!(format_duration(TimeDelta::zero()), "PT0S");
+    }
+
+    #[test]
+    fn set_top_level_properties() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VTODO\r\n",
+            "UID:abc\r\n",
+            "STATUS:NEEDS-ACTION\r\n",
+            "COMPLETED:2026\r\n",
+            " 0101T000000Z\r\n",
+            "BEGIN:VALARM\r\n",
+            "STATUS:KEPT\r\n",
+            "END:VALARM\r\n",
+            "END:VTODO\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let props = [("STATUS", Some("COMPLETED")), ("COMPLETED", None)];
+        let ical = set_properties(ical.as_bytes(), "VTODO", &props).unwrap();
+
+        assert_eq!(
+            String::from_utf8(ical).unwrap(),
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "BEGIN:VTODO\r\n",
+                "UID:abc\r\n",
+                "BEGIN:VALARM\r\n",
+                "STATUS:KEPT\r\n",
+                "END:VALARM\r\n",
+                "STATUS:COMPLETED\r\n",
+                "END:VTODO\r\n",
+                "END:VCALENDAR\r\n",
+            )
+        );
+
+        assert!(set_properties(b"BEGIN:VEVENT\r\nEND:VEVENT\r\n", "VTODO", &props).is_none());
+    }
+
+    #[test]
+    fn split_by_uid() {
+        let ical = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Test//EN\r\n",
+            "METHOD:PUBLISH\r\n",
+            "BEGIN:VTIMEZONE\r\n",
+            "TZID:Europe/Paris\r\n",
+            "END:VTIMEZONE\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:a\r\n",
+            "DTSTART;TZID=Europe/Paris:20260316T100000\r\n",
+            "RRULE:FREQ=DAILY\r\n",
+            "BEGIN:VALARM\r\n",
+            "UID:alarm\r\n",
+            "END:VALARM\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:b\r\n",
+            "SUMMARY:Long\r\n",
+            " er\r\n",
+            "DTSTART:20260316T100000Z\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:a\r\n",
+            "RECURRENCE-ID;TZID=Europe/Paris:20260317T100000\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+
+        let objects = split_objects(ical.as_bytes());
+        let uids: Vec<&str> = objects.iter().map(|o| o.uid.as_str()).collect();
+        assert_eq!(uids, ["a", "b"]);
+
+        assert_eq!(
+            String::from_utf8(objects[0].contents.clone()).unwrap(),
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Test//EN\r\n",
+                "BEGIN:VTIMEZONE\r\n",
+                "TZID:Europe/Paris\r\n",
+                "END:VTIMEZONE\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:a\r\n",
+                "DTSTART;TZID=Europe/Paris:20260316T100000\r\n",
+                "RRULE:FREQ=DAILY\r\n",
+                "BEGIN:VALARM\r\n",
+                "UID:alarm\r\n",
+                "END:VALARM\r\n",
+                "END:VEVENT\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:a\r\n",
+                "RECURRENCE-ID;TZID=Europe/Paris:20260317T100000\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            )
+        );
+
+        assert_eq!(
+            String::from_utf8(objects[1].contents.clone()).unwrap(),
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Test//EN\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:b\r\n",
+                "SUMMARY:Long\r\n",
+                " er\r\n",
+                "DTSTART:20260316T100000Z\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn merge_dedup_timezones() {
+        let item = |uid: &str| {
+            format!(
+                concat!(
+                    "BEGIN:VCALENDAR\r\n",
+                    "VERSION:2.0\r\n",
+                    "BEGIN:VTIMEZONE\r\n",
+                    "TZID:Europe/Paris\r\n",
+                    "END:VTIMEZONE\r\n",
+                    "BEGIN:VEVENT\r\n",
+                    "UID:{}\r\n",
+                    "END:VEVENT\r\n",
+                    "END:VCALENDAR\r\n",
+                ),
+                uid
+            )
+        };
+
+        let (a, b) = (item("a"), item("b"));
+        let merged = merge_objects(&[], [a.as_bytes(), b.as_bytes()]);
+        let merged = String::from_utf8(merged).unwrap();
+
+        assert_eq!(merged.matches("BEGIN:VTIMEZONE").count(), 1);
+        assert!(merged.ends_with(concat!(
+            "END:VTIMEZONE\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:a\r\n",
+            "END:VEVENT\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:b\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        )));
+    }
+}
@@ -5,8 +5,8 @@ use pimalaya_cli::printer::Printer;
 use crate::shared::{
     client::CalendarClient,
     items::{
-        create::ItemCreateCommand, delete::ItemDeleteCommand, list::ItemListCommand,
-        read::ItemReadCommand, update::ItemUpdateCommand,
+        create::ItemCreateCommand, delete::ItemDeleteCommand, edit::ItemEditCommand,
+        list::ItemListCommand, read::ItemReadCommand, update::ItemUpdateCommand,
     },
 };
 
@@ -18,6 +18,7 @@ pub enum ItemCommand {
     Read(ItemReadCommand),
     Create(ItemCreateCommand),
     Update(ItemUpdateCommand),
+    Edit(ItemEditCommand),
     Delete(ItemDeleteCommand),
 }
 
@@ -28,6 +29,7 @@ impl ItemCommand {
             Self::Read(cmd) => cmd.execute(printer, client),
             Self::Create(cmd) => cmd.execute(printer, client),
             Self::Update(cmd) => cmd.execute(printer, client),
+            Self::Edit(cmd) => cmd.execute(printer, client),
             Self::Delete(cmd) => cmd.execute(printer, client),
         }
     }
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    editor::{Edited, edit_item},
+};
+
+/// Edit an existing iCalendar item in `$EDITOR`.
+///
+/// The item is fetched, opened in `$VISUAL` or `$EDITOR` (falling
+/// back to `vi`), validated, then written back with the fetched ETag
+/// as `If-Match`. Invalid iCalendar or a concurrent change on the
+/// server prompts to edit again or to discard the changes.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct ItemEditCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Stable item identifier (iCal `UID`).
+    #[arg(value_name = "ITEM-ID")]
+    pub item_id: String,
+}
+
+impl ItemEditCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+
+        let msg = match edit_item(&mut client, &calendar_id, &self.item_id, None)? {
+            Edited::Updated => "Item successfully updated",
+            Edited::Unchanged => "Item unchanged, nothing to update",
+            Edited::Discarded => "Item changes discarded",
+        };
+
+        printer.out(Message::new(msg))
+    }
+}
@@ -1,6 +1,7 @@
 pub mod cli;
 pub mod create;
 pub mod delete;
+pub mod edit;
 pub mod list;
 pub mod read;
 pub mod update;
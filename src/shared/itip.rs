@@ -0,0 +1,558 @@
+//! iTIP (RFC 5546) scheduling messages, exchanged by mail (iMIP).
+//!
+//! Like [`set_properties`](crate::shared::ical::set_properties),
+//! items are handled at the content-line level so that properties and
+//! parameters calcard does not model survive the round trip. Only
+//! VEVENTs are scheduled.
+
+use std::{fs, ops::Range};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::{
+    account::context::Account,
+    shared::ical::{ContentLine, IcalWriter, PRODID, generate_uid, parse_lines},
+};
+
+/// `--save` flag of the commands generating iTIP messages.
+#[derive(Debug, Parser)]
+pub struct ItipSaveArg {
+    /// Save the message as `<UID>-<METHOD>.ics` under the downloads
+    /// directory instead of printing it.
+    #[arg(long)]
+    pub save: bool,
+}
+
+impl ItipSaveArg {
+    /// Prints the `method` message `contents` of the event `uid`, or
+    /// saves it under the downloads directory of `account`.
+    pub fn out(
+        self,
+        printer: &mut impl Printer,
+        account: &Account,
+        uid: &str,
+        method: &str,
+        contents: Vec<u8>,
+    ) -> Result<()> {
+        if !self.save {
+            let contents = String::from_utf8_lossy(&contents).into_owned();
+            return printer.out(Message::new(contents));
+        }
+
+        let uid: String = uid
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '@' => c,
+                _ => '_',
+            })
+            .collect();
+
+        let dir = account.downloads_dir();
+        let path = dir.join(format!("{uid}-{}.ics", method.to_ascii_lowercase()));
+
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Create downloads directory `{}` error", dir.display()))?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Write iTIP message `{}` error", path.display()))?;
+
+        printer.out(Message::new(format!(
+            "iTIP {method} successfully saved at `{}`",
+            path.display()
+        )))
+    }
+}
+
+fn serialize(lines: &[ContentLine]) -> Vec<u8> {
+    let mut writer = IcalWriter::new();
+
+    for line in lines {
+        line.write(&mut writer);
+    }
+
+    writer.finish()
+}
+
+/// Ranges of the VEVENT blocks directly under VCALENDAR, BEGIN and
+/// END lines included.
+fn event_ranges(lines: &[ContentLine]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut depth = 0usize;
+    let mut start = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let vevent = line.value.eq_ignore_ascii_case("VEVENT");
+
+        if line.is("BEGIN") {
+            if depth == 1 && vevent {
+                start = Some(i);
+            }
+            depth += 1;
+        } else if line.is("END") {
+            depth = depth.saturating_sub(1);
+            if depth == 1
+                && vevent
+                && let Some(start) = start.take()
+            {
+                ranges.push(start..i + 1);
+            }
+        }
+    }
+
+    ranges
+}
+
+/// Indices of the properties of the VEVENT `block` itself, nested
+/// components such as VALARM excluded.
+fn own_properties(lines: &[ContentLine], block: &Range<usize>) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut depth = 0usize;
+
+    for i in block.clone() {
+        let line = &lines[i];
+
+        if line.is("BEGIN") {
+            depth += 1;
+        } else if line.is("END") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 1 {
+            indices.push(i);
+        }
+    }
+
+    indices
+}
+
+fn own_value<'a>(lines: &'a [ContentLine], block: &Range<usize>, name: &str) -> Option<&'a str> {
+    own_properties(lines, block)
+        .into_iter()
+        .map(|i| &lines[i])
+        .find(|line| line.is(name))
+        .map(|line| line.value.as_str())
+}
+
+/// Normalizes a calendar user address: `mailto:` prefix dropped,
+/// lowercased.
+pub fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+
+    let address = match address.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &address[7..],
+        _ => address,
+    };
+
+    address.to_ascii_lowercase()
+}
+
+/// Normalized ORGANIZER of the first VEVENT of `contents`.
+pub fn organizer(contents: &[u8]) -> Option<String> {
+    let lines = parse_lines(contents);
+
+    event_ranges(&lines)
+        .iter()
+        .find_map(|block| own_value(&lines, block, "ORGANIZER"))
+        .map(normalize_address)
+}
+
+/// Normalized ATTENDEEs of the VEVENTs of `contents`, deduplicated.
+pub fn attendees(contents: &[u8]) -> Vec<String> {
+    let lines = parse_lines(contents);
+    let mut attendees = Vec::new();
+
+    for block in event_ranges(&lines) {
+        for i in own_properties(&lines, &block) {
+            if lines[i].is("ATTENDEE") {
+                let address = normalize_address(&lines[i].value);
+
+                if !attendees.contains(&address) {
+                    attendees.push(address);
+                }
+            }
+        }
+    }
+
+    attendees
+}
+
+/// Highest SEQUENCE of the VEVENTs of `contents`, 0 by default.
+pub fn sequence(contents: &[u8]) -> u32 {
+    let lines = parse_lines(contents);
+
+    event_ranges(&lines)
+        .iter()
+        .filter_map(|block| own_value(&lines, block, "SEQUENCE"))
+        .filter_map(|sequence| sequence.trim().parse().ok())
+        .max()
+        .unwrap_or_default()
+}
+
+/// Wraps the VCALENDAR `contents` into a `method` message, the METHOD
+/// property (re)set right after `BEGIN:VCALENDAR`.
+pub fn with_method(contents: &[u8], method: &str) -> Vec<u8> {
+    set_method(contents, Some(method))
+}
+
+/// Turns the iTIP message `contents` back into a plain VCALENDAR, as
+/// stored in calendars.
+pub fn without_method(contents: &[u8]) -> Vec<u8> {
+    set_method(contents, None)
+}
+
+fn set_method(contents: &[u8], method: Option<&str>) -> Vec<u8> {
+    let mut lines = parse_lines(contents);
+    lines.retain(|line| !line.is("METHOD"));
+
+    if let Some(method) = method {
+        let index = lines
+            .iter()
+            .position(|line| line.is("BEGIN") && line.value.eq_ignore_ascii_case("VCALENDAR"))
+            .map_or(0, |i| i + 1);
+
+        let method = ContentLine {
+            name: String::from("METHOD"),
+            params: Vec::new(),
+            value: method.to_ascii_uppercase(),
+        };
+
+        lines.insert(index, method);
+    }
+
+    serialize(&lines)
+}
+
+/// METHOD of the iTIP message `contents`, uppercased.
+pub fn method(contents: &[u8]) -> Option<String> {
+    parse_lines(contents)
+        .into_iter()
+        .find(|line| line.is("METHOD"))
+        .map(|line| line.value.to_ascii_uppercase())
+}
+
+/// Raw value of the property `name` of the first VEVENT of
+/// `contents`.
+pub fn event_value(contents: &[u8], name: &str) -> Option<String> {
+    let lines = parse_lines(contents);
+
+    event_ranges(&lines)
+        .iter()
+        .find_map(|block| own_value(&lines, block, name))
+        .map(ToOwned::to_owned)
+}
+
+/// Builds the METHOD:REQUEST VFREEBUSY asking the busy time of
+/// `attendees` between the UTC `start` and `end` (RFC 5546 §3.3.2),
+/// as POSTed to a CalDAV scheduling outbox.
+pub fn freebusy_request(
+    organizer: &str,
+    attendees: &[String],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<u8> {
+    let stamp = |dt: DateTime<Utc>| dt.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut ical = IcalWriter::new();
+    ical.begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .text("PRODID", PRODID)
+        .property("METHOD", &[], "REQUEST")
+        .begin("VFREEBUSY")
+        .text("UID", &generate_uid(now))
+        .property("DTSTAMP", &[], &stamp(now))
+        .property("DTSTART", &[], &stamp(start))
+        .property("DTEND", &[], &stamp(end))
+        .property("ORGANIZER", &[], &format!("mailto:{organizer}"));
+
+    for attendee in attendees {
+        ical.property("ATTENDEE", &[], &format!("mailto:{attendee}"));
+    }
+
+    ical.end("VFREEBUSY").end("VCALENDAR");
+    ical.finish()
+}
+
+/// Whether the VEVENT `block` is the one a reply targets: the
+/// override of `recurrence_id` when given, any otherwise.
+fn targets(lines: &[ContentLine], block: &Range<usize>, recurrence_id: Option<&str>) -> bool {
+    match recurrence_id {
+        Some(id) => own_value(lines, block, "RECURRENCE-ID").is_some_and(|rid| rid == id),
+        None => true,
+    }
+}
+
+/// Sets the PARTSTAT of the ATTENDEE `address` in the VEVENTs of
+/// `contents` (only the override of `recurrence_id` when given).
+/// Returns [`None`] when no such attendee is found.
+pub fn set_partstat(
+    contents: &[u8],
+    address: &str,
+    partstat: &str,
+    recurrence_id: Option<&str>,
+) -> Option<Vec<u8>> {
+    let mut lines = parse_lines(contents);
+    let address = normalize_address(address);
+    let mut found = false;
+
+    for block in event_ranges(&lines) {
+        if !targets(&lines, &block, recurrence_id) {
+            continue;
+        }
+
+        for i in own_properties(&lines, &block) {
+            let line = &mut lines[i];
+
+            if line.is("ATTENDEE") && normalize_address(&line.value) == address {
+                line.set_param("PARTSTAT", Some(partstat));
+                found = true;
+            }
+        }
+    }
+
+    found.then(|| serialize(&lines))
+}
+
+/// Properties of the invitation copied into a REPLY, besides the
+/// replying ATTENDEE.
+const REPLY_PROPERTIES: [&str; 8] = [
+    "UID",
+    "RECURRENCE-ID",
+    "SEQUENCE",
+    "DTSTART",
+    "DTEND",
+    "DURATION",
+    "SUMMARY",
+    "ORGANIZER",
+];
+
+/// Builds the METHOD:REPLY message of the ATTENDEE `address` to the
+/// invitation `contents`, answering `partstat` for every VEVENT the
+/// attendee is invited to (only the override of `recurrence_id` when
+/// given). VTIMEZONEs are kept for the TZID references.
+pub fn reply(
+    contents: &[u8],
+    address: &str,
+    partstat: &str,
+    recurrence_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<u8>> {
+    let lines = parse_lines(contents);
+    let address = normalize_address(address);
+
+    let mut ical = IcalWriter::new();
+    ical.begin("VCALENDAR")
+        .property("VERSION", &[], "2.0")
+        .text("PRODID", PRODID)
+        .property("METHOD", &[], "REPLY");
+
+    let mut depth = 0usize;
+    let mut vtimezone = false;
+
+    for line in &lines {
+        if line.is("BEGIN") {
+            depth += 1;
+            vtimezone |= depth == 2 && line.value.eq_ignore_ascii_case("VTIMEZONE");
+        }
+
+        if vtimezone {
+            line.write(&mut ical);
+        }
+
+        if line.is("END") {
+            depth = depth.saturating_sub(1);
+            vtimezone &= depth >= 2;
+        }
+    }
+
+    let mut replied = false;
+
+    for block in event_ranges(&lines) {
+        if !targets(&lines, &block, recurrence_id) {
+            continue;
+        }
+
+        let properties = own_properties(&lines, &block);
+
+        let Some(attendee) = properties
+            .iter()
+            .map(|i| &lines[*i])
+            .find(|line| line.is("ATTENDEE") && normalize_address(&line.value) == address)
+        else {
+            continue;
+        };
+
+        if own_value(&lines, &block, "ORGANIZER").is_none() {
+            bail!("Cannot reply to an event without ORGANIZER");
+        }
+
+        ical.begin("VEVENT");
+
+        for i in &properties {
+            let line = &lines[*i];
+
+            if REPLY_PROPERTIES.iter().any(|name| line.is(name)) {
+                line.write(&mut ical);
+            }
+        }
+
+        let mut attendee = attendee.clone();
+        attendee.set_param("PARTSTAT", Some(partstat));
+        attendee.set_param("RSVP", None);
+        attendee.write(&mut ical);
+
+        ical.property("DTSTAMP", &[], &now.format("%Y%m%dT%H%M%SZ").to_string())
+            .end("VEVENT");
+
+        replied = true;
+    }
+
+    if !replied {
+        bail!("Cannot find attendee `{address}` in the event");
+    }
+
+    ical.end("VCALENDAR");
+    Ok(ical.finish())
+}
+
+/// Answer of one attendee read from a REPLY message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reply {
+    pub uid: String,
+    pub recurrence_id: Option<String>,
+    /// Normalized address of the attendee.
+    pub attendee: String,
+    pub partstat: String,
+}
+
+/// Reads the answers of a METHOD:REPLY message.
+pub fn read_replies(contents: &[u8]) -> Result<Vec<Reply>> {
+    let lines = parse_lines(contents);
+
+    match lines.iter().find(|line| line.is("METHOD")) {
+        Some(method) if method.value.eq_ignore_ascii_case("REPLY") => (),
+        Some(method) => bail!("Expected an iTIP REPLY, got METHOD `{}`", method.value),
+        None => bail!("Expected an iTIP REPLY, got an object without METHOD"),
+    }
+
+    let mut replies = Vec::new();
+
+    for block in event_ranges(&lines) {
+        let Some(uid) = own_value(&lines, &block, "UID") else {
+            bail!("Invalid iTIP REPLY: VEVENT without UID");
+        };
+
+        let recurrence_id = own_value(&lines, &block, "RECURRENCE-ID");
+
+        for i in own_properties(&lines, &block) {
+            let line = &lines[i];
+
+            if !line.is("ATTENDEE") {
+                continue;
+            }
+
+            replies.push(Reply {
+                uid: uid.to_owned(),
+                recurrence_id: recurrence_id.map(ToOwned::to_owned),
+                attendee: normalize_address(&line.value),
+                partstat: line.param("PARTSTAT").unwrap_or("NEEDS-ACTION").to_owned(),
+            });
+        }
+    }
+
+    if replies.is_empty() {
+        bail!("Invalid iTIP REPLY: no ATTENDEE found");
+    }
+
+    Ok(replies)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::{Reply, read_replies, reply, set_partstat, with_method};
+
+    const INVITATION: &str = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "BEGIN:VEVENT\r\n",
+        "UID:abc\r\n",
+        "DTSTART:20260316T090000Z\r\n",
+        "SUMMARY:Standup\r\n",
+        "ORGANIZER;CN=Jane:mailto:jane@example.com\r\n",
+        "ATTENDEE;CN=\"Doe, John\";PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:John@\r\n",
+        " example.com\r\n",
+        "BEGIN:VALARM\r\n",
+        "ACTION:DISPLAY\r\n",
+        "END:VALARM\r\n",
+        "END:VEVENT\r\n",
+        "END:VCALENDAR\r\n",
+    );
+
+    #[test]
+    fn reply_round_trip() {
+        let now = DateTime::from_timestamp(0, 0).unwrap();
+        let message = reply(
+            INVITATION.as_bytes(),
+            "john@example.com",
+            "ACCEPTED",
+            None,
+            now,
+        );
+        let message = String::from_utf8(message.unwrap()).unwrap();
+
+        assert!(message.contains("METHOD:REPLY\r\n"));
+        assert!(message.contains("ATTENDEE;CN=\"Doe, John\";PARTSTAT=ACCEPTED:mailto:John@"));
+        assert!(!message.contains("VALARM"));
+
+        assert_eq!(
+            read_replies(message.as_bytes()).unwrap(),
+            [Reply {
+                uid: String::from("abc"),
+                recurrence_id: None,
+                attendee: String::from("john@example.com"),
+                partstat: String::from("ACCEPTED"),
+            }]
+        );
+
+        assert!(
+            reply(
+                INVITATION.as_bytes(),
+                "bob@example.com",
+                "ACCEPTED",
+                None,
+                now
+            )
+            .is_err()
+        );
+        assert!(read_replies(INVITATION.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn update_partstat() {
+        let ical = set_partstat(
+            INVITATION.as_bytes(),
+            "mailto:john@example.com",
+            "DECLINED",
+            None,
+        );
+        let ical = String::from_utf8(ical.unwrap()).unwrap();
+
+        assert!(ical.contains(";PARTSTAT=DECLINED;RSVP=TRUE:"));
+        assert!(ical.contains("BEGIN:VALARM\r\n"));
+        assert!(set_partstat(INVITATION.as_bytes(), "bob@example.com", "DECLINED", None).is_none());
+        assert!(
+            set_partstat(
+                INVITATION.as_bytes(),
+                "john@example.com",
+                "DECLINED",
+                Some("x")
+            )
+            .is_none()
+        );
+
+        let request = String::from_utf8(with_method(INVITATION.as_bytes(), "REQUEST")).unwrap();
+        assert!(request.starts_with("BEGIN:VCALENDAR\r\nMETHOD:REQUEST\r\nVERSION:2.0\r\n"));
+    }
+}
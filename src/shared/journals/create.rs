@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    io::{Read, stdin},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    datetime::parse_date,
+    ical::{IcalWriter, PRODID, generate_uid},
+};
+
+/// Create a new journal.
+///
+/// The body is read from a Markdown (or plain text) file, or from
+/// stdin with `-`, and stored as is in the DESCRIPTION. The SUMMARY
+/// comes from `--summary`, falling back to the first Markdown heading
+/// of the body. The journal is dated by `--date`, today by default.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct JournalCreateCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    #[command(flatten)]
+    pub body: BodyArg,
+
+    /// Title of the journal. Defaults to the first heading of the body.
+    #[arg(long, value_name = "TEXT")]
+    pub summary: Option<String>,
+
+    /// Day of the journal: `YYYY-MM-DD`, `today`, `yesterday`, etc.
+    #[arg(long, value_name = "DATE")]
+    pub date: Option<String>,
+}
+
+impl JournalCreateCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let now = Utc::now();
+        let today = client.account.timezone().from_utc(now).date();
+
+        let date = match &self.date {
+            Some(date) => parse_date(date, today)?,
+            None => today,
+        };
+
+        let body = self.body.read()?;
+
+        let Some(summary) = self.summary.or_else(|| body.as_deref().and_then(heading)) else {
+            bail!("Missing --summary, and the body has no heading to take it from");
+        };
+
+        let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut ical = IcalWriter::new();
+
+        ical.begin("VCALENDAR")
+            .property("VERSION", &[], "2.0")
+            .text("PRODID", PRODID)
+            .begin("VJOURNAL")
+            .text("UID", &generate_uid(now))
+            .property("DTSTAMP", &[], &dtstamp)
+            .property("CREATED", &[], &dtstamp)
+            .property(
+                "DTSTART",
+                &[("VALUE", "DATE")],
+                &date.format("%Y%m%d").to_string(),
+            )
+            .text("SUMMARY", &summary);
+
+        if let Some(body) = &body {
+            ical.text("DESCRIPTION", body);
+        }
+
+        ical.end("VJOURNAL").end("VCALENDAR");
+
+        let id = client.create_item(&calendar_id, ical.finish())?;
+        printer.out(Message::new(format!("Journal `{id}` successfully created")))
+    }
+}
+
+/// Positional journal body shared by `journal create` and `journal
+/// update`.
+#[derive(Debug, Parser)]
+pub struct BodyArg {
+    /// A path to a Markdown or text file, or `-` for stdin.
+    #[arg(value_name = "BODY")]
+    pub body: Option<String>,
+}
+
+impl BodyArg {
+    /// Reads the body, with trailing whitespace trimmed. Returns
+    /// [`None`] when no source was given.
+    pub fn read(self) -> Result<Option<String>> {
+        let Some(source) = self.body else {
+            return Ok(None);
+        };
+
+        let body = if source == "-" {
+            let mut body = String::new();
+            stdin()
+                .read_to_string(&mut body)
+                .context("Read journal body from stdin error")?;
+            body
+        } else {
+            fs::read_to_string(&source)
+                .with_context(|| format!("Read journal body from `{source}` error"))?
+        };
+
+        Ok(Some(body.trim_end().to_owned()))
+    }
+}
+
+/// Extracts the text of the first Markdown ATX heading of `body`.
+fn heading(body: &str) -> Option<String> {
+    body.lines().find_map(|line| {
+        let line = line.trim_start();
+        let text = line.trim_start_matches('#');
+
+        if text.len() == line.len() || line.len() - text.len() > 6 || !text.starts_with(' ') {
+            return None;
+        }
+
+        let text = text.trim().trim_end_matches('#').trim_end();
+        (!text.is_empty()).then(|| text.to_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::heading;
+
+    #[test]
+    fn first_heading() {
+        let body = "Some intro\n\n## Standup notes ##\n\n# Later\n";
+        assert_eq!(heading(body).as_deref(), Some("Standup notes"));
+        assert_eq!(heading("#hashtag\nno heading"), None);
+        assert_eq!(heading("####### too deep"), None);
+    }
+}
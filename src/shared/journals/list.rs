@@ -0,0 +1,207 @@
+use std::{cmp::Reverse, fmt};
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::Parser;
+use comfy_table::{Cell, Color, ContentArrangement, Row, Table};
+use io_calendar::{
+    calcard::icalendar::{ICalendarComponent, ICalendarComponentType, ICalendarProperty},
+    item::CalendarItem,
+};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    recurrence::{
+        component_date, component_text, component_tzid, is_date, is_utc, naive_date_time,
+    },
+    timezone::{Timezone, TzResolver, format_date_time},
+};
+
+/// List VJOURNAL items inside a calendar.
+///
+/// Non-VJOURNAL items are filtered out. Journals are indexed by their
+/// DTSTART date, newest first; undated ones come last. Pass `--from`
+/// and/or `--to` (YYYY-MM-DD, both inclusive) to only list the
+/// journals of a date range, filtered client-side before paging.
+///
+/// JSON output: `{"journals": [{"id", "date", "summary"}]}`.
+#[derive(Debug, Parser)]
+pub struct JournalListCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// 1-indexed page number. Defaults to 1.
+    #[arg(short, long, value_name = "N")]
+    pub page: Option<u32>,
+
+    /// Number of journals per page.
+    #[arg(short = 's', long, value_name = "N")]
+    pub page_size: Option<u32>,
+
+    /// Only list journals on or after this date (inclusive, YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<NaiveDate>,
+
+    /// Only list journals on or before this date (inclusive,
+    /// YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<NaiveDate>,
+
+    /// Maximum width of the rendered table, in terminal columns.
+    #[arg(long = "max-width", short = 'w', value_name = "COLUMNS")]
+    pub max_width: Option<u16>,
+}
+
+impl JournalListCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let timezone = client.account.timezone();
+        let items = client.list_all_items(&calendar_id, None)?;
+
+        let in_range = |date: Option<NaiveDateTime>| {
+            let date = date.map(|date| date.date());
+            let after_from = self.from.is_none_or(|from| date.is_some_and(|d| d >= from));
+            let before_to = self.to.is_none_or(|to| date.is_some_and(|d| d <= to));
+            after_from && before_to
+        };
+
+        let mut journals: Vec<(Option<NaiveDateTime>, JournalRow)> = items
+            .iter()
+            .filter_map(|item| extract_journal_row(item, timezone))
+            .filter(|(date, _)| in_range(*date))
+            .collect();
+
+        // Newest first, undated last.
+        journals.sort_by_key(|(date, _)| (date.is_none(), Reverse(*date)));
+
+        let page_size = self
+            .page_size
+            .unwrap_or(client.account.journals_list_page_size()) as usize;
+        let page = self.page.unwrap_or(1).max(1) as usize - 1;
+
+        let journals = journals
+            .into_iter()
+            .map(|(_, row)| row)
+            .skip(page * page_size)
+            .take(page_size)
+            .collect();
+
+        let table = Journals {
+            preset: client.account.table_preset().to_string(),
+            arrangement: client.account.table_arrangement(),
+            max_width: self.max_width,
+            colors: JournalColors {
+                id: client.account.journals_list_table_id_color(),
+                date: client.account.journals_list_table_date_color(),
+                summary: client.account.journals_list_table_summary_color(),
+            },
+            journals,
+        };
+
+        printer.out(table)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct JournalColors {
+    id: Color,
+    date: Color,
+    summary: Color,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JournalRow {
+    pub id: String,
+    pub date: Option<String>,
+    pub summary: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Journals {
+    #[serde(skip)]
+    pub preset: String,
+    #[serde(skip)]
+    pub arrangement: ContentArrangement,
+    #[serde(skip)]
+    pub max_width: Option<u16>,
+    #[serde(skip)]
+    colors: JournalColors,
+    pub journals: Vec<JournalRow>,
+}
+
+impl fmt::Display for Journals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+
+        table
+            .load_preset(&self.preset)
+            .set_content_arrangement(self.arrangement.clone())
+            .set_header(Row::from([
+                Cell::new("ID"),
+                Cell::new("DATE"),
+                Cell::new("SUMMARY"),
+            ]))
+            .add_rows(self.journals.iter().map(|j| {
+                let mut row = Row::new();
+                row.max_height(1);
+                row.add_cell(Cell::new(&j.id).fg(self.colors.id));
+                row.add_cell(Cell::new(j.date.as_deref().unwrap_or("")).fg(self.colors.date));
+                row.add_cell(Cell::new(&j.summary).fg(self.colors.summary));
+                row
+            }));
+
+        if let Some(width) = self.max_width {
+            table.set_width(width);
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{table}")
+    }
+}
+
+/// Parses `item` and pulls out the first VJOURNAL component's date and
+/// SUMMARY, the date paired with the row for filtering and sorting.
+/// Returns [`None`] when the item is not a recognisable VJOURNAL.
+fn extract_journal_row(
+    item: &CalendarItem,
+    timezone: Timezone,
+) -> Option<(Option<NaiveDateTime>, JournalRow)> {
+    let ical = item.as_ical()?;
+    let resolver = TzResolver::new(&ical);
+    let journal = ical
+        .components
+        .iter()
+        .find(|c| c.component_type == ICalendarComponentType::VJournal)?;
+
+    let date = journal_date(journal, &resolver, timezone);
+
+    let row = JournalRow {
+        id: item.id.clone(),
+        date: date.map(|(dt, all_day)| format_date_time(dt, all_day)),
+        summary: component_text(journal, &ICalendarProperty::Summary).unwrap_or_default(),
+    };
+
+    Some((date.map(|(dt, _)| dt), row))
+}
+
+/// DTSTART of a VJOURNAL converted into `timezone`, paired with
+/// whether it is a DATE value.
+pub fn journal_date(
+    journal: &ICalendarComponent,
+    resolver: &TzResolver,
+    timezone: Timezone,
+) -> Option<(NaiveDateTime, bool)> {
+    let pdt = component_date(journal, &ICalendarProperty::Dtstart)?;
+    let dt = naive_date_time(&pdt)?;
+
+    if is_date(&pdt) {
+        return Some((dt, true));
+    }
+
+    let tzid = component_tzid(journal, &ICalendarProperty::Dtstart);
+    let dt = timezone.localize(resolver, dt, tzid.as_deref(), is_utc(&pdt));
+    Some((dt, false))
+}
@@ -0,0 +1,6 @@
+pub mod cli;
+pub mod create;
+pub mod delete;
+pub mod list;
+pub mod read;
+pub mod update;
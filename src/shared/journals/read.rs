@@ -0,0 +1,109 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use io_calendar::calcard::icalendar::{ICalendarComponentType, ICalendarProperty, ICalendarValue};
+use pimalaya_cli::printer::{Message, Printer};
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    journals::list::journal_date,
+    recurrence::component_text,
+    timezone::{TzResolver, format_date_time},
+};
+
+/// Read a single journal.
+///
+/// Renders the date and SUMMARY of the journal, followed by its
+/// DESCRIPTION as plain text (several DESCRIPTION properties are
+/// separated by a blank line). Pass `--raw` to print the raw
+/// iCalendar bytes instead.
+///
+/// JSON output: `{"id", "date", "summary", "description"}`, or
+/// `{"message": "..."}` carrying the raw iCalendar with `--raw`.
+#[derive(Debug, Parser)]
+pub struct JournalReadCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Identifier of the journal, as shown by `journals list`.
+    #[arg(value_name = "JOURNAL-ID")]
+    pub journal_id: String,
+
+    /// Print the raw iCalendar bytes, untouched.
+    #[arg(short, long)]
+    pub raw: bool,
+}
+
+impl JournalReadCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let item = client.get_item(&calendar_id, &self.journal_id)?;
+
+        if self.raw {
+            let contents = String::from_utf8_lossy(&item.contents).into_owned();
+            return printer.out(Message::new(contents));
+        }
+
+        let Some(ical) = item.as_ical() else {
+            bail!("Journal `{}` is not valid iCalendar", self.journal_id);
+        };
+
+        let journal = ical
+            .components
+            .iter()
+            .find(|c| c.component_type == ICalendarComponentType::VJournal);
+
+        let Some(journal) = journal else {
+            bail!("Item `{}` does not contain any VJOURNAL", self.journal_id);
+        };
+
+        let resolver = TzResolver::new(&ical);
+        let date = journal_date(journal, &resolver, client.account.timezone());
+
+        let descriptions: Vec<&str> = journal
+            .entries
+            .iter()
+            .filter(|entry| entry.name == ICalendarProperty::Description)
+            .flat_map(|entry| &entry.values)
+            .filter_map(|value| match value {
+                ICalendarValue::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        printer.out(Journal {
+            id: item.id,
+            date: date.map(|(dt, all_day)| format_date_time(dt, all_day)),
+            summary: component_text(journal, &ICalendarProperty::Summary),
+            description: (!descriptions.is_empty()).then(|| descriptions.join("\n\n")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Journal {
+    pub id: String,
+    pub date: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+}
+
+impl fmt::Display for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(date) = &self.date {
+            writeln!(f, "Date: {date}")?;
+        }
+
+        writeln!(f, "Summary: {}", self.summary.as_deref().unwrap_or(""))?;
+
+        if let Some(description) = &self.description {
+            writeln!(f)?;
+            writeln!(f, "{description}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::{Result, bail};
+use chrono::Utc;
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdArg, client::CalendarClient, datetime::parse_date, ical::escape_text,
+    journals::create::BodyArg,
+};
+
+/// Update an existing journal.
+///
+/// Replaces the DESCRIPTION with the given body, and/or the SUMMARY
+/// and date with `--summary` and `--date`. Other properties are kept,
+/// and the journal is written back with the ETag it was fetched with.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct JournalUpdateCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Identifier of the journal, as shown by `journals list`.
+    #[arg(value_name = "JOURNAL-ID")]
+    pub journal_id: String,
+
+    #[command(flatten)]
+    pub body: BodyArg,
+
+    /// New title of the journal.
+    #[arg(long, value_name = "TEXT")]
+    pub summary: Option<String>,
+
+    /// New day of the journal: `YYYY-MM-DD`, `today`, etc.
+    #[arg(long, value_name = "DATE")]
+    pub date: Option<String>,
+}
+
+impl JournalUpdateCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let today = client.account.timezone().from_utc(Utc::now()).date();
+
+        let date = match &self.date {
+            Some(date) => Some(parse_date(date, today)?.format("%Y%m%d").to_string()),
+            None => None,
+        };
+
+        let summary = self.summary.as_deref().map(escape_text);
+        let description = self.body.read()?.as_deref().map(escape_text);
+
+        let mut props = Vec::new();
+
+        if let Some(date) = &date {
+            props.push(("DTSTART;VALUE=DATE", Some(date.as_str())));
+        }
+
+        if let Some(summary) = &summary {
+            props.push(("SUMMARY", Some(summary.as_str())));
+        }
+
+        if let Some(description) = &description {
+            props.push(("DESCRIPTION", Some(description.as_str())));
+        }
+
+        if props.is_empty() {
+            bail!("Nothing to update; pass a body, --summary or --date");
+        }
+
+        client.update_component(&calendar_id, &self.journal_id, "VJOURNAL", &props)?;
+        printer.out(Message::new("Journal successfully updated"))
+    }
+}
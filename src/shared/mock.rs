@@ -0,0 +1,66 @@
+//! Local HTTP server of the tests of the backends speaking plain HTTP.
+
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+/// Binds a listener to a free local port, returning it with its base
+/// URL.
+pub fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+    (listener, url)
+}
+
+/// Answers one request per connection of `listener` with each of the
+/// `responses` (status and body), returning the requests received,
+/// head and body.
+pub fn serve(
+    listener: TcpListener,
+    responses: Vec<(u16, String)>,
+) -> thread::JoinHandle<Vec<String>> {
+    thread::spawn(move || {
+        let mut requests = Vec::new();
+
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+
+                let text = String::from_utf8_lossy(&request);
+                let Some((head, rest)) = text.split_once("\r\n\r\n") else {
+                    continue;
+                };
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("Content-Length")
+                            .then(|| value.trim().parse().unwrap())
+                    })
+                    .unwrap_or(0);
+                if rest.len() >= length {
+                    break;
+                }
+            }
+
+            requests.push(String::from_utf8_lossy(&request).into_owned());
+
+            let response = format!(
+                "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len(),
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+
+        requests
+    })
+}
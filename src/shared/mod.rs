@@ -1,6 +1,20 @@
 pub mod arg;
+pub mod cache;
 pub mod calendars;
 pub mod client;
+pub mod datetime;
+pub mod editor;
 pub mod events;
+pub mod export;
 pub mod ical;
 pub mod items;
+pub mod itip;
+pub mod journals;
+#[cfg(all(test, any(feature = "graph", feature = "google", feature = "jmap")))]
+pub mod mock;
+pub mod query;
+pub mod recurrence;
+#[cfg(any(feature = "graph", feature = "google", feature = "jmap"))]
+pub mod rest;
+pub mod timezone;
+pub mod todos;
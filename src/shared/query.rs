@@ -0,0 +1,406 @@
+//! Filter expressions of `event list`.
+//!
+//! A query is a boolean combination of conditions over component
+//! properties, e.g. `summary~standup and category=work and not
+//! status=cancelled`:
+//!
+//! - conditions are `<field><op><value>`, where `op` is `=` (equals),
+//!   `!=`, `~` (contains) or `!~`, all case-insensitive;
+//! - fields are `summary`, `description`, `location`, `category`,
+//!   `attendee`, `organizer`, `status` and `uid`; a condition on a
+//!   multi-valued field (`category`, `attendee`) holds when any value
+//!   matches, and `=`/`~` never hold on a missing property;
+//! - `attendee` and `organizer` values are compared without their
+//!   `mailto:` prefix;
+//! - values are bare words or `"quoted strings"`;
+//! - conditions combine with `and`, `or`, `not` and parentheses, `not`
+//!   binding tighter than `and`, itself tighter than `or`.
+//!
+//! Queries are always evaluated client-side. Their top-level positive
+//! conditions can also be pushed down to a CalDAV `calendar-query`
+//! as `text-match` filters ([`Query::text_matches`]), which only
+//! narrows down what the server sends.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Error, Result, bail};
+use io_calendar::calcard::icalendar::ICalendarProperty;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Cond(Cond),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cond {
+    pub field: Field,
+    pub op: Op,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    NotEq,
+    Contains,
+    NotContains,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Summary,
+    Description,
+    Location,
+    Category,
+    Attendee,
+    Organizer,
+    Status,
+    Uid,
+}
+
+impl Field {
+    /// iCalendar property the field reads.
+    pub fn property(self) -> ICalendarProperty {
+        match self {
+            Self::Summary => ICalendarProperty::Summary,
+            Self::Description => ICalendarProperty::Description,
+            Self::Location => ICalendarProperty::Location,
+            Self::Category => ICalendarProperty::Categories,
+            Self::Attendee => ICalendarProperty::Attendee,
+            Self::Organizer => ICalendarProperty::Organizer,
+            Self::Status => ICalendarProperty::Status,
+            Self::Uid => ICalendarProperty::Uid,
+        }
+    }
+
+    /// Name of the property on the wire.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Summary => "SUMMARY",
+            Self::Description => "DESCRIPTION",
+            Self::Location => "LOCATION",
+            Self::Category => "CATEGORIES",
+            Self::Attendee => "ATTENDEE",
+            Self::Organizer => "ORGANIZER",
+            Self::Status => "STATUS",
+            Self::Uid => "UID",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = Error;
+
+    fn from_str(field: &str) -> Result<Self, Self::Err> {
+        Ok(match field.to_ascii_lowercase().as_str() {
+            "summary" => Self::Summary,
+            "description" => Self::Description,
+            "location" => Self::Location,
+            "category" | "categories" => Self::Category,
+            "attendee" => Self::Attendee,
+            "organizer" => Self::Organizer,
+            "status" => Self::Status,
+            "uid" => Self::Uid,
+            _ => bail!(
+                "Unknown query field `{field}`; expected summary, description, location, \
+                 category, attendee, organizer, status or uid"
+            ),
+        })
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eq => write!(f, "="),
+            Self::NotEq => write!(f, "!="),
+            Self::Contains => write!(f, "~"),
+            Self::NotContains => write!(f, "!~"),
+        }
+    }
+}
+
+impl Query {
+    /// Evaluates the query, `values` returning every value of a field
+    /// of the component under test.
+    pub fn matches(&self, values: &impl Fn(Field) -> Vec<String>) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(values) && b.matches(values),
+            Self::Or(a, b) => a.matches(values) || b.matches(values),
+            Self::Not(query) => !query.matches(values),
+            Self::Cond(cond) => cond.matches(&values(cond.field)),
+        }
+    }
+
+    /// Positive conditions every match must satisfy: the `=` and `~`
+    /// conditions reachable from the root through `and` only. Each one
+    /// implies its property contains the value, which is what a CalDAV
+    /// `text-match` tests.
+    pub fn text_matches(&self) -> Vec<(Field, &str)> {
+        match self {
+            Self::And(a, b) => {
+                let mut matches = a.text_matches();
+                matches.extend(b.text_matches());
+                matches
+            }
+            Self::Cond(Cond {
+                field,
+                op: Op::Eq | Op::Contains,
+                value,
+            }) => vec![(*field, value.as_str())],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Cond {
+    fn matches(&self, values: &[String]) -> bool {
+        let normalize = |value: &str| {
+            let value = value.to_lowercase();
+
+            match self.field {
+                Field::Attendee | Field::Organizer => match value.strip_prefix("mailto:") {
+                    Some(address) => address.to_owned(),
+                    None => value,
+                },
+                _ => value,
+            }
+        };
+
+        let expected = normalize(&self.value);
+        let any = |f: &dyn Fn(&str) -> bool| values.iter().any(|v| f(&normalize(v)));
+
+        match self.op {
+            Op::Eq => any(&|v| v == expected),
+            Op::NotEq => !any(&|v| v == expected),
+            Op::Contains => any(&|v| v.contains(&expected)),
+            Op::NotContains => !any(&|v| v.contains(&expected)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+        };
+
+        let query = parser.or()?;
+
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected `{token}` in query");
+        }
+
+        Ok(query)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Op(Op),
+    Word(String),
+    Quoted(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "("),
+            Self::Close => write!(f, ")"),
+            Self::Op(op) => write!(f, "{op}"),
+            Self::Word(word) => write!(f, "{word}"),
+            Self::Quoted(text) => write!(f, "\"{text}\""),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '=' => tokens.push(Token::Op(Op::Eq)),
+            '~' => tokens.push(Token::Op(Op::Contains)),
+            '!' if chars.next_if_eq(&'=').is_some() => tokens.push(Token::Op(Op::NotEq)),
+            '!' if chars.next_if_eq(&'~').is_some() => tokens.push(Token::Op(Op::NotContains)),
+            '"' | '\'' => {
+                let mut text = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('\\') => text.extend(chars.next()),
+                        Some(end) if end == c => break,
+                        Some(c) => text.push(c),
+                        None => bail!("Unterminated string in query"),
+                    }
+                }
+
+                tokens.push(Token::Quoted(text));
+            }
+            c => {
+                let mut word = String::from(c);
+
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()=~\"'".contains(c) || c == '!' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut query = self.and()?;
+
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut query = self.unary()?;
+
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => bail!("Missing `)` in query"),
+                }
+            }
+            Some(Token::Word(field)) => {
+                let field = field.parse()?;
+
+                let Some(Token::Op(op)) = self.next() else {
+                    bail!("Missing operator after query field; expected =, !=, ~ or !~");
+                };
+
+                let value = match self.next() {
+                    Some(Token::Word(value) | Token::Quoted(value)) => value,
+                    _ => bail!("Missing value after `{op}` in query"),
+                };
+
+                Ok(Query::Cond(Cond { field, op, value }))
+            }
+            Some(token) => bail!("Unexpected `{token}` in query"),
+            None => bail!("Unexpected end of query"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, Query};
+
+    fn values(field: Field) -> Vec<String> {
+        let values: &[&str] = match field {
+            Field::Summary => &["Daily Standup"],
+            Field::Category => &["work", "team"],
+            Field::Status => &["CONFIRMED"],
+            Field::Attendee => &["MAILTO:alice@example.com", "mailto:bob@example.com"],
+            _ => &[],
+        };
+
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn eval(query: &str) -> bool {
+        query.parse::<Query>().unwrap().matches(&values)
+    }
+
+    #[test]
+    fn evaluates() {
+        assert!(eval(
+            "summary~standup and category=work and not status=cancelled"
+        ));
+        assert!(eval("summary~'daily stand'"));
+        assert!(eval("category=team"));
+        assert!(!eval("category=home"));
+        assert!(eval("category!=home"));
+        assert!(!eval("location~paris"));
+        assert!(eval("location!~paris"));
+        assert!(eval(
+            "status=tentative or (summary ~ standup and not uid=x)"
+        ));
+        assert!(!eval("not summary~standup or status=cancelled"));
+        assert!(eval("attendee=alice@example.com"));
+        assert!(eval("attendee=mailto:bob@example.com"));
+        assert!(eval("attendee!=carol@example.com"));
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        assert!("summary".parse::<Query>().is_err());
+        assert!("color=red".parse::<Query>().is_err());
+        assert!("summary~x and".parse::<Query>().is_err());
+        assert!("(summary~x".parse::<Query>().is_err());
+        assert!("summary~\"x".parse::<Query>().is_err());
+        assert!("summary~x status=y".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn pushes_down_top_level_positive_conditions() {
+        let query: Query = "summary~standup and (category=work or category=team) and \
+                            not status=cancelled and location=office"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            query.text_matches(),
+            vec![(Field::Summary, "standup"), (Field::Location, "office")]
+        );
+    }
+}
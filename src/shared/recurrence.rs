@@ -0,0 +1,1162 @@
+//! Recurrence expansion (RFC 5545 §3.8.5) for the commands that
+//! render occurrences rather than raw items.
+//!
+//! calcard parses RRULE, RDATE, EXDATE and RECURRENCE-ID but leaves
+//! their expansion to the consumer, so this module turns a parsed
+//! [`ICalendar`] into the concrete [`Occurrence`]s of one component
+//! kind inside a bounded window. Times stay in the wall clock of their
+//! DTSTART: recurrence rules are defined in that local time, so any
+//! conversion to another zone happens after expansion.
+//!
+//! The rule engine covers the parts of RRULE calendar clients emit in
+//! practice: every FREQ, INTERVAL, COUNT, UNTIL, BYDAY (with ordinals),
+//! BYMONTHDAY, BYMONTH, BYSETPOS and WKST. BYHOUR, BYMINUTE, BYSECOND,
+//! BYYEARDAY and BYWEEKNO are ignored.
+//!
+//! Commands rendering occurrences in a target zone go through
+//! [`occurrences_in`], which expands then converts them.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use io_calendar::calcard::{
+    common::{PartialDateTime, Uri},
+    icalendar::{
+        ICalendar, ICalendarComponent, ICalendarComponentType, ICalendarDuration, ICalendarEntry,
+        ICalendarFrequency, ICalendarProperty, ICalendarRecurrenceRule, ICalendarValue,
+        ICalendarWeekday,
+    },
+};
+use log::warn;
+
+use crate::shared::timezone::{Timezone, TzResolver};
+
+/// Upper bound on the number of rule periods walked for one series,
+/// so a rule whose filters never match cannot spin forever. The walk
+/// starts near the window when the rule has no COUNT (see
+/// [`Rule::each`]), so the bound applies to the window, not to the
+/// age of the series.
+const MAX_PERIODS: u32 = 100_000;
+
+/// Upper bound on the number of occurrences emitted for one series
+/// when the window has no end.
+const MAX_UNBOUNDED_OCCURRENCES: usize = 1_000;
+
+/// One concrete instance of a (possibly recurring) component.
+#[derive(Clone, Debug)]
+pub struct Occurrence<'a> {
+    /// Component carrying the instance properties: the master for
+    /// generated instances, the override for RECURRENCE-ID ones.
+    pub component: &'a ICalendarComponent,
+    /// UID shared by the master and its overrides.
+    pub uid: Option<String>,
+    /// RECURRENCE-ID of the instance in iCalendar wire format, or
+    /// [`None`] for non-recurring components.
+    pub recurrence_id: Option<String>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Whether DTSTART is a DATE value (all-day component).
+    pub all_day: bool,
+    /// TZID parameter of DTSTART, [`None`] for UTC and floating times.
+    pub tzid: Option<String>,
+    /// Whether DTSTART is a UTC date-time.
+    pub utc: bool,
+}
+
+impl Occurrence<'_> {
+    /// Whether the instance overlaps the half-open `[from, to)` window.
+    /// Zero-length instances match when their start lies inside it.
+    pub fn overlaps(&self, from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> bool {
+        overlaps(self.start, self.end, from, to)
+    }
+}
+
+/// Whether `[start, end)` overlaps the half-open `[from, to)` window.
+/// Zero-length spans match when their start lies inside it.
+fn overlaps(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> bool {
+    let after_from = match from {
+        Some(from) if end > start => end > from,
+        Some(from) => start >= from,
+        None => true,
+    };
+
+    let before_to = match to {
+        Some(to) => start < to,
+        None => true,
+    };
+
+    after_from && before_to
+}
+
+/// Occurrence along with its bounds converted into a target zone.
+#[derive(Clone, Debug)]
+pub struct Localized<'a> {
+    pub occurrence: Occurrence<'a>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+/// Expands every `kind` component of `ical` into the occurrences that
+/// overlap the half-open `[from, to)` window of `timezone`, their
+/// bounds converted into it, sorted by start.
+///
+/// Occurrences are expanded in their own zone, which can be more than
+/// a day away from `timezone` (UTC-12 against UTC+14): the window is
+/// widened by two days for the expansion, then applied to the
+/// converted bounds. DATE components
+/// without DTEND last one day.
+pub fn occurrences_in<'a>(
+    ical: &'a ICalendar,
+    kind: &ICalendarComponentType,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    timezone: Timezone,
+) -> Vec<Localized<'a>> {
+    let margin = TimeDelta::days(2);
+    let resolver = TzResolver::new(ical);
+    let widened = (from.map(|from| from - margin), to.map(|to| to + margin));
+
+    let mut occurrences: Vec<Localized<'a>> = expand(ical, kind, widened.0, widened.1)
+        .into_iter()
+        .filter_map(|occurrence| {
+            let (start, end) = timezone.localize_occurrence(&resolver, &occurrence);
+
+            let end = match occurrence.all_day {
+                true => end.max(start + TimeDelta::days(1)),
+                false => end,
+            };
+
+            overlaps(start, end, from, to).then_some(Localized {
+                occurrence,
+                start,
+                end,
+            })
+        })
+        .collect();
+
+    occurrences.sort_by_key(|localized| localized.start);
+    occurrences
+}
+
+/// Expands every `kind` component of `ical` into the occurrences that
+/// overlap the half-open `[from, to)` window, sorted by start.
+///
+/// Components sharing a UID form one recurrence set: the master (no
+/// RECURRENCE-ID) generates instances from its RRULE and RDATE minus
+/// its EXDATE, and each override replaces the generated instance it
+/// points at. Overrides whose master is missing are kept as is.
+pub fn expand<'a>(
+    ical: &'a ICalendar,
+    kind: &ICalendarComponentType,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> Vec<Occurrence<'a>> {
+    let mut masters = Vec::new();
+    let mut overrides: HashMap<Option<String>, Vec<&ICalendarComponent>> = HashMap::new();
+
+    for component in &ical.components {
+        if &component.component_type != kind {
+            continue;
+        }
+
+        if component
+            .property(&ICalendarProperty::RecurrenceId)
+            .is_some()
+        {
+            let uid = component_text(component, &ICalendarProperty::Uid);
+            overrides.entry(uid).or_default().push(component);
+        } else {
+            masters.push(component);
+        }
+    }
+
+    let resolver = TzResolver::new(ical);
+    let mut occurrences = Vec::new();
+
+    for master in masters {
+        let uid = component_text(master, &ICalendarProperty::Uid);
+        let overridden = overrides.remove(&uid).unwrap_or_default();
+        expand_master(
+            &resolver,
+            master,
+            uid,
+            &overridden,
+            from,
+            to,
+            &mut occurrences,
+        );
+
+        for component in overridden {
+            push_single(component, from, to, &mut occurrences);
+        }
+    }
+
+    for component in overrides.into_values().flatten() {
+        push_single(component, from, to, &mut occurrences);
+    }
+
+    occurrences.sort_by_key(|occurrence| occurrence.start);
+    occurrences
+}
+
+fn expand_master<'a>(
+    resolver: &TzResolver,
+    master: &'a ICalendarComponent,
+    uid: Option<String>,
+    overridden: &[&ICalendarComponent],
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    out: &mut Vec<Occurrence<'a>>,
+) {
+    let Some(dtstart) = component_date(master, &ICalendarProperty::Dtstart) else {
+        return;
+    };
+
+    let mut rule = component_rule(master);
+
+    let rdates = entry_dates(master, &ICalendarProperty::Rdate);
+
+    if rule.is_none() && rdates.is_empty() {
+        push_single(master, from, to, out);
+        return;
+    }
+
+    let all_day = is_date(&dtstart);
+    let utc = is_utc(&dtstart);
+    let tzid = component_tzid(master, &ICalendarProperty::Dtstart);
+    let Some(start) = naive_date_time(&dtstart) else {
+        return;
+    };
+    let duration = component_end(master, start, all_day) - start;
+
+    // EXDATE and RECURRENCE-ID values may be expressed in another
+    // zone than DTSTART (typically UTC against a TZID), so they are
+    // compared as absolute instants whenever both sides resolve.
+    let instant = |dt: NaiveDateTime, zone: Option<&str>, in_utc: bool| Instant {
+        dt,
+        utc: match all_day {
+            true => None,
+            false => resolver.to_utc(dt, zone, in_utc),
+        },
+    };
+
+    let overridden = overridden.iter().filter_map(|component| {
+        let pdt = component_date(component, &ICalendarProperty::RecurrenceId)?;
+        let tzid = component_tzid(component, &ICalendarProperty::RecurrenceId);
+        Some(instant(
+            naive_date_time(&pdt)?,
+            tzid.as_deref(),
+            is_utc(&pdt),
+        ))
+    });
+
+    let excluded = master
+        .entries
+        .iter()
+        .filter(|entry| entry.name == ICalendarProperty::Exdate)
+        .flat_map(|entry| {
+            entry
+                .values
+                .iter()
+                .filter_map(partial_date_time)
+                .filter_map(|pdt| Some(instant(naive_date_time(pdt)?, entry.tz_id(), is_utc(pdt))))
+        })
+        .chain(overridden)
+        .collect::<Vec<_>>();
+
+    // UNTIL is in UTC whenever DTSTART carries a TZID, so it bounds
+    // the instances as an absolute instant rather than a wall clock.
+    let until = match &mut rule {
+        Some(rule) if rule.until_utc && tzid.is_some() && !utc && !all_day => {
+            rule.until.take().map(|until| until.and_utc())
+        }
+        _ => None,
+    };
+
+    let mut starts = Vec::new();
+    let mut in_window = 0;
+
+    match &rule {
+        Some(rule) => rule.each(start, from.map(|from| from - duration), |instance| {
+            if to.is_some_and(|to| instance >= to) {
+                return false;
+            }
+
+            if let Some(until) = until {
+                let instance = resolver
+                    .to_utc(instance, tzid.as_deref(), false)
+                    .unwrap_or_else(|| instance.and_utc());
+
+                if instance > until {
+                    return false;
+                }
+            }
+
+            if from.is_none_or(|from| instance + duration >= from) {
+                in_window += 1;
+            }
+
+            starts.push(instance);
+            to.is_some() || in_window < MAX_UNBOUNDED_OCCURRENCES
+        }),
+        None => starts.push(start),
+    }
+
+    starts.extend(rdates.iter().filter_map(naive_date_time));
+    starts.sort();
+    starts.dedup();
+
+    for instance in starts {
+        let key = instant(instance, tzid.as_deref(), utc);
+
+        if excluded.iter().any(|excluded| excluded.matches(&key)) {
+            continue;
+        }
+
+        let occurrence = Occurrence {
+            component: master,
+            uid: uid.clone(),
+            recurrence_id: Some(format_date_time(instance, all_day, utc)),
+            start: instance,
+            end: instance + duration,
+            all_day,
+            tzid: tzid.clone(),
+            utc,
+        };
+
+        if occurrence.overlaps(from, to) {
+            out.push(occurrence);
+        }
+    }
+}
+
+/// Instance start as written, along with the absolute instant it
+/// resolves to ([`None`] for floating times and dates).
+struct Instant {
+    dt: NaiveDateTime,
+    utc: Option<DateTime<Utc>>,
+}
+
+impl Instant {
+    /// Whether both starts denote the same instance: the same instant
+    /// when both resolve, the same wall clock otherwise.
+    fn matches(&self, other: &Self) -> bool {
+        match (self.utc, other.utc) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.dt == other.dt,
+        }
+    }
+}
+
+/// Pushes the single instance of `component` when it overlaps the
+/// window.
+fn push_single<'a>(
+    component: &'a ICalendarComponent,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    out: &mut Vec<Occurrence<'a>>,
+) {
+    if let Some(occurrence) = single(component)
+        && occurrence.overlaps(from, to)
+    {
+        out.push(occurrence);
+    }
+}
+
+/// Builds the instance described by the own DTSTART and DTEND (or
+/// DURATION) of `component`, ignoring any recurrence property.
+pub fn single(component: &ICalendarComponent) -> Option<Occurrence<'_>> {
+    let dtstart = component_date(component, &ICalendarProperty::Dtstart)?;
+    let start = naive_date_time(&dtstart)?;
+    let all_day = is_date(&dtstart);
+
+    let recurrence_id =
+        component_date(component, &ICalendarProperty::RecurrenceId).and_then(|pdt| {
+            Some(format_date_time(
+                naive_date_time(&pdt)?,
+                is_date(&pdt),
+                is_utc(&pdt),
+            ))
+        });
+
+    Some(Occurrence {
+        component,
+        uid: component_text(component, &ICalendarProperty::Uid),
+        recurrence_id,
+        start,
+        end: component_end(component, start, all_day),
+        all_day,
+        tzid: component_tzid(component, &ICalendarProperty::Dtstart),
+        utc: is_utc(&dtstart),
+    })
+}
+
+/// Instance of the first `kind` master component of `ical`, without
+/// expanding its recurrence.
+pub fn first<'a>(ical: &'a ICalendar, kind: &ICalendarComponentType) -> Option<Occurrence<'a>> {
+    ical.components
+        .iter()
+        .filter(|c| &c.component_type == kind)
+        .find(|c| c.property(&ICalendarProperty::RecurrenceId).is_none())
+        .and_then(single)
+}
+
+/// Resolves the exclusive end of a component instance starting at
+/// `start`: DTEND when present, otherwise DTSTART plus DURATION,
+/// otherwise one day for all-day components and zero otherwise.
+fn component_end(
+    component: &ICalendarComponent,
+    start: NaiveDateTime,
+    all_day: bool,
+) -> NaiveDateTime {
+    let end = component
+        .property(&ICalendarProperty::Dtend)
+        .or_else(|| component.property(&ICalendarProperty::Due))
+        .and_then(|entry| entry.values.iter().find_map(partial_date_time))
+        .and_then(naive_date_time);
+
+    if let Some(end) = end {
+        return end;
+    }
+
+    let duration = component
+        .property(&ICalendarProperty::Duration)
+        .and_then(|entry| {
+            entry.values.iter().find_map(|value| match value {
+                ICalendarValue::Duration(duration) => Some(time_delta(duration)),
+                _ => None,
+            })
+        });
+
+    match duration {
+        Some(duration) => start + duration,
+        None if all_day => start + TimeDelta::days(1),
+        None => start,
+    }
+}
+
+/// Parses the RRULE of `component`, if any.
+pub fn component_rule(component: &ICalendarComponent) -> Option<Rule> {
+    let entry = component.property(&ICalendarProperty::Rrule)?;
+    entry.values.iter().find_map(|value| match value {
+        ICalendarValue::RecurrenceRule(rule) => Some(Rule::from(rule.as_ref())),
+        _ => None,
+    })
+}
+
+/// Collects every date value of every `name` entry (EXDATE and RDATE
+/// may repeat and carry several values each).
+pub fn entry_dates(
+    component: &ICalendarComponent,
+    name: &ICalendarProperty,
+) -> Vec<PartialDateTime> {
+    component
+        .entries
+        .iter()
+        .filter(|entry: &&ICalendarEntry| &entry.name == name)
+        .flat_map(|entry| entry.values.iter().filter_map(partial_date_time))
+        .cloned()
+        .collect()
+}
+
+fn partial_date_time(value: &ICalendarValue) -> Option<&PartialDateTime> {
+    match value {
+        ICalendarValue::PartialDateTime(pdt) => Some(pdt),
+        _ => None,
+    }
+}
+
+/// First date value of the `name` property of `component`.
+pub fn component_date(
+    component: &ICalendarComponent,
+    name: &ICalendarProperty,
+) -> Option<PartialDateTime> {
+    let entry = component.property(name)?;
+    entry.values.iter().find_map(partial_date_time).cloned()
+}
+
+/// TZID parameter of the `name` property of `component`.
+pub fn component_tzid(component: &ICalendarComponent, name: &ICalendarProperty) -> Option<String> {
+    component.property(name)?.tz_id().map(ToOwned::to_owned)
+}
+
+/// First text value of the `name` property of `component`.
+pub fn component_text(component: &ICalendarComponent, name: &ICalendarProperty) -> Option<String> {
+    let entry = component.property(name)?;
+    entry.values.iter().find_map(|value| match value {
+        ICalendarValue::Text(text) => Some(text.clone()),
+        _ => None,
+    })
+}
+
+/// Every value of every `name` entry of `component` rendered as a
+/// string, whether calcard parsed it as text, integer, status,
+/// transparency, alarm action or URI (CAL-ADDRESS). Other value kinds
+/// are skipped.
+pub fn component_values(component: &ICalendarComponent, name: &ICalendarProperty) -> Vec<String> {
+    component
+        .entries
+        .iter()
+        .filter(|entry| &entry.name == name)
+        .flat_map(|entry| &entry.values)
+        .filter_map(|value| match value {
+            ICalendarValue::Text(text) => Some(text.clone()),
+            ICalendarValue::Integer(n) => Some(n.to_string()),
+            ICalendarValue::Status(status) => Some(status.as_str().to_owned()),
+            ICalendarValue::Transparency(transp) => Some(transp.as_str().to_owned()),
+            ICalendarValue::Action(action) => Some(action.as_str().to_owned()),
+            ICalendarValue::Uri(Uri::Location(uri)) => Some(uri.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `pdt` is a DATE value (no time part).
+pub fn is_date(pdt: &PartialDateTime) -> bool {
+    pdt.hour.is_none()
+}
+
+/// Whether `pdt` is a UTC date-time (trailing `Z`).
+pub fn is_utc(pdt: &PartialDateTime) -> bool {
+    pdt.tz_hour.is_some()
+}
+
+/// Converts a [`PartialDateTime`] into a naive date-time, using
+/// midnight for DATE values.
+pub fn naive_date_time(pdt: &PartialDateTime) -> Option<NaiveDateTime> {
+    let date = NaiveDate::from_ymd_opt(pdt.year? as i32, pdt.month? as u32, pdt.day? as u32)?;
+
+    date.and_hms_opt(
+        pdt.hour.unwrap_or(0) as u32,
+        pdt.minute.unwrap_or(0) as u32,
+        pdt.second.unwrap_or(0) as u32,
+    )
+}
+
+/// Renders a naive date-time in iCalendar wire format: `YYYYMMDD` for
+/// DATE values, `YYYYMMDDTHHMMSS` otherwise, with a trailing `Z` for
+/// UTC.
+pub fn format_date_time(dt: NaiveDateTime, all_day: bool, utc: bool) -> String {
+    match (all_day, utc) {
+        (true, _) => dt.format("%Y%m%d").to_string(),
+        (false, true) => dt.format("%Y%m%dT%H%M%SZ").to_string(),
+        (false, false) => dt.format("%Y%m%dT%H%M%S").to_string(),
+    }
+}
+
+/// Converts a parsed DURATION value into a [`TimeDelta`].
+pub fn time_delta(duration: &ICalendarDuration) -> TimeDelta {
+    let days = duration.weeks as i64 * 7 + duration.days as i64;
+    let seconds = days * 86_400
+        + duration.hours as i64 * 3_600
+        + duration.minutes as i64 * 60
+        + duration.seconds as i64;
+
+    TimeDelta::seconds(if duration.neg { -seconds } else { seconds })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of an RRULE the expansion engine understands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+    /// Whether UNTIL is a UTC date-time (trailing `Z`).
+    pub until_utc: bool,
+    /// `(ordinal, weekday)` pairs, e.g. `(Some(-1), Fri)` for `-1FR`.
+    pub by_day: Vec<(Option<i16>, Weekday)>,
+    pub by_month_day: Vec<i8>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday,
+}
+
+impl Rule {
+    /// Feeds every instance start to `f` in chronological order,
+    /// DTSTART first, until the rule is exhausted (COUNT, UNTIL) or
+    /// `f` returns false. Candidates of the first period falling at or
+    /// before DTSTART are skipped.
+    ///
+    /// Without COUNT, the walk starts at the period before the one
+    /// containing `after`, so that old series do not walk their whole
+    /// history: instances between DTSTART and that period are then
+    /// skipped. With COUNT, every period has to be walked to count the
+    /// instances.
+    pub fn each(
+        &self,
+        dtstart: NaiveDateTime,
+        after: Option<NaiveDateTime>,
+        mut f: impl FnMut(NaiveDateTime) -> bool,
+    ) {
+        if !f(dtstart) {
+            return;
+        }
+
+        let mut emitted = 1;
+        let first = self.first_period(dtstart, after);
+
+        for period in first..first.saturating_add(MAX_PERIODS) {
+            let Some(mut candidates) = self.candidates(dtstart, period) else {
+                return;
+            };
+
+            candidates.sort();
+            candidates.dedup();
+
+            if !self.by_set_pos.is_empty() {
+                candidates = select_set_pos(&candidates, &self.by_set_pos);
+            }
+
+            for instance in candidates {
+                if instance <= dtstart {
+                    continue;
+                }
+
+                if self.until.is_some_and(|until| instance > until) {
+                    return;
+                }
+
+                if self.count.is_some_and(|count| emitted >= count) {
+                    return;
+                }
+
+                emitted += 1;
+
+                if !f(instance) {
+                    return;
+                }
+            }
+        }
+
+        warn!("recurrence rule expansion stopped after {MAX_PERIODS} periods");
+    }
+
+    /// Index of the period to start walking from for instances after
+    /// `after`: one period early, since periods do not all start at
+    /// the time of DTSTART (weeks start at WKST, months at their first
+    /// day). Always 0 when the rule has a COUNT.
+    fn first_period(&self, dtstart: NaiveDateTime, after: Option<NaiveDateTime>) -> u32 {
+        let Some(after) = after.filter(|after| self.count.is_none() && *after > dtstart) else {
+            return 0;
+        };
+
+        let elapsed = after - dtstart;
+        let months = |after: NaiveDateTime| {
+            (after.year() - dtstart.year()) as i64 * 12 + after.month() as i64
+                - dtstart.month() as i64
+        };
+
+        let units = match self.freq {
+            Frequency::Secondly => elapsed.num_seconds(),
+            Frequency::Minutely => elapsed.num_minutes(),
+            Frequency::Hourly => elapsed.num_hours(),
+            Frequency::Daily => elapsed.num_days(),
+            Frequency::Weekly => elapsed.num_weeks(),
+            Frequency::Monthly => months(after),
+            Frequency::Yearly => (after.year() - dtstart.year()) as i64,
+        };
+
+        let period = units / self.interval as i64 - 1;
+        u32::try_from(period.max(0)).unwrap_or(u32::MAX)
+    }
+
+    /// Builds the candidate instances of the `period`-th rule period,
+    /// period 0 being the one containing `dtstart`. Returns [`None`]
+    /// once the period falls out of the representable date range.
+    fn candidates(&self, dtstart: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let step = period.checked_mul(self.interval)?;
+        let date = dtstart.date();
+        let time = dtstart.time();
+
+        let dates = match self.freq {
+            Frequency::Secondly | Frequency::Minutely | Frequency::Hourly => {
+                let unit = match self.freq {
+                    Frequency::Secondly => 1,
+                    Frequency::Minutely => 60,
+                    _ => 3_600,
+                };
+
+                let instance =
+                    dtstart.checked_add_signed(TimeDelta::try_seconds(step as i64 * unit)?)?;
+
+                let keep = self.matches_month(instance.date())
+                    && self.matches_month_day(instance.date())
+                    && self.matches_weekday(instance.date());
+
+                return Some(if keep { vec![instance] } else { Vec::new() });
+            }
+            Frequency::Daily => {
+                let day = date.checked_add_days(Days::new(step as u64))?;
+
+                let keep = self.matches_month(day)
+                    && self.matches_month_day(day)
+                    && self.matches_weekday(day);
+
+                if keep { vec![day] } else { Vec::new() }
+            }
+            Frequency::Weekly => {
+                let offset = days_from(self.week_start, date.weekday());
+                let week = date
+                    .checked_sub_days(Days::new(offset as u64))?
+                    .checked_add_days(Days::new(step as u64 * 7))?;
+
+                (0..7)
+                    .filter_map(|i| week.checked_add_days(Days::new(i)))
+                    .filter(|day| match self.by_day.is_empty() {
+                        true => day.weekday() == date.weekday(),
+                        false => self.matches_weekday(*day),
+                    })
+                    .filter(|day| self.matches_month(*day))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let first = first_of_month(date).checked_add_months(Months::new(step))?;
+
+                if !self.matches_month(first) {
+                    Vec::new()
+                } else {
+                    self.month_days(first, date.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = date.year().checked_add(step as i32)?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+
+                if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .filter_map(|month| NaiveDate::from_ymd_opt(year, *month, 1))
+                        .flat_map(|first| self.month_days(first, date.day()))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    (1..=12)
+                        .filter_map(|month| NaiveDate::from_ymd_opt(year, month, 1))
+                        .flat_map(|first| self.month_days(first, date.day()))
+                        .collect()
+                } else if !self.by_day.is_empty() {
+                    let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+                    self.weekdays_between(first, last)
+                } else {
+                    NaiveDate::from_ymd_opt(year, date.month(), date.day())
+                        .into_iter()
+                        .collect()
+                }
+            }
+        };
+
+        Some(dates.into_iter().map(|day| day.and_time(time)).collect())
+    }
+
+    /// Candidate days of the month starting at `first`: BYMONTHDAY
+    /// (limited by BYDAY), otherwise BYDAY, otherwise `default_day`.
+    fn month_days(&self, first: NaiveDate, default_day: u32) -> Vec<NaiveDate> {
+        let len = days_in_month(first);
+
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .filter_map(|day| match *day {
+                    day if day > 0 && day as u32 <= len => Some(day as u32),
+                    day if day < 0 && (-day) as u32 <= len => Some(len + 1 - (-day) as u32),
+                    _ => None,
+                })
+                .filter_map(|day| first.with_day(day))
+                .filter(|day| self.matches_weekday(*day))
+                .collect();
+        }
+
+        if !self.by_day.is_empty() {
+            let Some(last) = first.with_day(len) else {
+                return Vec::new();
+            };
+
+            return self.weekdays_between(first, last);
+        }
+
+        first.with_day(default_day).into_iter().collect()
+    }
+
+    /// Days between `first` and `last` (inclusive) matching BYDAY,
+    /// ordinals counted within that span.
+    fn weekdays_between(&self, first: NaiveDate, last: NaiveDate) -> Vec<NaiveDate> {
+        let mut days = Vec::new();
+
+        for (ordinal, weekday) in &self.by_day {
+            let matching: Vec<NaiveDate> = first
+                .iter_days()
+                .take_while(|day| *day <= last)
+                .filter(|day| day.weekday() == *weekday)
+                .collect();
+
+            match ordinal {
+                None | Some(0) => days.extend(matching),
+                Some(n) if *n > 0 => days.extend(matching.get(*n as usize - 1)),
+                Some(n) => days.extend(
+                    matching
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .and_then(|i| matching.get(i)),
+                ),
+            }
+        }
+
+        days
+    }
+
+    fn matches_month(&self, day: NaiveDate) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&day.month())
+    }
+
+    fn matches_month_day(&self, day: NaiveDate) -> bool {
+        if self.by_month_day.is_empty() {
+            return true;
+        }
+
+        let len = days_in_month(day) as i8;
+        let d = day.day() as i8;
+        self.by_month_day
+            .iter()
+            .any(|n| *n == d || *n == d - len - 1)
+    }
+
+    fn matches_weekday(&self, day: NaiveDate) -> bool {
+        self.by_day.is_empty() || self.by_day.iter().any(|(_, wd)| *wd == day.weekday())
+    }
+}
+
+impl From<&ICalendarRecurrenceRule> for Rule {
+    fn from(rule: &ICalendarRecurrenceRule) -> Self {
+        Self {
+            freq: match rule.freq {
+                ICalendarFrequency::Secondly => Frequency::Secondly,
+                ICalendarFrequency::Minutely => Frequency::Minutely,
+                ICalendarFrequency::Hourly => Frequency::Hourly,
+                ICalendarFrequency::Daily => Frequency::Daily,
+                ICalendarFrequency::Weekly => Frequency::Weekly,
+                ICalendarFrequency::Monthly => Frequency::Monthly,
+                ICalendarFrequency::Yearly => Frequency::Yearly,
+            },
+            interval: rule.interval.map(u32::from).unwrap_or(1).max(1),
+            count: rule.count,
+            until: rule.until.as_ref().and_then(naive_date_time),
+            until_utc: rule.until.as_ref().is_some_and(is_utc),
+            by_day: rule
+                .byday
+                .iter()
+                .map(|day| (day.ordwk, weekday(&day.weekday)))
+                .collect(),
+            by_month_day: rule.bymonthday.clone(),
+            by_month: rule.bymonth.iter().map(|m| m.month() as u32).collect(),
+            by_set_pos: rule.bysetpos.clone(),
+            week_start: rule.wkst.as_ref().map(weekday).unwrap_or(Weekday::Mon),
+        }
+    }
+}
+
+fn weekday(weekday: &ICalendarWeekday) -> Weekday {
+    match weekday {
+        ICalendarWeekday::Monday => Weekday::Mon,
+        ICalendarWeekday::Tuesday => Weekday::Tue,
+        ICalendarWeekday::Wednesday => Weekday::Wed,
+        ICalendarWeekday::Thursday => Weekday::Thu,
+        ICalendarWeekday::Friday => Weekday::Fri,
+        ICalendarWeekday::Saturday => Weekday::Sat,
+        ICalendarWeekday::Sunday => Weekday::Sun,
+    }
+}
+
+fn select_set_pos(candidates: &[NaiveDateTime], positions: &[i32]) -> Vec<NaiveDateTime> {
+    let len = candidates.len() as i32;
+
+    let mut selected: Vec<NaiveDateTime> = positions
+        .iter()
+        .filter_map(|pos| match *pos {
+            pos if pos > 0 && pos <= len => Some(candidates[pos as usize - 1]),
+            pos if pos < 0 && -pos <= len => Some(candidates[(len + pos) as usize]),
+            _ => None,
+        })
+        .collect();
+
+    selected.sort();
+    selected.dedup();
+    selected
+}
+
+/// Number of days from `start` forward to `weekday` (0 to 6).
+fn days_from(start: Weekday, weekday: Weekday) -> u32 {
+    (weekday.num_days_from_monday() + 7 - start.num_days_from_monday()) % 7
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = first_of_month(date);
+    first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn rule(freq: Frequency) -> Rule {
+        Rule {
+            freq,
+            interval: 1,
+            count: None,
+            until: None,
+            until_utc: false,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        }
+    }
+
+    fn collect(rule: &Rule, dtstart: NaiveDateTime, max: usize) -> Vec<NaiveDateTime> {
+        let mut out = Vec::new();
+        rule.each(dtstart, None, |dt| {
+            out.push(dt);
+            out.len() < max
+        });
+        out
+    }
+
+    #[test]
+    fn weekly_with_count() {
+        let mut r = rule(Frequency::Weekly);
+        r.count = Some(3);
+
+        let out = collect(&r, at(2026, 2, 2, 9), 10);
+        assert_eq!(
+            out,
+            vec![at(2026, 2, 2, 9), at(2026, 2, 9, 9), at(2026, 2, 16, 9)]
+        );
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval() {
+        let mut r = rule(Frequency::Weekly);
+        r.interval = 2;
+        r.by_day = vec![(None, Weekday::Mon), (None, Weekday::Thu)];
+
+        let out = collect(&r, at(2026, 2, 2, 9), 4);
+        assert_eq!(
+            out,
+            vec![
+                at(2026, 2, 2, 9),
+                at(2026, 2, 5, 9),
+                at(2026, 2, 16, 9),
+                at(2026, 2, 19, 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_until_is_inclusive() {
+        let mut r = rule(Frequency::Daily);
+        r.until = Some(at(2026, 2, 4, 9));
+
+        let out = collect(&r, at(2026, 2, 2, 9), 10);
+        assert_eq!(
+            out,
+            vec![at(2026, 2, 2, 9), at(2026, 2, 3, 9), at(2026, 2, 4, 9)]
+        );
+    }
+
+    #[test]
+    fn old_series_start_near_the_window() {
+        let r = rule(Frequency::Hourly);
+        let after = at(2026, 2, 2, 9);
+
+        // 40 years of hourly periods, far more than MAX_PERIODS.
+        let mut out = Vec::new();
+        r.each(at(1986, 1, 1, 0), Some(after), |dt| {
+            out.push(dt);
+            out.len() < 3
+        });
+        assert_eq!(
+            out,
+            vec![at(1986, 1, 1, 0), at(2026, 2, 2, 8), at(2026, 2, 2, 9)]
+        );
+
+        let mut weekly = rule(Frequency::Weekly);
+        weekly.interval = 2;
+        weekly.by_day = vec![(None, Weekday::Tue), (None, Weekday::Thu)];
+        let dtstart = at(2024, 1, 2, 9);
+        let expected: Vec<_> = collect(&weekly, dtstart, 200)
+            .into_iter()
+            .filter(|dt| *dt >= after)
+            .collect();
+
+        let mut out = Vec::new();
+        weekly.each(dtstart, Some(after), |dt| {
+            if dt >= after {
+                out.push(dt);
+            }
+            dt < expected[expected.len() - 1]
+        });
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn utc_until_bounds_zoned_instances() {
+        let ics = concat!(
+            "BEGIN:VCALENDAR\r\n",
+            "BEGIN:VEVENT\r\n",
+            "UID:standup\r\n",
+            "DTSTART;TZID=Europe/Paris:20260202T090000\r\n",
+            "DTEND;TZID=Europe/Paris:20260202T093000\r\n",
+            "RRULE:FREQ=DAILY;UNTIL=20260204T080000Z\r\n",
+            "END:VEVENT\r\n",
+            "END:VCALENDAR\r\n",
+        );
+        let ical = ICalendar::parse(ics).unwrap();
+
+        // 09:00 in Paris is 08:00 UTC: the last instance is included.
+        let starts: Vec<_> = expand(&ical, &ICalendarComponentType::VEvent, None, None)
+            .into_iter()
+            .map(|occurrence| occurrence.start)
+            .collect();
+        assert_eq!(
+            starts,
+            vec![at(2026, 2, 2, 9), at(2026, 2, 3, 9), at(2026, 2, 4, 9)]
+        );
+
+        // West of UTC, 09:00 in New York is 14:00 UTC: UNTIL at 10:00
+        // UTC on the 4th stops the series on the 3rd.
+        let ics = ics
+            .replace("Europe/Paris", "America/New_York")
+            .replace("T080000Z", "T100000Z");
+        let ical = ICalendar::parse(&ics).unwrap();
+        let starts: Vec<_> = expand(&ical, &ICalendarComponentType::VEvent, None, None)
+            .into_iter()
+            .map(|occurrence| occurrence.start)
+            .collect();
+        assert_eq!(starts, vec![at(2026, 2, 2, 9), at(2026, 2, 3, 9)]);
+    }
+
+    #[test]
+    fn monthly_last_friday() {
+        let mut r = rule(Frequency::Monthly);
+        r.by_day = vec![(Some(-1), Weekday::Fri)];
+
+        let out = collect(&r, at(2026, 1, 30, 16), 3);
+        assert_eq!(
+            out,
+            vec![
+                at(2026, 1, 30, 16),
+                at(2026, 2, 27, 16),
+                at(2026, 3, 27, 16)
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_by_month_day_within_first_month() {
+        let mut r = rule(Frequency::Monthly);
+        r.by_month_day = vec![1, 15];
+
+        let out = collect(&r, at(2026, 1, 1, 10), 4);
+        assert_eq!(
+            out,
+            vec![
+                at(2026, 1, 1, 10),
+                at(2026, 1, 15, 10),
+                at(2026, 2, 1, 10),
+                at(2026, 2, 15, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_skips_missing_days() {
+        let r = rule(Frequency::Monthly);
+
+        let out = collect(&r, at(2026, 1, 31, 8), 3);
+        assert_eq!(
+            out,
+            vec![at(2026, 1, 31, 8), at(2026, 3, 31, 8), at(2026, 5, 31, 8)]
+        );
+    }
+
+    #[test]
+    fn monthly_last_weekday_with_set_pos() {
+        let mut r = rule(Frequency::Monthly);
+        r.by_day = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ]
+        .into_iter()
+        .map(|wd| (None, wd))
+        .collect();
+        r.by_set_pos = vec![-1];
+
+        let out = collect(&r, at(2026, 1, 30, 17), 3);
+        assert_eq!(
+            out,
+            vec![
+                at(2026, 1, 30, 17),
+                at(2026, 2, 27, 17),
+                at(2026, 3, 31, 17)
+            ]
+        );
+    }
+
+    #[test]
+    fn yearly_by_month_and_ordinal_weekday() {
+        let mut r = rule(Frequency::Yearly);
+        r.by_month = vec![11];
+        r.by_day = vec![(Some(4), Weekday::Thu)];
+
+        let out = collect(&r, at(2026, 11, 26, 12), 2);
+        assert_eq!(out, vec![at(2026, 11, 26, 12), at(2027, 11, 25, 12)]);
+    }
+
+    #[test]
+    fn wire_format() {
+        assert_eq!(
+            format_date_time(at(2026, 2, 2, 9), false, true),
+            "20260202T090000Z"
+        );
+        assert_eq!(
+            format_date_time(at(2026, 2, 2, 9), false, false),
+            "20260202T090000"
+        );
+        assert_eq!(format_date_time(at(2026, 2, 2, 0), true, false), "20260202");
+    }
+}
@@ -0,0 +1,111 @@
+//! JSON transport of the backends speaking plain HTTP (`graph`,
+//! `jmap`, `google`), over the io-webdav client.
+//!
+//! Each request opens its own connection, like the ics feed download,
+//! authenticated by the io-http scheme the client was built with.
+
+use anyhow::{Context, Result, bail};
+use io_webdav::{client::WebdavClientStd, rfc4918::WebdavAuth};
+use log::debug;
+use pimalaya_stream::tls::Tls;
+use serde::de::DeserializeOwned;
+use serde_json::Value as Json;
+use url::Url;
+
+use crate::shared::client::Conflict;
+
+/// Client sending JSON requests with the same authentication.
+pub struct RestClient {
+    tls: Tls,
+    auth: WebdavAuth,
+}
+
+/// Response of a [`RestClient`] request, body fully read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RestResponse {
+    pub status: u16,
+    /// Target of a redirection.
+    pub location: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl RestClient {
+    pub fn new(tls: Tls, auth: WebdavAuth) -> Self {
+        Self { tls, auth }
+    }
+
+    /// Sends a `method` request to `url` with `headers` and an
+    /// optional JSON `body`, whatever the response status.
+    pub fn send(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &[(&str, String)],
+        body: Option<&Json>,
+    ) -> Result<RestResponse> {
+        debug!("{method} {url}");
+
+        let mut headers = headers.to_vec();
+        headers.push(("Accept", "application/json".into()));
+
+        let body = match body {
+            Some(body) => {
+                headers.push(("Content-Type", "application/json".into()));
+                serde_json::to_vec(body).context("Serialize JSON request error")?
+            }
+            None => Vec::new(),
+        };
+
+        let mut client = WebdavClientStd::connect(url, &self.tls, self.auth.clone())?;
+        let response = client
+            .send(method, url, &headers, &body)
+            .with_context(|| format!("{method} `{url}` error"))?;
+
+        debug!("{method} {url}: {}", response.status);
+
+        Ok(RestResponse {
+            status: response.status,
+            location: response.header("Location").map(ToOwned::to_owned),
+            body: response.body,
+        })
+    }
+
+    /// Same as [`RestClient::send`], bailing unless the response is
+    /// successful.
+    pub fn send_json(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &[(&str, String)],
+        body: Option<&Json>,
+    ) -> Result<RestResponse> {
+        self.send(method, url, headers, body)?.check(method, url)
+    }
+}
+
+impl RestResponse {
+    /// Bails with the status and the start of the body unless the
+    /// response is successful. A failed precondition is a
+    /// [`Conflict`].
+    pub fn check(self, method: &str, url: &Url) -> Result<Self> {
+        if (200..300).contains(&self.status) {
+            return Ok(self);
+        }
+
+        if self.status == 412 {
+            return Err(Conflict.into());
+        }
+
+        let body = String::from_utf8_lossy(&self.body);
+        let body: String = body.trim().chars().take(500).collect();
+        bail!(
+            "{method} `{url}` failed with status {}: {body}",
+            self.status
+        )
+    }
+
+    /// Deserializes the JSON body.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("Parse JSON response error")
+    }
+}
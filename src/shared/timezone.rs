@@ -0,0 +1,636 @@
+//! Timezone resolution for rendered times.
+//!
+//! iCalendar times come in three flavours: UTC (trailing `Z`), local
+//! to a TZID (an embedded VTIMEZONE or, in practice, an IANA name), or
+//! floating. [`TzResolver`] maps the first two to an absolute instant,
+//! and [`Timezone`] converts that instant into the wall clock the user
+//! asked for (`--tz`, then `event.timezone`, then the local zone).
+//! Floating times and DATE values are rendered as is.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use anyhow::{Error, bail};
+use chrono::{
+    DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone,
+    Utc,
+};
+use chrono_tz::OffsetComponents;
+use io_calendar::calcard::{
+    common::PartialDateTime,
+    icalendar::{ICalendar, ICalendarComponent, ICalendarComponentType, ICalendarProperty},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::shared::{
+    ical::IcalWriter,
+    recurrence::{
+        self, Occurrence, Rule, component_date, component_rule, component_text, entry_dates,
+    },
+};
+
+/// Target zone every displayed time is converted into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timezone {
+    /// The zone of the machine running calendula.
+    #[default]
+    Local,
+    /// An IANA zone (`Europe/Paris`, `UTC`).
+    Tz(chrono_tz::Tz),
+}
+
+impl Timezone {
+    /// IANA zone of this zone: the system zone for [`Timezone::Local`],
+    /// when the platform names it.
+    pub fn iana(&self) -> Option<chrono_tz::Tz> {
+        match self {
+            Self::Local => iana_time_zone::get_timezone()
+                .ok()
+                .and_then(|name| iana(&name)),
+            Self::Tz(tz) => Some(*tz),
+        }
+    }
+
+    /// Converts an absolute instant into this zone's wall clock.
+    pub fn from_utc(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Self::Local => utc.with_timezone(&Local).naive_local(),
+            Self::Tz(tz) => utc.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Converts a wall-clock time of this zone into an absolute
+    /// instant. Times falling in a DST gap are shifted forward.
+    pub fn to_utc(&self, dt: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            Self::Local => local_to_utc(&Local, dt),
+            Self::Tz(tz) => local_to_utc(tz, dt),
+        }
+    }
+
+    /// Converts `dt`, expressed in the `tzid` zone (or UTC), into this
+    /// zone's wall clock. Floating and unresolvable times are returned
+    /// untouched.
+    pub fn localize(
+        &self,
+        resolver: &TzResolver,
+        dt: NaiveDateTime,
+        tzid: Option<&str>,
+        utc: bool,
+    ) -> NaiveDateTime {
+        match resolver.to_utc(dt, tzid, utc) {
+            Some(utc) => self.from_utc(utc),
+            None => dt,
+        }
+    }
+
+    /// Converts the bounds of `occurrence` into this zone's wall clock.
+    /// All-day occurrences are dates, which belong to no zone, so they
+    /// are returned untouched.
+    pub fn localize_occurrence(
+        &self,
+        resolver: &TzResolver,
+        occurrence: &Occurrence<'_>,
+    ) -> (NaiveDateTime, NaiveDateTime) {
+        if occurrence.all_day {
+            return (occurrence.start, occurrence.end);
+        }
+
+        let tzid = occurrence.tzid.as_deref();
+        let start = self.localize(resolver, occurrence.start, tzid, occurrence.utc);
+        let end = self.localize(resolver, occurrence.end, tzid, occurrence.utc);
+        (start, end)
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = Error;
+
+    fn from_str(tz: &str) -> Result<Self, Self::Err> {
+        if tz.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+
+        match tz.parse::<chrono_tz::Tz>() {
+            Ok(tz) => Ok(Self::Tz(tz)),
+            Err(_) => bail!("Invalid timezone `{tz}`; expected `local` or an IANA name"),
+        }
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Tz(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl Serialize for Timezone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timezone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tz = String::deserialize(deserializer)?;
+        tz.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Resolves the TZIDs of one parsed iCalendar object.
+///
+/// IANA names win over embedded VTIMEZONE definitions: they carry the
+/// full transition history, whereas a VTIMEZONE often only describes
+/// the current rules. Embedded definitions cover everything else
+/// (Outlook's Windows zone names, custom zones).
+#[derive(Clone, Debug, Default)]
+pub struct TzResolver {
+    zones: HashMap<String, Zone>,
+}
+
+impl TzResolver {
+    pub fn new(ical: &ICalendar) -> Self {
+        let mut zones = HashMap::new();
+
+        for component in &ical.components {
+            if component.component_type != ICalendarComponentType::VTimezone {
+                continue;
+            }
+
+            let Some(tzid) = component_text(component, &ICalendarProperty::Tzid) else {
+                continue;
+            };
+
+            let zone = match iana(&tzid) {
+                Some(tz) => Zone::Tz(tz),
+                None => Zone::Custom(observances(ical, component)),
+            };
+
+            zones.insert(tzid, zone);
+        }
+
+        Self { zones }
+    }
+
+    /// Converts `dt`, local to `tzid` (or UTC when `utc` is set), into
+    /// an absolute instant. Returns [`None`] for floating times and
+    /// unknown TZIDs.
+    pub fn to_utc(
+        &self,
+        dt: NaiveDateTime,
+        tzid: Option<&str>,
+        utc: bool,
+    ) -> Option<DateTime<Utc>> {
+        if utc {
+            return Some(dt.and_utc());
+        }
+
+        let tzid = tzid?;
+
+        match self.zones.get(tzid) {
+            Some(Zone::Tz(tz)) => Some(local_to_utc(tz, dt)),
+            Some(Zone::Custom(observances)) => {
+                let offset = custom_offset(observances, dt)?;
+                Some((dt - TimeDelta::seconds(offset)).and_utc())
+            }
+            None => iana(tzid).map(|tz| local_to_utc(&tz, dt)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Zone {
+    Tz(chrono_tz::Tz),
+    Custom(Vec<Observance>),
+}
+
+/// One STANDARD or DAYLIGHT sub-component of a VTIMEZONE.
+#[derive(Clone, Debug)]
+struct Observance {
+    start: NaiveDateTime,
+    rule: Option<Rule>,
+    rdates: Vec<NaiveDateTime>,
+    offset_from: i64,
+    offset_to: i64,
+}
+
+impl Observance {
+    /// Latest onset of this observance at or before `dt`.
+    fn last_onset(&self, dt: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut last = None;
+
+        match &self.rule {
+            Some(rule) => rule.each(self.start, None, |onset| {
+                if onset > dt {
+                    return false;
+                }
+                last = Some(onset);
+                true
+            }),
+            None if self.start <= dt => last = Some(self.start),
+            None => (),
+        }
+
+        let rdate = self.rdates.iter().filter(|rdate| **rdate <= dt).max();
+        last.max(rdate.copied())
+    }
+}
+
+fn observances(ical: &ICalendar, vtimezone: &ICalendarComponent) -> Vec<Observance> {
+    vtimezone
+        .component_ids
+        .iter()
+        .filter_map(|id| ical.components.get(*id as usize))
+        .filter(|c| {
+            matches!(
+                c.component_type,
+                ICalendarComponentType::Standard | ICalendarComponentType::Daylight
+            )
+        })
+        .filter_map(|c| {
+            let start = component_date(c, &ICalendarProperty::Dtstart)?;
+
+            Some(Observance {
+                start: recurrence::naive_date_time(&start)?,
+                rule: component_rule(c),
+                rdates: entry_dates(c, &ICalendarProperty::Rdate)
+                    .iter()
+                    .filter_map(recurrence::naive_date_time)
+                    .collect(),
+                offset_from: component_offset(c, &ICalendarProperty::Tzoffsetfrom)?,
+                offset_to: component_offset(c, &ICalendarProperty::Tzoffsetto)?,
+            })
+        })
+        .collect()
+}
+
+/// Reads a UTC-OFFSET property as seconds east of UTC. calcard parses
+/// offsets into the zone fields of a [`PartialDateTime`].
+fn component_offset(component: &ICalendarComponent, name: &ICalendarProperty) -> Option<i64> {
+    let pdt: PartialDateTime = component_date(component, name)?;
+    let seconds = pdt.tz_hour? as i64 * 3_600 + pdt.tz_minute.unwrap_or(0) as i64 * 60;
+    Some(if pdt.tz_minus { -seconds } else { seconds })
+}
+
+/// Offset in effect at local time `dt`: the TZOFFSETTO of the latest
+/// observance onset, or the TZOFFSETFROM of the earliest observance
+/// when `dt` predates them all.
+fn custom_offset(observances: &[Observance], dt: NaiveDateTime) -> Option<i64> {
+    let latest = observances
+        .iter()
+        .filter_map(|o| Some((o.last_onset(dt)?, o.offset_to)))
+        .max_by_key(|(onset, _)| *onset);
+
+    match latest {
+        Some((_, offset)) => Some(offset),
+        None => observances
+            .iter()
+            .min_by_key(|o| o.start)
+            .map(|o| o.offset_from),
+    }
+}
+
+/// Resolves a TZID to an IANA zone, tolerating the prefixed forms some
+/// producers emit (`/mozilla.org/20050126_1/Europe/Paris`).
+fn iana(tzid: &str) -> Option<chrono_tz::Tz> {
+    let segments: Vec<&str> = tzid.trim_matches('/').split('/').collect();
+
+    (0..segments.len()).find_map(|i| segments[i..].join("/").parse().ok())
+}
+
+fn local_to_utc<Tz: TimeZone>(tz: &Tz, dt: NaiveDateTime) -> DateTime<Utc> {
+    let mut local = dt;
+
+    // A wall-clock time inside a DST gap does not exist: walk forward
+    // until the zone accepts it, like calendar clients do.
+    for _ in 0..4 {
+        if let Some(resolved) = tz.from_local_datetime(&local).earliest() {
+            return resolved.with_timezone(&Utc);
+        }
+        local += TimeDelta::minutes(30);
+    }
+
+    dt.and_utc()
+}
+
+/// Renders a converted time for display: `YYYY-MM-DD HH:MM`, or
+/// `YYYY-MM-DD` for DATE values.
+pub fn format_date_time(dt: NaiveDateTime, all_day: bool) -> String {
+    if all_day {
+        dt.format("%Y-%m-%d").to_string()
+    } else {
+        dt.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+/// Writes a VTIMEZONE for the IANA zone `tz`, starting in `year`.
+///
+/// Calendula itself and most clients resolve the IANA TZID directly;
+/// the definition is there for the ones that only trust embedded
+/// zones. Each UTC offset transition of `year` becomes a STANDARD or
+/// DAYLIGHT observance, with a yearly RRULE (`BYMONTH` plus an
+/// ordinal `BYDAY`) whenever the same rule also gives the transition
+/// of the next year, so that recurring events keep following the DST
+/// changes. Zones without transition get a single observance.
+pub fn write_vtimezone(writer: &mut IcalWriter, tz: chrono_tz::Tz, year: i32) {
+    let this_year = transitions(tz, year);
+    let next_year = transitions(tz, year + 1);
+
+    writer.begin("VTIMEZONE").text("TZID", tz.name());
+
+    if this_year.is_empty()
+        && let Some(start) = NaiveDate::from_ymd_opt(year, 1, 1)
+    {
+        let start = start.and_time(NaiveTime::MIN);
+        let (offset, dst) = utc_offset(tz, start.and_utc());
+        let start = start + TimeDelta::seconds(offset);
+        write_observance(writer, start, offset, offset, dst, None);
+    }
+
+    for (onset, from, to, dst) in this_year {
+        let next = next_year
+            .iter()
+            .find(|(_, next_from, next_to, next_dst)| {
+                (next_from, next_to, next_dst) == (&from, &to, &dst)
+            })
+            .map(|(onset, ..)| *onset);
+        let rule = next.and_then(|next| yearly_rule(onset, next));
+        write_observance(writer, onset, from, to, dst, rule.as_deref());
+    }
+
+    writer.end("VTIMEZONE");
+}
+
+/// UTC offset of `tz` at `utc`, in seconds, and whether it is DST.
+fn utc_offset(tz: chrono_tz::Tz, utc: DateTime<Utc>) -> (i64, bool) {
+    let offset = tz.offset_from_utc_datetime(&utc.naive_utc());
+    let dst = !offset.dst_offset().is_zero();
+    (i64::from(offset.fix().local_minus_utc()), dst)
+}
+
+/// UTC offset transitions of `tz` during `year`: their onset in the
+/// wall clock of the previous offset, the offsets before and after,
+/// and whether the new offset is DST.
+fn transitions(tz: chrono_tz::Tz, year: i32) -> Vec<(NaiveDateTime, i64, i64, bool)> {
+    let Some(start) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+        return Vec::new();
+    };
+
+    let mut transitions = Vec::new();
+    let mut day = start.and_time(NaiveTime::MIN).and_utc();
+
+    while day.year() == year {
+        let next = day + TimeDelta::days(1);
+        let (from, _) = utc_offset(tz, day);
+        let (to, dst) = utc_offset(tz, next);
+
+        if from != to {
+            // Narrow the transition down to the minute.
+            let (mut lo, mut hi) = (0, 24 * 60);
+
+            while hi - lo > 1 {
+                let mid = (lo + hi) / 2;
+
+                if utc_offset(tz, day + TimeDelta::minutes(mid)).0 == from {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+
+            let onset = day + TimeDelta::minutes(hi);
+            let onset = onset.naive_utc() + TimeDelta::seconds(from);
+            transitions.push((onset, from, to, dst));
+        }
+
+        day = next;
+    }
+
+    transitions
+}
+
+/// Yearly RRULE giving `onset` then `next`, a year later: same month,
+/// time and weekday, at the same ordinal (`2SU`) or last (`-1SU`)
+/// position in the month.
+fn yearly_rule(onset: NaiveDateTime, next: NaiveDateTime) -> Option<String> {
+    let date = onset.date();
+    let weekday = date.weekday();
+
+    if next.time() != onset.time() || next.month() != date.month() || next.weekday() != weekday {
+        return None;
+    }
+
+    let ordinal = (date.day() as i32 - 1) / 7 + 1;
+    let last = date.day() + 7 > days_in_month(date);
+    let next_ordinal = (next.day() as i32 - 1) / 7 + 1;
+    let next_last = next.day() + 7 > days_in_month(next.date());
+
+    let ordinal = match (last && next_last, ordinal == next_ordinal) {
+        (true, _) => -1,
+        (false, true) => ordinal,
+        (false, false) => return None,
+    };
+
+    let weekday = weekday.to_string()[..2].to_ascii_uppercase();
+    Some(format!(
+        "FREQ=YEARLY;BYMONTH={};BYDAY={ordinal}{weekday}",
+        date.month()
+    ))
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+fn write_observance(
+    writer: &mut IcalWriter,
+    dtstart: NaiveDateTime,
+    from: i64,
+    to: i64,
+    dst: bool,
+    rule: Option<&str>,
+) {
+    let kind = if dst { "DAYLIGHT" } else { "STANDARD" };
+
+    writer
+        .begin(kind)
+        .property("DTSTART", &[], &dtstart.format("%Y%m%dT%H%M%S").to_string())
+        .property("TZOFFSETFROM", &[], &format_utc_offset(from))
+        .property("TZOFFSETTO", &[], &format_utc_offset(to));
+
+    if let Some(rule) = rule {
+        writer.property("RRULE", &[], rule);
+    }
+
+    writer.end(kind);
+}
+
+/// Formats a UTC offset in seconds as `+HHMM` (or `+HHMMSS`).
+fn format_utc_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let (hours, minutes, seconds) = (offset / 3_600, offset % 3_600 / 60, offset % 60);
+
+    if seconds == 0 {
+        format!("{sign}{hours:02}{minutes:02}")
+    } else {
+        format!("{sign}{hours:02}{minutes:02}{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_target_zone() {
+        assert_eq!("local".parse::<Timezone>().unwrap(), Timezone::Local);
+        assert_eq!(
+            "Europe/Paris".parse::<Timezone>().unwrap(),
+            Timezone::Tz(chrono_tz::Europe::Paris)
+        );
+        assert!("Mars/Olympus".parse::<Timezone>().is_err());
+    }
+
+    #[test]
+    fn iana_tolerates_prefixes() {
+        assert_eq!(iana("Europe/Paris"), Some(chrono_tz::Europe::Paris));
+        assert_eq!(
+            iana("/mozilla.org/20050126_1/Europe/Paris"),
+            Some(chrono_tz::Europe::Paris)
+        );
+        assert_eq!(iana("W. Europe Standard Time"), None);
+    }
+
+    #[test]
+    fn tzid_to_target_zone() {
+        let resolver = TzResolver::default();
+        let target = Timezone::Tz(chrono_tz::America::New_York);
+
+        // 09:00 in Paris (UTC+1 in winter) is 03:00 in New York (UTC-5).
+        let dt = target.localize(&resolver, at(2026, 2, 2, 9), Some("Europe/Paris"), false);
+        assert_eq!(dt, at(2026, 2, 2, 3));
+
+        let dt = target.localize(&resolver, at(2026, 2, 2, 9), None, true);
+        assert_eq!(dt, at(2026, 2, 2, 4));
+
+        // Floating times stay untouched.
+        let dt = target.localize(&resolver, at(2026, 2, 2, 9), None, false);
+        assert_eq!(dt, at(2026, 2, 2, 9));
+    }
+
+    #[test]
+    fn custom_observances() {
+        let observances = vec![
+            Observance {
+                start: at(1970, 10, 25, 3),
+                rule: Some(Rule {
+                    freq: recurrence::Frequency::Yearly,
+                    interval: 1,
+                    count: None,
+                    until: None,
+                    until_utc: false,
+                    by_day: vec![(Some(-1), chrono::Weekday::Sun)],
+                    by_month_day: Vec::new(),
+                    by_month: vec![10],
+                    by_set_pos: Vec::new(),
+                    week_start: chrono::Weekday::Mon,
+                }),
+                rdates: Vec::new(),
+                offset_from: 7_200,
+                offset_to: 3_600,
+            },
+            Observance {
+                start: at(1970, 3, 29, 2),
+                rule: Some(Rule {
+                    freq: recurrence::Frequency::Yearly,
+                    interval: 1,
+                    count: None,
+                    until: None,
+                    until_utc: false,
+                    by_day: vec![(Some(-1), chrono::Weekday::Sun)],
+                    by_month_day: Vec::new(),
+                    by_month: vec![3],
+                    by_set_pos: Vec::new(),
+                    week_start: chrono::Weekday::Mon,
+                }),
+                rdates: Vec::new(),
+                offset_from: 3_600,
+                offset_to: 7_200,
+            },
+        ];
+
+        assert_eq!(custom_offset(&observances, at(2026, 2, 2, 9)), Some(3_600));
+        assert_eq!(custom_offset(&observances, at(2026, 7, 2, 9)), Some(7_200));
+        assert_eq!(custom_offset(&observances, at(1960, 7, 2, 9)), Some(3_600));
+    }
+
+    #[test]
+    fn vtimezone_observances() {
+        let mut writer = IcalWriter::new();
+        write_vtimezone(&mut writer, chrono_tz::Europe::Paris, 2026);
+        let vtimezone = String::from_utf8(writer.finish()).unwrap();
+
+        assert!(vtimezone.starts_with("BEGIN:VTIMEZONE\r\nTZID:Europe/Paris\r\n"));
+        assert!(vtimezone.contains(concat!(
+            "BEGIN:DAYLIGHT\r\nDTSTART:20260329T020000\r\nTZOFFSETFROM:+0100\r\n",
+            "TZOFFSETTO:+0200\r\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n",
+        )));
+        assert!(vtimezone.contains(concat!(
+            "BEGIN:STANDARD\r\nDTSTART:20261025T030000\r\nTZOFFSETFROM:+0200\r\n",
+            "TZOFFSETTO:+0100\r\nRRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n",
+        )));
+
+        let mut writer = IcalWriter::new();
+        write_vtimezone(&mut writer, chrono_tz::America::New_York, 2026);
+        let vtimezone = String::from_utf8(writer.finish()).unwrap();
+        assert!(vtimezone.contains("RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\n"));
+        assert!(vtimezone.contains("RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\n"));
+
+        let mut writer = IcalWriter::new();
+        write_vtimezone(&mut writer, chrono_tz::Asia::Tokyo, 2026);
+        let vtimezone = String::from_utf8(writer.finish()).unwrap();
+        assert!(vtimezone.contains(
+            "BEGIN:STANDARD\r\nDTSTART:20260101T090000\r\nTZOFFSETFROM:+0900\r\nTZOFFSETTO:+0900\r\nEND:STANDARD\r\n"
+        ));
+    }
+
+    #[test]
+    fn vtimezone_resolves_later_years() {
+        let mut writer = IcalWriter::new();
+        writer.begin("VCALENDAR").property("VERSION", &[], "2.0");
+        write_vtimezone(&mut writer, chrono_tz::Europe::Paris, 2026);
+        writer.end("VCALENDAR");
+        let contents = writer.finish();
+
+        let ical = ICalendar::parse(std::str::from_utf8(&contents).unwrap()).unwrap();
+        let vtimezone = ical
+            .components
+            .iter()
+            .find(|c| c.component_type == ICalendarComponentType::VTimezone)
+            .unwrap();
+
+        // The rules keep giving the offsets of Paris years later.
+        let observances = observances(&ical, vtimezone);
+        assert_eq!(custom_offset(&observances, at(2030, 7, 2, 9)), Some(7_200));
+        assert_eq!(custom_offset(&observances, at(2030, 12, 2, 9)), Some(3_600));
+    }
+}
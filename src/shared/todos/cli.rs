@@ -0,0 +1,39 @@
+use anyhow::Result;
+use clap::Subcommand;
+use pimalaya_cli::printer::Printer;
+
+use crate::shared::{
+    client::CalendarClient,
+    todos::{
+        create::TodoCreateCommand,
+        done::{TodoDoneCommand, TodoUndoneCommand},
+        list::TodoListCommand,
+        priority::TodoSetPriorityCommand,
+    },
+};
+
+/// Shared API to manage VTODO items: list, create, done, undone,
+/// set-priority.
+///
+/// Use the `item` commands to read, edit or delete them.
+#[derive(Debug, Subcommand)]
+pub enum TodoCommand {
+    #[command(visible_alias = "ls")]
+    List(TodoListCommand),
+    Create(TodoCreateCommand),
+    Done(TodoDoneCommand),
+    Undone(TodoUndoneCommand),
+    SetPriority(TodoSetPriorityCommand),
+}
+
+impl TodoCommand {
+    pub fn execute(self, printer: &mut impl Printer, client: CalendarClient) -> Result<()> {
+        match self {
+            Self::List(cmd) => cmd.execute(printer, client),
+            Self::Create(cmd) => cmd.execute(printer, client),
+            Self::Done(cmd) => cmd.execute(printer, client),
+            Self::Undone(cmd) => cmd.execute(printer, client),
+            Self::SetPriority(cmd) => cmd.execute(printer, client),
+        }
+    }
+}
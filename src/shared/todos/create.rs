@@ -0,0 +1,225 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    datetime::parse_date_time,
+    ical::{IcalWriter, PRODID, generate_uid},
+    timezone::{Timezone, write_vtimezone},
+    todos::priority::parse_priority,
+};
+
+/// Create a new todo from flags.
+///
+/// `--summary` is required, the rest is optional. Dates without time
+/// are written as DATE values; date times are read in the zone picked
+/// by the global `--tz` flag (falling back to `event.timezone`, then
+/// the local zone), like `event create` does.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct TodoCreateCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    #[command(flatten)]
+    pub fields: TodoFields,
+}
+
+impl TodoCreateCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let contents = self.fields.build(client.account.timezone(), Utc::now())?;
+        let id = client.create_item(&calendar_id, contents)?;
+        printer.out(Message::new(format!("Todo `{id}` successfully created")))
+    }
+}
+
+/// Flags describing a todo.
+#[derive(Debug, Parser)]
+pub struct TodoFields {
+    /// Title of the todo.
+    #[arg(long, value_name = "TEXT")]
+    pub summary: String,
+
+    /// When the todo is due: `YYYY-MM-DD`, `tomorrow 18:00`, etc.
+    #[arg(long, value_name = "DATETIME")]
+    pub due: Option<String>,
+
+    /// When work on the todo can start.
+    #[arg(long, value_name = "DATETIME")]
+    pub start: Option<String>,
+
+    /// Priority: 1 (highest) to 9 (lowest), or `high`, `medium`, `low`.
+    #[arg(long, value_name = "PRIORITY", value_parser = parse_priority)]
+    pub priority: Option<u8>,
+
+    /// Free-form description of the todo.
+    #[arg(long, value_name = "TEXT")]
+    pub description: Option<String>,
+}
+
+impl TodoFields {
+    /// Generates a VCALENDAR holding a single VTODO, with times read in
+    /// the `timezone` wall clock.
+    pub fn build(&self, timezone: Timezone, now: DateTime<Utc>) -> Result<Vec<u8>> {
+        let today = timezone.from_utc(now).date();
+
+        let start = match &self.start {
+            Some(start) => Some(parse_date_time(start, today)?),
+            None => None,
+        };
+
+        let due = match &self.due {
+            Some(due) => Some(parse_date_time(due, today)?),
+            None => None,
+        };
+
+        if let (Some((start, _)), Some((due, _))) = (start, due)
+            && due < start
+        {
+            bail!("Due date of the todo must not be before its start");
+        }
+
+        let mut ical = IcalWriter::new();
+
+        ical.begin("VCALENDAR")
+            .property("VERSION", &[], "2.0")
+            .text("PRODID", PRODID);
+
+        let timed = start.into_iter().chain(due).find(|(_, has_time)| *has_time);
+
+        if let Timezone::Tz(tz) = timezone
+            && let Some((dt, _)) = timed
+        {
+            write_vtimezone(&mut ical, tz, dt.year());
+        }
+
+        let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        ical.begin("VTODO")
+            .text("UID", &generate_uid(now))
+            .property("DTSTAMP", &[], &dtstamp)
+            .property("CREATED", &[], &dtstamp)
+            .text("SUMMARY", &self.summary)
+            .property("STATUS", &[], "NEEDS-ACTION");
+
+        if let Some((start, has_time)) = start {
+            date_time(&mut ical, "DTSTART", start, has_time, timezone);
+        }
+
+        if let Some((due, has_time)) = due {
+            date_time(&mut ical, "DUE", due, has_time, timezone);
+        }
+
+        if let Some(priority) = self.priority {
+            ical.property("PRIORITY", &[], &priority.to_string());
+        }
+
+        if let Some(description) = &self.description {
+            ical.text("DESCRIPTION", description);
+        }
+
+        ical.end("VTODO").end("VCALENDAR");
+
+        Ok(ical.finish())
+    }
+}
+
+/// Writes a DTSTART/DUE property: a DATE without time, a TZID time for
+/// IANA zones, a UTC time for the local zone.
+fn date_time(
+    ical: &mut IcalWriter,
+    name: &str,
+    dt: NaiveDateTime,
+    has_time: bool,
+    timezone: Timezone,
+) {
+    if !has_time {
+        let date = dt.format("%Y%m%d").to_string();
+        ical.property(name, &[("VALUE", "DATE")], &date);
+        return;
+    }
+
+    match timezone {
+        Timezone::Tz(tz) => {
+            let dt = dt.format("%Y%m%dT%H%M%S").to_string();
+            ical.property(name, &[("TZID", tz.name())], &dt);
+        }
+        Timezone::Local => {
+            let dt = timezone.to_utc(dt).format("%Y%m%dT%H%M%SZ").to_string();
+            ical.property(name, &[], &dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn build(fields: &TodoFields) -> Result<String> {
+        let paris = Timezone::Tz(chrono_tz::Europe::Paris);
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap();
+        let contents = fields.build(paris, now)?;
+        Ok(String::from_utf8(contents).unwrap())
+    }
+
+    #[test]
+    fn build_todo() {
+        let fields = TodoFields {
+            summary: String::from("Groceries"),
+            due: Some(String::from("tomorrow 18:00")),
+            start: Some(String::from("2026-03-01")),
+            priority: Some(1),
+            description: Some(String::from("Milk, eggs")),
+        };
+
+        let ical = build(&fields).unwrap();
+
+        assert!(ical.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Paris\r\n"));
+        assert!(ical.contains("BEGIN:VTODO\r\n"));
+        assert!(ical.contains("DTSTAMP:20260301T080000Z\r\n"));
+        assert!(ical.contains("SUMMARY:Groceries\r\n"));
+        assert!(ical.contains("STATUS:NEEDS-ACTION\r\n"));
+        assert!(ical.contains("DTSTART;VALUE=DATE:20260301\r\n"));
+        assert!(ical.contains("DUE;TZID=Europe/Paris:20260302T180000\r\n"));
+        assert!(ical.contains("PRIORITY:1\r\n"));
+        assert!(ical.contains("DESCRIPTION:Milk\\, eggs\r\n"));
+    }
+
+    #[test]
+    fn build_todo_with_dates_only() {
+        let fields = TodoFields {
+            summary: String::from("Taxes"),
+            due: Some(String::from("2026-04-15")),
+            start: None,
+            priority: None,
+            description: None,
+        };
+
+        let ical = build(&fields).unwrap();
+
+        assert!(!ical.contains("VTIMEZONE"));
+        assert!(ical.contains("DUE;VALUE=DATE:20260415\r\n"));
+        assert!(!ical.contains("DTSTART"));
+        assert!(!ical.contains("PRIORITY"));
+    }
+
+    #[test]
+    fn build_todo_due_before_start() {
+        let fields = TodoFields {
+            summary: String::from("Taxes"),
+            due: Some(String::from("2026-04-15")),
+            start: Some(String::from("2026-04-16")),
+            priority: None,
+            description: None,
+        };
+
+        assert!(build(&fields).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use chrono::Utc;
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
+
+/// Mark todos as completed.
+///
+/// Sets STATUS to COMPLETED, PERCENT-COMPLETE to 100 and COMPLETED to
+/// the current time.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct TodoDoneCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Identifiers of the todos, as shown by `todo list`.
+    #[arg(value_name = "TODO-ID", required = true)]
+    pub todo_ids: Vec<String>,
+}
+
+impl TodoDoneCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let completed = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let props = done_props(&completed);
+
+        for id in &self.todo_ids {
+            client.update_component(&calendar_id, id, "VTODO", &props)?;
+        }
+
+        printer.out(Message::new("Todo(s) successfully marked as done"))
+    }
+}
+
+/// Reopen completed todos.
+///
+/// Sets STATUS back to NEEDS-ACTION, and drops COMPLETED and
+/// PERCENT-COMPLETE.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct TodoUndoneCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Identifiers of the todos, as shown by `todo list`.
+    #[arg(value_name = "TODO-ID", required = true)]
+    pub todo_ids: Vec<String>,
+}
+
+impl TodoUndoneCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+
+        for id in &self.todo_ids {
+            client.update_component(&calendar_id, id, "VTODO", &UNDONE_PROPS)?;
+        }
+
+        printer.out(Message::new("Todo(s) successfully reopened"))
+    }
+}
+
+/// Properties marking a todo as completed at the UTC date time
+/// `completed`.
+fn done_props(completed: &str) -> [(&'static str, Option<&str>); 3] {
+    [
+        ("STATUS", Some("COMPLETED")),
+        ("PERCENT-COMPLETE", Some("100")),
+        ("COMPLETED", Some(completed)),
+    ]
+}
+
+/// Properties reopening a completed todo.
+const UNDONE_PROPS: [(&str, Option<&str>); 3] = [
+    ("STATUS", Some("NEEDS-ACTION")),
+    ("PERCENT-COMPLETE", None),
+    ("COMPLETED", None),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{UNDONE_PROPS, done_props};
+    use crate::shared::ical::set_properties;
+
+    const TODO: &str = concat!(
+        "BEGIN:VCALENDAR\r\n",
+        "VERSION:2.0\r\n",
+        "PRODID:-//Test//EN\r\n",
+        "BEGIN:VTODO\r\n",
+        "UID:groceries@example.com\r\n",
+        "DTSTAMP:20260301T080000Z\r\n",
+        "SUMMARY:Groceries\r\n",
+        "STATUS:IN-PROCESS\r\n",
+        "PERCENT-COMPLETE:40\r\n",
+        "BEGIN:VALARM\r\n",
+        "ACTION:DISPLAY\r\n",
+        "TRIGGER:-PT15M\r\n",
+        "END:VALARM\r\n",
+        "END:VTODO\r\n",
+        "END:VCALENDAR\r\n",
+    );
+
+    #[test]
+    fn done_then_undone() {
+        let props = done_props("20260302T180000Z");
+        let done = set_properties(TODO.as_bytes(), "VTODO", &props).unwrap();
+        let text = String::from_utf8(done.clone()).unwrap();
+
+        assert!(text.contains("STATUS:COMPLETED\r\n"));
+        assert!(text.contains("PERCENT-COMPLETE:100\r\n"));
+        assert!(text.contains("COMPLETED:20260302T180000Z\r\n"));
+        assert!(!text.contains("STATUS:IN-PROCESS"));
+        assert!(!text.contains("PERCENT-COMPLETE:40"));
+        assert!(text.contains("BEGIN:VALARM\r\nACTION:DISPLAY\r\n"));
+
+        let undone = set_properties(&done, "VTODO", &UNDONE_PROPS).unwrap();
+        let text = String::from_utf8(undone).unwrap();
+
+        assert!(text.contains("STATUS:NEEDS-ACTION\r\n"));
+        assert!(!text.contains("COMPLETED:"));
+        assert!(!text.contains("PERCENT-COMPLETE"));
+        assert!(text.contains("SUMMARY:Groceries\r\n"));
+    }
+}
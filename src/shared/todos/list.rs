@@ -0,0 +1,385 @@
+use std::{cmp::Ordering, fmt};
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use clap::Parser;
+use comfy_table::{Cell, Color, ContentArrangement, Row, Table};
+use io_calendar::{
+    calcard::icalendar::{ICalendarComponentType, ICalendarProperty},
+    item::CalendarItem,
+};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::shared::{
+    arg::CalendarIdArg,
+    client::CalendarClient,
+    datetime::parse_date,
+    recurrence::{
+        component_date, component_text, component_tzid, component_values, is_date, is_utc,
+        naive_date_time,
+    },
+    timezone::{Timezone, TzResolver, format_date_time},
+};
+
+/// List VTODO items inside a calendar.
+///
+/// Non-VTODO items are filtered out. Completed and cancelled todos are
+/// hidden unless `--all` is passed. Todos are sorted by due date
+/// (undated ones last), then by priority.
+///
+/// Filter on the due date with `--due-before` and `--due-after` (both
+/// inclusive, YYYY-MM-DD, `today` or `tomorrow`), or `--overdue` for
+/// open todos already past due. Filtering happens client-side, before
+/// paging. Due dates are rendered in the zone picked by the global
+/// `--tz` flag, falling back to `event.timezone`, then the local zone.
+///
+/// JSON output: `{"todos": [{"id", "summary", "status", "due",
+/// "priority", "percent-complete"}]}`.
+#[derive(Debug, Parser)]
+pub struct TodoListCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// 1-indexed page number. Defaults to 1.
+    #[arg(short, long, value_name = "N")]
+    pub page: Option<u32>,
+
+    /// Number of todos per page.
+    #[arg(short = 's', long, value_name = "N")]
+    pub page_size: Option<u32>,
+
+    /// Include completed and cancelled todos.
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Only list todos due on or before this date.
+    #[arg(long, value_name = "DATE")]
+    pub due_before: Option<String>,
+
+    /// Only list todos due on or after this date.
+    #[arg(long, value_name = "DATE")]
+    pub due_after: Option<String>,
+
+    /// Only list open todos whose due time has passed.
+    #[arg(long, conflicts_with = "all")]
+    pub overdue: bool,
+
+    /// Maximum width of the rendered table, in terminal columns.
+    #[arg(long = "max-width", short = 'w', value_name = "COLUMNS")]
+    pub max_width: Option<u16>,
+}
+
+impl TodoListCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let timezone = client.account.timezone();
+        let now = timezone.from_utc(Utc::now());
+        let today = now.date();
+
+        let due_after = match &self.due_after {
+            Some(date) => Some(parse_date(date, today)?),
+            None => None,
+        };
+
+        let due_before = match &self.due_before {
+            Some(date) => Some(parse_date(date, today)?),
+            None => None,
+        };
+
+        let filter = TodoFilter {
+            all: self.all,
+            due_after,
+            due_before,
+            overdue: self.overdue.then_some(now),
+        };
+
+        let items = client.list_all_items(&calendar_id, None)?;
+
+        let mut todos: Vec<(Option<NaiveDateTime>, TodoRow)> = items
+            .iter()
+            .filter_map(|item| extract_todo_row(item, timezone))
+            .filter(|(due, row)| filter.matches(*due, row))
+            .collect();
+
+        todos.sort_by(|(a_due, a), (b_due, b)| compare(*a_due, a, *b_due, b));
+
+        let page_size = self
+            .page_size
+            .unwrap_or(client.account.todos_list_page_size()) as usize;
+        let page = self.page.unwrap_or(1).max(1) as usize - 1;
+
+        let todos = todos
+            .into_iter()
+            .map(|(_, row)| row)
+            .skip(page * page_size)
+            .take(page_size)
+            .collect();
+
+        let table = Todos {
+            preset: client.account.table_preset().to_string(),
+            arrangement: client.account.table_arrangement(),
+            max_width: self.max_width,
+            colors: TodoColors {
+                id: client.account.todos_list_table_id_color(),
+                summary: client.account.todos_list_table_summary_color(),
+                status: client.account.todos_list_table_status_color(),
+                due: client.account.todos_list_table_due_color(),
+                priority: client.account.todos_list_table_priority_color(),
+                percent: client.account.todos_list_table_percent_color(),
+            },
+            todos,
+        };
+
+        printer.out(table)
+    }
+}
+
+/// Client-side filter of `todo list`.
+#[derive(Clone, Debug, Default)]
+struct TodoFilter {
+    all: bool,
+    due_after: Option<NaiveDate>,
+    due_before: Option<NaiveDate>,
+    /// Current time, set by `--overdue`.
+    overdue: Option<NaiveDateTime>,
+}
+
+impl TodoFilter {
+    fn matches(&self, due: Option<NaiveDateTime>, row: &TodoRow) -> bool {
+        if !self.all && row.is_closed() {
+            return false;
+        }
+
+        if let Some(now) = self.overdue
+            && !due.is_some_and(|due| due < now)
+        {
+            return false;
+        }
+
+        if let Some(after) = self.due_after
+            && !due.is_some_and(|due| due.date() >= after)
+        {
+            return false;
+        }
+
+        if let Some(before) = self.due_before
+            && !due.is_some_and(|due| due.date() <= before)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Orders todos by due date (undated last), then by priority (1 is
+/// the highest, unset the lowest).
+fn compare(
+    a_due: Option<NaiveDateTime>,
+    a: &TodoRow,
+    b_due: Option<NaiveDateTime>,
+    b: &TodoRow,
+) -> Ordering {
+    let rank = |priority: Option<u8>| priority.unwrap_or(10);
+
+    match (a_due, b_due) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| rank(a.priority).cmp(&rank(b.priority)))
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TodoColors {
+    id: Color,
+    summary: Color,
+    status: Color,
+    due: Color,
+    priority: Color,
+    percent: Color,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TodoRow {
+    pub id: String,
+    pub summary: String,
+    /// STATUS of the todo, `NEEDS-ACTION` when unset.
+    pub status: String,
+    pub due: Option<String>,
+    pub priority: Option<u8>,
+    pub percent_complete: Option<u8>,
+}
+
+impl TodoRow {
+    fn is_closed(&self) -> bool {
+        matches!(self.status.as_str(), "COMPLETED" | "CANCELLED")
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Todos {
+    #[serde(skip)]
+    pub preset: String,
+    #[serde(skip)]
+    pub arrangement: ContentArrangement,
+    #[serde(skip)]
+    pub max_width: Option<u16>,
+    #[serde(skip)]
+    colors: TodoColors,
+    pub todos: Vec<TodoRow>,
+}
+
+impl fmt::Display for Todos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new();
+
+        table
+            .load_preset(&self.preset)
+            .set_content_arrangement(self.arrangement.clone())
+            .set_header(Row::from([
+                Cell::new("ID"),
+                Cell::new("SUMMARY"),
+                Cell::new("STATUS"),
+                Cell::new("DUE"),
+                Cell::new("PRIORITY"),
+                Cell::new("PERCENT"),
+            ]))
+            .add_rows(self.todos.iter().map(|t| {
+                let priority = t.priority.map(|p| p.to_string()).unwrap_or_default();
+                let percent = t
+                    .percent_complete
+                    .map(|p| format!("{p}%"))
+                    .unwrap_or_default();
+
+                let mut row = Row::new();
+                row.max_height(1);
+                row.add_cell(Cell::new(&t.id).fg(self.colors.id));
+                row.add_cell(Cell::new(&t.summary).fg(self.colors.summary));
+                row.add_cell(Cell::new(&t.status).fg(self.colors.status));
+                row.add_cell(Cell::new(t.due.as_deref().unwrap_or("")).fg(self.colors.due));
+                row.add_cell(Cell::new(priority).fg(self.colors.priority));
+                row.add_cell(Cell::new(percent).fg(self.colors.percent));
+                row
+            }));
+
+        if let Some(width) = self.max_width {
+            table.set_width(width);
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{table}")
+    }
+}
+
+/// Parses `item` and pulls out the first VTODO component's SUMMARY,
+/// STATUS, DUE, PRIORITY and PERCENT-COMPLETE, the due time converted
+/// into `timezone` and paired with the row for filtering and sorting.
+/// Returns [`None`] when the item is not a recognisable VTODO.
+fn extract_todo_row(
+    item: &CalendarItem,
+    timezone: Timezone,
+) -> Option<(Option<NaiveDateTime>, TodoRow)> {
+    let ical = item.as_ical()?;
+    let resolver = TzResolver::new(&ical);
+
+    let todo = ical.components.iter().find(|c| {
+        c.component_type == ICalendarComponentType::VTodo
+            && c.property(&ICalendarProperty::RecurrenceId).is_none()
+    })?;
+
+    let due = component_date(todo, &ICalendarProperty::Due).and_then(|pdt| {
+        let dt = naive_date_time(&pdt)?;
+        let all_day = is_date(&pdt);
+
+        if all_day {
+            return Some((dt, true));
+        }
+
+        let tzid = component_tzid(todo, &ICalendarProperty::Due);
+        let dt = timezone.localize(&resolver, dt, tzid.as_deref(), is_utc(&pdt));
+        Some((dt, false))
+    });
+
+    let value = |name| component_values(todo, &name).into_iter().next();
+
+    let row = TodoRow {
+        id: item.id.clone(),
+        summary: component_text(todo, &ICalendarProperty::Summary).unwrap_or_default(),
+        status: value(ICalendarProperty::Status)
+            .map(|status| status.to_ascii_uppercase())
+            .unwrap_or_else(|| String::from("NEEDS-ACTION")),
+        due: due.map(|(dt, all_day)| format_date_time(dt, all_day)),
+        // PRIORITY 0 means undefined.
+        priority: value(ICalendarProperty::Priority)
+            .and_then(|priority| priority.parse().ok())
+            .filter(|priority| *priority > 0),
+        percent_complete: value(ICalendarProperty::PercentComplete)
+            .and_then(|percent| percent.parse().ok()),
+    };
+
+    Some((due.map(|(dt, _)| dt), row))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{TodoFilter, TodoRow};
+
+    fn row(status: &str) -> TodoRow {
+        TodoRow {
+            id: String::from("id"),
+            summary: String::new(),
+            status: status.to_owned(),
+            due: None,
+            priority: None,
+            percent_complete: None,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn hides_closed_todos_unless_all() {
+        let filter = TodoFilter::default();
+        assert!(filter.matches(None, &row("NEEDS-ACTION")));
+        assert!(!filter.matches(None, &row("COMPLETED")));
+        assert!(!filter.matches(None, &row("CANCELLED")));
+
+        let filter = TodoFilter {
+            all: true,
+            ..TodoFilter::default()
+        };
+        assert!(filter.matches(None, &row("COMPLETED")));
+    }
+
+    #[test]
+    fn filters_on_due_dates() {
+        let filter = TodoFilter {
+            due_after: Some(date(10)),
+            due_before: Some(date(12)),
+            ..TodoFilter::default()
+        };
+
+        let due = |day| date(day).and_hms_opt(23, 0, 0);
+        assert!(!filter.matches(None, &row("NEEDS-ACTION")));
+        assert!(!filter.matches(due(9), &row("NEEDS-ACTION")));
+        assert!(filter.matches(due(10), &row("NEEDS-ACTION")));
+        assert!(filter.matches(due(12), &row("IN-PROCESS")));
+        assert!(!filter.matches(due(13), &row("NEEDS-ACTION")));
+
+        let filter = TodoFilter {
+            overdue: due(11),
+            ..TodoFilter::default()
+        };
+        assert!(filter.matches(due(10), &row("NEEDS-ACTION")));
+        assert!(!filter.matches(due(11), &row("NEEDS-ACTION")));
+        assert!(!filter.matches(None, &row("NEEDS-ACTION")));
+    }
+}
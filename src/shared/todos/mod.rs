@@ -0,0 +1,5 @@
+pub mod cli;
+pub mod create;
+pub mod done;
+pub mod list;
+pub mod priority;
@@ -0,0 +1,69 @@
+use anyhow::{Result, bail};
+use clap::Parser;
+use pimalaya_cli::printer::{Message, Printer};
+
+use crate::shared::{arg::CalendarIdArg, client::CalendarClient};
+
+/// Set the priority of a todo.
+///
+/// `0` or `none` clears it.
+///
+/// JSON output: `{"message": "..."}`.
+#[derive(Debug, Parser)]
+pub struct TodoSetPriorityCommand {
+    #[command(flatten)]
+    pub calendar: CalendarIdArg,
+
+    /// Identifier of the todo, as shown by `todo list`.
+    #[arg(value_name = "TODO-ID")]
+    pub todo_id: String,
+
+    /// Priority: 1 (highest) to 9 (lowest), `high`, `medium`, `low`,
+    /// or `none`.
+    #[arg(value_name = "PRIORITY", value_parser = parse_priority)]
+    pub priority: u8,
+}
+
+impl TodoSetPriorityCommand {
+    pub fn execute(self, printer: &mut impl Printer, mut client: CalendarClient) -> Result<()> {
+        let calendar_id = client.account.calendar_id(self.calendar.id)?;
+        let priority = (self.priority > 0).then(|| self.priority.to_string());
+        let props = [("PRIORITY", priority.as_deref())];
+
+        client.update_component(&calendar_id, &self.todo_id, "VTODO", &props)?;
+        printer.out(Message::new("Todo priority successfully updated"))
+    }
+}
+
+/// Parses a PRIORITY value (RFC 5545 §3.8.1.9): a number from 0
+/// (undefined) to 9, or the CUA names `high` (1), `medium` (5) and
+/// `low` (9).
+pub fn parse_priority(s: &str) -> Result<u8> {
+    let priority = match s.trim().to_ascii_lowercase().as_str() {
+        "none" => 0,
+        "high" => 1,
+        "medium" => 5,
+        "low" => 9,
+        n => match n.parse() {
+            Ok(n) if n <= 9 => n,
+            _ => bail!("Invalid priority `{s}`; expected 0-9, `high`, `medium` or `low`"),
+        },
+    };
+
+    Ok(priority)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_priority;
+
+    #[test]
+    fn priorities() {
+        assert_eq!(parse_priority("High").unwrap(), 1);
+        assert_eq!(parse_priority("medium").unwrap(), 5);
+        assert_eq!(parse_priority("7").unwrap(), 7);
+        assert_eq!(parse_priority("none").unwrap(), 0);
+        assert!(parse_priority("10").is_err());
+        assert!(parse_priority("urgent").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::{Result, anyhow, bail};
+use clap::Parser;
+use io_calendar::client::CalendarClientStd;
+use log::info;
+use pimalaya_cli::printer::Printer;
+use pimalaya_config::toml::TomlConfig;
+
+use crate::{
+    caldav::client::connect_and_resolve,
+    cli::load_or_wizard,
+    sync::{
+        conflict::ConflictPolicy,
+        engine::{SyncReport, Syncer},
+        status::{SyncStatus, status_path},
+    },
+    vdir,
+};
+
+/// Synchronize the CalDAV and vdir backends of an account, both ways.
+///
+/// Pairs the `[caldav]` and `[vdir]` blocks of the account: every
+/// CalDAV calendar (or the ones selected by `-k` / `sync.calendars`)
+/// is mirrored into the vdir collection of the same id, created when
+/// missing. Item creations, modifications and deletions are propagated
+/// in both directions, using a status database kept under the XDG data
+/// directory (or `sync.status-dir`) to know what changed since the last
+/// run. Items changed on both sides are settled by `--conflict`,
+/// falling back to `sync.conflict`, then `ask`.
+///
+/// JSON output: `{"account", "dry-run", "calendars": [{"id", "local",
+/// "remote", "conflicts", "skipped"}]}`, where `local` and `remote` are
+/// `{"created", "updated", "deleted"}` counters.
+#[derive(Debug, Parser)]
+pub struct SyncCommand {
+    /// Synchronize only this calendar (repeatable).
+    #[arg(short = 'k', long = "calendar", value_name = "CALENDAR-ID")]
+    pub calendars: Vec<String>,
+
+    /// Conflict policy: `remote-wins`, `local-wins`, `ask` or
+    /// `keep-both`.
+    #[arg(long, value_name = "POLICY")]
+    pub conflict: Option<ConflictPolicy>,
+
+    /// Print what would change without writing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl SyncCommand {
+    pub fn execute(
+        self,
+        printer: &mut impl Printer,
+        config_paths: &[PathBuf],
+        account_name: Option<&str>,
+    ) -> Result<()> {
+        let mut config = load_or_wizard(config_paths)?;
+        let (name, mut account_config) = config
+            .take_account(account_name)?
+            .ok_or_else(|| anyhow!("Cannot find account"))?;
+
+        let Some(caldav_config) = account_config.caldav.take() else {
+            bail!("CalDAV config is missing for account `{name}`");
+        };
+
+        let Some(vdir_config) = account_config.vdir.take() else {
+            bail!("Vdir config is missing for account `{name}`");
+        };
+
+        let sync_config = account_config.sync.unwrap_or_default();
+        let path = status_path(sync_config.status_dir.as_deref(), &name)?;
+        let mut status = SyncStatus::load(&path)?;
+
+        let remote = connect_and_resolve(&caldav_config)?;
+        let remote = io_calendar::webdav::client::WebdavClientStd::new(remote);
+
+        let mut syncer = Syncer {
+            remote: CalendarClientStd::from(remote),
+            local: CalendarClientStd::from(vdir::client::build(&vdir_config)),
+            policy: self.conflict.or(sync_config.conflict).unwrap_or_default(),
+            dry_run: self.dry_run,
+        };
+
+        let selected = if self.calendars.is_empty() {
+            sync_config.calendars.unwrap_or_default()
+        } else {
+            self.calendars
+        };
+
+        let mut calendars = syncer.remote.list_calendars()?;
+
+        if !selected.is_empty() {
+            for id in &selected {
+                if !calendars.iter().any(|calendar| &calendar.id == id) {
+                    bail!("Cannot find calendar `{id}` on the CalDAV side");
+                }
+            }
+
+            calendars.retain(|calendar| selected.contains(&calendar.id));
+        }
+
+        let local_ids: HashSet<String> = syncer
+            .local
+            .list_calendars()?
+            .into_iter()
+            .map(|calendar| calendar.id)
+            .collect();
+
+        let mut report = SyncReport {
+            account: name,
+            dry_run: self.dry_run,
+            calendars: Vec::new(),
+        };
+
+        for calendar in calendars {
+            let mut local_exists = local_ids.contains(&calendar.id);
+
+            if !local_exists && !self.dry_run {
+                info!("creating vdir collection `{}`", calendar.id);
+                syncer.local.create_calendar(
+                    &calendar.id,
+                    &calendar.name,
+                    calendar.description.as_deref(),
+                    calendar.color.as_deref(),
+                )?;
+                local_exists = true;
+            }
+
+            let entries = status.calendars.entry(calendar.id.clone()).or_default();
+            let result = syncer.sync_calendar(&calendar.id, local_exists, entries);
+
+            // Persist what succeeded before bailing, so the next run
+            // does not mistake applied changes for new ones.
+            if !self.dry_run {
+                status.save(&path)?;
+            }
+
+            report.calendars.push(result?);
+        }
+
+        printer.out(report)
+    }
+}
@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Error, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::sync::plan::Action;
+
+/// How `calendula sync` settles an item changed on both sides since
+/// the last run.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// The CalDAV version overwrites the vdir one.
+    RemoteWins,
+    /// The vdir version overwrites the CalDAV one.
+    LocalWins,
+    /// Prompt for every conflict.
+    #[default]
+    Ask,
+    /// Keep the CalDAV version under the original UID, and copy the
+    /// vdir version to both sides under a fresh UID.
+    KeepBoth,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "remote-wins" => Ok(Self::RemoteWins),
+            "local-wins" => Ok(Self::LocalWins),
+            "ask" => Ok(Self::Ask),
+            "keep-both" => Ok(Self::KeepBoth),
+            policy => bail!(
+                "Invalid conflict policy `{policy}`; expected `remote-wins`, `local-wins`, `ask` or `keep-both`"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RemoteWins => write!(f, "remote-wins"),
+            Self::LocalWins => write!(f, "local-wins"),
+            Self::Ask => write!(f, "ask"),
+            Self::KeepBoth => write!(f, "keep-both"),
+        }
+    }
+}
+
+/// Shape of a conflict detected by the planner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// Both sides changed the item, differently.
+    BothModified,
+    /// Both sides created an item with the same UID, differently.
+    BothCreated,
+    /// The vdir side deleted an item the CalDAV side modified.
+    LocalDeleted,
+    /// The CalDAV side deleted an item the vdir side modified.
+    RemoteDeleted,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BothModified => write!(f, "modified on both sides"),
+            Self::BothCreated => write!(f, "created on both sides"),
+            Self::LocalDeleted => write!(f, "deleted locally, modified remotely"),
+            Self::RemoteDeleted => write!(f, "deleted remotely, modified locally"),
+        }
+    }
+}
+
+impl Conflict {
+    /// Turns the conflict on `uid` into the actions settling it under
+    /// `policy`. [`ConflictPolicy::Ask`] settles nothing: the caller
+    /// prompts, then resolves again with the chosen policy.
+    pub fn resolve(self, uid: &str, policy: ConflictPolicy) -> Vec<Action> {
+        let uid = uid.to_owned();
+
+        match (policy, self) {
+            (ConflictPolicy::Ask, _) => vec![Action::Skip(uid)],
+            (ConflictPolicy::RemoteWins, Self::BothModified | Self::BothCreated) => {
+                vec![Action::UpdateLocal(uid)]
+            }
+            (ConflictPolicy::RemoteWins, Self::LocalDeleted) => vec![Action::CreateLocal(uid)],
+            (ConflictPolicy::RemoteWins, Self::RemoteDeleted) => vec![Action::DeleteLocal(uid)],
+            (ConflictPolicy::LocalWins, Self::BothModified | Self::BothCreated) => {
+                vec![Action::UpdateRemote(uid)]
+            }
+            (ConflictPolicy::LocalWins, Self::LocalDeleted) => vec![Action::DeleteRemote(uid)],
+            (ConflictPolicy::LocalWins, Self::RemoteDeleted) => vec![Action::CreateRemote(uid)],
+            (ConflictPolicy::KeepBoth, Self::BothModified | Self::BothCreated) => {
+                vec![Action::Duplicate(uid.clone()), Action::UpdateLocal(uid)]
+            }
+            // Nothing to duplicate when one side is gone: keeping both
+            // means keeping the surviving modification.
+            (ConflictPolicy::KeepBoth, Self::LocalDeleted) => vec![Action::CreateLocal(uid)],
+            (ConflictPolicy::KeepBoth, Self::RemoteDeleted) => vec![Action::CreateRemote(uid)],
+        }
+    }
+}
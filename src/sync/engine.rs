@@ -0,0 +1,376 @@
+//! Applies the plan of every calendar pair to both sides.
+
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use io_calendar::{
+    calcard::icalendar::ICalendarProperty, client::CalendarClientStd, item::CalendarItem,
+};
+use log::{debug, info};
+use pimalaya_cli::prompt;
+use serde::Serialize;
+
+use crate::{
+    shared::{ical::generate_uid, recurrence::component_text},
+    sync::{
+        conflict::{Conflict, ConflictPolicy},
+        plan::{Action, plan},
+        status::{CalendarStatus, Side, StatusEntry},
+    },
+};
+
+/// Two-way synchronizer between a CalDAV (remote) and a vdir (local)
+/// client.
+pub struct Syncer {
+    pub remote: CalendarClientStd,
+    pub local: CalendarClientStd,
+    pub policy: ConflictPolicy,
+    pub dry_run: bool,
+}
+
+/// Items of one side of a calendar, keyed by UID.
+type Items = HashMap<String, CalendarItem>;
+
+impl Syncer {
+    /// Synchronizes the calendar `calendar_id`, updating its `status`
+    /// entries as actions succeed. `local_exists` is false when the
+    /// vdir collection is yet to be created (dry runs only).
+    pub fn sync_calendar(
+        &mut self,
+        calendar_id: &str,
+        local_exists: bool,
+        status: &mut CalendarStatus,
+    ) -> Result<CalendarReport> {
+        let remote = index(self.remote.list_items(calendar_id, None, None, None)?);
+
+        let local = if local_exists {
+            index(self.local.list_items(calendar_id, None, None, None)?)
+        } else {
+            Items::new()
+        };
+
+        let sides = |items: &Items| -> HashMap<String, Side> {
+            let sides = items
+                .iter()
+                .map(|(uid, item)| (uid.clone(), Side::new(item)));
+            sides.collect()
+        };
+
+        let mut report = CalendarReport::new(calendar_id);
+
+        for action in plan(&sides(&remote), &sides(&local), status) {
+            let actions = match action {
+                Action::Conflict(uid, conflict) => {
+                    report.conflicts += 1;
+                    let policy = self.policy_for(&uid, conflict)?;
+                    conflict.resolve(&uid, policy)
+                }
+                action => vec![action],
+            };
+
+            for action in actions {
+                debug!("{calendar_id}: {action:?}");
+                report.count(&action);
+
+                if !self.dry_run {
+                    self.apply(calendar_id, action, &remote, &local, status)?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn policy_for(&self, uid: &str, conflict: Conflict) -> Result<ConflictPolicy> {
+        if self.policy != ConflictPolicy::Ask || self.dry_run {
+            return Ok(self.policy);
+        }
+
+        let msg = format!("Item `{uid}` was {conflict}. Which version to keep?");
+        let choices = [Keep::Remote, Keep::Local, Keep::Both, Keep::Neither];
+
+        Ok(match prompt::item(&msg, choices, None)? {
+            Keep::Remote => ConflictPolicy::RemoteWins,
+            Keep::Local => ConflictPolicy::LocalWins,
+            Keep::Both => ConflictPolicy::KeepBoth,
+            Keep::Neither => ConflictPolicy::Ask,
+        })
+    }
+
+    fn apply(
+        &mut self,
+        calendar_id: &str,
+        action: Action,
+        remote: &Items,
+        local: &Items,
+        status: &mut CalendarStatus,
+    ) -> Result<()> {
+        match action {
+            Action::CreateLocal(uid) => {
+                let item = get(remote, &uid)?;
+                let id = self.local.create_item(calendar_id, item.contents.clone())?;
+                let entry = StatusEntry {
+                    remote: Side::new(item),
+                    local: Side::new(&self.local.get_item(calendar_id, &id)?),
+                };
+                status.insert(uid, entry);
+            }
+            Action::UpdateLocal(uid) => {
+                let item = get(remote, &uid)?;
+                let id = &get(local, &uid)?.id;
+                self.local
+                    .update_item(calendar_id, id, item.contents.clone(), None)?;
+                let entry = StatusEntry {
+                    remote: Side::new(item),
+                    local: Side::new(&self.local.get_item(calendar_id, id)?),
+                };
+                status.insert(uid, entry);
+            }
+            Action::DeleteLocal(uid) => {
+                self.local.delete_item(calendar_id, &get(local, &uid)?.id)?;
+                status.remove(&uid);
+            }
+            Action::CreateRemote(uid) => {
+                let item = get(local, &uid)?;
+                let id = self
+                    .remote
+                    .create_item(calendar_id, item.contents.clone())?;
+                let entry = StatusEntry {
+                    remote: Side::new(&self.remote.get_item(calendar_id, &id)?),
+                    local: Side::new(item),
+                };
+                status.insert(uid, entry);
+            }
+            Action::UpdateRemote(uid) => {
+                let item = get(local, &uid)?;
+                let current = get(remote, &uid)?;
+                let (id, etag) = (&current.id, current.etag.as_deref());
+                self.remote
+                    .update_item(calendar_id, id, item.contents.clone(), etag)?;
+                let entry = StatusEntry {
+                    remote: Side::new(&self.remote.get_item(calendar_id, id)?),
+                    local: Side::new(item),
+                };
+                status.insert(uid, entry);
+            }
+            Action::DeleteRemote(uid) => {
+                self.remote
+                    .delete_item(calendar_id, &get(remote, &uid)?.id)?;
+                status.remove(&uid);
+            }
+            Action::Duplicate(uid) => {
+                let item = get(local, &uid)?;
+                let new_uid = generate_uid(Utc::now());
+                let contents = replace_uid(&item.contents, &uid, &new_uid);
+                info!("{calendar_id}: copying local version of `{uid}` as `{new_uid}`");
+
+                let remote_id = self.remote.create_item(calendar_id, contents.clone())?;
+                let local_id = self.local.create_item(calendar_id, contents)?;
+                let entry = StatusEntry {
+                    remote: Side::new(&self.remote.get_item(calendar_id, &remote_id)?),
+                    local: Side::new(&self.local.get_item(calendar_id, &local_id)?),
+                };
+                status.insert(new_uid, entry);
+            }
+            Action::Record(uid) => {
+                let entry = StatusEntry {
+                    remote: Side::new(get(remote, &uid)?),
+                    local: Side::new(get(local, &uid)?),
+                };
+                status.insert(uid, entry);
+            }
+            Action::Forget(uid) => {
+                status.remove(&uid);
+            }
+            Action::Skip(uid) => {
+                info!("{calendar_id}: leaving conflict on `{uid}` unsettled");
+            }
+            Action::Conflict(..) => unreachable!("conflicts are resolved before being applied"),
+        }
+
+        Ok(())
+    }
+}
+
+/// Version picked by the user for an `ask` conflict.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Keep {
+    Remote,
+    Local,
+    Both,
+    Neither,
+}
+
+impl fmt::Display for Keep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Remote => write!(f, "Keep the remote (CalDAV) version"),
+            Self::Local => write!(f, "Keep the local (vdir) version"),
+            Self::Both => write!(f, "Keep both"),
+            Self::Neither => write!(f, "Skip for now"),
+        }
+    }
+}
+
+/// Indexes items by UID, falling back to the item id for items
+/// without any.
+fn index(items: Vec<CalendarItem>) -> Items {
+    items
+        .into_iter()
+        .map(|item| (item_uid(&item).unwrap_or_else(|| item.id.clone()), item))
+        .collect()
+}
+
+fn item_uid(item: &CalendarItem) -> Option<String> {
+    let ical = item.as_ical()?;
+
+    ical.components
+        .iter()
+        .find_map(|component| component_text(component, &ICalendarProperty::Uid))
+}
+
+fn get<'a>(items: &'a Items, uid: &str) -> Result<&'a CalendarItem> {
+    items
+        .get(uid)
+        .ok_or_else(|| anyhow!("Item `{uid}` vanished during synchronization"))
+}
+
+/// Rewrites the `UID` lines holding `old` to `new`.
+fn replace_uid(contents: &[u8], old: &str, new: &str) -> Vec<u8> {
+    let contents = String::from_utf8_lossy(contents);
+
+    contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let eol = &line[line.trim_end().len()..];
+
+            match line.trim_end().split_once(':') {
+                Some((name, value)) if name.eq_ignore_ascii_case("UID") && value == old => {
+                    format!("{name}:{new}{eol}")
+                }
+                _ => line.to_owned(),
+            }
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Changes applied to one side of a calendar.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Changes {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl fmt::Display for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} created, {} updated, {} deleted",
+            self.created, self.updated, self.deleted
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CalendarReport {
+    pub id: String,
+    pub local: Changes,
+    pub remote: Changes,
+    pub conflicts: usize,
+    pub skipped: usize,
+}
+
+impl CalendarReport {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            local: Changes::default(),
+            remote: Changes::default(),
+            conflicts: 0,
+            skipped: 0,
+        }
+    }
+
+    fn count(&mut self, action: &Action) {
+        match action {
+            Action::CreateLocal(_) => self.local.created += 1,
+            Action::UpdateLocal(_) => self.local.updated += 1,
+            Action::DeleteLocal(_) => self.local.deleted += 1,
+            Action::CreateRemote(_) => self.remote.created += 1,
+            Action::UpdateRemote(_) => self.remote.updated += 1,
+            Action::DeleteRemote(_) => self.remote.deleted += 1,
+            Action::Duplicate(_) => {
+                self.local.created += 1;
+                self.remote.created += 1;
+            }
+            Action::Skip(_) => self.skipped += 1,
+            Action::Record(_) | Action::Forget(_) | Action::Conflict(..) => (),
+        }
+    }
+}
+
+impl fmt::Display for CalendarReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Calendar `{}`:", self.id)?;
+        writeln!(f, "  local (vdir): {}", self.local)?;
+        writeln!(f, "  remote (CalDAV): {}", self.remote)?;
+
+        if self.conflicts > 0 {
+            writeln!(
+                f,
+                "  conflicts: {} ({} left unsettled)",
+                self.conflicts, self.skipped
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SyncReport {
+    pub account: String,
+    pub dry_run: bool,
+    pub calendars: Vec<CalendarReport>,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dry_run {
+            writeln!(
+                f,
+                "Dry run for account `{}`, nothing written:",
+                self.account
+            )?;
+        } else {
+            writeln!(f, "Synchronized account `{}`:", self.account)?;
+        }
+
+        for calendar in &self.calendars {
+            write!(f, "{calendar}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::replace_uid;
+
+    #[test]
+    fn rewrites_matching_uid_lines_only() {
+        let ical = "BEGIN:VEVENT\r\nUID:abc\r\nSUMMARY:UID:abc\r\nEND:VEVENT\r\n";
+        let ical = replace_uid(ical.as_bytes(), "abc", "xyz");
+
+        assert_eq!(
+            String::from_utf8(ical).unwrap(),
+            "BEGIN:VEVENT\r\nUID:xyz\r\nSUMMARY:UID:abc\r\nEND:VEVENT\r\n"
+        );
+    }
+}
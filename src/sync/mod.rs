@@ -0,0 +1,15 @@
+//! Two-way synchronization between the `[caldav]` and `[vdir]` blocks
+//! of one account.
+//!
+//! Calendars are discovered on the CalDAV side and mirrored into vdir
+//! collections of the same id. Items are paired by UID; a per-account
+//! status database (`status.rs`) remembers the state of every pair as
+//! of the last run, which lets the planner (`plan.rs`) tell creations,
+//! modifications and deletions apart on each side. Conflicting changes
+//! are settled by the configured [`conflict::ConflictPolicy`].
+
+pub mod cli;
+pub mod conflict;
+pub mod engine;
+pub mod plan;
+pub mod status;
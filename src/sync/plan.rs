@@ -0,0 +1,186 @@
+//! Pure diff of one calendar pair against its status entries.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::sync::{
+    conflict::Conflict,
+    status::{CalendarStatus, Side},
+};
+
+/// One step of a synchronization, keyed by item UID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    CreateLocal(String),
+    UpdateLocal(String),
+    DeleteLocal(String),
+    CreateRemote(String),
+    UpdateRemote(String),
+    DeleteRemote(String),
+    /// Copy the vdir version to both sides under a fresh UID.
+    Duplicate(String),
+    /// Both sides already agree: only the status needs refreshing.
+    Record(String),
+    /// Both sides are gone: drop the status entry.
+    Forget(String),
+    /// A conflict left unsettled.
+    Skip(String),
+    Conflict(String, Conflict),
+}
+
+/// Diffs the current `remote` and `local` items (indexed by UID)
+/// against the `status` recorded by the previous run.
+///
+/// A remote item changed when its ETag differs from the recorded one,
+/// a local item when its content hash does.
+pub fn plan(
+    remote: &HashMap<String, Side>,
+    local: &HashMap<String, Side>,
+    status: &CalendarStatus,
+) -> Vec<Action> {
+    let uids: BTreeSet<&String> = remote
+        .keys()
+        .chain(local.keys())
+        .chain(status.keys())
+        .collect();
+    let mut actions = Vec::new();
+
+    for uid in uids {
+        let uid = uid.clone();
+        let entry = status.get(&uid);
+
+        let action = match (remote.get(&uid), local.get(&uid), entry) {
+            (Some(r), Some(l), Some(entry)) => {
+                let remote_changed = r.changed_since(&entry.remote);
+                let local_changed = l.hash != entry.local.hash;
+
+                match (remote_changed, local_changed) {
+                    (false, false) => continue,
+                    (true, false) => Action::UpdateLocal(uid),
+                    (false, true) => Action::UpdateRemote(uid),
+                    (true, true) if r.hash == l.hash => Action::Record(uid),
+                    (true, true) => Action::Conflict(uid, Conflict::BothModified),
+                }
+            }
+            (Some(r), None, Some(entry)) if r.changed_since(&entry.remote) => {
+                Action::Conflict(uid, Conflict::LocalDeleted)
+            }
+            (Some(_), None, Some(_)) => Action::DeleteRemote(uid),
+            (None, Some(l), Some(entry)) if l.hash != entry.local.hash => {
+                Action::Conflict(uid, Conflict::RemoteDeleted)
+            }
+            (None, Some(_), Some(_)) => Action::DeleteLocal(uid),
+            (None, None, Some(_)) => Action::Forget(uid),
+            (Some(r), Some(l), None) if r.hash == l.hash => Action::Record(uid),
+            (Some(_), Some(_), None) => Action::Conflict(uid, Conflict::BothCreated),
+            (Some(_), None, None) => Action::CreateLocal(uid),
+            (None, Some(_), None) => Action::CreateRemote(uid),
+            (None, None, None) => continue,
+        };
+
+        actions.push(action);
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Action, plan};
+    use crate::sync::{
+        conflict::Conflict,
+        status::{CalendarStatus, Side, StatusEntry},
+    };
+
+    fn side(etag: &str, hash: u64) -> Side {
+        Side {
+            id: String::from("id"),
+            etag: Some(etag.to_owned()),
+            hash,
+        }
+    }
+
+    fn synced(uid: &str) -> (String, StatusEntry) {
+        let entry = StatusEntry {
+            remote: side("r1", 1),
+            local: side("l1", 1),
+        };
+        (uid.to_owned(), entry)
+    }
+
+    #[test]
+    fn propagates_one_sided_changes() {
+        let status: CalendarStatus = [
+            "same",
+            "remote-edit",
+            "local-edit",
+            "local-del",
+            "remote-del",
+        ]
+        .into_iter()
+        .map(synced)
+        .collect();
+
+        let remote = HashMap::from([
+            (String::from("same"), side("r1", 1)),
+            (String::from("remote-edit"), side("r2", 2)),
+            (String::from("local-edit"), side("r1", 1)),
+            (String::from("local-del"), side("r1", 1)),
+            (String::from("new-remote"), side("r1", 3)),
+        ]);
+
+        let local = HashMap::from([
+            (String::from("same"), side("l1", 1)),
+            (String::from("remote-edit"), side("l1", 1)),
+            (String::from("local-edit"), side("l2", 2)),
+            (String::from("remote-del"), side("l1", 1)),
+            (String::from("new-local"), side("l1", 4)),
+        ]);
+
+        assert_eq!(
+            plan(&remote, &local, &status),
+            vec![
+                Action::DeleteRemote(String::from("local-del")),
+                Action::UpdateRemote(String::from("local-edit")),
+                Action::CreateRemote(String::from("new-local")),
+                Action::CreateLocal(String::from("new-remote")),
+                Action::DeleteLocal(String::from("remote-del")),
+                Action::UpdateLocal(String::from("remote-edit")),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let status: CalendarStatus = ["both", "same-edit", "local-del", "remote-del"]
+            .into_iter()
+            .map(synced)
+            .collect();
+
+        let remote = HashMap::from([
+            (String::from("both"), side("r2", 2)),
+            (String::from("same-edit"), side("r2", 5)),
+            (String::from("local-del"), side("r2", 2)),
+            (String::from("new"), side("r1", 6)),
+        ]);
+
+        let local = HashMap::from([
+            (String::from("both"), side("l2", 3)),
+            (String::from("same-edit"), side("l2", 5)),
+            (String::from("remote-del"), side("l2", 2)),
+            (String::from("new"), side("l1", 7)),
+        ]);
+
+        assert_eq!(
+            plan(&remote, &local, &status),
+            vec![
+                Action::Conflict(String::from("both"), Conflict::BothModified),
+                Action::Conflict(String::from("local-del"), Conflict::LocalDeleted),
+                Action::Conflict(String::from("new"), Conflict::BothCreated),
+                Action::Conflict(String::from("remote-del"), Conflict::RemoteDeleted),
+                Action::Record(String::from("same-edit")),
+            ]
+        );
+    }
+}
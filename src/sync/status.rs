@@ -0,0 +1,115 @@
+//! Status database of `calendula sync`.
+//!
+//! One JSON file per account records, for every synchronized item
+//! UID, the id, ETag and content hash of both sides as of the last
+//! successful run. It is what tells a modification from a creation,
+//! and a deletion from an item never seen.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use io_calendar::item::CalendarItem;
+use serde::{Deserialize, Serialize};
+
+use crate::shared::cache::write_atomic;
+
+/// Status entries of one calendar, keyed by item UID.
+pub type CalendarStatus = BTreeMap<String, StatusEntry>;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SyncStatus {
+    /// Status entries keyed by calendar id.
+    #[serde(default)]
+    pub calendars: BTreeMap<String, CalendarStatus>,
+}
+
+impl SyncStatus {
+    /// Loads the status from `path`. A missing file is an empty status
+    /// (first run).
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Read sync status `{}` error", path.display()))?;
+
+        serde_json::from_str(&json)
+            .with_context(|| format!("Parse sync status `{}` error", path.display()))
+    }
+
+    /// Writes the status to `path` through a temporary file, so that an
+    /// interrupted run never leaves a truncated database behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("Serialize sync status error")?;
+        write_atomic(path, &json).context("Save sync status error")
+    }
+}
+
+/// State of one item pair as of the last run.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct StatusEntry {
+    pub remote: Side,
+    pub local: Side,
+}
+
+/// State of an item on one side: its id (href or file name), its
+/// ETag and a hash of its contents.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Side {
+    pub id: String,
+    pub etag: Option<String>,
+    pub hash: u64,
+}
+
+impl Side {
+    pub fn new(item: &CalendarItem) -> Self {
+        Self {
+            id: item.id.clone(),
+            etag: item.etag.clone(),
+            hash: hash_contents(&item.contents),
+        }
+    }
+
+    /// Tells whether the item changed since `previous` was recorded:
+    /// ETags are authoritative when both are known, contents decide
+    /// otherwise.
+    pub fn changed_since(&self, previous: &Side) -> bool {
+        match (&self.etag, &previous.etag) {
+            (Some(etag), Some(previous)) => etag != previous,
+            _ => self.hash != previous.hash,
+        }
+    }
+}
+
+/// Hashes item contents with 64-bit FNV-1a, stable across builds and
+/// platforms (unlike the std hasher), so the status survives upgrades.
+pub fn hash_contents(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Path of the status database of `account`: `<dir>/<account>.json`,
+/// where `dir` is the `sync.status-dir` config or the XDG data
+/// directory (`~/.local/share/calendula/sync`).
+pub fn status_path(dir: Option<&Path>, account: &str) -> Result<PathBuf> {
+    let dir = match dir {
+        Some(dir) => shellexpand::full(&dir.to_string_lossy())
+            .map(|dir| PathBuf::from(dir.into_owned()))
+            .unwrap_or_else(|_| dir.to_owned()),
+        None => dirs::data_dir()
+            .ok_or_else(|| anyhow!("Cannot find the user data directory"))?
+            .join(env!("CARGO_PKG_NAME"))
+            .join("sync"),
+    };
+
+    Ok(dir.join(format!("{account}.json")))
+}
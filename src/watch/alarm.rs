@@ -0,0 +1,168 @@
+//! VALARM components (RFC 5545 §3.6.6) and the instants they fire at.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use io_calendar::calcard::icalendar::{
+    ICalendar, ICalendarComponent, ICalendarComponentType, ICalendarParameter, ICalendarProperty,
+    ICalendarRelated, ICalendarValue,
+};
+
+use crate::shared::recurrence::{component_text, component_values, naive_date_time, time_delta};
+
+/// When an alarm triggers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Offset from the start (or the end, with `RELATED=END`) of the
+    /// occurrence.
+    Relative {
+        offset: TimeDelta,
+        related_end: bool,
+    },
+    /// Fixed UTC instant.
+    Absolute(DateTime<Utc>),
+}
+
+/// One VALARM of an event.
+#[derive(Clone, Debug)]
+pub struct Alarm {
+    pub trigger: Trigger,
+    /// Additional repetitions after the first trigger (REPEAT).
+    pub repeat: u32,
+    /// Delay between repetitions (DURATION).
+    pub interval: TimeDelta,
+    pub action: String,
+    pub description: Option<String>,
+}
+
+impl Alarm {
+    /// Instants the alarm fires at for an occurrence spanning
+    /// `[start, end)`: the trigger, then every repetition.
+    pub fn fire_times(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let first = match &self.trigger {
+            Trigger::Relative {
+                offset,
+                related_end: true,
+            } => end + *offset,
+            Trigger::Relative { offset, .. } => start + *offset,
+            Trigger::Absolute(instant) => *instant,
+        };
+
+        // Repetitions need a positive interval to be meaningful.
+        let repeat = if self.interval > TimeDelta::zero() {
+            self.repeat
+        } else {
+            0
+        };
+
+        (0..=repeat)
+            .map(|n| first + self.interval * n as i32)
+            .collect()
+    }
+}
+
+/// VALARMs nested in the event `component` of `ical`. Alarms without
+/// a usable TRIGGER are skipped.
+pub fn alarms(ical: &ICalendar, component: &ICalendarComponent) -> Vec<Alarm> {
+    component
+        .component_ids
+        .iter()
+        .filter_map(|id| ical.components.get(*id as usize))
+        .filter(|c| c.component_type == ICalendarComponentType::VAlarm)
+        .filter_map(alarm)
+        .collect()
+}
+
+fn alarm(component: &ICalendarComponent) -> Option<Alarm> {
+    let entry = component.property(&ICalendarProperty::Trigger)?;
+
+    let related_end = entry
+        .params
+        .iter()
+        .any(|param| matches!(param, ICalendarParameter::Related(ICalendarRelated::End)));
+
+    let trigger = entry.values.iter().find_map(|value| match value {
+        ICalendarValue::Duration(duration) => Some(Trigger::Relative {
+            offset: time_delta(duration),
+            related_end,
+        }),
+        ICalendarValue::PartialDateTime(pdt) => {
+            naive_date_time(pdt).map(|dt| Trigger::Absolute(dt.and_utc()))
+        }
+        _ => None,
+    })?;
+
+    let repeat = component_values(component, &ICalendarProperty::Repeat)
+        .first()
+        .and_then(|repeat| repeat.parse().ok())
+        .unwrap_or_default();
+
+    let interval = component
+        .property(&ICalendarProperty::Duration)
+        .and_then(|entry| {
+            entry.values.iter().find_map(|value| match value {
+                ICalendarValue::Duration(duration) => Some(time_delta(duration)),
+                _ => None,
+            })
+        })
+        .unwrap_or_default();
+
+    let action = component_values(component, &ICalendarProperty::Action)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| String::from("DISPLAY"));
+
+    Some(Alarm {
+        trigger,
+        repeat,
+        interval,
+        action,
+        description: component_text(component, &ICalendarProperty::Description),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::{Alarm, Trigger};
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        format!("2026-03-16T{hour:02}:{minute:02}:00Z")
+            .parse()
+            .unwrap()
+    }
+
+    fn alarm(trigger: Trigger, repeat: u32, interval: TimeDelta) -> Alarm {
+        Alarm {
+            trigger,
+            repeat,
+            interval,
+            action: String::from("DISPLAY"),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn fire_times() {
+        let (start, end) = (at(10, 0), at(11, 0));
+
+        let before_start = Trigger::Relative {
+            offset: TimeDelta::minutes(-15),
+            related_end: false,
+        };
+        let repeated = alarm(before_start, 2, TimeDelta::minutes(5));
+        assert_eq!(
+            repeated.fire_times(start, end),
+            [at(9, 45), at(9, 50), at(9, 55)]
+        );
+
+        let at_end = Trigger::Relative {
+            offset: TimeDelta::zero(),
+            related_end: true,
+        };
+        let no_interval = alarm(at_end, 3, TimeDelta::zero());
+        assert_eq!(no_interval.fire_times(start, end), [at(11, 0)]);
+
+        let absolute = alarm(Trigger::Absolute(at(8, 30)), 0, TimeDelta::zero());
+        assert_eq!(absolute.fire_times(start, end), [at(8, 30)]);
+    }
+}
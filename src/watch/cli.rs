@@ -0,0 +1,310 @@
+use std::{fmt, thread, time::Duration};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::Parser;
+use io_calendar::calcard::icalendar::{ICalendarComponentType, ICalendarProperty};
+use log::{debug, warn};
+use pimalaya_cli::printer::Printer;
+use serde::Serialize;
+
+use crate::{
+    shared::{
+        arg::CalendarIdsArg,
+        client::CalendarClient,
+        datetime::parse_duration,
+        recurrence::{self, Localized, component_text, component_values},
+        timezone::format_date_time,
+    },
+    watch::{
+        alarm::{Trigger, alarms},
+        fired::{FiredAlarms, fired_path},
+        notify,
+    },
+};
+
+/// How long after its trigger an alarm still fires when `--catch-up`
+/// is omitted.
+const DEFAULT_CATCH_UP_MINUTES: i64 = 15;
+
+/// How far ahead occurrences are looked up. Alarms triggering earlier
+/// than that before their occurrence fire late, once it gets closer.
+const LOOKAHEAD_DAYS: i64 = 8;
+
+/// How far back occurrences are looked up, for alarms related to the
+/// end of long events.
+const LOOKBEHIND_DAYS: i64 = 2;
+
+/// Watch calendars and notify their alarms (alias `remind`).
+///
+/// Runs as a daemon: every `--interval` seconds (the `watch.interval`
+/// config, otherwise 60), the events of the selected calendars are
+/// loaded, recurrences expanded, and the trigger instants of their
+/// VALARMs computed, whether relative to the start or end of the
+/// occurrence or absolute, including their REPEAT/DURATION
+/// repetitions. The daemon also wakes up right on time for the next
+/// alarm due before the end of the interval. Changes made to the
+/// calendars meanwhile, including vdir files edited by other tools,
+/// are picked up at the next look.
+///
+/// Each alarm due runs the `--notify-cmd` command through the shell
+/// (the `watch.notify-cmd` config, otherwise `notify-send
+/// --app-name=calendula {summary} {start}`). The placeholders
+/// `{summary}`, `{description}`, `{location}`, `{start}`, `{end}`,
+/// `{uid}`, `{account}`, `{calendar}`, `{action}` and `{trigger}` are
+/// replaced by the shell-quoted field of the alarm and its event.
+///
+/// Fired alarms are remembered under the XDG data directory, so a
+/// restarted daemon does not notify them twice; alarms missed while it
+/// was stopped still fire if they triggered less than `--catch-up` ago
+/// (15 minutes by default). Pass `--once` to run a single pass, e.g.
+/// from cron or a systemd timer.
+///
+/// Repeat `-k`, pass `--all-calendars` and/or select several accounts
+/// with `--account` to watch several calendars at once.
+///
+/// JSON output: one `{"account", "calendar", "uid", "recurrence-id",
+/// "summary", "description", "location", "start", "end", "action",
+/// "trigger"}` object per fired alarm.
+#[derive(Debug, Parser)]
+pub struct WatchCommand {
+    #[command(flatten)]
+    pub calendars: CalendarIdsArg,
+
+    /// Seconds between two looks at the calendars.
+    #[arg(long, value_name = "SECONDS")]
+    pub interval: Option<u64>,
+
+    /// Command run through the shell for each fired alarm.
+    #[arg(long, value_name = "COMMAND")]
+    pub notify_cmd: Option<String>,
+
+    /// How late an alarm may still fire, e.g. `15m` or `1h`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub catch_up: Option<TimeDelta>,
+
+    /// Run a single pass then exit.
+    #[arg(long)]
+    pub once: bool,
+}
+
+impl WatchCommand {
+    /// Watches the calendars of every client, one per account selected
+    /// by `--account`, polling at the interval of the first one.
+    pub fn execute(
+        self,
+        printer: &mut impl Printer,
+        mut clients: Vec<CalendarClient>,
+    ) -> Result<()> {
+        let Some(interval) = clients
+            .first()
+            .map(|client| client.account.watch_interval())
+        else {
+            bail!("Cannot watch alarms without any account");
+        };
+
+        let interval = TimeDelta::seconds(self.interval.unwrap_or(interval).max(1) as i64);
+        let catch_up = self
+            .catch_up
+            .unwrap_or(TimeDelta::minutes(DEFAULT_CATCH_UP_MINUTES));
+
+        let path = fired_path()?;
+        let mut fired = FiredAlarms::load(&path)?;
+
+        loop {
+            let now = Utc::now();
+            let mut next = now + interval;
+
+            for client in &mut clients {
+                let pending = match self.pending(client, now) {
+                    Ok(pending) => pending,
+                    Err(err) if !self.once => {
+                        warn!("cannot load alarms of `{}`: {err:#}", client.account_name);
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+
+                for alarm in pending {
+                    if alarm.fire > now {
+                        next = next.min(alarm.fire);
+                        continue;
+                    }
+
+                    if now - alarm.fire > catch_up || fired.contains(&alarm.key) {
+                        continue;
+                    }
+
+                    let template = match &self.notify_cmd {
+                        Some(cmd) => cmd.as_str(),
+                        None => client.account.watch_notify_cmd(),
+                    };
+
+                    let command = notify::render(template, &alarm.event.fields());
+                    debug!("run notification command `{command}`");
+
+                    // A failing command must not make the alarm fire
+                    // again at every pass.
+                    if let Err(err) = notify::run(&command) {
+                        warn!("{err:#}");
+                    }
+
+                    fired.insert(alarm.key, now);
+                    fired.save(&path, now)?;
+                    printer.out(alarm.event)?;
+                }
+            }
+
+            if self.once {
+                return Ok(());
+            }
+
+            let delay = (next - Utc::now()).max(TimeDelta::seconds(1));
+            thread::sleep(delay.to_std().unwrap_or(Duration::from_secs(1)));
+        }
+    }
+
+    /// Computes the fire instants of the alarms of the occurrences
+    /// around `now`, in the selected calendars of `client`.
+    fn pending(&self, client: &mut CalendarClient, now: DateTime<Utc>) -> Result<Vec<Pending>> {
+        let timezone = client.account.timezone();
+        let from = timezone.from_utc(now - TimeDelta::days(LOOKBEHIND_DAYS));
+        let to = timezone.from_utc(now + TimeDelta::days(LOOKAHEAD_DAYS));
+        let kind = ICalendarComponentType::VEvent;
+        let mut pending = Vec::new();
+
+        for calendar_id in self.calendars.resolve(client)? {
+            for item in client.list_all_items(&calendar_id, None)? {
+                let Some(ical) = item.as_ical() else {
+                    continue;
+                };
+
+                let occurrences =
+                    recurrence::occurrences_in(&ical, &kind, Some(from), Some(to), timezone);
+
+                for Localized {
+                    occurrence,
+                    start,
+                    end,
+                } in occurrences
+                {
+                    let component = occurrence.component;
+
+                    let status = component_values(component, &ICalendarProperty::Status);
+                    if status.iter().any(|status| status == "CANCELLED") {
+                        continue;
+                    }
+
+                    let (start_utc, end_utc) = (timezone.to_utc(start), timezone.to_utc(end));
+                    let instance = occurrence
+                        .recurrence_id
+                        .clone()
+                        .unwrap_or_else(|| start_utc.timestamp().to_string());
+
+                    let description = component_text(component, &ICalendarProperty::Description);
+                    let event = FiredAlarm {
+                        account: client.account_name.clone(),
+                        calendar: calendar_id.clone(),
+                        uid: occurrence.uid.clone().unwrap_or_else(|| item.id.clone()),
+                        recurrence_id: occurrence.recurrence_id.clone(),
+                        summary: component_text(component, &ICalendarProperty::Summary)
+                            .unwrap_or_default(),
+                        description: String::new(),
+                        location: component_text(component, &ICalendarProperty::Location)
+                            .unwrap_or_default(),
+                        start: format_date_time(start, occurrence.all_day),
+                        end: format_date_time(end, occurrence.all_day),
+                        action: String::new(),
+                        trigger: String::new(),
+                    };
+
+                    for (index, alarm) in alarms(&ical, component).into_iter().enumerate() {
+                        // Absolute triggers fire once for the whole
+                        // series, not once per occurrence.
+                        let instance = match alarm.trigger {
+                            Trigger::Absolute(_) => "absolute",
+                            Trigger::Relative { .. } => instance.as_str(),
+                        };
+
+                        let fires = alarm.fire_times(start_utc, end_utc);
+
+                        for (n, fire) in fires.into_iter().enumerate() {
+                            let mut event = event.clone();
+                            event.description = alarm
+                                .description
+                                .clone()
+                                .or_else(|| description.clone())
+                                .unwrap_or_default();
+                            event.action = alarm.action.clone();
+                            event.trigger = format_date_time(timezone.from_utc(fire), false);
+
+                            pending.push(Pending {
+                                key: format!(
+                                    "{}/{}/{}/{instance}/{index}/{n}",
+                                    event.account, event.calendar, event.uid
+                                ),
+                                fire,
+                                event,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+}
+
+/// Fire instant of one alarm of one occurrence, not necessarily due
+/// yet.
+struct Pending {
+    /// Identifies the alarm in the fired alarms file.
+    key: String,
+    fire: DateTime<Utc>,
+    event: FiredAlarm,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FiredAlarm {
+    pub account: String,
+    pub calendar: String,
+    pub uid: String,
+    pub recurrence_id: Option<String>,
+    pub summary: String,
+    pub description: String,
+    pub location: String,
+    pub start: String,
+    pub end: String,
+    pub action: String,
+    pub trigger: String,
+}
+
+impl FiredAlarm {
+    /// Values of the notification command placeholders.
+    fn fields(&self) -> [(&str, &str); 10] {
+        [
+            ("summary", &self.summary),
+            ("description", &self.description),
+            ("location", &self.location),
+            ("start", &self.start),
+            ("end", &self.end),
+            ("uid", &self.uid),
+            ("account", &self.account),
+            ("calendar", &self.calendar),
+            ("action", &self.action),
+            ("trigger", &self.trigger),
+        ]
+    }
+}
+
+impl fmt::Display for FiredAlarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}  {} ({}, {}/{})",
+            self.trigger, self.summary, self.start, self.account, self.calendar
+        )
+    }
+}
@@ -0,0 +1,75 @@
+//! Alarms already fired by `calendula watch`.
+//!
+//! One JSON file under the XDG data directory
+//! (`~/.local/share/calendula/watch/fired.json`) maps the key of each
+//! fired alarm to the instant it fired at, so that a restarted daemon
+//! does not notify twice. Entries older than [`RETENTION_DAYS`] are
+//! pruned on save.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::shared::cache::write_atomic;
+
+/// How long fired alarms are remembered.
+const RETENTION_DAYS: i64 = 30;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FiredAlarms {
+    /// Fire instants (Unix timestamps) keyed by alarm key.
+    #[serde(default)]
+    pub alarms: BTreeMap<String, i64>,
+}
+
+impl FiredAlarms {
+    /// Loads the fired alarms from `path`. A missing file means none
+    /// fired yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Read fired alarms `{}` error", path.display()))?;
+
+        serde_json::from_str(&json)
+            .with_context(|| format!("Parse fired alarms `{}` error", path.display()))
+    }
+
+    /// Forgets the alarms fired more than [`RETENTION_DAYS`] before
+    /// `now`, then writes the rest to `path` through a temporary file.
+    pub fn save(&mut self, path: &Path, now: DateTime<Utc>) -> Result<()> {
+        let oldest = (now - TimeDelta::days(RETENTION_DAYS)).timestamp();
+        self.alarms.retain(|_, fired| *fired >= oldest);
+
+        let json = serde_json::to_vec_pretty(self).context("Serialize fired alarms error")?;
+        write_atomic(path, &json).context("Save fired alarms error")
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.alarms.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: String, fired: DateTime<Utc>) {
+        self.alarms.insert(key, fired.timestamp());
+    }
+}
+
+/// Path of the fired alarms file: `fired.json` under the XDG data
+/// directory (`~/.local/share/calendula/watch`).
+pub fn fired_path() -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("Cannot find the user data directory"))?
+        .join(env!("CARGO_PKG_NAME"))
+        .join("watch");
+
+    Ok(dir.join("fired.json"))
+}
@@ -0,0 +1,12 @@
+//! Alarm daemon.
+//!
+//! `calendula watch` polls the selected calendars, computes the
+//! trigger instants of the VALARMs of their upcoming occurrences
+//! (`alarm.rs`), runs a notification command for each due one
+//! (`notify.rs`), and remembers what already fired (`fired.rs`) so a
+//! restart does not notify twice.
+
+pub mod alarm;
+pub mod cli;
+pub mod fired;
+pub mod notify;
@@ -0,0 +1,160 @@
+//! Notification command run for each fired alarm.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+/// Fills the `{name}` placeholders of `template` with the shell-quoted
+/// value of the matching `fields` entry. Unknown placeholders are left
+/// as is; `{{` and `}}` stand for literal braces.
+pub fn render(template: &str, fields: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(index) = rest.find(['{', '}']) {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(tail) = rest.strip_prefix("{{") {
+            out.push('{');
+            rest = tail;
+            continue;
+        }
+
+        if let Some(tail) = rest.strip_prefix("}}") {
+            out.push('}');
+            rest = tail;
+            continue;
+        }
+
+        let field = rest[1..].find('}').and_then(|end| {
+            let name = &rest[1..end + 1];
+            let (_, value) = fields.iter().find(|(key, _)| *key == name)?;
+            Some((end + 2, value))
+        });
+
+        match field {
+            Some((len, value)) => {
+                out.push_str(&shell_quote(value));
+                rest = &rest[len..];
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Quotes `value` as a single word of the shell [`run`] uses.
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        cmd_quote(value)
+    } else {
+        posix_quote(value)
+    }
+}
+
+/// Quotes `value` as a single POSIX shell word.
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quotes `value` as a single argument of a `cmd /C` command line:
+/// first for the program parsing its arguments (backslashes before
+/// quotes doubled, quotes escaped), then for `cmd` itself, which sees
+/// every metacharacter escaped by a caret.
+fn cmd_quote(value: &str) -> String {
+    let mut arg = String::from('"');
+    let mut backslashes = 0;
+
+    for c in value.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                arg.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                arg.push('"');
+                backslashes = 0;
+            }
+            c => {
+                arg.extend(std::iter::repeat_n('\\', backslashes));
+                arg.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+
+    arg.extend(std::iter::repeat_n('\\', backslashes * 2));
+    arg.push('"');
+
+    let mut quoted = String::with_capacity(arg.len() * 2);
+
+    for c in arg.chars() {
+        if "()%!^\"<>&|".contains(c) {
+            quoted.push('^');
+        }
+        quoted.push(c);
+    }
+
+    quoted
+}
+
+/// Runs the rendered notification `command` through the shell.
+pub fn run(command: &str) -> Result<()> {
+    let status = shell(command)
+        .status()
+        .with_context(|| format!("Run notification command `{command}` error"))?;
+
+    if !status.success() {
+        bail!("Notification command `{command}` failed with {status}");
+    }
+
+    Ok(())
+}
+
+/// Builds the `cmd /C` invocation of `command`, passed verbatim: it is
+/// already quoted for `cmd`.
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").raw_arg(command);
+    shell
+}
+
+/// Builds the `sh -c` invocation of `command`.
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmd_quote, render};
+
+    #[cfg(not(windows))]
+    #[test]
+    fn render_quoted_fields() {
+        let fields = [("summary", "Rock'n'roll; rm -rf ~"), ("start", "10:00")];
+
+        assert_eq!(
+            render("notify-send {summary} {start} {unknown} {{x}}", &fields),
+            "notify-send 'Rock'\\''n'\\''roll; rm -rf ~' '10:00' {unknown} {x}"
+        );
+    }
+
+    #[test]
+    fn cmd_quote_escapes_metacharacters() {
+        assert_eq!(cmd_quote("10:00"), "^\"10:00^\"");
+        assert_eq!(
+            cmd_quote(r#"a & "b" 100% \"#),
+            r#"^"a ^& \^"b\^" 100^% \\^""#
+        );
+    }
+}
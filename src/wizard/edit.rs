@@ -296,6 +296,7 @@ fn wizard_to_caldav(cfg: WizardCaldavConfig) -> Result<CaldavConfig> {
         home,
         tls: Default::default(),
         auth: caldav_auth_to_config(cfg.auth),
+        cache: None,
     })
 }
 